@@ -319,6 +319,14 @@ export const scopeMessages = defineMessages({
     id: "scopes.sessionAccess.description",
     defaultMessage: "Access modrinth-issued sessions",
   },
+  openidLabel: {
+    id: "scopes.openid.label",
+    defaultMessage: "Sign in with your account",
+  },
+  openidDescription: {
+    id: "scopes.openid.description",
+    defaultMessage: "Verify your identity with OpenID Connect",
+  },
 });
 
 const scopeDefinitions = [
@@ -562,6 +570,12 @@ const scopeDefinitions = [
     label: scopeMessages.sessionAccessLabel,
     desc: scopeMessages.sessionAccessDescription,
   },
+  {
+    id: "OPENID",
+    value: BigInt(1) << BigInt(41),
+    label: scopeMessages.openidLabel,
+    desc: scopeMessages.openidDescription,
+  },
 ];
 
 const Scopes = scopeDefinitions.reduce(
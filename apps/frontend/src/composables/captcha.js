@@ -0,0 +1,21 @@
+let pending = null;
+
+// 各入口使用的人机验证服务，由后端 CAPTCHA_PROVIDER_* 决定
+export const useCaptchaConfig = () => {
+  const config = useState("captcha-config", () => null);
+
+  if (!config.value && !pending && import.meta.client) {
+    pending = useBaseFetch("auth/captcha", {}, true)
+      .then((res) => {
+        config.value = res;
+      })
+      .catch((err) => {
+        console.error("获取人机验证配置失败:", err);
+      })
+      .finally(() => {
+        pending = null;
+      });
+  }
+
+  return config;
+};
@@ -0,0 +1,8 @@
+import dayjs from "dayjs";
+
+// 新注册的账号在论坛发帖、回复时需要完成人机验证，
+// 天数由后端 CAPTCHA_FORUM_NEW_ACCOUNT_DAYS 决定
+export const needsForumCaptcha = (user, captchaConfig) =>
+  !!user &&
+  !!captchaConfig &&
+  dayjs().diff(dayjs(user.created), "day") < captchaConfig.forum_new_account_days;
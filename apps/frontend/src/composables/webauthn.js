@@ -0,0 +1,70 @@
+// 服务端以 base64url 字符串传递二进制字段，浏览器 API 需要 ArrayBuffer
+const toBuffer = (value) => {
+  const base64 = value.replace(/-/g, "+").replace(/_/g, "/");
+  const padded = base64.padEnd(Math.ceil(base64.length / 4) * 4, "=");
+  return Uint8Array.from(atob(padded), (c) => c.charCodeAt(0)).buffer;
+};
+
+const toBase64Url = (buffer) =>
+  btoa(String.fromCharCode(...new Uint8Array(buffer)))
+    .replace(/\+/g, "-")
+    .replace(/\//g, "_")
+    .replace(/=+$/, "");
+
+const toDescriptors = (credentials) =>
+  (credentials || []).map((credential) => ({ ...credential, id: toBuffer(credential.id) }));
+
+export const isWebauthnSupported = () =>
+  import.meta.client && typeof window.PublicKeyCredential !== "undefined";
+
+// 注册新的通行密钥，options 为服务端返回的注册选项
+export const createPasskey = async (options) => {
+  const publicKey = {
+    ...options.publicKey,
+    challenge: toBuffer(options.publicKey.challenge),
+    user: {
+      ...options.publicKey.user,
+      id: toBuffer(options.publicKey.user.id),
+    },
+    excludeCredentials: toDescriptors(options.publicKey.excludeCredentials),
+  };
+
+  const credential = await navigator.credentials.create({ publicKey });
+
+  return {
+    id: credential.id,
+    rawId: toBase64Url(credential.rawId),
+    type: credential.type,
+    response: {
+      attestationObject: toBase64Url(credential.response.attestationObject),
+      clientDataJSON: toBase64Url(credential.response.clientDataJSON),
+    },
+    extensions: credential.getClientExtensionResults(),
+  };
+};
+
+// 使用通行密钥完成认证，options 为服务端返回的认证选项
+export const getPasskey = async (options) => {
+  const publicKey = {
+    ...options.publicKey,
+    challenge: toBuffer(options.publicKey.challenge),
+    allowCredentials: toDescriptors(options.publicKey.allowCredentials),
+  };
+
+  const credential = await navigator.credentials.get({ publicKey });
+
+  return {
+    id: credential.id,
+    rawId: toBase64Url(credential.rawId),
+    type: credential.type,
+    response: {
+      authenticatorData: toBase64Url(credential.response.authenticatorData),
+      clientDataJSON: toBase64Url(credential.response.clientDataJSON),
+      signature: toBase64Url(credential.response.signature),
+      userHandle: credential.response.userHandle
+        ? toBase64Url(credential.response.userHandle)
+        : null,
+    },
+    extensions: credential.getClientExtensionResults(),
+  };
+};
@@ -0,0 +1,16 @@
+-- 论坛帖子与回复的编辑记录
+ALTER TABLE discussions ADD COLUMN edited_at timestamp with time zone;
+ALTER TABLE posts ADD COLUMN edited_at timestamp with time zone;
+
+CREATE TABLE forum_edit_history (
+    id            BIGSERIAL PRIMARY KEY,
+    discussion_id bigint NOT NULL REFERENCES discussions(id),  -- 所属讨论
+    post_id       bigint REFERENCES posts(id),                 -- 为空表示编辑的是讨论主题本身
+    title         varchar(1000),                               -- 编辑前的标题（仅讨论主题）
+    content       varchar(65536) NOT NULL,                     -- 编辑前的内容
+    edited_by     bigint NOT NULL REFERENCES users(id),        -- 执行编辑的用户
+    edited_at     timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX forum_edit_history_discussion_idx ON forum_edit_history (discussion_id, edited_at DESC) WHERE post_id IS NULL;
+CREATE INDEX forum_edit_history_post_idx ON forum_edit_history (post_id, edited_at DESC);
@@ -0,0 +1,15 @@
+-- 论坛回复与问题评论的表情回应
+CREATE TABLE reactions (
+    target_type varchar(32) NOT NULL,  -- 'post' 或 'issue_comment'
+    target_id   bigint NOT NULL,       -- 回复或评论的ID
+    user_id     bigint NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reaction    varchar(32) NOT NULL,  -- 表情标识，取值见 REACTION_TYPES
+    created_at  timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (target_type, target_id, user_id, reaction)
+);
+
+CREATE INDEX reactions_target_idx ON reactions (target_type, target_id);
+
+-- 被采纳的回答
+ALTER TABLE discussions ADD COLUMN accepted_post_id bigint REFERENCES posts(id);
+ALTER TABLE issues ADD COLUMN accepted_comment_id bigint REFERENCES issue_comments(id);
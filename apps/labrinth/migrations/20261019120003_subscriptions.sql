@@ -0,0 +1,34 @@
+-- 关注讨论、问题、项目问题列表与百科页面
+CREATE TABLE subscriptions (
+    user_id     bigint NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    target_type varchar(32) NOT NULL,  -- discussion/issue/project_issues/wiki
+    target_id   bigint NOT NULL,
+    active      boolean NOT NULL DEFAULT true,   -- false 表示用户已取消关注，参与讨论时不会再自动关注
+    automatic   boolean NOT NULL DEFAULT false,  -- 是否因参与讨论而自动关注
+    created_at  timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, target_type, target_id)
+);
+
+CREATE INDEX subscriptions_target_idx ON subscriptions (target_type, target_id) WHERE active;
+
+-- 按类型屏蔽关注通知
+CREATE TABLE subscription_mutes (
+    user_id     bigint NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    target_type varchar(32) NOT NULL,
+    PRIMARY KEY (user_id, target_type)
+);
+
+-- 待合并发送的关注事件
+CREATE TABLE subscription_events (
+    id          BIGSERIAL PRIMARY KEY,
+    user_id     bigint NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    target_type varchar(32) NOT NULL,
+    target_id   bigint NOT NULL,
+    event_type  varchar(32) NOT NULL,  -- new_post/new_comment/state_change/wiki_edit_accepted
+    actor_id    bigint REFERENCES users(id) ON DELETE SET NULL,
+    title       varchar(1000) NOT NULL,
+    link        varchar(1000) NOT NULL,
+    created_at  timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX subscription_events_user_idx ON subscription_events (user_id, target_type, target_id);
@@ -0,0 +1,36 @@
+-- 项目自定义标签，mod_id 为空的是全站通用标签
+ALTER TABLE issue_labels ADD COLUMN mod_id bigint REFERENCES mods(id) ON DELETE CASCADE;
+CREATE INDEX issue_labels_mod_id_idx ON issue_labels (mod_id);
+
+-- 里程碑，可关联即将发布的版本号，对应版本发布后自动关闭
+CREATE TABLE issue_milestones (
+    id             SERIAL PRIMARY KEY,
+    mod_id         bigint NOT NULL REFERENCES mods(id) ON DELETE CASCADE,
+    title          varchar(100) NOT NULL,
+    description    varchar(2000) NOT NULL DEFAULT '',
+    version_number varchar(255),                         -- 关联的版本号
+    version_id     bigint REFERENCES versions(id) ON DELETE SET NULL, -- 发布后的版本
+    state          varchar(20) NOT NULL DEFAULT 'open',  -- open 或 closed
+    due_date       timestamptz,
+    created_at     timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    closed_at      timestamptz
+);
+
+CREATE INDEX issue_milestones_mod_id_idx ON issue_milestones (mod_id);
+
+-- 问题模板（错误报告/崩溃报告/建议等），fields 为字段定义数组
+CREATE TABLE issue_templates (
+    id            SERIAL PRIMARY KEY,
+    mod_id        bigint NOT NULL REFERENCES mods(id) ON DELETE CASCADE,
+    name          varchar(100) NOT NULL,
+    template_type varchar(32) NOT NULL,  -- bug / crash / suggestion / other
+    description   varchar(500) NOT NULL DEFAULT '',
+    fields        jsonb NOT NULL DEFAULT '[]'::jsonb,
+    sort_order    integer NOT NULL DEFAULT 0,
+    created_at    timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX issue_templates_mod_id_idx ON issue_templates (mod_id);
+
+ALTER TABLE issues ADD COLUMN milestone_id integer REFERENCES issue_milestones(id) ON DELETE SET NULL;
+ALTER TABLE issues ADD COLUMN template_id integer REFERENCES issue_templates(id) ON DELETE SET NULL;
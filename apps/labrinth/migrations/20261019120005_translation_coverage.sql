@@ -0,0 +1,19 @@
+-- 汉化追踪整合包的每个版本的汉化覆盖率报告
+CREATE TABLE translation_coverage (
+    version_id             bigint PRIMARY KEY REFERENCES versions(id) ON DELETE CASCADE,
+    mod_id                 bigint NOT NULL REFERENCES mods(id) ON DELETE CASCADE,
+    translation_version_id bigint REFERENCES versions(id) ON DELETE SET NULL, -- 对比时使用的汉化版本
+    previous_version_id    bigint REFERENCES versions(id) ON DELETE SET NULL, -- 用于计算新增/移除键的上一个版本
+    status                 varchar(20) NOT NULL,                  -- complete / failed / unavailable
+    error                  text,
+    source_keys            jsonb NOT NULL DEFAULT '[]'::jsonb,    -- 整合包中全部 en_us 键
+    new_keys               jsonb NOT NULL DEFAULT '[]'::jsonb,
+    removed_keys           jsonb NOT NULL DEFAULT '[]'::jsonb,
+    untranslated_keys      jsonb NOT NULL DEFAULT '[]'::jsonb,
+    total_keys             integer NOT NULL DEFAULT 0,
+    translated_keys        integer NOT NULL DEFAULT 0,
+    created_at             timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at             timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX translation_coverage_mod_id_idx ON translation_coverage (mod_id);
@@ -0,0 +1,12 @@
+-- 汉化追踪整合包发布新版本后，在汉化资源上自动创建的“汉化过期”任务
+CREATE TABLE translation_tasks (
+    version_id             bigint PRIMARY KEY REFERENCES versions(id) ON DELETE CASCADE, -- 整合包的新版本
+    mod_id                 bigint NOT NULL REFERENCES mods(id) ON DELETE CASCADE,        -- 被追踪的整合包
+    issue_id               bigint NOT NULL REFERENCES issues(id) ON DELETE CASCADE,      -- 汉化资源上的任务
+    state                  varchar(20) NOT NULL DEFAULT 'open',                          -- open 或 closed
+    translation_version_id bigint REFERENCES versions(id) ON DELETE SET NULL,            -- 关闭任务的汉化版本
+    created_at             timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    closed_at              timestamptz
+);
+
+CREATE INDEX translation_tasks_mod_id_idx ON translation_tasks (mod_id);
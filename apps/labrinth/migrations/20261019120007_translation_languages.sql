@@ -0,0 +1,23 @@
+-- 汉化追踪支持多种目标语言，每种语言对应一个本地化资源
+CREATE TABLE mod_translation_trackers (
+    mod_id           bigint NOT NULL REFERENCES mods(id) ON DELETE CASCADE,
+    language_code    varchar(16) NOT NULL,  -- Minecraft 语言代码，例如 zh_cn / zh_tw / ja_jp
+    translation_slug varchar(64) NOT NULL,  -- 本地化资源的 slug
+    PRIMARY KEY (mod_id, language_code)
+);
+
+CREATE INDEX mod_translation_trackers_slug_idx ON mod_translation_trackers (LOWER(translation_slug));
+
+INSERT INTO mod_translation_trackers (mod_id, language_code, translation_slug)
+SELECT id, 'zh_cn', translation_tracker FROM mods WHERE translation_tracker IS NOT NULL;
+
+ALTER TABLE mods DROP COLUMN translation_tracker;
+
+-- 覆盖率报告与汉化任务按语言区分
+ALTER TABLE translation_coverage ADD COLUMN language_code varchar(16) NOT NULL DEFAULT 'zh_cn';
+ALTER TABLE translation_coverage DROP CONSTRAINT translation_coverage_pkey;
+ALTER TABLE translation_coverage ADD PRIMARY KEY (version_id, language_code);
+
+ALTER TABLE translation_tasks ADD COLUMN language_code varchar(16) NOT NULL DEFAULT 'zh_cn';
+ALTER TABLE translation_tasks DROP CONSTRAINT translation_tasks_pkey;
+ALTER TABLE translation_tasks ADD PRIMARY KEY (version_id, language_code);
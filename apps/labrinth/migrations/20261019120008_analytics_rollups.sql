@@ -0,0 +1,48 @@
+-- 没有 ClickHouse 的部署使用的分析数据汇总表（ANALYTICS_BACKEND=postgres）
+-- 浏览量、下载量和游玩时间按小时汇总，不保存单条记录
+
+CREATE TABLE analytics_views (
+    bucket timestamptz NOT NULL,
+    project_id bigint NOT NULL,
+    country varchar(8) NOT NULL DEFAULT '',
+    views bigint NOT NULL DEFAULT 0,
+    PRIMARY KEY (project_id, bucket, country)
+);
+
+CREATE TABLE analytics_downloads (
+    bucket timestamptz NOT NULL,
+    project_id bigint NOT NULL,
+    version_id bigint NOT NULL,
+    country varchar(8) NOT NULL DEFAULT '',
+    downloads bigint NOT NULL DEFAULT 0,
+    PRIMARY KEY (project_id, bucket, version_id, country)
+);
+
+CREATE TABLE analytics_playtime (
+    bucket timestamptz NOT NULL,
+    project_id bigint NOT NULL,
+    version_id bigint NOT NULL,
+    loader varchar(255) NOT NULL DEFAULT '',
+    game_version varchar(255) NOT NULL DEFAULT '',
+    parent bigint NOT NULL DEFAULT 0,
+    seconds bigint NOT NULL DEFAULT 0,
+    PRIMARY KEY (project_id, bucket, version_id, loader, game_version, parent)
+);
+
+-- 刷量检测判定为可疑的下载（对应 ClickHouse 的 download_fraud 表）
+CREATE TABLE analytics_download_fraud (
+    id bigserial PRIMARY KEY,
+    recorded timestamptz NOT NULL,
+    user_id bigint NOT NULL,
+    project_id bigint NOT NULL,
+    version_id bigint NOT NULL,
+    ip varchar(64) NOT NULL,
+    asn bigint NOT NULL DEFAULT 0,
+    user_agent text NOT NULL DEFAULT '',
+    attempts integer NOT NULL DEFAULT 1,
+    score integer NOT NULL,
+    reasons text[] NOT NULL DEFAULT '{}'
+);
+
+CREATE INDEX analytics_download_fraud_recorded ON analytics_download_fraud (recorded);
+CREATE INDEX analytics_download_fraud_project ON analytics_download_fraud (project_id, recorded);
@@ -0,0 +1,9 @@
+-- Postgres 分析后端的细分维度汇总（版本、游戏版本、加载器、来源、启动器）
+CREATE TABLE analytics_breakdowns (
+    bucket timestamptz NOT NULL,
+    project_id bigint NOT NULL,
+    breakdown varchar(32) NOT NULL,
+    key varchar(255) NOT NULL DEFAULT '',
+    total bigint NOT NULL DEFAULT 0,
+    PRIMARY KEY (project_id, bucket, breakdown, key)
+);
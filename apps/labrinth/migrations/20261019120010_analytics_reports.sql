@@ -0,0 +1,12 @@
+-- 分析报告邮件订阅：按周或按月向用户发送所选项目的数据汇总
+CREATE TABLE analytics_report_subscriptions (
+    id bigserial PRIMARY KEY,
+    user_id bigint NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    frequency varchar(16) NOT NULL CHECK (frequency IN ('weekly', 'monthly')),
+    project_ids bigint[] NOT NULL DEFAULT '{}',
+    last_sent timestamptz,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX analytics_report_subscriptions_user_id
+    ON analytics_report_subscriptions(user_id);
@@ -0,0 +1,12 @@
+-- 整合包版本解析后的文件列表，用于对比两个版本之间的模组和配置文件变化
+CREATE TABLE modpack_files (
+    version_id bigint NOT NULL REFERENCES versions(id) ON DELETE CASCADE,
+    path varchar(1024) NOT NULL,
+    -- index: modrinth.index.json 中的文件；override: 覆盖目录中的模组/资源包/光影；config: 其他覆盖文件
+    kind varchar(16) NOT NULL,
+    -- 与 hashes 表一致，存储十六进制字符串的字节
+    sha1 bytea NOT NULL,
+    PRIMARY KEY (version_id, path)
+);
+
+CREATE INDEX modpack_files_sha1 ON modpack_files(sha1);
@@ -0,0 +1,17 @@
+-- 公开客户端（桌面应用、启动器等）无法保存密钥，必须使用 PKCE
+ALTER TABLE oauth_clients ADD COLUMN is_public boolean NOT NULL DEFAULT FALSE;
+
+CREATE TABLE oauth_refresh_tokens (
+    id bigint PRIMARY KEY,
+    authorization_id bigint NOT NULL REFERENCES oauth_client_authorizations(id) ON DELETE CASCADE,
+    -- 与该刷新令牌一同签发的访问令牌，刷新或撤销时一并失效
+    access_token_id bigint NULL REFERENCES oauth_access_tokens(id) ON DELETE SET NULL,
+    token_hash text NOT NULL UNIQUE,
+    scopes bigint NOT NULL,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP + interval '90 days',
+    -- 轮换后记录时间，旧令牌再次被使用时视为泄露
+    rotated timestamptz NULL
+);
+
+CREATE INDEX oauth_refresh_token_authorization ON oauth_refresh_tokens(authorization_id);
@@ -0,0 +1,19 @@
+-- ID 令牌签名密钥，ES256 密钥由服务自动轮换，RS256 密钥从配置导入
+CREATE TABLE oidc_signing_keys (
+    kid text PRIMARY KEY,
+    algorithm varchar(16) NOT NULL,
+    -- 加密存储的 PKCS#8 私钥
+    private_key text NOT NULL,
+    public_jwk jsonb NOT NULL,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    -- 被新密钥替换的时间，退役后仍在 JWKS 中保留一段时间以便验证已签发的令牌
+    retired timestamptz NULL
+);
+
+CREATE INDEX oidc_signing_keys_algorithm ON oidc_signing_keys(algorithm) WHERE retired IS NULL;
+
+ALTER TABLE oauth_clients ADD COLUMN id_token_signed_response_alg varchar(16) NOT NULL DEFAULT 'ES256';
+
+-- 记录每个应用获取过的用户信息声明
+ALTER TABLE oauth_client_authorizations ADD COLUMN released_claims text[] NOT NULL DEFAULT '{}';
+ALTER TABLE oauth_client_authorizations ADD COLUMN claims_released timestamptz NULL;
@@ -0,0 +1,17 @@
+-- 用户注册的 WebAuthn 通行密钥，可用于无密码登录或作为第二因素
+CREATE TABLE user_webauthn_credentials (
+    id bigint PRIMARY KEY,
+    user_id bigint NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- base64url 编码的凭据 ID
+    credential_id text NOT NULL UNIQUE,
+    name varchar(64) NOT NULL,
+    -- 序列化的凭据（公钥、签名计数等），每次使用后更新
+    passkey jsonb NOT NULL,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_used timestamptz NULL
+);
+
+CREATE INDEX user_webauthn_credentials_user_id ON user_webauthn_credentials(user_id);
+
+-- 要求持有删除项目或收益权限的成员使用通行密钥作为第二因素
+ALTER TABLE organizations ADD COLUMN require_passkey_2fa boolean NOT NULL DEFAULT FALSE;
@@ -0,0 +1,17 @@
+-- 短信发送记录，供客服排查用户收不到验证码的问题
+CREATE TABLE sms_send_logs (
+    id bigserial PRIMARY KEY,
+    user_id bigint NULL REFERENCES users(id) ON DELETE SET NULL,
+    phone_number varchar(32) NOT NULL,
+    ip varchar(64) NULL,
+    -- 服务商：aliyun、tencent 或 local
+    provider varchar(16) NOT NULL,
+    -- sent（已发送）、failed（服务商返回错误）或 rate_limited（触发发送频率限制）
+    status varchar(16) NOT NULL,
+    provider_message_id text NULL,
+    error text NULL,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX sms_send_logs_phone_number ON sms_send_logs(phone_number, created DESC);
+CREATE INDEX sms_send_logs_user_id ON sms_send_logs(user_id, created DESC);
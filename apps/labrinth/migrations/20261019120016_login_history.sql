@@ -0,0 +1,19 @@
+-- 登录历史，用于识别新国家、不可能的移动速度和新设备等异常登录
+CREATE TABLE user_login_history (
+    id bigserial PRIMARY KEY,
+    user_id bigint NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- 会话可能已被删除，因此不设外键
+    session_id bigint NULL,
+    ip varchar(64) NOT NULL,
+    country varchar(8) NULL,
+    city varchar(255) NULL,
+    latitude double precision NULL,
+    longitude double precision NULL,
+    -- 操作系统和浏览器，如 "Windows 10 / Chrome"
+    device varchar(255) NOT NULL,
+    -- 本次登录命中的异常：new_country、impossible_travel、new_device
+    risk_flags text[] NOT NULL DEFAULT '{}',
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX user_login_history_user_id ON user_login_history(user_id, created DESC);
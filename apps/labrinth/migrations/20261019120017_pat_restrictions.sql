@@ -0,0 +1,6 @@
+-- 个人访问令牌的资源和来源限制，NULL 表示不限制
+-- 限定项目或组织后，令牌只能操作这些项目（或组织下的项目）
+ALTER TABLE pats ADD COLUMN project_ids bigint[] NULL;
+ALTER TABLE pats ADD COLUMN organization_ids bigint[] NULL;
+-- 允许使用令牌的 IP 范围，如 "203.0.113.0/24"、"2001:db8::/32"
+ALTER TABLE pats ADD COLUMN allowed_ips varchar(64)[] NULL;
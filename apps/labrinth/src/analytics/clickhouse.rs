@@ -0,0 +1,200 @@
+use super::{AnalyticsBreakdown, AnalyticsStorage};
+use crate::clickhouse::{
+    ReturnBreakdown, ReturnCountry, ReturnDownloadFraud, ReturnIntervals,
+    ReturnTotal,
+};
+use crate::models::analytics::{
+    Download, PageView, Playtime, SuspiciousDownload,
+};
+use crate::models::ids::ProjectId;
+use crate::routes::ApiError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+pub struct ClickhouseAnalytics {
+    client: Arc<clickhouse::Client>,
+}
+
+impl ClickhouseAnalytics {
+    pub fn new(client: clickhouse::Client) -> Self {
+        ClickhouseAnalytics {
+            client: Arc::new(client),
+        }
+    }
+
+    async fn insert<T>(&self, table: &str, rows: Vec<T>) -> Result<(), ApiError>
+    where
+        T: clickhouse::RowOwned + clickhouse::RowWrite,
+    {
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let mut insert = self.client.insert::<T>(table).await?;
+        for row in &rows {
+            insert.write(row).await?;
+        }
+        insert.end().await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AnalyticsStorage for ClickhouseAnalytics {
+    async fn insert_views(&self, views: Vec<PageView>) -> Result<(), ApiError> {
+        self.insert("views", views).await
+    }
+
+    async fn insert_downloads(
+        &self,
+        downloads: Vec<Download>,
+    ) -> Result<(), ApiError> {
+        self.insert("downloads", downloads).await
+    }
+
+    async fn insert_playtimes(
+        &self,
+        playtimes: Vec<Playtime>,
+    ) -> Result<(), ApiError> {
+        self.insert("playtime", playtimes).await
+    }
+
+    async fn insert_suspicious_downloads(
+        &self,
+        downloads: Vec<SuspiciousDownload>,
+    ) -> Result<(), ApiError> {
+        self.insert("download_fraud", downloads).await
+    }
+
+    async fn fetch_playtimes(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        resolution_minutes: u32,
+    ) -> Result<Vec<ReturnIntervals>, ApiError> {
+        crate::clickhouse::fetch_playtimes(
+            projects,
+            start_date,
+            end_date,
+            resolution_minutes,
+            self.client.clone(),
+        )
+        .await
+    }
+
+    async fn fetch_views(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        resolution_minutes: u32,
+    ) -> Result<Vec<ReturnIntervals>, ApiError> {
+        crate::clickhouse::fetch_views(
+            projects,
+            start_date,
+            end_date,
+            resolution_minutes,
+            self.client.clone(),
+        )
+        .await
+    }
+
+    async fn fetch_downloads(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        resolution_minutes: u32,
+    ) -> Result<Vec<ReturnIntervals>, ApiError> {
+        crate::clickhouse::fetch_downloads(
+            projects,
+            start_date,
+            end_date,
+            resolution_minutes,
+            self.client.clone(),
+        )
+        .await
+    }
+
+    async fn fetch_countries_downloads(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<ReturnCountry>, ApiError> {
+        crate::clickhouse::fetch_countries_downloads(
+            projects,
+            start_date,
+            end_date,
+            self.client.clone(),
+        )
+        .await
+    }
+
+    async fn fetch_countries_views(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<ReturnCountry>, ApiError> {
+        crate::clickhouse::fetch_countries_views(
+            projects,
+            start_date,
+            end_date,
+            self.client.clone(),
+        )
+        .await
+    }
+
+    async fn fetch_breakdown(
+        &self,
+        breakdown: AnalyticsBreakdown,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        resolution_minutes: u32,
+    ) -> Result<Vec<ReturnBreakdown>, ApiError> {
+        crate::clickhouse::fetch_breakdown(
+            breakdown,
+            projects,
+            start_date,
+            end_date,
+            resolution_minutes,
+            self.client.clone(),
+        )
+        .await
+    }
+
+    async fn fetch_download_fraud_projects(
+        &self,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<ReturnDownloadFraud>, ApiError> {
+        crate::clickhouse::fetch_download_fraud_projects(
+            start_date,
+            end_date,
+            limit,
+            self.client.clone(),
+        )
+        .await
+    }
+
+    async fn fetch_downloads_total(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<ReturnTotal>, ApiError> {
+        crate::clickhouse::fetch_downloads_total(
+            projects,
+            start_date,
+            end_date,
+            self.client.clone(),
+        )
+        .await
+    }
+}
@@ -0,0 +1,131 @@
+//! 分析数据存储
+//!
+//! 浏览量、下载量、游玩时间等分析数据的写入与查询。
+//! 由 `ANALYTICS_BACKEND` 选择后端：`clickhouse`（默认）或 `postgres`，
+//! 后者使用按小时汇总的表，适用于没有 ClickHouse 的小型部署和 CI。
+
+use crate::clickhouse::{
+    ReturnBreakdown, ReturnCountry, ReturnDownloadFraud, ReturnIntervals,
+    ReturnTotal,
+};
+use crate::models::analytics::{
+    Download, PageView, Playtime, SuspiciousDownload,
+};
+use crate::models::ids::ProjectId;
+use crate::routes::ApiError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+mod clickhouse;
+mod postgres;
+
+pub use self::clickhouse::ClickhouseAnalytics;
+pub use self::postgres::PostgresAnalytics;
+
+/// 下载量和浏览量的细分维度
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AnalyticsBreakdown {
+    DownloadVersion,
+    DownloadGameVersion,
+    DownloadLoader,
+    DownloadReferrer,
+    DownloadLauncher,
+    ViewReferrer,
+}
+
+impl AnalyticsBreakdown {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnalyticsBreakdown::DownloadVersion => "download_version",
+            AnalyticsBreakdown::DownloadGameVersion => "download_game_version",
+            AnalyticsBreakdown::DownloadLoader => "download_loader",
+            AnalyticsBreakdown::DownloadReferrer => "download_referrer",
+            AnalyticsBreakdown::DownloadLauncher => "download_launcher",
+            AnalyticsBreakdown::ViewReferrer => "view_referrer",
+        }
+    }
+}
+
+#[async_trait]
+pub trait AnalyticsStorage {
+    async fn insert_views(&self, views: Vec<PageView>) -> Result<(), ApiError>;
+
+    async fn insert_downloads(
+        &self,
+        downloads: Vec<Download>,
+    ) -> Result<(), ApiError>;
+
+    async fn insert_playtimes(
+        &self,
+        playtimes: Vec<Playtime>,
+    ) -> Result<(), ApiError>;
+
+    async fn insert_suspicious_downloads(
+        &self,
+        downloads: Vec<SuspiciousDownload>,
+    ) -> Result<(), ApiError>;
+
+    async fn fetch_playtimes(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        resolution_minutes: u32,
+    ) -> Result<Vec<ReturnIntervals>, ApiError>;
+
+    async fn fetch_views(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        resolution_minutes: u32,
+    ) -> Result<Vec<ReturnIntervals>, ApiError>;
+
+    async fn fetch_downloads(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        resolution_minutes: u32,
+    ) -> Result<Vec<ReturnIntervals>, ApiError>;
+
+    async fn fetch_countries_downloads(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<ReturnCountry>, ApiError>;
+
+    async fn fetch_countries_views(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<ReturnCountry>, ApiError>;
+
+    /// 按细分维度统计的下载量或浏览量，`key` 为维度值（版本为数字 ID）
+    async fn fetch_breakdown(
+        &self,
+        breakdown: AnalyticsBreakdown,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        resolution_minutes: u32,
+    ) -> Result<Vec<ReturnBreakdown>, ApiError>;
+
+    /// 可疑下载最多的项目
+    async fn fetch_download_fraud_projects(
+        &self,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<ReturnDownloadFraud>, ApiError>;
+
+    /// 项目在时间段内计入的下载总数
+    async fn fetch_downloads_total(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<ReturnTotal>, ApiError>;
+}
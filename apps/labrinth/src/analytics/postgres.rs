@@ -0,0 +1,617 @@
+use super::{AnalyticsBreakdown, AnalyticsStorage};
+use crate::clickhouse::{
+    ReturnBreakdown, ReturnCountry, ReturnDownloadFraud, ReturnIntervals,
+    ReturnTotal,
+};
+use crate::models::analytics::{
+    Download, PageView, Playtime, SuspiciousDownload,
+};
+use crate::models::ids::ProjectId;
+use crate::routes::ApiError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+// 汇总粒度（秒），查询的最小分辨率为一小时
+const BUCKET_SECONDS: i64 = 60 * 60;
+
+/// 将记录时间（0.1 毫秒）转换为所在小时的起始时间
+fn bucket(recorded: i64) -> DateTime<Utc> {
+    let seconds = recorded / 10_000;
+    DateTime::from_timestamp(seconds - seconds % BUCKET_SECONDS, 0)
+        .unwrap_or_default()
+}
+
+fn project_ids(projects: &[ProjectId]) -> Vec<i64> {
+    projects.iter().map(|x| x.0 as i64).collect()
+}
+
+/// 查询时间间隔（秒），不小于汇总粒度
+fn resolution_seconds(resolution_minutes: u32) -> i64 {
+    (resolution_minutes as i64 * 60).max(BUCKET_SECONDS)
+}
+
+/// 基于 Postgres 按小时汇总表的分析存储，不保存单条浏览/下载记录
+pub struct PostgresAnalytics {
+    pool: PgPool,
+}
+
+type BreakdownRollup =
+    HashMap<(DateTime<Utc>, i64, AnalyticsBreakdown, String), i64>;
+
+impl PostgresAnalytics {
+    pub fn new(pool: PgPool) -> Self {
+        PostgresAnalytics { pool }
+    }
+
+    async fn insert_breakdowns(
+        &self,
+        rollup: BreakdownRollup,
+    ) -> Result<(), ApiError> {
+        if rollup.is_empty() {
+            return Ok(());
+        }
+
+        let mut buckets = Vec::new();
+        let mut projects = Vec::new();
+        let mut breakdowns = Vec::new();
+        let mut keys = Vec::new();
+        let mut counts = Vec::new();
+        for ((bucket, project, breakdown, key), count) in rollup {
+            buckets.push(bucket);
+            projects.push(project);
+            breakdowns.push(breakdown.as_str().to_string());
+            keys.push(key);
+            counts.push(count);
+        }
+
+        sqlx::query!(
+            "
+            INSERT INTO analytics_breakdowns (bucket, project_id, breakdown, key, total)
+            SELECT * FROM UNNEST($1::timestamptz[], $2::bigint[], $3::varchar[], $4::varchar[], $5::bigint[])
+            ON CONFLICT (project_id, bucket, breakdown, key)
+            DO UPDATE SET total = analytics_breakdowns.total + EXCLUDED.total
+            ",
+            &buckets[..],
+            &projects[..],
+            &breakdowns[..],
+            &keys[..],
+            &counts[..]
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AnalyticsStorage for PostgresAnalytics {
+    async fn insert_views(&self, views: Vec<PageView>) -> Result<(), ApiError> {
+        let mut rollup: HashMap<(DateTime<Utc>, i64, String), i64> =
+            HashMap::new();
+        let mut breakdowns = BreakdownRollup::new();
+        for view in views {
+            *breakdowns
+                .entry((
+                    bucket(view.recorded),
+                    view.project_id as i64,
+                    AnalyticsBreakdown::ViewReferrer,
+                    view.referrer,
+                ))
+                .or_default() += 1;
+            *rollup
+                .entry((
+                    bucket(view.recorded),
+                    view.project_id as i64,
+                    view.country,
+                ))
+                .or_default() += 1;
+        }
+        if rollup.is_empty() {
+            return Ok(());
+        }
+
+        let mut buckets = Vec::new();
+        let mut projects = Vec::new();
+        let mut countries = Vec::new();
+        let mut counts = Vec::new();
+        for ((bucket, project, country), count) in rollup {
+            buckets.push(bucket);
+            projects.push(project);
+            countries.push(country);
+            counts.push(count);
+        }
+
+        sqlx::query!(
+            "
+            INSERT INTO analytics_views (bucket, project_id, country, views)
+            SELECT * FROM UNNEST($1::timestamptz[], $2::bigint[], $3::varchar[], $4::bigint[])
+            ON CONFLICT (project_id, bucket, country)
+            DO UPDATE SET views = analytics_views.views + EXCLUDED.views
+            ",
+            &buckets[..],
+            &projects[..],
+            &countries[..],
+            &counts[..]
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.insert_breakdowns(breakdowns).await
+    }
+
+    async fn insert_downloads(
+        &self,
+        downloads: Vec<Download>,
+    ) -> Result<(), ApiError> {
+        let mut rollup: HashMap<(DateTime<Utc>, i64, i64, String), i64> =
+            HashMap::new();
+        let mut breakdowns = BreakdownRollup::new();
+        for download in downloads {
+            let bucket = bucket(download.recorded);
+            let project_id = download.project_id as i64;
+            let keys = [
+                (
+                    AnalyticsBreakdown::DownloadVersion,
+                    download.version_id.to_string(),
+                ),
+                (AnalyticsBreakdown::DownloadReferrer, download.referrer),
+                (AnalyticsBreakdown::DownloadLauncher, download.launcher),
+            ]
+            .into_iter()
+            .chain(
+                download
+                    .game_versions
+                    .into_iter()
+                    .map(|x| (AnalyticsBreakdown::DownloadGameVersion, x)),
+            )
+            .chain(
+                download
+                    .loaders
+                    .into_iter()
+                    .map(|x| (AnalyticsBreakdown::DownloadLoader, x)),
+            );
+            for (breakdown, key) in keys {
+                *breakdowns
+                    .entry((bucket, project_id, breakdown, key))
+                    .or_default() += 1;
+            }
+
+            *rollup
+                .entry((
+                    bucket,
+                    project_id,
+                    download.version_id as i64,
+                    download.country,
+                ))
+                .or_default() += 1;
+        }
+        if rollup.is_empty() {
+            return Ok(());
+        }
+
+        let mut buckets = Vec::new();
+        let mut projects = Vec::new();
+        let mut versions = Vec::new();
+        let mut countries = Vec::new();
+        let mut counts = Vec::new();
+        for ((bucket, project, version, country), count) in rollup {
+            buckets.push(bucket);
+            projects.push(project);
+            versions.push(version);
+            countries.push(country);
+            counts.push(count);
+        }
+
+        sqlx::query!(
+            "
+            INSERT INTO analytics_downloads (bucket, project_id, version_id, country, downloads)
+            SELECT * FROM UNNEST($1::timestamptz[], $2::bigint[], $3::bigint[], $4::varchar[], $5::bigint[])
+            ON CONFLICT (project_id, bucket, version_id, country)
+            DO UPDATE SET downloads = analytics_downloads.downloads + EXCLUDED.downloads
+            ",
+            &buckets[..],
+            &projects[..],
+            &versions[..],
+            &countries[..],
+            &counts[..]
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.insert_breakdowns(breakdowns).await
+    }
+
+    async fn insert_playtimes(
+        &self,
+        playtimes: Vec<Playtime>,
+    ) -> Result<(), ApiError> {
+        let mut rollup: HashMap<
+            (DateTime<Utc>, i64, i64, String, String, i64),
+            i64,
+        > = HashMap::new();
+        for playtime in playtimes {
+            *rollup
+                .entry((
+                    bucket(playtime.recorded),
+                    playtime.project_id as i64,
+                    playtime.version_id as i64,
+                    playtime.loader,
+                    playtime.game_version,
+                    playtime.parent as i64,
+                ))
+                .or_default() += playtime.seconds as i64;
+        }
+        if rollup.is_empty() {
+            return Ok(());
+        }
+
+        let mut buckets = Vec::new();
+        let mut projects = Vec::new();
+        let mut versions = Vec::new();
+        let mut loaders = Vec::new();
+        let mut game_versions = Vec::new();
+        let mut parents = Vec::new();
+        let mut seconds = Vec::new();
+        for ((bucket, project, version, loader, game_version, parent), total) in
+            rollup
+        {
+            buckets.push(bucket);
+            projects.push(project);
+            versions.push(version);
+            loaders.push(loader);
+            game_versions.push(game_version);
+            parents.push(parent);
+            seconds.push(total);
+        }
+
+        sqlx::query!(
+            "
+            INSERT INTO analytics_playtime (bucket, project_id, version_id, loader, game_version, parent, seconds)
+            SELECT * FROM UNNEST($1::timestamptz[], $2::bigint[], $3::bigint[], $4::varchar[], $5::varchar[], $6::bigint[], $7::bigint[])
+            ON CONFLICT (project_id, bucket, version_id, loader, game_version, parent)
+            DO UPDATE SET seconds = analytics_playtime.seconds + EXCLUDED.seconds
+            ",
+            &buckets[..],
+            &projects[..],
+            &versions[..],
+            &loaders[..],
+            &game_versions[..],
+            &parents[..],
+            &seconds[..]
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_suspicious_downloads(
+        &self,
+        downloads: Vec<SuspiciousDownload>,
+    ) -> Result<(), ApiError> {
+        if downloads.is_empty() {
+            return Ok(());
+        }
+
+        let mut transaction = self.pool.begin().await?;
+        for download in downloads {
+            sqlx::query!(
+                "
+                INSERT INTO analytics_download_fraud (
+                    recorded, user_id, project_id, version_id, ip, asn,
+                    user_agent, attempts, score, reasons
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                ",
+                DateTime::from_timestamp_millis(download.recorded / 10)
+                    .unwrap_or_default(),
+                download.user_id as i64,
+                download.project_id as i64,
+                download.version_id as i64,
+                download.ip.to_string(),
+                download.asn as i64,
+                download.user_agent,
+                download.attempts as i32,
+                download.score as i32,
+                &download.reasons[..]
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    async fn fetch_playtimes(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        resolution_minutes: u32,
+    ) -> Result<Vec<ReturnIntervals>, ApiError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                (FLOOR(EXTRACT(EPOCH FROM bucket) / $1) * $1)::bigint AS "time!",
+                project_id,
+                SUM(seconds)::bigint AS "total!"
+            FROM analytics_playtime
+            WHERE bucket >= date_trunc('hour', $2::timestamptz) AND bucket <= $3
+            AND project_id = ANY($4)
+            GROUP BY 1, project_id
+            "#,
+            resolution_seconds(resolution_minutes) as f64,
+            start_date,
+            end_date,
+            &project_ids(&projects)[..]
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ReturnIntervals {
+                time: row.time as u32,
+                id: row.project_id as u64,
+                total: row.total as u64,
+            })
+            .collect())
+    }
+
+    async fn fetch_views(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        resolution_minutes: u32,
+    ) -> Result<Vec<ReturnIntervals>, ApiError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                (FLOOR(EXTRACT(EPOCH FROM bucket) / $1) * $1)::bigint AS "time!",
+                project_id,
+                SUM(views)::bigint AS "total!"
+            FROM analytics_views
+            WHERE bucket >= date_trunc('hour', $2::timestamptz) AND bucket <= $3
+            AND project_id = ANY($4)
+            GROUP BY 1, project_id
+            "#,
+            resolution_seconds(resolution_minutes) as f64,
+            start_date,
+            end_date,
+            &project_ids(&projects)[..]
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ReturnIntervals {
+                time: row.time as u32,
+                id: row.project_id as u64,
+                total: row.total as u64,
+            })
+            .collect())
+    }
+
+    async fn fetch_downloads(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        resolution_minutes: u32,
+    ) -> Result<Vec<ReturnIntervals>, ApiError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                (FLOOR(EXTRACT(EPOCH FROM bucket) / $1) * $1)::bigint AS "time!",
+                project_id,
+                SUM(downloads)::bigint AS "total!"
+            FROM analytics_downloads
+            WHERE bucket >= date_trunc('hour', $2::timestamptz) AND bucket <= $3
+            AND project_id = ANY($4)
+            GROUP BY 1, project_id
+            "#,
+            resolution_seconds(resolution_minutes) as f64,
+            start_date,
+            end_date,
+            &project_ids(&projects)[..]
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ReturnIntervals {
+                time: row.time as u32,
+                id: row.project_id as u64,
+                total: row.total as u64,
+            })
+            .collect())
+    }
+
+    async fn fetch_countries_downloads(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<ReturnCountry>, ApiError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT country, project_id, SUM(downloads)::bigint AS "total!"
+            FROM analytics_downloads
+            WHERE bucket >= date_trunc('hour', $1::timestamptz) AND bucket <= $2
+            AND project_id = ANY($3)
+            GROUP BY country, project_id
+            "#,
+            start_date,
+            end_date,
+            &project_ids(&projects)[..]
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ReturnCountry {
+                country: row.country,
+                id: row.project_id as u64,
+                total: row.total as u64,
+            })
+            .collect())
+    }
+
+    async fn fetch_countries_views(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<ReturnCountry>, ApiError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT country, project_id, SUM(views)::bigint AS "total!"
+            FROM analytics_views
+            WHERE bucket >= date_trunc('hour', $1::timestamptz) AND bucket <= $2
+            AND project_id = ANY($3)
+            GROUP BY country, project_id
+            "#,
+            start_date,
+            end_date,
+            &project_ids(&projects)[..]
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ReturnCountry {
+                country: row.country,
+                id: row.project_id as u64,
+                total: row.total as u64,
+            })
+            .collect())
+    }
+
+    async fn fetch_breakdown(
+        &self,
+        breakdown: AnalyticsBreakdown,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        resolution_minutes: u32,
+    ) -> Result<Vec<ReturnBreakdown>, ApiError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                (FLOOR(EXTRACT(EPOCH FROM bucket) / $1) * $1)::bigint AS "time!",
+                project_id,
+                key,
+                SUM(total)::bigint AS "total!"
+            FROM analytics_breakdowns
+            WHERE bucket >= date_trunc('hour', $2::timestamptz) AND bucket <= $3
+            AND project_id = ANY($4)
+            AND breakdown = $5
+            GROUP BY 1, project_id, key
+            "#,
+            resolution_seconds(resolution_minutes) as f64,
+            start_date,
+            end_date,
+            &project_ids(&projects)[..],
+            breakdown.as_str()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ReturnBreakdown {
+                time: row.time as u32,
+                id: row.project_id as u64,
+                key: row.key,
+                total: row.total as u64,
+            })
+            .collect())
+    }
+
+    async fn fetch_download_fraud_projects(
+        &self,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<ReturnDownloadFraud>, ApiError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                f.project_id,
+                COUNT(*) AS "suspicious!",
+                COUNT(DISTINCT f.ip) AS "unique_ips!",
+                COUNT(DISTINCT f.user_id) AS "unique_users!",
+                MAX(f.score) AS "max_score!",
+                ARRAY(
+                    SELECT DISTINCT r
+                    FROM analytics_download_fraud f2, UNNEST(f2.reasons) r
+                    WHERE f2.project_id = f.project_id
+                    AND f2.recorded BETWEEN $1 AND $2
+                ) AS "reasons!",
+                MAX(f.recorded) AS "last_seen!"
+            FROM analytics_download_fraud f
+            WHERE f.recorded BETWEEN $1 AND $2
+            GROUP BY f.project_id
+            ORDER BY 2 DESC
+            LIMIT $3
+            "#,
+            start_date,
+            end_date,
+            limit as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ReturnDownloadFraud {
+                id: row.project_id as u64,
+                suspicious: row.suspicious as u64,
+                unique_ips: row.unique_ips as u64,
+                unique_users: row.unique_users as u64,
+                max_score: row.max_score as u32,
+                reasons: row.reasons,
+                last_seen: row.last_seen.timestamp() as u32,
+            })
+            .collect())
+    }
+
+    async fn fetch_downloads_total(
+        &self,
+        projects: Vec<ProjectId>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<ReturnTotal>, ApiError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT project_id, SUM(downloads)::bigint AS "total!"
+            FROM analytics_downloads
+            WHERE bucket >= date_trunc('hour', $1::timestamptz) AND bucket <= $2
+            AND project_id = ANY($3)
+            GROUP BY project_id
+            "#,
+            start_date,
+            end_date,
+            &project_ids(&projects)[..]
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ReturnTotal {
+                id: row.project_id as u64,
+                total: row.total as u64,
+            })
+            .collect())
+    }
+}
@@ -1,13 +1,17 @@
+use crate::auth::AuthenticationError;
 use crate::database;
 use crate::database::models::Collection;
 use crate::database::models::organization_item::Organization as DBOrganization;
 use crate::database::models::project_item::QueryProject;
 use crate::database::models::team_item::TeamMember as DBTeamMember;
+use crate::database::models::user_purchase_item::UserPurchase;
 use crate::database::models::version_item::QueryVersion;
 use crate::database::redis::RedisPool;
 use crate::database::{Project, Version, models};
+use crate::models::threads::ThreadType;
 use crate::models::users::User;
 use crate::routes::ApiError;
+use actix_web::{HttpMessage, HttpRequest};
 use itertools::Itertools;
 use sqlx::PgPool;
 
@@ -497,3 +501,325 @@ pub async fn is_visible_organization(
 
     Ok(visible)
 }
+
+/// 个人访问令牌限定的项目和组织
+///
+/// 由 `get_user_from_headers` 在使用受限令牌时写入请求扩展，
+/// 各个修改项目、版本、团队或组织的接口通过下方的 `check_pat_*` 检查
+#[derive(Clone, Debug)]
+pub struct PatResourceRestriction {
+    pub project_ids: Vec<models::ids::ProjectId>,
+    pub organization_ids: Vec<models::ids::OrganizationId>,
+}
+
+impl PatResourceRestriction {
+    fn allows_project(
+        &self,
+        project_id: models::ids::ProjectId,
+        organization_id: Option<models::ids::OrganizationId>,
+    ) -> bool {
+        self.project_ids.contains(&project_id)
+            || organization_id
+                .is_some_and(|x| self.organization_ids.contains(&x))
+    }
+}
+
+fn pat_restriction(req: &HttpRequest) -> Option<PatResourceRestriction> {
+    req.extensions().get::<PatResourceRestriction>().cloned()
+}
+
+/// 检查受限令牌能否操作该项目
+pub fn check_pat_project(
+    req: &HttpRequest,
+    project: &Project,
+) -> Result<(), AuthenticationError> {
+    match pat_restriction(req) {
+        Some(restriction)
+            if !restriction
+                .allows_project(project.id, project.organization_id) =>
+        {
+            Err(AuthenticationError::PatResourceForbidden)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 检查受限令牌能否操作该项目，仅在令牌受限时才查询项目所属组织
+pub async fn check_pat_project_id(
+    req: &HttpRequest,
+    project_id: models::ids::ProjectId,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(), AuthenticationError> {
+    let Some(restriction) = pat_restriction(req) else {
+        return Ok(());
+    };
+    if restriction.project_ids.contains(&project_id) {
+        return Ok(());
+    }
+
+    let organization_id = if restriction.organization_ids.is_empty() {
+        None
+    } else {
+        database::models::Project::get_id(project_id, pool, redis)
+            .await?
+            .and_then(|x| x.inner.organization_id)
+    };
+
+    if restriction.allows_project(project_id, organization_id) {
+        Ok(())
+    } else {
+        Err(AuthenticationError::PatResourceForbidden)
+    }
+}
+
+/// 检查受限令牌能否操作该版本所属的项目
+pub async fn check_pat_version_id(
+    req: &HttpRequest,
+    version_id: crate::models::ids::VersionId,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(), ApiError> {
+    if pat_restriction(req).is_none() {
+        return Ok(());
+    }
+
+    let version =
+        database::models::Version::get(version_id.into(), pool, redis)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+    check_pat_project_id(req, version.inner.project_id, pool, redis).await?;
+
+    Ok(())
+}
+
+/// 检查受限令牌能否操作该组织
+pub fn check_pat_organization(
+    req: &HttpRequest,
+    organization_id: models::ids::OrganizationId,
+) -> Result<(), AuthenticationError> {
+    match pat_restriction(req) {
+        Some(restriction)
+            if !restriction.organization_ids.contains(&organization_id) =>
+        {
+            Err(AuthenticationError::PatResourceForbidden)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 检查受限令牌能否操作该团队所属的项目或组织
+pub async fn check_pat_team(
+    req: &HttpRequest,
+    team_id: models::ids::TeamId,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(), AuthenticationError> {
+    if pat_restriction(req).is_none() {
+        return Ok(());
+    }
+
+    match database::models::team_item::Team::get_association(team_id, pool)
+        .await?
+    {
+        Some(database::models::team_item::TeamAssociationId::Project(
+            project_id,
+        )) => check_pat_project_id(req, project_id, pool, redis).await,
+        Some(database::models::team_item::TeamAssociationId::Organization(
+            organization_id,
+        )) => check_pat_organization(req, organization_id),
+        None => Err(AuthenticationError::PatResourceForbidden),
+    }
+}
+
+pub async fn is_authorized_thread(
+    thread: &database::models::Thread,
+    user: &User,
+    pool: &PgPool,
+) -> Result<bool, ApiError> {
+    if user.role.is_mod() {
+        return Ok(true);
+    }
+
+    let user_id: database::models::UserId = user.id.into();
+    Ok(match thread.type_ {
+        ThreadType::Report => {
+            if let Some(report_id) = thread.report_id {
+                let report_exists = sqlx::query!(
+                    "SELECT EXISTS(SELECT 1 FROM reports WHERE id = $1 AND reporter = $2)",
+                    report_id as database::models::ids::ReportId,
+                    user_id as database::models::ids::UserId,
+                )
+                .fetch_one(pool)
+                .await?
+                .exists;
+
+                report_exists.unwrap_or(false)
+            } else {
+                false
+            }
+        }
+        ThreadType::Project => {
+            if let Some(project_id) = thread.project_id {
+                let project_exists = sqlx::query!(
+                    "SELECT EXISTS(SELECT 1 FROM mods m INNER JOIN team_members tm ON tm.team_id = m.team_id AND tm.user_id = $2 WHERE m.id = $1)",
+                    project_id as database::models::ids::ProjectId,
+                    user_id as database::models::ids::UserId,
+                )
+                    .fetch_one(pool)
+                    .await?
+                    .exists;
+
+                if !project_exists.unwrap_or(false) {
+                    let org_exists = sqlx::query!(
+                        "SELECT EXISTS(SELECT 1 FROM mods m INNER JOIN organizations o ON m.organization_id = o.id INNER JOIN team_members tm ON tm.team_id = o.team_id AND tm.user_id = $2 WHERE m.id = $1)",
+                        project_id as database::models::ids::ProjectId,
+                        user_id as database::models::ids::UserId,
+                    )
+                        .fetch_one(pool)
+                        .await?
+                        .exists;
+
+                    org_exists.unwrap_or(false)
+                } else {
+                    true
+                }
+            } else {
+                false
+            }
+        }
+        ThreadType::DirectMessage => thread.members.contains(&user_id),
+        ThreadType::VersionLink => {
+            // 获取版本链接信息
+            let link_info = sqlx::query!(
+                r#"
+                SELECT vlv.version_id, vlv.joining_version_id, v1.mod_id as translation_project_id, v2.mod_id as original_project_id
+                FROM version_link_version vlv
+                INNER JOIN versions v1 ON v1.id = vlv.version_id
+                INNER JOIN versions v2 ON v2.id = vlv.joining_version_id
+                WHERE vlv.thread_id = $1
+                "#,
+                thread.id.0
+            )
+            .fetch_optional(pool)
+            .await?;
+
+            if let Some(link) = link_info {
+                // 检查用户是否是翻译项目的成员
+                let translation_member = sqlx::query!(
+                    "SELECT EXISTS(SELECT 1 FROM mods m INNER JOIN team_members tm ON tm.team_id = m.team_id AND tm.user_id = $2 WHERE m.id = $1)",
+                    link.translation_project_id,
+                    user_id as database::models::ids::UserId,
+                )
+                .fetch_one(pool)
+                .await?
+                .exists
+                .unwrap_or(false);
+
+                if translation_member {
+                    return Ok(true);
+                }
+
+                // 检查用户是否是原项目的成员
+
+                sqlx::query!(
+                    "SELECT EXISTS(SELECT 1 FROM mods m INNER JOIN team_members tm ON tm.team_id = m.team_id AND tm.user_id = $2 WHERE m.id = $1)",
+                    link.original_project_id,
+                    user_id as database::models::ids::UserId,
+                )
+                .fetch_one(pool)
+                .await?
+                .exists
+                .unwrap_or(false)
+            } else {
+                false
+            }
+        }
+        ThreadType::BanAppeal => {
+            // 封禁申诉线程：申诉人可以访问
+            if let Some(ban_appeal_id) = thread.ban_appeal_id {
+                let appeal_exists = sqlx::query!(
+                    "SELECT EXISTS(SELECT 1 FROM user_ban_appeals WHERE id = $1 AND user_id = $2)",
+                    ban_appeal_id as database::models::ids::BanAppealId,
+                    user_id as database::models::ids::UserId,
+                )
+                .fetch_one(pool)
+                .await?
+                .exists;
+
+                appeal_exists.unwrap_or(false)
+            } else {
+                false
+            }
+        }
+        ThreadType::CreatorApplication => {
+            // 创作者申请线程：申请人可以访问
+            if let Some(creator_application_id) = thread.creator_application_id
+            {
+                let application_exists = sqlx::query!(
+                    "SELECT EXISTS(SELECT 1 FROM creator_applications WHERE id = $1 AND user_id = $2)",
+                    creator_application_id as database::models::ids::CreatorApplicationId,
+                    user_id as database::models::ids::UserId,
+                )
+                .fetch_one(pool)
+                .await?
+                .exists;
+
+                application_exists.unwrap_or(false)
+            } else {
+                false
+            }
+        }
+    })
+}
+
+/// 检查用户是否有权访问付费项目的 Wiki
+pub async fn check_wiki_paid_access(
+    user: &User,
+    project: &database::models::project_item::Project,
+    pool: &PgPool,
+) -> Result<bool, ApiError> {
+    // 管理员/版主
+    if user.role.is_admin() || user.role.is_mod() {
+        return Ok(true);
+    }
+
+    let user_id: database::models::UserId = user.id.into();
+    let project_id = project.id;
+
+    // 团队成员
+    let team_member = database::models::TeamMember::get_from_user_id_project(
+        project_id, user_id, false, pool,
+    )
+    .await
+    .map_err(ApiError::Database)?;
+    if team_member.is_some() {
+        return Ok(true);
+    }
+
+    // 组织成员
+    let organization =
+        database::models::Organization::get_associated_organization_project_id(
+            project_id, pool,
+        )
+        .await
+        .map_err(ApiError::Database)?;
+    if let Some(org) = organization {
+        let org_member =
+            database::models::TeamMember::get_from_user_id_organization(
+                org.id, user_id, false, pool,
+            )
+            .await
+            .map_err(ApiError::Database)?;
+        if org_member.is_some() {
+            return Ok(true);
+        }
+    }
+
+    // 已购买
+    let has_purchased = UserPurchase::check_access(user_id, project_id, pool)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(has_purchased)
+}
@@ -0,0 +1,239 @@
+//! 异常登录检测
+//!
+//! 创建会话时与用户的登录历史比对，识别新国家、不可能的移动速度和新设备。
+//! 出现异常时邮件提醒用户，邮件中的“这不是我”链接会撤销该用户的所有会话和令牌并
+//! 要求重置密码；高风险且未启用两步验证的登录需要先通过短信或邮件验证码确认，
+//! 两者都未绑定时直接拒绝登录。
+
+use crate::auth::AuthenticationError;
+use crate::auth::email::send_email;
+use crate::database::models::flow_item::Flow;
+use crate::database::models::login_history_item::{
+    LoginHistory, LoginHistoryBuilder,
+};
+use crate::database::models::{DatabaseError, SessionId, User, UserId};
+use crate::database::redis::RedisPool;
+use crate::routes::internal::session::{SessionMetadata, get_session_metadata};
+use actix_web::HttpRequest;
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use tracing::warn;
+
+/// 参与比对的最近登录记录数
+const HISTORY_LIMIT: i64 = 50;
+/// 超过该速度（公里/小时）视为不可能的移动
+const MAX_TRAVEL_SPEED_KMH: f64 = 900.0;
+/// 距离较近时 IP 定位误差较大，不判断移动速度
+const MIN_TRAVEL_DISTANCE_KM: f64 = 500.0;
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// 高风险登录的额外验证方式：短信验证码
+pub const STEP_UP_PHONE: &str = "phone";
+/// 未绑定手机号时改用邮件验证码
+pub const STEP_UP_EMAIL: &str = "email";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginRiskFlag {
+    NewCountry,
+    ImpossibleTravel,
+    NewDevice,
+}
+
+impl LoginRiskFlag {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoginRiskFlag::NewCountry => "new_country",
+            LoginRiskFlag::ImpossibleTravel => "impossible_travel",
+            LoginRiskFlag::NewDevice => "new_device",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            LoginRiskFlag::NewCountry => "首次在该国家或地区登录",
+            LoginRiskFlag::ImpossibleTravel => "与上次登录的地点相距过远",
+            LoginRiskFlag::NewDevice => "首次使用该设备登录",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LoginRisk {
+    pub flags: Vec<LoginRiskFlag>,
+}
+
+impl LoginRisk {
+    pub fn is_anomalous(&self) -> bool {
+        !self.flags.is_empty()
+    }
+
+    /// 高风险：不可能的移动速度，或在新的国家使用新设备
+    pub fn is_high(&self) -> bool {
+        self.flags.contains(&LoginRiskFlag::ImpossibleTravel)
+            || (self.flags.contains(&LoginRiskFlag::NewCountry)
+                && self.flags.contains(&LoginRiskFlag::NewDevice))
+    }
+}
+
+/// 设备标识：操作系统和浏览器
+pub fn device(metadata: &SessionMetadata) -> String {
+    format!(
+        "{} / {}",
+        metadata.os.as_deref().unwrap_or("未知系统"),
+        metadata.platform.as_deref().unwrap_or("未知浏览器"),
+    )
+}
+
+/// 与最近的登录记录比对，首次登录不视为异常
+pub async fn assess_login(
+    user_id: UserId,
+    metadata: &SessionMetadata,
+    exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+) -> Result<LoginRisk, DatabaseError> {
+    let history =
+        LoginHistory::get_recent(user_id, HISTORY_LIMIT, exec).await?;
+    let mut risk = LoginRisk::default();
+    if history.is_empty() {
+        return Ok(risk);
+    }
+
+    if let Some(country) = &metadata.country
+        && !history.iter().any(|x| x.country.as_ref() == Some(country))
+    {
+        risk.flags.push(LoginRiskFlag::NewCountry);
+    }
+
+    if let (Some(latitude), Some(longitude)) =
+        (metadata.latitude, metadata.longitude)
+        && let Some(last) = history.iter().find(|x| x.latitude.is_some())
+        && let (Some(last_latitude), Some(last_longitude)) =
+            (last.latitude, last.longitude)
+    {
+        let distance =
+            distance_km((latitude, longitude), (last_latitude, last_longitude));
+        // 不足一分钟按一分钟计算
+        let hours =
+            (Utc::now() - last.created).num_seconds().max(60) as f64 / 3600.0;
+        if distance >= MIN_TRAVEL_DISTANCE_KM
+            && distance / hours > MAX_TRAVEL_SPEED_KMH
+        {
+            risk.flags.push(LoginRiskFlag::ImpossibleTravel);
+        }
+    }
+
+    let device = device(metadata);
+    if !history.iter().any(|x| x.device == device) {
+        risk.flags.push(LoginRiskFlag::NewDevice);
+    }
+
+    Ok(risk)
+}
+
+/// 两点间的球面距离（公里）
+fn distance_km(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (a.0.to_radians(), a.1.to_radians());
+    let (lat2, lon2) = (b.0.to_radians(), b.1.to_radians());
+
+    let h = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+/// 未启用其他第二因素的用户在高风险登录时需要的额外验证方式，
+/// 优先使用短信，其次邮件；两者都未绑定时拒绝登录
+pub async fn step_up_method(
+    req: &HttpRequest,
+    user: &User,
+    pool: &PgPool,
+) -> Result<Option<&'static str>, AuthenticationError> {
+    let metadata = get_session_metadata(req).await?;
+    if !assess_login(user.id, &metadata, pool).await?.is_high() {
+        return Ok(None);
+    }
+
+    if user.phone_number.is_some() {
+        Ok(Some(STEP_UP_PHONE))
+    } else if user.email.is_some() {
+        Ok(Some(STEP_UP_EMAIL))
+    } else {
+        Err(AuthenticationError::LoginBlocked)
+    }
+}
+
+/// 记录登录，出现异常时邮件提醒用户
+pub async fn record_login(
+    user_id: UserId,
+    session_id: SessionId,
+    metadata: &SessionMetadata,
+    risk: &LoginRisk,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<(), AuthenticationError> {
+    LoginHistoryBuilder {
+        user_id,
+        session_id: Some(session_id),
+        ip: metadata.ip.clone(),
+        country: metadata.country.clone(),
+        city: metadata.city.clone(),
+        latitude: metadata.latitude,
+        longitude: metadata.longitude,
+        device: device(metadata),
+        risk_flags: risk.flags.iter().map(|x| x.as_str().to_string()).collect(),
+    }
+    .insert(transaction)
+    .await?;
+
+    if !risk.is_anomalous() {
+        return Ok(());
+    }
+
+    let Some(email) = User::get_id(user_id, &mut **transaction, redis)
+        .await?
+        .and_then(|x| x.email)
+    else {
+        return Ok(());
+    };
+
+    let flow = Flow::LoginAlert { user_id }
+        .insert(Duration::days(7), redis)
+        .await?;
+
+    let location = match (&metadata.city, &metadata.country) {
+        (Some(city), Some(country)) => format!("{city}, {country}"),
+        (None, Some(country)) => country.clone(),
+        _ => "未知地点".to_string(),
+    };
+    let time = Utc::now()
+        .with_timezone(&chrono::FixedOffset::east_opt(8 * 3600).unwrap())
+        .format("%Y-%m-%d %H:%M:%S");
+    let reasons = risk
+        .flags
+        .iter()
+        .map(|x| x.description())
+        .collect::<Vec<_>>()
+        .join("、");
+
+    // 提醒邮件发送失败不影响登录
+    if let Err(err) = send_email(
+        email,
+        "检测到新的登录",
+        &format!(
+            "您的 BBSMC 账号于 {time}（北京时间）在 {location} 使用 {} 登录，IP 地址为 {}。此次登录{reasons}。",
+            device(metadata),
+            metadata.ip,
+        ),
+        "如果这是您本人的操作，可以忽略此邮件。如果不是，请点击上方按钮，我们将立即退出该账号的所有登录并要求您重置密码。",
+        Some((
+            "这不是我",
+            &format!(
+                "{}/auth/login-alert?flow={}",
+                dotenvy::var("SITE_URL")?,
+                flow
+            ),
+        )),
+    ) {
+        warn!("发送登录提醒邮件失败: {}", err);
+    }
+
+    Ok(())
+}
@@ -1,8 +1,10 @@
 pub mod checks;
 pub mod email;
+pub mod login_risk;
 pub mod oauth;
 pub mod templates;
 pub mod validate;
+pub mod webauthn;
 pub use crate::auth::email::send_email;
 pub use checks::{
     check_forum_ban,
@@ -60,6 +62,12 @@ pub enum AuthenticationError {
     Url,
     #[error("您的账号已被全局封禁：{0}")]
     UserBanned(String),
+    #[error("通行密钥验证失败: {0}")]
+    Webauthn(#[from] webauthn_rs::prelude::WebauthnError),
+    #[error("该访问令牌无权操作此资源")]
+    PatResourceForbidden,
+    #[error("检测到异常登录，且账号未绑定手机号或邮箱，无法验证身份")]
+    LoginBlocked,
 }
 
 impl actix_web::ResponseError for AuthenticationError {
@@ -86,6 +94,9 @@ impl actix_web::ResponseError for AuthenticationError {
             AuthenticationError::DuplicateUser => StatusCode::BAD_REQUEST,
             AuthenticationError::SocketError => StatusCode::BAD_REQUEST,
             AuthenticationError::UserBanned(..) => StatusCode::FORBIDDEN,
+            AuthenticationError::Webauthn(..) => StatusCode::UNAUTHORIZED,
+            AuthenticationError::PatResourceForbidden => StatusCode::FORBIDDEN,
+            AuthenticationError::LoginBlocked => StatusCode::FORBIDDEN,
         }
     }
 
@@ -115,6 +126,9 @@ impl AuthenticationError {
             AuthenticationError::DuplicateUser => "duplicate_user",
             AuthenticationError::SocketError => "socket",
             AuthenticationError::UserBanned(..) => "user_banned",
+            AuthenticationError::Webauthn(..) => "webauthn_error",
+            AuthenticationError::PatResourceForbidden => "pat_forbidden",
+            AuthenticationError::LoginBlocked => "login_blocked",
         }
     }
 }
@@ -0,0 +1,359 @@
+//! 设备授权流程
+//!
+//! 供启动器、命令行工具等无法打开浏览器回调的客户端使用：客户端申请设备码，
+//! 用户在网站上输入展示的用户代码并确认授权，客户端轮询令牌端点换取令牌。
+//! 参见：IETF RFC 8628 (https://datatracker.ietf.org/doc/html/rfc8628)
+
+use super::errors::{OAuthError, OAuthErrorType};
+use super::{TokenRequest, TokenResponse, issue_id_token, issue_tokens};
+use crate::auth::get_user_from_headers;
+use crate::database::models::generate_oauth_client_authorization_id;
+use crate::database::models::oauth_client_authorization_item::OAuthClientAuthorization;
+use crate::database::models::oauth_client_item::OAuthClient as DBOAuthClient;
+use crate::database::models::oauth_device_code_item::{
+    DeviceCodeStatus, DevicePollState, OAuthDeviceCode,
+};
+use crate::database::redis::RedisPool;
+use crate::models::ids::OAuthClientId;
+use crate::models::pats::Scopes;
+use crate::queue::session::AuthQueue;
+use actix_web::http::header::CACHE_CONTROL;
+use actix_web::web::{Data, Query, ServiceConfig};
+use actix_web::{HttpRequest, HttpResponse, get, post, web};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+pub const DEVICE_CODE_GRANT_TYPE: &str =
+    "urn:ietf:params:oauth:grant-type:device_code";
+
+/// 设备码有效期（分钟）
+const DEVICE_CODE_EXPIRY_MINUTES: i64 = 15;
+/// 默认轮询间隔（秒）
+const POLL_INTERVAL: i64 = 5;
+/// 收到 slow_down 后轮询间隔增加的秒数（IETF RFC 8628 Section 3.5）
+const SLOW_DOWN_INCREMENT: i64 = 5;
+
+pub fn config(cfg: &mut ServiceConfig) {
+    cfg.service(device_authorization)
+        .service(get_device_authorization)
+        .service(accept_device_authorization)
+        .service(reject_device_authorization);
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeviceAuthorizationRequest {
+    pub client_id: Option<OAuthClientId>,
+    pub client_secret: Option<String>,
+    pub scope: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeviceAuthorizationResponse {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub verification_uri_complete: String,
+    pub expires_in: i64,
+    pub interval: i64,
+}
+
+#[post("device/code")]
+/// 为客户端创建设备码和用户代码
+/// Per IETF RFC 8628 Section 3.1 (https://datatracker.ietf.org/doc/html/rfc8628#section-3.1)
+pub async fn device_authorization(
+    req: HttpRequest,
+    req_params: web::Form<DeviceAuthorizationRequest>,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+) -> Result<HttpResponse, OAuthError> {
+    let client = super::authenticate_client(
+        &req,
+        req_params.client_id,
+        req_params.client_secret.as_deref(),
+        &pool,
+    )
+    .await?;
+
+    let requested_scopes =
+        req_params
+            .scope
+            .as_ref()
+            .map_or(Ok(client.max_scopes), |s| {
+                Scopes::parse_from_oauth_scopes(s)
+                    .map_err(OAuthErrorType::FailedScopeParse)
+            })?;
+    if !client.max_scopes.contains(requested_scopes) {
+        return Err(OAuthError::error(OAuthErrorType::ScopesTooBroad));
+    }
+
+    let device_code = OAuthDeviceCode::new(
+        client.id,
+        requested_scopes,
+        Utc::now() + Duration::minutes(DEVICE_CODE_EXPIRY_MINUTES),
+        POLL_INTERVAL,
+    );
+    device_code.upsert(&redis).await?;
+
+    let user_code = device_code.display_user_code();
+    let verification_uri = format!(
+        "{}/auth/device",
+        dotenvy::var("SITE_URL").unwrap_or_default()
+    );
+
+    Ok(HttpResponse::Ok()
+        .append_header((CACHE_CONTROL, "no-store"))
+        .json(DeviceAuthorizationResponse {
+            verification_uri_complete: format!(
+                "{verification_uri}?user_code={user_code}"
+            ),
+            device_code: device_code.device_code,
+            user_code,
+            verification_uri,
+            expires_in: DEVICE_CODE_EXPIRY_MINUTES * 60,
+            interval: device_code.interval,
+        }))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeviceCodeQuery {
+    pub user_code: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeviceAuthorizationInfo {
+    pub user_code: String,
+    pub client_id: OAuthClientId,
+    pub client_name: String,
+    pub client_icon: Option<String>,
+    pub requested_scopes: Scopes,
+}
+
+#[get("device")]
+/// 用户输入代码后，获取待确认的授权信息
+pub async fn get_device_authorization(
+    req: HttpRequest,
+    Query(query): Query<DeviceCodeQuery>,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+    session_queue: Data<AuthQueue>,
+) -> Result<HttpResponse, OAuthError> {
+    get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_AUTH_WRITE]),
+    )
+    .await?;
+
+    let device_code = get_pending_device_code(&query.user_code, &redis).await?;
+    let client = DBOAuthClient::get(device_code.client_id, &**pool)
+        .await?
+        .ok_or(OAuthErrorType::InvalidClientId(device_code.client_id))?;
+
+    Ok(HttpResponse::Ok().json(DeviceAuthorizationInfo {
+        user_code: device_code.display_user_code(),
+        client_id: client.id.into(),
+        client_name: client.name,
+        client_icon: client.icon_url,
+        requested_scopes: device_code.scopes,
+    }))
+}
+
+#[post("device/accept")]
+pub async fn accept_device_authorization(
+    req: HttpRequest,
+    body: web::Json<DeviceCodeQuery>,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+    session_queue: Data<AuthQueue>,
+) -> Result<HttpResponse, OAuthError> {
+    respond_to_device_authorization(true, req, body, pool, redis, session_queue)
+        .await
+}
+
+#[post("device/reject")]
+pub async fn reject_device_authorization(
+    req: HttpRequest,
+    body: web::Json<DeviceCodeQuery>,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+    session_queue: Data<AuthQueue>,
+) -> Result<HttpResponse, OAuthError> {
+    respond_to_device_authorization(
+        false,
+        req,
+        body,
+        pool,
+        redis,
+        session_queue,
+    )
+    .await
+}
+
+async fn respond_to_device_authorization(
+    accept: bool,
+    req: HttpRequest,
+    body: web::Json<DeviceCodeQuery>,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+    session_queue: Data<AuthQueue>,
+) -> Result<HttpResponse, OAuthError> {
+    let current_user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_AUTH_WRITE]),
+    )
+    .await?
+    .1;
+
+    let mut device_code =
+        get_pending_device_code(&body.user_code, &redis).await?;
+
+    if accept {
+        let user_id = current_user.id.into();
+        let existing_authorization = OAuthClientAuthorization::get(
+            device_code.client_id,
+            user_id,
+            &**pool,
+        )
+        .await?;
+
+        let mut transaction = pool.begin().await?;
+
+        let authorization_id = match existing_authorization {
+            Some(authorization) => authorization.id,
+            None => {
+                generate_oauth_client_authorization_id(&mut transaction).await?
+            }
+        };
+        OAuthClientAuthorization::upsert(
+            authorization_id,
+            device_code.client_id,
+            user_id,
+            device_code.scopes,
+            &mut transaction,
+        )
+        .await?;
+
+        transaction.commit().await?;
+
+        device_code.status = DeviceCodeStatus::Approved {
+            user_id,
+            authorization_id,
+        };
+    } else {
+        device_code.status = DeviceCodeStatus::Denied;
+    }
+    device_code.upsert(&redis).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn get_pending_device_code(
+    user_code: &str,
+    redis: &RedisPool,
+) -> Result<OAuthDeviceCode, OAuthError> {
+    OAuthDeviceCode::get_by_user_code(user_code, redis)
+        .await?
+        .filter(|x| x.status == DeviceCodeStatus::Pending)
+        .ok_or_else(|| OAuthError::error(OAuthErrorType::InvalidUserCode))
+}
+
+/// 客户端轮询令牌端点，用户确认授权后换取令牌
+/// Per IETF RFC 8628 Section 3.4 (https://datatracker.ietf.org/doc/html/rfc8628#section-3.4)
+pub(super) async fn exchange_device_code(
+    client: &DBOAuthClient,
+    req_params: &TokenRequest,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<TokenResponse, OAuthError> {
+    let device_code = req_params
+        .device_code
+        .as_deref()
+        .ok_or(OAuthErrorType::MissingParameter("device_code"))?;
+
+    // 设备码随过期时间从 Redis 中删除，找不到即视为已过期
+    let device_code = OAuthDeviceCode::get(device_code, redis)
+        .await?
+        .ok_or(OAuthErrorType::ExpiredToken)?;
+    if device_code.client_id != client.id {
+        return Err(OAuthError::error(OAuthErrorType::InvalidDeviceCode));
+    }
+
+    if device_code.status == DeviceCodeStatus::Pending {
+        // 轮询状态单独保存，不会覆盖用户同时写入的确认结果
+        let now = Utc::now();
+        let poll_state = device_code.get_poll_state(redis).await?;
+        let mut interval = poll_state
+            .as_ref()
+            .map_or(device_code.interval, |x| x.interval);
+        let too_fast = poll_state.is_some_and(|x| {
+            now < x.last_polled + Duration::seconds(x.interval)
+        });
+        if too_fast {
+            interval += SLOW_DOWN_INCREMENT;
+        }
+        device_code
+            .set_poll_state(
+                &DevicePollState {
+                    interval,
+                    last_polled: now,
+                },
+                redis,
+            )
+            .await?;
+
+        return Err(OAuthError::error(if too_fast {
+            OAuthErrorType::SlowDown
+        } else {
+            OAuthErrorType::AuthorizationPending
+        }));
+    }
+
+    // 设备码只能换取一次令牌，并发轮询时只有取到记录的请求继续
+    let device_code = OAuthDeviceCode::take(&device_code.device_code, redis)
+        .await?
+        .ok_or(OAuthErrorType::ExpiredToken)?;
+
+    match device_code.status {
+        DeviceCodeStatus::Pending => {
+            Err(OAuthError::error(OAuthErrorType::AuthorizationPending))
+        }
+        DeviceCodeStatus::Denied => {
+            Err(OAuthError::error(OAuthErrorType::AccessDenied))
+        }
+        DeviceCodeStatus::Approved {
+            user_id,
+            authorization_id,
+        } => {
+            let scopes = device_code.scopes - Scopes::restricted();
+
+            let mut transaction = pool.begin().await?;
+            let mut token = issue_tokens(
+                authorization_id,
+                client.id,
+                user_id,
+                scopes,
+                &mut transaction,
+            )
+            .await?;
+            token.id_token = issue_id_token(
+                client,
+                authorization_id,
+                user_id,
+                scopes,
+                None,
+                pool,
+                redis,
+            )
+            .await?;
+            transaction.commit().await?;
+
+            Ok(token)
+        }
+    }
+}
@@ -59,26 +59,44 @@ impl actix_web::ResponseError for OAuthError {
         match self.error_type {
             OAuthErrorType::AuthenticationError(_)
             | OAuthErrorType::FailedScopeParse(_)
-            | OAuthErrorType::ScopesTooBroad
-            | OAuthErrorType::AccessDenied => {
+            | OAuthErrorType::ScopesTooBroad => {
                 if self.valid_redirect_uri.is_some() {
                     StatusCode::OK
                 } else {
                     StatusCode::INTERNAL_SERVER_ERROR
                 }
             }
+            // 设备授权流程没有重定向 URI，拒绝授权通过令牌端点返回
+            OAuthErrorType::AccessDenied => {
+                if self.valid_redirect_uri.is_some() {
+                    StatusCode::OK
+                } else {
+                    StatusCode::BAD_REQUEST
+                }
+            }
             OAuthErrorType::RedirectUriNotConfigured(_)
             | OAuthErrorType::ClientMissingRedirectURI { client_id: _ }
             | OAuthErrorType::InvalidAcceptFlowId
             | OAuthErrorType::MalformedId(_)
             | OAuthErrorType::InvalidClientId(_)
             | OAuthErrorType::InvalidAuthCode
-            | OAuthErrorType::OnlySupportsAuthorizationCodeGrant(_)
+            | OAuthErrorType::UnsupportedGrantType(_)
             | OAuthErrorType::RedirectUriChanged(_)
-            | OAuthErrorType::UnauthorizedClient => StatusCode::BAD_REQUEST,
+            | OAuthErrorType::UnauthorizedClient
+            | OAuthErrorType::PkceRequired
+            | OAuthErrorType::InvalidCodeChallenge(_)
+            | OAuthErrorType::InvalidCodeVerifier
+            | OAuthErrorType::MissingParameter(_)
+            | OAuthErrorType::InvalidRefreshToken
+            | OAuthErrorType::AuthorizationPending
+            | OAuthErrorType::SlowDown
+            | OAuthErrorType::ExpiredToken
+            | OAuthErrorType::InvalidDeviceCode
+            | OAuthErrorType::InvalidUserCode => StatusCode::BAD_REQUEST,
             OAuthErrorType::ClientAuthenticationFailed => {
                 StatusCode::UNAUTHORIZED
             }
+            OAuthErrorType::SigningKey(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 
@@ -137,10 +155,34 @@ pub enum OAuthErrorType {
     UnauthorizedClient,
     #[error("提供的重定向 URI 与授权流程开始时使用的 URI 不一致")]
     RedirectUriChanged(Option<String>),
-    #[error("授权类型 ({0}) 不受支持，仅支持 \"authorization_code\"")]
-    OnlySupportsAuthorizationCodeGrant(String),
+    #[error(
+        "授权类型 ({0}) 不受支持，仅支持 \"authorization_code\"、\"refresh_token\" 和 \"urn:ietf:params:oauth:grant-type:device_code\""
+    )]
+    UnsupportedGrantType(String),
+    #[error("公开客户端必须使用 PKCE")]
+    PkceRequired,
+    #[error("PKCE 质询无效：{0}")]
+    InvalidCodeChallenge(String),
+    #[error("提供的 code_verifier 与授权时的质询不匹配")]
+    InvalidCodeVerifier,
+    #[error("缺少参数 {0}")]
+    MissingParameter(&'static str),
+    #[error("提供的刷新令牌无效、已过期或已被使用")]
+    InvalidRefreshToken,
+    #[error("签发 ID 令牌失败：{0}")]
+    SigningKey(String),
     #[error("用户拒绝了授权请求")]
     AccessDenied,
+    #[error("用户尚未完成授权")]
+    AuthorizationPending,
+    #[error("轮询过于频繁，请增大轮询间隔")]
+    SlowDown,
+    #[error("设备码已过期，请重新发起授权")]
+    ExpiredToken,
+    #[error("提供的设备码无效")]
+    InvalidDeviceCode,
+    #[error("提供的用户代码无效或已过期")]
+    InvalidUserCode,
 }
 
 impl From<crate::database::models::DatabaseError> for OAuthErrorType {
@@ -162,20 +204,30 @@ impl OAuthErrorType {
         match self {
             Self::RedirectUriNotConfigured(_)
             | Self::ClientMissingRedirectURI { client_id: _ } => "invalid_uri",
-            Self::AuthenticationError(_) | Self::InvalidAcceptFlowId => {
-                "server_error"
-            }
-            Self::RedirectUriChanged(_) | Self::MalformedId(_) => {
-                "invalid_request"
-            }
+            Self::AuthenticationError(_)
+            | Self::InvalidAcceptFlowId
+            | Self::SigningKey(_) => "server_error",
+            Self::RedirectUriChanged(_)
+            | Self::MalformedId(_)
+            | Self::PkceRequired
+            | Self::InvalidCodeChallenge(_)
+            | Self::MissingParameter(_)
+            | Self::InvalidUserCode => "invalid_request",
             Self::FailedScopeParse(_) | Self::ScopesTooBroad => "invalid_scope",
             Self::InvalidClientId(_) | Self::ClientAuthenticationFailed => {
                 "invalid_client"
             }
             Self::InvalidAuthCode
-            | Self::OnlySupportsAuthorizationCodeGrant(_) => "invalid_grant",
+            | Self::InvalidCodeVerifier
+            | Self::InvalidRefreshToken
+            | Self::InvalidDeviceCode => "invalid_grant",
+            Self::UnsupportedGrantType(_) => "unsupported_grant_type",
             Self::UnauthorizedClient => "unauthorized_client",
             Self::AccessDenied => "access_denied",
+            // IETF RFC 8628 Section 3.5 (https://datatracker.ietf.org/doc/html/rfc8628#section-3.5)
+            Self::AuthorizationPending => "authorization_pending",
+            Self::SlowDown => "slow_down",
+            Self::ExpiredToken => "expired_token",
         }
         .to_string()
     }
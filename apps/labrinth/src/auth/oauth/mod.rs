@@ -4,38 +4,54 @@ use crate::auth::validate::extract_authorization_header;
 use crate::database::models::flow_item::Flow;
 use crate::database::models::oauth_client_authorization_item::OAuthClientAuthorization;
 use crate::database::models::oauth_client_item::OAuthClient as DBOAuthClient;
-use crate::database::models::oauth_token_item::OAuthAccessToken;
+use crate::database::models::oauth_token_item::{
+    OAuthAccessToken, OAuthRefreshToken,
+};
 use crate::database::models::{
     OAuthClientAuthorizationId, generate_oauth_access_token_id,
-    generate_oauth_client_authorization_id,
+    generate_oauth_client_authorization_id, generate_oauth_refresh_token_id,
 };
 use crate::database::redis::RedisPool;
 use crate::models;
-use crate::models::ids::OAuthClientId;
+use crate::models::ids::base62_impl::parse_base62;
+use crate::models::ids::{OAuthClientId, UserId};
 use crate::models::pats::Scopes;
 use crate::queue::session::AuthQueue;
-use actix_web::http::header::{CACHE_CONTROL, LOCATION, PRAGMA};
+use crate::util::guards::ADMIN_KEY_HEADER;
+use actix_web::http::header::{AUTHORIZATION, CACHE_CONTROL, LOCATION, PRAGMA};
 use actix_web::web::{Data, Query, ServiceConfig};
 use actix_web::{HttpRequest, HttpResponse, get, post, web};
-use chrono::Duration;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{Duration, Utc};
+use itertools::Itertools;
 use rand::distributions::Alphanumeric;
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha20Rng;
 use serde::{Deserialize, Serialize};
 use sqlx::postgres::PgPool;
+use subtle::ConstantTimeEq;
 
 use self::errors::{OAuthError, OAuthErrorType};
+use self::pkce::CodeChallenge;
 
 use super::AuthenticationError;
 
+pub mod device;
 pub mod errors;
+pub mod oidc;
+pub mod pkce;
 pub mod uris;
 
 pub fn config(cfg: &mut ServiceConfig) {
     cfg.service(init_oauth)
         .service(accept_client_scopes)
         .service(reject_client_scopes)
-        .service(request_token);
+        .service(request_token)
+        .service(revoke_token)
+        .service(introspect_token)
+        .configure(device::config)
+        .configure(oidc::config);
 }
 
 #[derive(Serialize, Deserialize)]
@@ -44,6 +60,10 @@ pub struct OAuthInit {
     pub redirect_uri: Option<String>,
     pub scope: Option<String>,
     pub state: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    // OpenID Connect Core 1.0 Section 3.1.2.1 (https://openid.net/specs/openid-connect-core-1_0.html#AuthRequest)
+    pub nonce: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -105,6 +125,15 @@ pub async fn init_oauth(
             ));
         }
 
+        let code_challenge = CodeChallenge::parse(
+            oauth_info.code_challenge.as_deref(),
+            oauth_info.code_challenge_method.as_deref(),
+            client.is_public,
+        )
+        .map_err(|e| {
+            OAuthError::redirect(e, &oauth_info.state, &redirect_uri)
+        })?;
+
         let existing_authorization =
             OAuthClientAuthorization::get(client.id, user.id.into(), &**pool)
                 .await
@@ -126,6 +155,8 @@ pub async fn init_oauth(
                     requested_scopes,
                     redirect_uris,
                     oauth_info.state,
+                    code_challenge,
+                    oauth_info.nonce,
                     &redis,
                 )
                 .await
@@ -139,6 +170,8 @@ pub async fn init_oauth(
                     scopes: requested_scopes,
                     redirect_uris,
                     state: oauth_info.state.clone(),
+                    code_challenge,
+                    nonce: oauth_info.nonce.clone(),
                 }
                 .insert(Duration::minutes(30), &redis)
                 .await
@@ -202,9 +235,16 @@ pub async fn reject_client_scopes(
 #[derive(Serialize, Deserialize)]
 pub struct TokenRequest {
     pub grant_type: String,
-    pub code: String,
+    pub code: Option<String>,
     pub redirect_uri: Option<String>,
-    pub client_id: models::ids::OAuthClientId,
+    pub client_id: Option<models::ids::OAuthClientId>,
+    pub client_secret: Option<String>,
+    // IETF RFC 7636 Section 4.5 (https://datatracker.ietf.org/doc/html/rfc7636#section-4.5)
+    pub code_verifier: Option<String>,
+    // IETF RFC 6749 Section 6 (https://datatracker.ietf.org/doc/html/rfc6749#section-6)
+    pub refresh_token: Option<String>,
+    // IETF RFC 8628 Section 3.4 (https://datatracker.ietf.org/doc/html/rfc8628#section-3.4)
+    pub device_code: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -212,102 +252,478 @@ pub struct TokenResponse {
     pub access_token: String,
     pub token_type: String,
     pub expires_in: i64,
+    pub refresh_token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
 }
 
 #[post("token")]
 /// Params should be in the urlencoded request body
 /// And client secret should be in the HTTP basic authorization header
 /// Per IETF RFC6749 Section 4.1.3 (https://datatracker.ietf.org/doc/html/rfc6749#section-4.1.3)
+/// Public clients omit the secret and prove possession with PKCE instead
 pub async fn request_token(
     req: HttpRequest,
     req_params: web::Form<TokenRequest>,
     pool: Data<PgPool>,
     redis: Data<RedisPool>,
 ) -> Result<HttpResponse, OAuthError> {
-    let req_client_id = req_params.client_id;
-    let client = DBOAuthClient::get(req_client_id.into(), &**pool).await?;
-    if let Some(client) = client {
-        authenticate_client_token_request(&req, &client)?;
-
-        // Ensure auth code is single use
-        // per IETF RFC6749 Section 10.5 (https://datatracker.ietf.org/doc/html/rfc6749#section-10.5)
-        let flow = Flow::take_if(
-            &req_params.code,
-            |f| matches!(f, Flow::OAuthAuthorizationCodeSupplied { .. }),
-            &redis,
-        )
-        .await?;
-        if let Some(Flow::OAuthAuthorizationCodeSupplied {
-            user_id,
-            client_id,
-            authorization_id,
-            scopes,
-            original_redirect_uri,
-        }) = flow
-        {
-            // https://datatracker.ietf.org/doc/html/rfc6749#section-4.1.3
-            if req_client_id != client_id.into() {
-                return Err(OAuthError::error(
-                    OAuthErrorType::UnauthorizedClient,
-                ));
-            }
+    let client = authenticate_client(
+        &req,
+        req_params.client_id,
+        req_params.client_secret.as_deref(),
+        &pool,
+    )
+    .await?;
 
-            if original_redirect_uri != req_params.redirect_uri {
-                return Err(OAuthError::error(
-                    OAuthErrorType::RedirectUriChanged(
-                        req_params.redirect_uri.clone(),
-                    ),
-                ));
-            }
+    let token = match req_params.grant_type.as_str() {
+        "authorization_code" => {
+            exchange_authorization_code(&client, &req_params, &pool, &redis)
+                .await?
+        }
+        "refresh_token" => {
+            exchange_refresh_token(&client, &req_params, &pool, &redis).await?
+        }
+        device::DEVICE_CODE_GRANT_TYPE => {
+            device::exchange_device_code(&client, &req_params, &pool, &redis)
+                .await?
+        }
+        grant_type => {
+            return Err(OAuthError::error(
+                OAuthErrorType::UnsupportedGrantType(grant_type.to_string()),
+            ));
+        }
+    };
+
+    // IETF RFC6749 Section 5.1 (https://datatracker.ietf.org/doc/html/rfc6749#section-5.1)
+    Ok(HttpResponse::Ok()
+        .append_header((CACHE_CONTROL, "no-store"))
+        .append_header((PRAGMA, "no-cache"))
+        .json(token))
+}
 
-            if req_params.grant_type != "authorization_code" {
+async fn exchange_authorization_code(
+    client: &DBOAuthClient,
+    req_params: &TokenRequest,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<TokenResponse, OAuthError> {
+    let code = req_params
+        .code
+        .as_deref()
+        .ok_or(OAuthErrorType::MissingParameter("code"))?;
+
+    // Ensure auth code is single use
+    // per IETF RFC6749 Section 10.5 (https://datatracker.ietf.org/doc/html/rfc6749#section-10.5)
+    let flow = Flow::take_if(
+        code,
+        |f| matches!(f, Flow::OAuthAuthorizationCodeSupplied { .. }),
+        redis,
+    )
+    .await?;
+    let Some(Flow::OAuthAuthorizationCodeSupplied {
+        user_id,
+        client_id,
+        authorization_id,
+        scopes,
+        original_redirect_uri,
+        code_challenge,
+        nonce,
+    }) = flow
+    else {
+        return Err(OAuthError::error(OAuthErrorType::InvalidAuthCode));
+    };
+
+    // https://datatracker.ietf.org/doc/html/rfc6749#section-4.1.3
+    if client.id != client_id {
+        return Err(OAuthError::error(OAuthErrorType::UnauthorizedClient));
+    }
+
+    if original_redirect_uri != req_params.redirect_uri {
+        return Err(OAuthError::error(OAuthErrorType::RedirectUriChanged(
+            req_params.redirect_uri.clone(),
+        )));
+    }
+
+    // IETF RFC 7636 Section 4.6 (https://datatracker.ietf.org/doc/html/rfc7636#section-4.6)
+    match (code_challenge, req_params.code_verifier.as_deref()) {
+        (Some(challenge), Some(verifier)) => {
+            if !challenge.verify(verifier) {
                 return Err(OAuthError::error(
-                    OAuthErrorType::OnlySupportsAuthorizationCodeGrant(
-                        req_params.grant_type.clone(),
-                    ),
+                    OAuthErrorType::InvalidCodeVerifier,
                 ));
             }
+        }
+        (Some(_), None) => {
+            return Err(OAuthError::error(OAuthErrorType::MissingParameter(
+                "code_verifier",
+            )));
+        }
+        (None, Some(_)) => {
+            return Err(OAuthError::error(OAuthErrorType::InvalidCodeVerifier));
+        }
+        (None, None) if client.is_public => {
+            return Err(OAuthError::error(OAuthErrorType::PkceRequired));
+        }
+        (None, None) => {}
+    }
 
-            let scopes = scopes - Scopes::restricted();
+    let scopes = scopes - Scopes::restricted();
 
-            let mut transaction = pool.begin().await?;
-            let token_id =
-                generate_oauth_access_token_id(&mut transaction).await?;
-            let token = generate_access_token();
-            let token_hash = OAuthAccessToken::hash_token(&token);
-            let time_until_expiration = OAuthAccessToken {
-                id: token_id,
-                authorization_id,
-                token_hash,
-                scopes,
-                created: Default::default(),
-                expires: Default::default(),
-                last_used: None,
-                client_id,
-                user_id,
-            }
-            .insert(&mut *transaction)
-            .await?;
+    let mut transaction = pool.begin().await?;
+    let mut token = issue_tokens(
+        authorization_id,
+        client_id,
+        user_id,
+        scopes,
+        &mut transaction,
+    )
+    .await?;
+    token.id_token = issue_id_token(
+        client,
+        authorization_id,
+        user_id,
+        scopes,
+        nonce,
+        pool,
+        redis,
+    )
+    .await?;
+    transaction.commit().await?;
 
-            transaction.commit().await?;
+    Ok(token)
+}
 
-            // IETF RFC6749 Section 5.1 (https://datatracker.ietf.org/doc/html/rfc6749#section-5.1)
-            Ok(HttpResponse::Ok()
-                .append_header((CACHE_CONTROL, "no-store"))
-                .append_header((PRAGMA, "no-cache"))
-                .json(TokenResponse {
-                    access_token: token,
-                    token_type: "Bearer".to_string(),
-                    expires_in: time_until_expiration.num_seconds(),
-                }))
-        } else {
-            Err(OAuthError::error(OAuthErrorType::InvalidAuthCode))
+/// 使用刷新令牌换取新的令牌对，旧的刷新令牌和访问令牌随之失效
+///
+/// 已轮换的刷新令牌被再次使用时，说明令牌可能已经泄露，
+/// 此时撤销该授权下的所有令牌
+/// 参见：IETF RFC 6819 5.2.2.3 (https://datatracker.ietf.org/doc/html/rfc6819#section-5.2.2.3)
+async fn exchange_refresh_token(
+    client: &DBOAuthClient,
+    req_params: &TokenRequest,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<TokenResponse, OAuthError> {
+    let refresh_token = req_params
+        .refresh_token
+        .as_deref()
+        .ok_or(OAuthErrorType::MissingParameter("refresh_token"))?;
+
+    let token_hash = OAuthAccessToken::hash_token(refresh_token);
+    let token = OAuthRefreshToken::get(token_hash, pool)
+        .await?
+        .filter(|x| x.client_id == client.id)
+        .ok_or(OAuthErrorType::InvalidRefreshToken)?;
+
+    if token.expires < Utc::now() {
+        return Err(OAuthError::error(OAuthErrorType::InvalidRefreshToken));
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    if token.rotated.is_some()
+        || !OAuthRefreshToken::mark_rotated(token.id, &mut *transaction).await?
+    {
+        OAuthAccessToken::remove_all_for_authorization(
+            token.authorization_id,
+            &mut transaction,
+        )
+        .await?;
+        transaction.commit().await?;
+
+        return Err(OAuthError::error(OAuthErrorType::InvalidRefreshToken));
+    }
+
+    if let Some(access_token_id) = token.access_token_id {
+        OAuthAccessToken::remove(access_token_id, &mut *transaction).await?;
+    }
+
+    let scopes = token.scopes - Scopes::restricted();
+    let mut new_token = issue_tokens(
+        token.authorization_id,
+        token.client_id,
+        token.user_id,
+        scopes,
+        &mut transaction,
+    )
+    .await?;
+    new_token.id_token = issue_id_token(
+        client,
+        token.authorization_id,
+        token.user_id,
+        scopes,
+        None,
+        pool,
+        redis,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(new_token)
+}
+
+async fn issue_tokens(
+    authorization_id: OAuthClientAuthorizationId,
+    client_id: crate::database::models::OAuthClientId,
+    user_id: crate::database::models::UserId,
+    scopes: Scopes,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<TokenResponse, OAuthError> {
+    let token_id = generate_oauth_access_token_id(transaction).await?;
+    let token = generate_token("mro");
+    let time_until_expiration = OAuthAccessToken {
+        id: token_id,
+        authorization_id,
+        token_hash: OAuthAccessToken::hash_token(&token),
+        scopes,
+        created: Default::default(),
+        expires: Default::default(),
+        last_used: None,
+        client_id,
+        user_id,
+    }
+    .insert(&mut **transaction)
+    .await?;
+
+    let refresh_token_id = generate_oauth_refresh_token_id(transaction).await?;
+    let refresh_token = generate_token("mrr");
+    OAuthRefreshToken {
+        id: refresh_token_id,
+        authorization_id,
+        access_token_id: Some(token_id),
+        token_hash: OAuthAccessToken::hash_token(&refresh_token),
+        scopes,
+        created: Default::default(),
+        expires: Default::default(),
+        rotated: None,
+        client_id,
+        user_id,
+    }
+    .insert(&mut **transaction)
+    .await?;
+
+    Ok(TokenResponse {
+        access_token: token,
+        token_type: "Bearer".to_string(),
+        expires_in: time_until_expiration.num_seconds(),
+        refresh_token,
+        id_token: None,
+    })
+}
+
+/// 授权范围包含 openid 时签发 ID 令牌
+async fn issue_id_token(
+    client: &DBOAuthClient,
+    authorization_id: OAuthClientAuthorizationId,
+    user_id: crate::database::models::UserId,
+    scopes: Scopes,
+    nonce: Option<String>,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<Option<String>, OAuthError> {
+    if !scopes.contains(Scopes::OPENID) {
+        return Ok(None);
+    }
+
+    oidc::generate_id_token(
+        client,
+        authorization_id,
+        user_id,
+        scopes,
+        nonce,
+        pool,
+        redis,
+    )
+    .await
+    .map(Some)
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TokenRevocationRequest {
+    pub token: String,
+    pub client_id: Option<models::ids::OAuthClientId>,
+    pub client_secret: Option<String>,
+}
+
+#[post("revoke")]
+/// Revokes an access token or refresh token issued to the client
+/// Per IETF RFC7009 (https://datatracker.ietf.org/doc/html/rfc7009)
+pub async fn revoke_token(
+    req: HttpRequest,
+    req_params: web::Form<TokenRevocationRequest>,
+    pool: Data<PgPool>,
+) -> Result<HttpResponse, OAuthError> {
+    let client = authenticate_client(
+        &req,
+        req_params.client_id,
+        req_params.client_secret.as_deref(),
+        &pool,
+    )
+    .await?;
+
+    let token_hash = OAuthAccessToken::hash_token(&req_params.token);
+    let mut transaction = pool.begin().await?;
+
+    // 无效的令牌同样返回 200（IETF RFC 7009 Section 2.2）
+    match req_params.token.split_once('_') {
+        Some(("mrr", _)) => {
+            if let Some(token) =
+                OAuthRefreshToken::get(token_hash, &mut *transaction)
+                    .await?
+                    .filter(|x| x.client_id == client.id)
+            {
+                if let Some(access_token_id) = token.access_token_id {
+                    OAuthAccessToken::remove(
+                        access_token_id,
+                        &mut *transaction,
+                    )
+                    .await?;
+                }
+                OAuthRefreshToken::remove(token.id, &mut *transaction).await?;
+            }
+        }
+        Some(("mro", _)) => {
+            if let Some(token) =
+                OAuthAccessToken::get(token_hash, &mut *transaction)
+                    .await?
+                    .filter(|x| x.client_id == client.id)
+            {
+                OAuthAccessToken::remove(token.id, &mut *transaction).await?;
+            }
         }
+        _ => {}
+    }
+
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TokenIntrospectionRequest {
+    pub token: String,
+    pub client_id: Option<models::ids::OAuthClientId>,
+    pub client_secret: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct TokenIntrospectionResponse {
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<models::ids::OAuthClientId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<UserId>,
+}
+
+#[post("introspect")]
+/// Returns the state of an access token or refresh token
+/// Callers are either our own services (authenticated with the admin key),
+/// or confidential clients inspecting tokens issued to themselves
+/// Per IETF RFC7662 (https://datatracker.ietf.org/doc/html/rfc7662)
+pub async fn introspect_token(
+    req: HttpRequest,
+    req_params: web::Form<TokenIntrospectionRequest>,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+) -> Result<HttpResponse, OAuthError> {
+    let is_internal = req.headers().get(ADMIN_KEY_HEADER).is_some_and(|it| {
+        dotenvy::var("LABRINTH_ADMIN_KEY")
+            .is_ok_and(|key| it.as_bytes().ct_eq(key.as_bytes()).into())
+    });
+
+    let requesting_client = if is_internal {
+        None
     } else {
-        Err(OAuthError::error(OAuthErrorType::InvalidClientId(
-            req_client_id.into(),
-        )))
+        let client = authenticate_client(
+            &req,
+            req_params.client_id,
+            req_params.client_secret.as_deref(),
+            &pool,
+        )
+        .await?;
+
+        // 公开客户端无法证明身份，不能查询令牌
+        if client.is_public {
+            return Err(OAuthError::error(
+                OAuthErrorType::ClientAuthenticationFailed,
+            ));
+        }
+
+        Some(client.id)
+    };
+
+    let token_hash = OAuthAccessToken::hash_token(&req_params.token);
+    let token = match req_params.token.split_once('_') {
+        Some(("mro", _)) => {
+            OAuthAccessToken::get(token_hash, &**pool).await?.map(|x| {
+                (
+                    "access_token",
+                    x.client_id,
+                    x.user_id,
+                    x.scopes,
+                    x.created,
+                    x.expires,
+                )
+            })
+        }
+        Some(("mrr", _)) => OAuthRefreshToken::get(token_hash, &**pool)
+            .await?
+            .filter(|x| x.rotated.is_none())
+            .map(|x| {
+                (
+                    "refresh_token",
+                    x.client_id,
+                    x.user_id,
+                    x.scopes,
+                    x.created,
+                    x.expires,
+                )
+            }),
+        _ => None,
+    };
+
+    let mut response = TokenIntrospectionResponse::default();
+
+    if let Some((token_type, client_id, user_id, scopes, created, expires)) =
+        token.filter(|(_, client_id, _, _, _, expires)| {
+            *expires > Utc::now()
+                && requesting_client.is_none_or(|x| x == *client_id)
+        })
+    {
+        let user =
+            crate::database::models::User::get_id(user_id, &**pool, &redis)
+                .await?;
+
+        if let Some(user) = user {
+            response = TokenIntrospectionResponse {
+                active: true,
+                scope: Some(
+                    scopes.iter_names().map(|(name, _)| name).join(" "),
+                ),
+                client_id: Some(client_id.into()),
+                username: Some(user.username),
+                token_type: Some(token_type.to_string()),
+                exp: Some(expires.timestamp()),
+                iat: Some(created.timestamp()),
+                sub: Some(user.id.into()),
+            };
+        }
     }
+
+    Ok(HttpResponse::Ok()
+        .append_header((CACHE_CONTROL, "no-store"))
+        .json(response))
 }
 
 pub async fn accept_or_reject_client_scopes(
@@ -341,6 +757,8 @@ pub async fn accept_or_reject_client_scopes(
         scopes,
         redirect_uris,
         state,
+        code_challenge,
+        nonce,
     }) = flow
     {
         if current_user.id != user_id.into() {
@@ -377,6 +795,8 @@ pub async fn accept_or_reject_client_scopes(
                 scopes,
                 redirect_uris,
                 state,
+                code_challenge,
+                nonce,
                 &redis,
             )
             .await
@@ -392,30 +812,101 @@ pub async fn accept_or_reject_client_scopes(
     }
 }
 
-fn authenticate_client_token_request(
+/// 认证令牌端点的客户端，支持以下方式：
+/// - client_secret_basic：`Authorization: Basic base64(client_id:client_secret)`
+/// - client_secret_post：请求体中的 client_id 和 client_secret
+/// - 旧方式：Authorization 头中直接放置客户端密钥
+///
+/// Per IETF RFC6749 Section 2.3.1 (https://datatracker.ietf.org/doc/html/rfc6749#section-2.3.1)
+async fn authenticate_client(
     req: &HttpRequest,
-    client: &DBOAuthClient,
-) -> Result<(), OAuthError> {
-    let client_secret = extract_authorization_header(req)?;
-    let hashed_client_secret = DBOAuthClient::hash_secret(client_secret);
-    if client.secret_hash != hashed_client_secret {
-        Err(OAuthError::error(
+    client_id: Option<OAuthClientId>,
+    client_secret: Option<&str>,
+    pool: &PgPool,
+) -> Result<DBOAuthClient, OAuthError> {
+    let basic_credentials = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.strip_prefix("Basic "))
+        .map(parse_basic_client_credentials)
+        .transpose()?;
+
+    let (client_id, client_secret) = match basic_credentials {
+        Some((basic_id, basic_secret)) => {
+            if client_id.is_some_and(|id| id != basic_id) {
+                return Err(OAuthError::error(
+                    OAuthErrorType::ClientAuthenticationFailed,
+                ));
+            }
+            (basic_id, Some(basic_secret))
+        }
+        None => {
+            let client_id = client_id
+                .ok_or(OAuthErrorType::MissingParameter("client_id"))?;
+            let client_secret = match client_secret {
+                Some(secret) => Some(secret.to_string()),
+                None => extract_authorization_header(req)
+                    .ok()
+                    .map(|x| x.to_string()),
+            };
+            (client_id, client_secret)
+        }
+    };
+
+    let client = DBOAuthClient::get(client_id.into(), pool)
+        .await?
+        .ok_or_else(|| OAuthErrorType::InvalidClientId(client_id.into()))?;
+
+    // 公开客户端没有密钥，由 PKCE 和刷新令牌与客户端的绑定保证安全
+    if client.is_public {
+        return Ok(client);
+    }
+
+    let client_secret =
+        client_secret.ok_or(OAuthErrorType::ClientAuthenticationFailed)?;
+    if client.secret_hash != DBOAuthClient::hash_secret(&client_secret) {
+        return Err(OAuthError::error(
             OAuthErrorType::ClientAuthenticationFailed,
-        ))
-    } else {
-        Ok(())
+        ));
     }
+
+    Ok(client)
+}
+
+fn parse_basic_client_credentials(
+    credentials: &str,
+) -> Result<(OAuthClientId, String), OAuthErrorType> {
+    let decoded = STANDARD
+        .decode(credentials.trim())
+        .ok()
+        .and_then(|x| String::from_utf8(x).ok())
+        .ok_or(OAuthErrorType::ClientAuthenticationFailed)?;
+    let (id, secret) = decoded
+        .split_once(':')
+        .ok_or(OAuthErrorType::ClientAuthenticationFailed)?;
+
+    // 凭据在编码前经过 application/x-www-form-urlencoded 编码
+    let id = urlencoding::decode(id)
+        .map_err(|_| OAuthErrorType::ClientAuthenticationFailed)?;
+    let secret = urlencoding::decode(secret)
+        .map_err(|_| OAuthErrorType::ClientAuthenticationFailed)?;
+    let id = parse_base62(&id)
+        .map_err(|_| OAuthErrorType::ClientAuthenticationFailed)?;
+
+    Ok((OAuthClientId(id), secret.into_owned()))
 }
 
-fn generate_access_token() -> String {
+fn generate_token(prefix: &str) -> String {
     let random = ChaCha20Rng::from_entropy()
         .sample_iter(&Alphanumeric)
         .take(60)
         .map(char::from)
         .collect::<String>();
-    format!("mro_{}", random)
+    format!("{}_{}", prefix, random)
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn init_oauth_code_flow(
     user_id: crate::database::models::UserId,
     client_id: OAuthClientId,
@@ -423,6 +914,8 @@ async fn init_oauth_code_flow(
     scopes: Scopes,
     redirect_uris: OAuthRedirectUris,
     state: Option<String>,
+    code_challenge: Option<CodeChallenge>,
+    nonce: Option<String>,
     redis: &RedisPool,
 ) -> Result<HttpResponse, OAuthError> {
     let code = Flow::OAuthAuthorizationCodeSupplied {
@@ -431,6 +924,8 @@ async fn init_oauth_code_flow(
         authorization_id,
         scopes,
         original_redirect_uri: redirect_uris.original.clone(),
+        code_challenge,
+        nonce,
     }
     .insert(Duration::minutes(10), redis)
     .await
@@ -0,0 +1,551 @@
+//! OpenID Connect 支持
+//!
+//! 在 OAuth 授权码流程之上签发 ID 令牌，并提供发现文档、JWKS 和 userinfo 接口。
+//! ES256 签名密钥由服务自动生成并定期轮换；RS256 密钥无法由 ring 生成，
+//! 需要通过 `OIDC_RSA_PRIVATE_KEY` 配置，更换配置即完成轮换。
+
+use super::errors::{OAuthError, OAuthErrorType};
+use crate::auth::validate::extract_authorization_header;
+use crate::database::models::oauth_client_authorization_item::OAuthClientAuthorization;
+use crate::database::models::oauth_client_item::OAuthClient as DBOAuthClient;
+use crate::database::models::oauth_token_item::OAuthAccessToken;
+use crate::database::models::oidc_key_item::OidcSigningKey;
+use crate::database::models::{OAuthClientAuthorizationId, user_item};
+use crate::database::redis::RedisPool;
+use crate::models::ids::{OAuthClientId, UserId};
+use crate::models::pats::Scopes;
+use crate::util::encrypt::{decrypt, encrypt};
+use crate::util::env::parse_var;
+use actix_web::http::StatusCode;
+use actix_web::http::header::{CACHE_CONTROL, WWW_AUTHENTICATE};
+use actix_web::web::{Data, ServiceConfig};
+use actix_web::{HttpRequest, HttpResponse, get, route};
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use chrono::{Duration, Utc};
+use ring::rand::SystemRandom;
+use ring::rsa::PublicKeyComponents;
+use ring::signature::{
+    ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair, KeyPair, RSA_PKCS1_SHA256,
+    RsaKeyPair,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Digest;
+use sqlx::PgPool;
+
+/// ID 令牌有效期（秒）
+const ID_TOKEN_LIFETIME: i64 = 3600;
+
+/// 退役密钥在 JWKS 中保留的天数
+const RETIRED_KEY_RETENTION_DAYS: i64 = 7;
+
+pub fn config(cfg: &mut ServiceConfig) {
+    cfg.service(userinfo);
+}
+
+pub fn well_known_config(cfg: &mut ServiceConfig) {
+    cfg.service(openid_configuration).service(jwks);
+}
+
+/// ID 令牌签名算法
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default,
+)]
+pub enum SigningAlgorithm {
+    #[serde(rename = "RS256")]
+    Rs256,
+    #[default]
+    #[serde(rename = "ES256")]
+    Es256,
+}
+
+impl SigningAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SigningAlgorithm::Rs256 => "RS256",
+            SigningAlgorithm::Es256 => "ES256",
+        }
+    }
+
+    pub fn from_string(string: &str) -> SigningAlgorithm {
+        match string {
+            "RS256" => SigningAlgorithm::Rs256,
+            _ => SigningAlgorithm::Es256,
+        }
+    }
+
+    /// RS256 密钥只能通过 `OIDC_RSA_PRIVATE_KEY` 导入，未配置时不可用
+    pub fn is_available(&self) -> bool {
+        match self {
+            SigningAlgorithm::Rs256 => dotenvy::var("OIDC_RSA_PRIVATE_KEY")
+                .is_ok_and(|x| !x.is_empty()),
+            SigningAlgorithm::Es256 => true,
+        }
+    }
+}
+
+/// 根据授权范围发放的用户声明
+///
+/// 参见：OpenID Connect Core 1.0 5.1 (https://openid.net/specs/openid-connect-core-1_0.html#StandardClaims)
+#[derive(Serialize, Deserialize, Default)]
+pub struct UserClaims {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub picture: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
+}
+
+impl UserClaims {
+    /// `profile` 对应 USER_READ，`email` 对应 USER_READ_EMAIL
+    pub fn new(user: &user_item::User, scopes: Scopes) -> Self {
+        let mut claims = UserClaims::default();
+
+        if scopes.contains(Scopes::USER_READ) {
+            claims.preferred_username = Some(user.username.clone());
+            claims.name = Some(user.username.clone());
+            claims.picture = user.avatar_url.clone();
+            claims.profile = dotenvy::var("SITE_URL")
+                .ok()
+                .map(|site_url| format!("{}/user/{}", site_url, user.username));
+        }
+
+        if scopes.contains(Scopes::USER_READ_EMAIL) && user.email.is_some() {
+            claims.email = user.email.clone();
+            claims.email_verified = Some(user.email_verified);
+        }
+
+        claims
+    }
+
+    /// 发放的声明名称，用于记录用户同意的内容
+    pub fn names(&self) -> Vec<String> {
+        let mut names = vec!["sub".to_string()];
+        if let Ok(serde_json::Value::Object(map)) = serde_json::to_value(self) {
+            names.extend(map.keys().cloned());
+        }
+        names
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: UserId,
+    pub aud: OAuthClientId,
+    pub exp: i64,
+    pub iat: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(flatten)]
+    pub claims: UserClaims,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UserInfo {
+    pub sub: UserId,
+    #[serde(flatten)]
+    pub claims: UserClaims,
+}
+
+fn issuer() -> Result<String, OAuthErrorType> {
+    dotenvy::var("SELF_ADDR")
+        .map_err(|_| OAuthErrorType::SigningKey("SELF_ADDR 未配置".to_string()))
+}
+
+/// 为授权签发 ID 令牌，并记录发放给应用的声明
+///
+/// 参见：OpenID Connect Core 1.0 3.1.3.6 (https://openid.net/specs/openid-connect-core-1_0.html#CodeIDToken)
+pub async fn generate_id_token(
+    client: &DBOAuthClient,
+    authorization_id: OAuthClientAuthorizationId,
+    user_id: crate::database::models::UserId,
+    scopes: Scopes,
+    nonce: Option<String>,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<String, OAuthError> {
+    let user = user_item::User::get_id(user_id, pool, redis)
+        .await?
+        .ok_or(OAuthErrorType::InvalidAuthCode)?;
+
+    let now = Utc::now();
+    let claims = UserClaims::new(&user, scopes);
+    let released_claims = claims.names();
+
+    let id_token_claims = IdTokenClaims {
+        iss: issuer()?,
+        sub: user.id.into(),
+        aud: client.id.into(),
+        exp: (now + Duration::seconds(ID_TOKEN_LIFETIME)).timestamp(),
+        iat: now.timestamp(),
+        nonce,
+        claims,
+    };
+
+    let key = get_signing_key(client, pool).await?;
+    let id_token = sign_jwt(&key, &id_token_claims)?;
+
+    OAuthClientAuthorization::record_released_claims(
+        authorization_id,
+        &released_claims,
+        pool,
+    )
+    .await?;
+
+    Ok(id_token)
+}
+
+/// 获取客户端配置的签名密钥，不会退回其他算法
+async fn get_signing_key(
+    client: &DBOAuthClient,
+    pool: &PgPool,
+) -> Result<OidcSigningKey, OAuthErrorType> {
+    let algorithm =
+        SigningAlgorithm::from_string(&client.id_token_signed_response_alg)
+            .as_str();
+
+    if let Some(key) = OidcSigningKey::get_active(algorithm, pool).await? {
+        return Ok(key);
+    }
+
+    // 首次启动时调度器可能尚未生成或导入密钥
+    rotate_signing_keys(pool).await?;
+
+    OidcSigningKey::get_active(algorithm, pool)
+        .await?
+        .ok_or_else(|| {
+            OAuthErrorType::SigningKey(format!("未配置 {algorithm} 签名密钥"))
+        })
+}
+
+/// 生成新的 ES256 密钥、导入配置的 RS256 密钥，并清理过期的退役密钥
+pub async fn rotate_signing_keys(pool: &PgPool) -> Result<(), OAuthErrorType> {
+    let rotation_days: i64 = parse_var("OIDC_KEY_ROTATION_DAYS").unwrap_or(30);
+    let now = Utc::now();
+
+    let mut transaction = pool.begin().await?;
+
+    let current = OidcSigningKey::get_active(
+        SigningAlgorithm::Es256.as_str(),
+        &mut *transaction,
+    )
+    .await?;
+    if current.is_none_or(|x| x.created + Duration::days(rotation_days) < now) {
+        let key = generate_es256_key()?;
+        log::info!("已生成新的 ES256 签名密钥 {}", key.kid);
+        key.insert(&mut transaction).await?;
+    }
+
+    if let Ok(rsa_key) = dotenvy::var("OIDC_RSA_PRIVATE_KEY") {
+        let key = import_rsa_key(&rsa_key)?;
+        let current = OidcSigningKey::get_active(
+            SigningAlgorithm::Rs256.as_str(),
+            &mut *transaction,
+        )
+        .await?;
+        if current.is_none_or(|x| x.kid != key.kid) {
+            log::info!("已导入新的 RS256 签名密钥 {}", key.kid);
+            key.insert(&mut transaction).await?;
+        }
+    }
+
+    // 退役密钥保留到其签发的 ID 令牌全部过期之后
+    OidcSigningKey::remove_retired_before(
+        now - Duration::days(RETIRED_KEY_RETENTION_DAYS),
+        &mut *transaction,
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+fn signing_error(message: &str) -> OAuthErrorType {
+    OAuthErrorType::SigningKey(message.to_string())
+}
+
+fn generate_es256_key() -> Result<OidcSigningKey, OAuthErrorType> {
+    let rng = SystemRandom::new();
+    let pkcs8 =
+        EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+            .map_err(|_| signing_error("生成 ES256 密钥失败"))?;
+    let key_pair = EcdsaKeyPair::from_pkcs8(
+        &ECDSA_P256_SHA256_FIXED_SIGNING,
+        pkcs8.as_ref(),
+        &rng,
+    )
+    .map_err(|_| signing_error("解析 ES256 密钥失败"))?;
+
+    // 未压缩的公钥格式：0x04 || X || Y
+    let public_key = key_pair.public_key().as_ref();
+    let x = URL_SAFE_NO_PAD.encode(&public_key[1..33]);
+    let y = URL_SAFE_NO_PAD.encode(&public_key[33..65]);
+
+    // IETF RFC 7638 3.2 (https://datatracker.ietf.org/doc/html/rfc7638#section-3.2)
+    let kid = thumbprint(&format!(
+        r#"{{"crv":"P-256","kty":"EC","x":"{x}","y":"{y}"}}"#
+    ));
+
+    new_signing_key(
+        SigningAlgorithm::Es256,
+        kid.clone(),
+        pkcs8.as_ref(),
+        json!({ "kty": "EC", "crv": "P-256", "x": x, "y": y }),
+    )
+}
+
+/// 导入 Base64 编码的 PKCS#8 DER 格式 RSA 私钥
+fn import_rsa_key(encoded: &str) -> Result<OidcSigningKey, OAuthErrorType> {
+    let der = STANDARD
+        .decode(encoded.trim())
+        .map_err(|_| signing_error("OIDC_RSA_PRIVATE_KEY 不是有效的 Base64"))?;
+    let key_pair = RsaKeyPair::from_pkcs8(&der).map_err(|_| {
+        signing_error("OIDC_RSA_PRIVATE_KEY 不是有效的 PKCS#8 RSA 私钥")
+    })?;
+
+    let components = PublicKeyComponents::<Vec<u8>>::from(key_pair.public());
+    let n = URL_SAFE_NO_PAD.encode(&components.n);
+    let e = URL_SAFE_NO_PAD.encode(&components.e);
+
+    let kid = thumbprint(&format!(r#"{{"e":"{e}","kty":"RSA","n":"{n}"}}"#));
+
+    new_signing_key(
+        SigningAlgorithm::Rs256,
+        kid,
+        &der,
+        json!({ "kty": "RSA", "n": n, "e": e }),
+    )
+}
+
+fn thumbprint(canonical_jwk: &str) -> String {
+    URL_SAFE_NO_PAD.encode(sha2::Sha256::digest(canonical_jwk.as_bytes()))
+}
+
+fn new_signing_key(
+    algorithm: SigningAlgorithm,
+    kid: String,
+    pkcs8: &[u8],
+    mut public_jwk: serde_json::Value,
+) -> Result<OidcSigningKey, OAuthErrorType> {
+    public_jwk["kid"] = json!(kid);
+    public_jwk["alg"] = json!(algorithm.as_str());
+    public_jwk["use"] = json!("sig");
+
+    let private_key = encrypt(&STANDARD.encode(pkcs8))
+        .map_err(|e| OAuthErrorType::SigningKey(e.to_string()))?;
+
+    Ok(OidcSigningKey {
+        kid,
+        algorithm: algorithm.as_str().to_string(),
+        private_key,
+        public_jwk,
+        created: Utc::now(),
+        retired: None,
+    })
+}
+
+fn encode_jwt_part(value: &impl Serialize) -> Result<String, OAuthErrorType> {
+    serde_json::to_vec(value)
+        .map(|x| URL_SAFE_NO_PAD.encode(x))
+        .map_err(|_| signing_error("序列化 JWT 失败"))
+}
+
+/// 生成 JWS 紧凑序列化的 JWT
+///
+/// 参见：IETF RFC 7515 7.1 (https://datatracker.ietf.org/doc/html/rfc7515#section-7.1)
+fn sign_jwt(
+    key: &OidcSigningKey,
+    claims: &impl Serialize,
+) -> Result<String, OAuthErrorType> {
+    let header = json!({
+        "alg": key.algorithm,
+        "kid": key.kid,
+        "typ": "JWT",
+    });
+
+    let signing_input =
+        format!("{}.{}", encode_jwt_part(&header)?, encode_jwt_part(claims)?);
+
+    let pkcs8 = decrypt(&key.private_key)
+        .ok()
+        .and_then(|x| STANDARD.decode(x).ok())
+        .ok_or_else(|| signing_error("解密签名密钥失败"))?;
+
+    let rng = SystemRandom::new();
+    let signature = match SigningAlgorithm::from_string(&key.algorithm) {
+        SigningAlgorithm::Es256 => {
+            let key_pair = EcdsaKeyPair::from_pkcs8(
+                &ECDSA_P256_SHA256_FIXED_SIGNING,
+                &pkcs8,
+                &rng,
+            )
+            .map_err(|_| signing_error("解析 ES256 密钥失败"))?;
+
+            key_pair
+                .sign(&rng, signing_input.as_bytes())
+                .map_err(|_| signing_error("ES256 签名失败"))?
+                .as_ref()
+                .to_vec()
+        }
+        SigningAlgorithm::Rs256 => {
+            let key_pair = RsaKeyPair::from_pkcs8(&pkcs8)
+                .map_err(|_| signing_error("解析 RS256 密钥失败"))?;
+
+            let mut signature = vec![0; key_pair.public().modulus_len()];
+            key_pair
+                .sign(
+                    &RSA_PKCS1_SHA256,
+                    &rng,
+                    signing_input.as_bytes(),
+                    &mut signature,
+                )
+                .map_err(|_| signing_error("RS256 签名失败"))?;
+            signature
+        }
+    };
+
+    Ok(format!(
+        "{}.{}",
+        signing_input,
+        URL_SAFE_NO_PAD.encode(signature)
+    ))
+}
+
+#[get("openid-configuration")]
+/// OpenID Provider 元数据
+/// Per OpenID Connect Discovery 1.0 Section 3 (https://openid.net/specs/openid-connect-discovery-1_0.html#ProviderMetadata)
+pub async fn openid_configuration() -> Result<HttpResponse, OAuthError> {
+    let issuer = issuer()?;
+    let site_url = dotenvy::var("SITE_URL").unwrap_or_default();
+    let oauth_url = format!("{issuer}/_internal/oauth");
+    let signing_algorithms = [SigningAlgorithm::Rs256, SigningAlgorithm::Es256]
+        .into_iter()
+        .filter(|x| x.is_available())
+        .map(|x| x.as_str())
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok()
+        .append_header((CACHE_CONTROL, "public, max-age=3600"))
+        .json(json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{site_url}/auth/authorize"),
+            "token_endpoint": format!("{oauth_url}/token"),
+            "userinfo_endpoint": format!("{oauth_url}/userinfo"),
+            "revocation_endpoint": format!("{oauth_url}/revoke"),
+            "introspection_endpoint": format!("{oauth_url}/introspect"),
+            "device_authorization_endpoint": format!("{oauth_url}/device/code"),
+            "jwks_uri": format!("{issuer}/.well-known/jwks.json"),
+            "scopes_supported": ["openid", "profile", "email"],
+            "response_types_supported": ["code"],
+            "grant_types_supported": [
+                "authorization_code",
+                "refresh_token",
+                super::device::DEVICE_CODE_GRANT_TYPE,
+            ],
+            "subject_types_supported": ["public"],
+            "id_token_signing_alg_values_supported": signing_algorithms,
+            "token_endpoint_auth_methods_supported": [
+                "client_secret_basic",
+                "client_secret_post",
+                "none",
+            ],
+            "code_challenge_methods_supported": ["S256", "plain"],
+            "claims_supported": [
+                "iss",
+                "sub",
+                "aud",
+                "exp",
+                "iat",
+                "nonce",
+                "preferred_username",
+                "name",
+                "picture",
+                "profile",
+                "email",
+                "email_verified",
+            ],
+        })))
+}
+
+#[get("jwks.json")]
+/// 当前和近期退役的签名公钥
+/// Per IETF RFC 7517 Section 5 (https://datatracker.ietf.org/doc/html/rfc7517#section-5)
+pub async fn jwks(pool: Data<PgPool>) -> Result<HttpResponse, OAuthError> {
+    let keys = OidcSigningKey::get_published(&**pool)
+        .await?
+        .into_iter()
+        .map(|x| x.public_jwk)
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok()
+        .append_header((CACHE_CONTROL, "public, max-age=300"))
+        .json(json!({ "keys": keys })))
+}
+
+/// 参见：IETF RFC 6750 3.1 (https://datatracker.ietf.org/doc/html/rfc6750#section-3.1)
+fn bearer_error(status: StatusCode, error: &str) -> HttpResponse {
+    HttpResponse::build(status)
+        .append_header((WWW_AUTHENTICATE, format!("Bearer error=\"{error}\"")))
+        .finish()
+}
+
+#[route("userinfo", method = "GET", method = "POST")]
+/// Returns claims about the user that authorized the access token
+/// Per OpenID Connect Core 1.0 Section 5.3 (https://openid.net/specs/openid-connect-core-1_0.html#UserInfo)
+pub async fn userinfo(
+    req: HttpRequest,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+) -> Result<HttpResponse, OAuthError> {
+    let token = extract_authorization_header(&req)
+        .ok()
+        .filter(|x| x.starts_with("mro_"));
+
+    let access_token = match token {
+        Some(token) => {
+            OAuthAccessToken::get(OAuthAccessToken::hash_token(token), &**pool)
+                .await?
+                .filter(|x| x.expires > Utc::now())
+        }
+        None => None,
+    };
+
+    let Some(access_token) = access_token else {
+        return Ok(bearer_error(StatusCode::UNAUTHORIZED, "invalid_token"));
+    };
+
+    if !access_token.scopes.contains(Scopes::OPENID) {
+        return Ok(bearer_error(StatusCode::FORBIDDEN, "insufficient_scope"));
+    }
+
+    let Some(user) =
+        user_item::User::get_id(access_token.user_id, &**pool, &redis).await?
+    else {
+        return Ok(bearer_error(StatusCode::UNAUTHORIZED, "invalid_token"));
+    };
+
+    let claims = UserClaims::new(&user, access_token.scopes);
+    OAuthClientAuthorization::record_released_claims(
+        access_token.authorization_id,
+        &claims.names(),
+        &**pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok()
+        .append_header((CACHE_CONTROL, "no-store"))
+        .json(UserInfo {
+            sub: user.id.into(),
+            claims,
+        }))
+}
@@ -0,0 +1,96 @@
+use super::errors::OAuthErrorType;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use subtle::ConstantTimeEq;
+
+/// PKCE 质询方法
+///
+/// 参见：IETF RFC 7636 4.2 (https://datatracker.ietf.org/doc/html/rfc7636#section-4.2)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodeChallengeMethod {
+    #[serde(rename = "plain")]
+    Plain,
+    #[serde(rename = "S256")]
+    S256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CodeChallenge {
+    pub challenge: String,
+    pub method: CodeChallengeMethod,
+}
+
+impl CodeChallenge {
+    /// 解析授权请求中的 PKCE 参数
+    ///
+    /// 公开客户端必须提供 S256 质询，机密客户端可以选择使用
+    pub fn parse(
+        challenge: Option<&str>,
+        method: Option<&str>,
+        is_public_client: bool,
+    ) -> Result<Option<CodeChallenge>, OAuthErrorType> {
+        let Some(challenge) = challenge else {
+            if is_public_client {
+                return Err(OAuthErrorType::PkceRequired);
+            }
+
+            return Ok(None);
+        };
+
+        // 未指定方法时默认为 plain（IETF RFC 7636 4.3）
+        let method = match method.unwrap_or("plain") {
+            "plain" => CodeChallengeMethod::Plain,
+            "S256" => CodeChallengeMethod::S256,
+            method => {
+                return Err(OAuthErrorType::InvalidCodeChallenge(format!(
+                    "不支持的质询方法 {method}"
+                )));
+            }
+        };
+
+        if is_public_client && method != CodeChallengeMethod::S256 {
+            return Err(OAuthErrorType::InvalidCodeChallenge(
+                "公开客户端必须使用 S256 质询方法".to_string(),
+            ));
+        }
+
+        if !is_valid_code(challenge) {
+            return Err(OAuthErrorType::InvalidCodeChallenge(
+                "质询格式错误".to_string(),
+            ));
+        }
+
+        Ok(Some(CodeChallenge {
+            challenge: challenge.to_string(),
+            method,
+        }))
+    }
+
+    /// 校验令牌请求中的 code_verifier
+    ///
+    /// 参见：IETF RFC 7636 4.6 (https://datatracker.ietf.org/doc/html/rfc7636#section-4.6)
+    pub fn verify(&self, verifier: &str) -> bool {
+        if !is_valid_code(verifier) {
+            return false;
+        }
+
+        let computed = match self.method {
+            CodeChallengeMethod::Plain => verifier.to_string(),
+            CodeChallengeMethod::S256 => {
+                URL_SAFE_NO_PAD.encode(sha2::Sha256::digest(verifier))
+            }
+        };
+
+        computed.as_bytes().ct_eq(self.challenge.as_bytes()).into()
+    }
+}
+
+/// 质询和验证码都是 43 到 128 个非保留字符（IETF RFC 7636 4.1）
+fn is_valid_code(code: &str) -> bool {
+    (43..=128).contains(&code.len())
+        && code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-._~".contains(c))
+}
@@ -1,13 +1,16 @@
 use super::AuthProvider;
 use crate::auth::AuthenticationError;
+use crate::auth::checks::PatResourceRestriction;
 use crate::database::models::user_item;
 use crate::database::redis::RedisPool;
 use crate::models::pats::Scopes;
 use crate::models::users::User;
 use crate::queue::session::AuthQueue;
 use crate::routes::internal::session::get_session_metadata;
-use actix_web::HttpRequest;
+use crate::util::ip::{convert_to_ip_v6, ip_in_range};
+use crate::util::ratelimit::client_ip;
 use actix_web::http::header::{AUTHORIZATION, HeaderValue};
+use actix_web::{HttpMessage, HttpRequest};
 use chrono::Utc;
 
 pub async fn get_user_from_headers<'a, E>(
@@ -81,12 +84,35 @@ where
                 return Err(AuthenticationError::InvalidCredentials);
             }
 
+            if let Some(allowed_ips) = &pat.allowed_ips {
+                let ip = convert_to_ip_v6(&client_ip(req))
+                    .map_err(|_| AuthenticationError::InvalidCredentials)?;
+                if !allowed_ips.iter().any(|range| ip_in_range(ip, range)) {
+                    return Err(AuthenticationError::InvalidCredentials);
+                }
+            }
+
+            // 限定了项目或组织的令牌只保留与资源相关的权限，
+            // 具体能操作哪些资源由各接口通过 checks::check_pat_* 检查
+            let scopes = if pat.is_resource_restricted() {
+                req.extensions_mut().insert(PatResourceRestriction {
+                    project_ids: pat.project_ids.clone().unwrap_or_default(),
+                    organization_ids: pat
+                        .organization_ids
+                        .clone()
+                        .unwrap_or_default(),
+                });
+                pat.scopes & Scopes::resource_scoped()
+            } else {
+                pat.scopes
+            };
+
             let user =
                 user_item::User::get_id(pat.user_id, executor, redis).await?;
 
             session_queue.add_pat(pat.id).await;
 
-            user.map(|x| (pat.scopes, x))
+            user.map(|x| (scopes, x))
         }
         Some(("mra", _)) => {
             let session = crate::database::models::session_item::Session::get(
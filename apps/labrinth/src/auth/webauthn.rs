@@ -0,0 +1,163 @@
+//! WebAuthn 通行密钥
+//!
+//! 通行密钥既可用于无密码登录，也可在密码或第三方登录后代替 TOTP 作为第二因素。
+//! 组织可以要求持有敏感权限的成员使用通行密钥作为第二因素。
+
+use crate::auth::AuthenticationError;
+use crate::database::models::organization_item::Organization;
+use crate::database::models::team_item::{Team, TeamAssociationId};
+use crate::database::models::webauthn_credential_item::WebauthnCredential;
+use crate::database::models::{DatabaseError, TeamId, User, UserId};
+use crate::database::redis::RedisPool;
+use crate::models::teams::ProjectPermissions;
+use crate::routes::ApiError;
+use base64::Engine;
+use sqlx::PgPool;
+use url::Url;
+use webauthn_rs::prelude::{Uuid, Webauthn, WebauthnBuilder};
+
+/// 组织开启通行密钥要求后，持有其中任一权限的成员（及所有者）必须使用通行密钥
+pub const PASSKEY_2FA_PERMISSIONS: ProjectPermissions =
+    ProjectPermissions::DELETE_PROJECT.union(ProjectPermissions::VIEW_PAYOUTS);
+
+/// 第二因素：TOTP 验证码（含备用码）
+pub const SECOND_FACTOR_TOTP: &str = "totp";
+/// 第二因素：通行密钥
+pub const SECOND_FACTOR_WEBAUTHN: &str = "webauthn";
+
+/// 依据 `SITE_URL` 构建 WebAuthn 依赖方，`WEBAUTHN_RP_ID` 可覆盖默认的域名
+pub fn webauthn() -> Result<Webauthn, AuthenticationError> {
+    let origin = Url::parse(&dotenvy::var("SITE_URL")?)
+        .map_err(|_| AuthenticationError::Url)?;
+    let rp_id = match dotenvy::var("WEBAUTHN_RP_ID") {
+        Ok(rp_id) => rp_id,
+        Err(_) => origin
+            .host_str()
+            .ok_or(AuthenticationError::Url)?
+            .to_string(),
+    };
+
+    Ok(WebauthnBuilder::new(&rp_id, &origin)?
+        .rp_name("BBSMC")
+        .build()?)
+}
+
+/// 通行密钥中保存的用户句柄，无密码登录时用于识别用户
+pub fn user_handle(user_id: UserId) -> Uuid {
+    Uuid::from_u64_pair(0, user_id.0 as u64)
+}
+
+pub fn user_id_from_handle(handle: Uuid) -> UserId {
+    UserId(handle.as_u64_pair().1 as i64)
+}
+
+/// 凭据 ID 以 base64url 编码存储
+pub fn encode_credential_id(credential_id: &[u8]) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(credential_id)
+}
+
+pub async fn has_passkey(
+    user_id: UserId,
+    pool: &PgPool,
+) -> Result<bool, DatabaseError> {
+    Ok(
+        !WebauthnCredential::get_users_with_credentials(&[user_id], pool)
+            .await?
+            .is_empty(),
+    )
+}
+
+/// 用户是否必须使用通行密钥完成两步验证（所在组织开启了要求且已注册通行密钥）
+pub async fn passkey_2fa_enforced(
+    user_id: UserId,
+    pool: &PgPool,
+) -> Result<bool, DatabaseError> {
+    Ok(has_passkey(user_id, pool).await?
+        && Organization::is_passkey_2fa_required(
+            user_id,
+            PASSKEY_2FA_PERMISSIONS,
+            pool,
+        )
+        .await?)
+}
+
+/// 登录时可用的第二因素，为空表示无需第二因素
+pub async fn second_factor_methods(
+    user: &User,
+    pool: &PgPool,
+) -> Result<Vec<&'static str>, DatabaseError> {
+    let mut methods = Vec::new();
+
+    if has_passkey(user.id, pool).await? {
+        methods.push(SECOND_FACTOR_WEBAUTHN);
+    }
+    if user.totp_secret.is_some()
+        && !passkey_2fa_enforced(user.id, pool).await?
+    {
+        methods.push(SECOND_FACTOR_TOTP);
+    }
+
+    Ok(methods)
+}
+
+/// 向团队成员授予敏感权限前，检查所属组织的通行密钥要求
+pub async fn check_passkey_2fa_requirement(
+    team_id: TeamId,
+    user_id: UserId,
+    permissions: ProjectPermissions,
+    is_owner: bool,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(), ApiError> {
+    if !is_owner && !permissions.intersects(PASSKEY_2FA_PERMISSIONS) {
+        return Ok(());
+    }
+
+    let organization = match Team::get_association(team_id, pool).await? {
+        Some(TeamAssociationId::Organization(id)) => {
+            Organization::get_id(id, pool, redis).await?
+        }
+        Some(TeamAssociationId::Project(id)) => {
+            Organization::get_associated_organization_project_id(id, pool)
+                .await?
+        }
+        None => None,
+    };
+
+    if organization.is_some_and(|x| x.require_passkey_2fa)
+        && !has_passkey(user_id, pool).await?
+    {
+        return Err(ApiError::InvalidInput(
+            "该组织要求持有删除项目或收益权限的成员使用通行密钥进行两步验证，请先注册通行密钥".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// 组织开启通行密钥要求前，确认所有持有敏感权限的成员都已注册通行密钥
+pub async fn check_organization_passkeys(
+    organization: &Organization,
+    pool: &PgPool,
+) -> Result<(), ApiError> {
+    let members = Organization::get_sensitive_members(
+        organization.id,
+        PASSKEY_2FA_PERMISSIONS,
+        pool,
+    )
+    .await?;
+    let with_passkeys =
+        WebauthnCredential::get_users_with_credentials(&members, pool).await?;
+
+    let missing = members
+        .iter()
+        .filter(|x| !with_passkeys.contains(x))
+        .count();
+    if missing > 0 {
+        return Err(ApiError::InvalidInput(format!(
+            "还有 {missing} 位持有删除项目或收益权限的成员尚未注册通行密钥，无法开启此要求"
+        )));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use super::{CaptchaError, CaptchaOutcome, CaptchaVerifier};
+use async_trait::async_trait;
+use hex::ToHex;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+
+const VALIDATE_URL: &str = "https://gcaptcha4.geetest.com/validate";
+
+/// 极验 v4，前端提交的验证结果为包含
+/// `lot_number`、`captcha_output`、`pass_token`、`gen_time` 的 JSON 字符串
+pub struct GeetestCaptcha {
+    client: reqwest::Client,
+    captcha_id: String,
+    captcha_key: String,
+}
+
+impl GeetestCaptcha {
+    pub fn new(captcha_id: &str, captcha_key: &str) -> Self {
+        GeetestCaptcha {
+            client: reqwest::Client::new(),
+            captcha_id: captcha_id.to_string(),
+            captcha_key: captcha_key.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct GeetestChallenge {
+    lot_number: String,
+    captcha_output: String,
+    pass_token: String,
+    gen_time: String,
+}
+
+#[derive(Deserialize)]
+struct GeetestResponse {
+    status: String,
+    result: Option<String>,
+    reason: Option<String>,
+    msg: Option<String>,
+}
+
+#[async_trait]
+impl CaptchaVerifier for GeetestCaptcha {
+    fn provider(&self) -> &'static str {
+        "geetest"
+    }
+
+    fn site_key(&self) -> Option<String> {
+        Some(self.captcha_id.clone())
+    }
+
+    async fn verify(
+        &self,
+        challenge: &str,
+        _ip: Option<&str>,
+    ) -> Result<CaptchaOutcome, CaptchaError> {
+        let challenge: GeetestChallenge = serde_json::from_str(challenge)?;
+
+        // HMAC 接受任意长度的密钥，不会失败
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(self.captcha_key.as_bytes())
+                .expect("HMAC can take key of any size");
+        mac.update(challenge.lot_number.as_bytes());
+        let sign_token = mac.finalize().into_bytes().encode_hex::<String>();
+
+        let response: GeetestResponse = self
+            .client
+            .post(VALIDATE_URL)
+            .query(&[("captcha_id", &self.captcha_id)])
+            .form(&[
+                ("lot_number", challenge.lot_number.as_str()),
+                ("captcha_output", challenge.captcha_output.as_str()),
+                ("pass_token", challenge.pass_token.as_str()),
+                ("gen_time", challenge.gen_time.as_str()),
+                ("sign_token", sign_token.as_str()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        // status 为 error 表示请求本身有误（如 captcha_id 无效）
+        let success = response.status == "success"
+            && response.result.as_deref() == Some("success");
+        Ok(CaptchaOutcome {
+            success,
+            score: None,
+            message: (!success).then(|| {
+                format!(
+                    "人机验证失败: {}",
+                    response.reason.or(response.msg).unwrap_or_default()
+                )
+            }),
+        })
+    }
+}
@@ -0,0 +1,74 @@
+use super::{CaptchaError, CaptchaOutcome, CaptchaVerifier};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+const VERIFY_URL: &str = "https://api.hcaptcha.com/siteverify";
+
+/// hCaptcha，企业版会额外返回风险分
+pub struct HCaptcha {
+    client: reqwest::Client,
+    site_key: String,
+    secret: String,
+}
+
+impl HCaptcha {
+    pub fn new(site_key: &str, secret: &str) -> Self {
+        HCaptcha {
+            client: reqwest::Client::new(),
+            site_key: site_key.to_string(),
+            secret: secret.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct HCaptchaResponse {
+    success: bool,
+    score: Option<f32>,
+    #[serde(rename = "error-codes", default)]
+    error_codes: Vec<String>,
+}
+
+#[async_trait]
+impl CaptchaVerifier for HCaptcha {
+    fn provider(&self) -> &'static str {
+        "hcaptcha"
+    }
+
+    fn site_key(&self) -> Option<String> {
+        Some(self.site_key.clone())
+    }
+
+    async fn verify(
+        &self,
+        challenge: &str,
+        ip: Option<&str>,
+    ) -> Result<CaptchaOutcome, CaptchaError> {
+        let mut form = vec![
+            ("secret", self.secret.as_str()),
+            ("response", challenge),
+            ("sitekey", self.site_key.as_str()),
+        ];
+        if let Some(ip) = ip {
+            form.push(("remoteip", ip));
+        }
+
+        let response: HCaptchaResponse = self
+            .client
+            .post(VERIFY_URL)
+            .form(&form)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(CaptchaOutcome {
+            success: response.success,
+            score: response.score,
+            message: (!response.success).then(|| {
+                format!("人机验证失败: {}", response.error_codes.join(","))
+            }),
+        })
+    }
+}
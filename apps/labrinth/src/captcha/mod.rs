@@ -0,0 +1,312 @@
+//! 人机验证
+//!
+//! 每个需要人机验证的入口可以单独选择服务商，由 `CAPTCHA_PROVIDER_<入口>`
+//! 指定（如 `CAPTCHA_PROVIDER_LOGIN`），未设置时使用 `CAPTCHA_PROVIDER`，
+//! 默认为 `tac`。可选服务商：`tac`（自建 TAC 验证码服务）、`hcaptcha`、
+//! `turnstile`（Cloudflare Turnstile）、`geetest`（极验 v4）以及用于测试的
+//! `test`。
+//!
+//! 服务商返回的风险分超过 `CAPTCHA_RISK_THRESHOLD` 时拒绝本次请求，
+//! 并计入与内容风控共用的风险计数。
+
+use crate::database::redis::RedisPool;
+use crate::models::users::User;
+use crate::routes::ApiError;
+use crate::util::env::parse_var;
+use crate::util::ratelimit::client_ip;
+use actix_web::HttpRequest;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{error, info};
+
+mod geetest;
+mod hcaptcha;
+mod tac;
+mod test;
+mod turnstile;
+
+pub use geetest::GeetestCaptcha;
+pub use hcaptcha::HCaptcha;
+pub use tac::TacCaptcha;
+pub use test::TestCaptcha;
+pub use turnstile::TurnstileCaptcha;
+
+/// 风险分阈值的默认值
+const DEFAULT_RISK_THRESHOLD: f32 = 0.8;
+
+#[derive(Error, Debug)]
+pub enum CaptchaError {
+    #[error("与人机验证服务通信时出错: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("人机验证数据格式错误: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("人机验证服务缺少配置: {0}")]
+    Env(#[from] dotenvy::Error),
+    #[error("指定了无效的人机验证服务：{0}")]
+    InvalidProvider(String),
+}
+
+/// 服务商的验证结果
+#[derive(Debug, Clone, Default)]
+pub struct CaptchaOutcome {
+    pub success: bool,
+    /// 风险分，0 表示正常用户，1 表示几乎可以确定是机器人；
+    /// 服务商不提供时为空
+    pub score: Option<f32>,
+    /// 验证失败时服务商返回的原因
+    pub message: Option<String>,
+}
+
+#[async_trait]
+pub trait CaptchaVerifier {
+    /// 服务商名称，返回给前端用于加载对应的组件
+    fn provider(&self) -> &'static str;
+
+    /// 前端组件需要的公开站点标识
+    fn site_key(&self) -> Option<String>;
+
+    /// 校验前端提交的验证结果
+    async fn verify(
+        &self,
+        challenge: &str,
+        ip: Option<&str>,
+    ) -> Result<CaptchaOutcome, CaptchaError>;
+}
+
+/// 需要人机验证的入口
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptchaAction {
+    Register,
+    Login,
+    PasswordReset,
+    PhoneCode,
+    /// 新注册账号在论坛发帖或回复
+    Forum,
+}
+
+impl CaptchaAction {
+    pub const ALL: [CaptchaAction; 5] = [
+        CaptchaAction::Register,
+        CaptchaAction::Login,
+        CaptchaAction::PasswordReset,
+        CaptchaAction::PhoneCode,
+        CaptchaAction::Forum,
+    ];
+
+    /// 为该入口单独指定服务商的环境变量
+    pub fn provider_var(&self) -> &'static str {
+        match self {
+            CaptchaAction::Register => "CAPTCHA_PROVIDER_REGISTER",
+            CaptchaAction::Login => "CAPTCHA_PROVIDER_LOGIN",
+            CaptchaAction::PasswordReset => "CAPTCHA_PROVIDER_PASSWORD_RESET",
+            CaptchaAction::PhoneCode => "CAPTCHA_PROVIDER_PHONE_CODE",
+            CaptchaAction::Forum => "CAPTCHA_PROVIDER_FORUM",
+        }
+    }
+
+    /// 该入口配置的服务商名称
+    pub fn provider_name(&self) -> String {
+        dotenvy::var(self.provider_var())
+            .or_else(|_| dotenvy::var("CAPTCHA_PROVIDER"))
+            .unwrap_or_else(|_| "tac".to_string())
+    }
+}
+
+/// 各入口使用的人机验证服务
+pub struct Captcha {
+    verifiers: HashMap<CaptchaAction, Arc<dyn CaptchaVerifier + Send + Sync>>,
+    risk_threshold: f32,
+}
+
+/// 返回给前端的单个入口的人机验证配置
+#[derive(Serialize)]
+pub struct CaptchaConfig {
+    pub provider: &'static str,
+    pub site_key: Option<String>,
+}
+
+/// 返回给前端的人机验证配置
+#[derive(Serialize)]
+pub struct CaptchaSettings {
+    pub actions: HashMap<CaptchaAction, CaptchaConfig>,
+    /// 注册未满该天数的账号在论坛发帖、回复时需要人机验证
+    pub forum_new_account_days: i64,
+}
+
+impl Captcha {
+    /// 按环境变量为每个入口创建验证服务，配置无效时返回错误
+    pub fn from_env() -> Result<Self, CaptchaError> {
+        let mut providers: HashMap<
+            String,
+            Arc<dyn CaptchaVerifier + Send + Sync>,
+        > = HashMap::new();
+        let mut verifiers = HashMap::new();
+
+        for action in CaptchaAction::ALL {
+            let provider = action.provider_name();
+            let verifier = match providers.get(&provider) {
+                Some(verifier) => verifier.clone(),
+                None => {
+                    let verifier = verifier_from_env(&provider)?;
+                    providers.insert(provider, verifier.clone());
+                    verifier
+                }
+            };
+            verifiers.insert(action, verifier);
+        }
+
+        Ok(Captcha {
+            verifiers,
+            risk_threshold: parse_var("CAPTCHA_RISK_THRESHOLD")
+                .unwrap_or(DEFAULT_RISK_THRESHOLD),
+        })
+    }
+
+    fn verifier(
+        &self,
+        action: CaptchaAction,
+    ) -> &Arc<dyn CaptchaVerifier + Send + Sync> {
+        // from_env 为每个入口都创建了验证服务
+        &self.verifiers[&action]
+    }
+
+    pub fn config(&self) -> CaptchaSettings {
+        let actions = CaptchaAction::ALL
+            .into_iter()
+            .map(|action| {
+                let verifier = self.verifier(action);
+                (
+                    action,
+                    CaptchaConfig {
+                        provider: verifier.provider(),
+                        site_key: verifier.site_key(),
+                    },
+                )
+            })
+            .collect();
+
+        CaptchaSettings {
+            actions,
+            forum_new_account_days: forum_new_account_days(),
+        }
+    }
+
+    /// 校验人机验证结果
+    ///
+    /// 风险计数以用户名为键，未登录的入口以 IP 为键。
+    pub async fn check(
+        &self,
+        action: CaptchaAction,
+        challenge: &str,
+        req: &HttpRequest,
+        username: Option<&str>,
+        redis: &RedisPool,
+    ) -> Result<(), ApiError> {
+        let ip = client_ip(req);
+        let ip = (!ip.is_empty()).then_some(ip.as_str());
+        let risk_key = match (username, ip) {
+            (Some(username), _) => Some(username.to_string()),
+            (None, Some(ip)) => Some(format!("ip:{ip}")),
+            (None, None) => None,
+        };
+
+        if let Some(risk_key) = &risk_key {
+            crate::util::risk::check_risk_limit(risk_key, redis).await?;
+        }
+
+        let verifier = self.verifier(action);
+        let outcome = verifier.verify(challenge, ip).await.map_err(|err| {
+            error!("人机验证请求失败 ({}): {}", verifier.provider(), err);
+            ApiError::Turnstile
+        })?;
+
+        if !outcome.success {
+            return Err(ApiError::InvalidInput(
+                outcome
+                    .message
+                    .unwrap_or_else(|| "人机验证失败，请重试".to_string()),
+            ));
+        }
+
+        if let Some(score) = outcome.score
+            && score >= self.risk_threshold
+        {
+            info!(
+                "人机验证风险分过高 ({}, {:?}): {}",
+                verifier.provider(),
+                action,
+                score
+            );
+            if let Some(risk_key) = &risk_key {
+                crate::util::risk::record_risk_event(risk_key, redis).await?;
+            }
+
+            return Err(ApiError::InvalidInput(
+                "人机验证未通过，请稍后重试".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 注册未满 `CAPTCHA_FORUM_NEW_ACCOUNT_DAYS` 天的账号在论坛发帖、
+    /// 回复时需要人机验证
+    pub async fn check_forum_new_account(
+        &self,
+        user: &User,
+        challenge: Option<&str>,
+        req: &HttpRequest,
+        redis: &RedisPool,
+    ) -> Result<(), ApiError> {
+        if Utc::now() - user.created >= Duration::days(forum_new_account_days())
+        {
+            return Ok(());
+        }
+
+        let challenge = challenge.ok_or_else(|| {
+            ApiError::InvalidInput(
+                "新注册的账号发帖或回复需要完成人机验证".to_string(),
+            )
+        })?;
+        self.check(
+            CaptchaAction::Forum,
+            challenge,
+            req,
+            Some(&user.username),
+            redis,
+        )
+        .await
+    }
+}
+
+fn forum_new_account_days() -> i64 {
+    parse_var("CAPTCHA_FORUM_NEW_ACCOUNT_DAYS").unwrap_or(7)
+}
+
+/// 按服务商名称创建验证服务
+pub fn verifier_from_env(
+    provider: &str,
+) -> Result<Arc<dyn CaptchaVerifier + Send + Sync>, CaptchaError> {
+    Ok(match provider {
+        "tac" => Arc::new(TacCaptcha::new(&dotenvy::var("TAC_URL")?)),
+        "hcaptcha" => Arc::new(HCaptcha::new(
+            &dotenvy::var("HCAPTCHA_SITE_KEY")?,
+            &dotenvy::var("HCAPTCHA_SECRET")?,
+        )),
+        "turnstile" => Arc::new(TurnstileCaptcha::new(
+            &dotenvy::var("TURNSTILE_SITE_KEY")?,
+            &dotenvy::var("TURNSTILE_SECRET")?,
+        )),
+        "geetest" => Arc::new(GeetestCaptcha::new(
+            &dotenvy::var("GEETEST_CAPTCHA_ID")?,
+            &dotenvy::var("GEETEST_CAPTCHA_KEY")?,
+        )),
+        "test" => Arc::new(TestCaptcha),
+        _ => return Err(CaptchaError::InvalidProvider(provider.to_string())),
+    })
+}
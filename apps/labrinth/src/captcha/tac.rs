@@ -0,0 +1,57 @@
+use super::{CaptchaError, CaptchaOutcome, CaptchaVerifier};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+/// 自建的 TAC 验证码服务，`TAC_URL` 拼接前端提交的验证 ID 即为校验地址
+pub struct TacCaptcha {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl TacCaptcha {
+    pub fn new(url: &str) -> Self {
+        TacCaptcha {
+            client: reqwest::Client::new(),
+            url: url.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct TacResponse {
+    code: i32,
+    msg: String,
+    success: bool,
+}
+
+#[async_trait]
+impl CaptchaVerifier for TacCaptcha {
+    fn provider(&self) -> &'static str {
+        "tac"
+    }
+
+    fn site_key(&self) -> Option<String> {
+        None
+    }
+
+    async fn verify(
+        &self,
+        challenge: &str,
+        _ip: Option<&str>,
+    ) -> Result<CaptchaOutcome, CaptchaError> {
+        let response: TacResponse = self
+            .client
+            .post(format!("{}{}", self.url, challenge))
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        let success = response.code == 200 && response.success;
+        Ok(CaptchaOutcome {
+            success,
+            score: None,
+            message: (!success).then_some(response.msg),
+        })
+    }
+}
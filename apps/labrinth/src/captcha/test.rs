@@ -0,0 +1,41 @@
+use super::{CaptchaError, CaptchaOutcome, CaptchaVerifier};
+use async_trait::async_trait;
+
+/// 用于开发环境和 CI 的确定性验证：
+/// `pass` 通过，`risky` 通过但风险分为 1，其余均失败
+pub struct TestCaptcha;
+
+#[async_trait]
+impl CaptchaVerifier for TestCaptcha {
+    fn provider(&self) -> &'static str {
+        "test"
+    }
+
+    fn site_key(&self) -> Option<String> {
+        None
+    }
+
+    async fn verify(
+        &self,
+        challenge: &str,
+        _ip: Option<&str>,
+    ) -> Result<CaptchaOutcome, CaptchaError> {
+        Ok(match challenge {
+            "pass" => CaptchaOutcome {
+                success: true,
+                score: Some(0.0),
+                message: None,
+            },
+            "risky" => CaptchaOutcome {
+                success: true,
+                score: Some(1.0),
+                message: None,
+            },
+            _ => CaptchaOutcome {
+                success: false,
+                score: None,
+                message: Some("人机验证失败，请重试".to_string()),
+            },
+        })
+    }
+}
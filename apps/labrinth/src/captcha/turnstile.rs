@@ -0,0 +1,71 @@
+use super::{CaptchaError, CaptchaOutcome, CaptchaVerifier};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+const VERIFY_URL: &str =
+    "https://challenges.cloudflare.com/turnstile/v0/siteverify";
+
+/// Cloudflare Turnstile，不提供风险分
+pub struct TurnstileCaptcha {
+    client: reqwest::Client,
+    site_key: String,
+    secret: String,
+}
+
+impl TurnstileCaptcha {
+    pub fn new(site_key: &str, secret: &str) -> Self {
+        TurnstileCaptcha {
+            client: reqwest::Client::new(),
+            site_key: site_key.to_string(),
+            secret: secret.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TurnstileResponse {
+    success: bool,
+    #[serde(rename = "error-codes", default)]
+    error_codes: Vec<String>,
+}
+
+#[async_trait]
+impl CaptchaVerifier for TurnstileCaptcha {
+    fn provider(&self) -> &'static str {
+        "turnstile"
+    }
+
+    fn site_key(&self) -> Option<String> {
+        Some(self.site_key.clone())
+    }
+
+    async fn verify(
+        &self,
+        challenge: &str,
+        ip: Option<&str>,
+    ) -> Result<CaptchaOutcome, CaptchaError> {
+        let mut form =
+            vec![("secret", self.secret.as_str()), ("response", challenge)];
+        if let Some(ip) = ip {
+            form.push(("remoteip", ip));
+        }
+
+        let response: TurnstileResponse = self
+            .client
+            .post(VERIFY_URL)
+            .form(&form)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(CaptchaOutcome {
+            success: response.success,
+            score: None,
+            message: (!response.success).then(|| {
+                format!("人机验证失败: {}", response.error_codes.join(","))
+            }),
+        })
+    }
+}
@@ -1,6 +1,8 @@
 use std::sync::Arc;
 
-use crate::{models::ids::ProjectId, routes::ApiError};
+use crate::{
+    analytics::AnalyticsBreakdown, models::ids::ProjectId, routes::ApiError,
+};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -18,6 +20,31 @@ pub struct ReturnCountry {
     pub total: u64,
 }
 
+#[derive(clickhouse::Row, Serialize, Deserialize, Clone, Debug)]
+pub struct ReturnBreakdown {
+    pub time: u32,
+    pub id: u64,
+    pub key: String,
+    pub total: u64,
+}
+
+#[derive(clickhouse::Row, Serialize, Deserialize, Clone, Debug)]
+pub struct ReturnTotal {
+    pub id: u64,
+    pub total: u64,
+}
+
+#[derive(clickhouse::Row, Serialize, Deserialize, Clone, Debug)]
+pub struct ReturnDownloadFraud {
+    pub id: u64,
+    pub suspicious: u64,
+    pub unique_ips: u64,
+    pub unique_users: u64,
+    pub max_score: u32,
+    pub reasons: Vec<String>,
+    pub last_seen: u32,
+}
+
 // 只能使用 project_id 或 version_id 之一
 // 获取播放时间，返回 ReturnPlaytimes 的 Vec
 pub async fn fetch_playtimes(
@@ -162,3 +189,106 @@ pub async fn fetch_countries_views(
 
     Ok(query.fetch_all().await?)
 }
+
+// 获取可疑下载最多的项目
+pub async fn fetch_download_fraud_projects(
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    limit: u32,
+    client: Arc<clickhouse::Client>,
+) -> Result<Vec<ReturnDownloadFraud>, ApiError> {
+    let query = client
+        .query(
+            "
+            SELECT
+                project_id AS id,
+                count(1) AS suspicious,
+                uniqExact(ip) AS unique_ips,
+                uniqExact(user_id) AS unique_users,
+                max(score) AS max_score,
+                groupUniqArrayArray(reasons) AS reasons,
+                toUnixTimestamp(max(recorded)) AS last_seen
+            FROM download_fraud
+            WHERE recorded BETWEEN ? AND ?
+            GROUP BY project_id
+            ORDER BY suspicious DESC
+            LIMIT ?
+            ",
+        )
+        .bind(start_date.timestamp())
+        .bind(end_date.timestamp())
+        .bind(limit);
+
+    Ok(query.fetch_all().await?)
+}
+
+// 获取项目在时间段内计入的下载总数
+pub async fn fetch_downloads_total(
+    projects: Vec<ProjectId>,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    client: Arc<clickhouse::Client>,
+) -> Result<Vec<ReturnTotal>, ApiError> {
+    let query = client
+        .query(
+            "
+            SELECT
+                project_id AS id,
+                count(1) AS total
+            FROM downloads
+            WHERE recorded BETWEEN ? AND ? AND project_id IN ?
+            GROUP BY project_id
+            ",
+        )
+        .bind(start_date.timestamp())
+        .bind(end_date.timestamp())
+        .bind(projects.iter().map(|x| x.0).collect::<Vec<_>>());
+
+    Ok(query.fetch_all().await?)
+}
+
+// 按细分维度获取下载量或浏览量
+pub async fn fetch_breakdown(
+    breakdown: AnalyticsBreakdown,
+    projects: Vec<ProjectId>,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    resolution_minutes: u32,
+    client: Arc<clickhouse::Client>,
+) -> Result<Vec<ReturnBreakdown>, ApiError> {
+    let (table, key) = match breakdown {
+        AnalyticsBreakdown::DownloadVersion => {
+            ("downloads", "toString(version_id)")
+        }
+        AnalyticsBreakdown::DownloadGameVersion => {
+            ("downloads", "arrayJoin(game_versions)")
+        }
+        AnalyticsBreakdown::DownloadLoader => {
+            ("downloads", "arrayJoin(loaders)")
+        }
+        AnalyticsBreakdown::DownloadReferrer => ("downloads", "referrer"),
+        AnalyticsBreakdown::DownloadLauncher => ("downloads", "launcher"),
+        AnalyticsBreakdown::ViewReferrer => ("views", "referrer"),
+    };
+
+    let query = client
+        .query(&format!(
+            "
+            SELECT
+                toUnixTimestamp(toStartOfInterval(recorded, toIntervalMinute(?))) AS time,
+                project_id AS id,
+                {key} AS key,
+                count(1) AS total
+            FROM {table}
+            WHERE recorded BETWEEN ? AND ?
+                  AND project_id IN ?
+            GROUP BY time, project_id, key
+            "
+        ))
+        .bind(resolution_minutes)
+        .bind(start_date.timestamp())
+        .bind(end_date.timestamp())
+        .bind(projects.iter().map(|x| x.0).collect::<Vec<_>>());
+
+    Ok(query.fetch_all().await?)
+}
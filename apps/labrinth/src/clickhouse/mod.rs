@@ -70,6 +70,32 @@ pub async fn init_client_with_database(
         .execute()
         .await?;
 
+    client
+        .query(&format!(
+            "
+            CREATE TABLE IF NOT EXISTS {database}.download_fraud
+            (
+                recorded DateTime64(4),
+
+                user_id UInt64,
+                project_id UInt64,
+                version_id UInt64,
+
+                ip IPv6,
+                asn UInt32,
+                user_agent String,
+
+                attempts UInt32,
+                score UInt32,
+                reasons Array(String)
+            )
+            ENGINE = MergeTree()
+            PRIMARY KEY (project_id, recorded, ip)
+            "
+        ))
+        .execute()
+        .await?;
+
     client
         .query(&format!(
             "
@@ -93,5 +119,29 @@ pub async fn init_client_with_database(
         .execute()
         .await?;
 
+    // 下载和浏览的细分维度
+    client
+        .query(&format!(
+            "
+            ALTER TABLE {database}.downloads
+                ADD COLUMN IF NOT EXISTS referrer String,
+                ADD COLUMN IF NOT EXISTS launcher String,
+                ADD COLUMN IF NOT EXISTS loaders Array(String),
+                ADD COLUMN IF NOT EXISTS game_versions Array(String)
+            "
+        ))
+        .execute()
+        .await?;
+
+    client
+        .query(&format!(
+            "
+            ALTER TABLE {database}.views
+                ADD COLUMN IF NOT EXISTS referrer String
+            "
+        ))
+        .execute()
+        .await?;
+
     Ok(client.with_database(database))
 }
@@ -0,0 +1,240 @@
+use crate::database::models::{DatabaseError, ProjectId, UserId};
+use chrono::{DateTime, Duration, Months, Utc};
+use futures_util::TryStreamExt;
+use serde::{Deserialize, Serialize};
+
+/// 分析报告的发送频率
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFrequency {
+    Weekly,
+    Monthly,
+}
+
+impl ReportFrequency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportFrequency::Weekly => "weekly",
+            ReportFrequency::Monthly => "monthly",
+        }
+    }
+
+    pub fn from_string(string: &str) -> Option<ReportFrequency> {
+        match string {
+            "weekly" => Some(ReportFrequency::Weekly),
+            "monthly" => Some(ReportFrequency::Monthly),
+            _ => None,
+        }
+    }
+
+    /// 以 `end` 结束的一个统计周期的开始时间
+    pub fn period_start(&self, end: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            ReportFrequency::Weekly => end - Duration::weeks(1),
+            ReportFrequency::Monthly => end
+                .checked_sub_months(Months::new(1))
+                .unwrap_or(end - Duration::days(30)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsReportSubscription {
+    pub id: i64,
+    pub user_id: UserId,
+    pub frequency: ReportFrequency,
+    pub project_ids: Vec<ProjectId>,
+    pub last_sent: Option<DateTime<Utc>>,
+    pub created: DateTime<Utc>,
+}
+
+impl AnalyticsReportSubscription {
+    pub async fn insert(
+        user_id: UserId,
+        frequency: ReportFrequency,
+        project_ids: &[ProjectId],
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<i64, DatabaseError> {
+        let project_ids = project_ids.iter().map(|x| x.0).collect::<Vec<_>>();
+
+        let id = sqlx::query!(
+            "
+            INSERT INTO analytics_report_subscriptions (user_id, frequency, project_ids)
+            VALUES ($1, $2, $3)
+            RETURNING id
+            ",
+            user_id.0,
+            frequency.as_str(),
+            &project_ids
+        )
+        .fetch_one(&mut **transaction)
+        .await?
+        .id;
+
+        Ok(id)
+    }
+
+    pub async fn get<'a, E>(
+        id: i64,
+        exec: E,
+    ) -> Result<Option<AnalyticsReportSubscription>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let subscription = sqlx::query!(
+            "
+            SELECT id, user_id, frequency, project_ids, last_sent, created
+            FROM analytics_report_subscriptions
+            WHERE id = $1
+            ",
+            id
+        )
+        .fetch_optional(exec)
+        .await?
+        .map(|row| AnalyticsReportSubscription {
+            id: row.id,
+            user_id: UserId(row.user_id),
+            frequency: ReportFrequency::from_string(&row.frequency)
+                .unwrap_or(ReportFrequency::Weekly),
+            project_ids: row.project_ids.into_iter().map(ProjectId).collect(),
+            last_sent: row.last_sent,
+            created: row.created,
+        });
+
+        Ok(subscription)
+    }
+
+    pub async fn get_user<'a, E>(
+        user_id: UserId,
+        exec: E,
+    ) -> Result<Vec<AnalyticsReportSubscription>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let subscriptions = sqlx::query!(
+            "
+            SELECT id, user_id, frequency, project_ids, last_sent, created
+            FROM analytics_report_subscriptions
+            WHERE user_id = $1
+            ORDER BY created
+            ",
+            user_id.0
+        )
+        .fetch(exec)
+        .map_ok(|row| AnalyticsReportSubscription {
+            id: row.id,
+            user_id: UserId(row.user_id),
+            frequency: ReportFrequency::from_string(&row.frequency)
+                .unwrap_or(ReportFrequency::Weekly),
+            project_ids: row.project_ids.into_iter().map(ProjectId).collect(),
+            last_sent: row.last_sent,
+            created: row.created,
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(subscriptions)
+    }
+
+    /// 到期需要发送的订阅：从未发送过，或距上次发送已满一个周期
+    pub async fn get_due<'a, E>(
+        now: DateTime<Utc>,
+        limit: i64,
+        exec: E,
+    ) -> Result<Vec<AnalyticsReportSubscription>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let subscriptions = sqlx::query!(
+            "
+            SELECT id, user_id, frequency, project_ids, last_sent, created
+            FROM analytics_report_subscriptions
+            WHERE last_sent IS NULL
+            OR (frequency = 'weekly' AND last_sent <= $1::timestamptz - INTERVAL '7 days')
+            OR (frequency = 'monthly' AND last_sent <= $1::timestamptz - INTERVAL '1 month')
+            ORDER BY last_sent NULLS FIRST
+            LIMIT $2
+            ",
+            now,
+            limit
+        )
+        .fetch(exec)
+        .map_ok(|row| AnalyticsReportSubscription {
+            id: row.id,
+            user_id: UserId(row.user_id),
+            frequency: ReportFrequency::from_string(&row.frequency)
+                .unwrap_or(ReportFrequency::Weekly),
+            project_ids: row.project_ids.into_iter().map(ProjectId).collect(),
+            last_sent: row.last_sent,
+            created: row.created,
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(subscriptions)
+    }
+
+    pub async fn update(
+        id: i64,
+        frequency: ReportFrequency,
+        project_ids: &[ProjectId],
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        let project_ids = project_ids.iter().map(|x| x.0).collect::<Vec<_>>();
+
+        sqlx::query!(
+            "
+            UPDATE analytics_report_subscriptions
+            SET frequency = $2, project_ids = $3
+            WHERE id = $1
+            ",
+            id,
+            frequency.as_str(),
+            &project_ids
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn mark_sent<'a, E>(
+        id: i64,
+        sent: DateTime<Utc>,
+        exec: E,
+    ) -> Result<(), DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        sqlx::query!(
+            "
+            UPDATE analytics_report_subscriptions
+            SET last_sent = $2
+            WHERE id = $1
+            ",
+            id,
+            sent
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove(
+        id: i64,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            DELETE FROM analytics_report_subscriptions
+            WHERE id = $1
+            ",
+            id
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use super::ids::*;
 use crate::auth::AuthProvider;
+use crate::auth::oauth::pkce::CodeChallenge;
 use crate::auth::oauth::uris::OAuthRedirectUris;
 use crate::database::models::DatabaseError;
 use crate::database::redis::RedisPool;
@@ -10,6 +11,9 @@ use rand::distributions::Alphanumeric;
 use rand_chacha::ChaCha20Rng;
 use rand_chacha::rand_core::SeedableRng;
 use serde::{Deserialize, Serialize};
+use webauthn_rs::prelude::{
+    DiscoverableAuthentication, PasskeyAuthentication, PasskeyRegistration,
+};
 
 const FLOWS_NAMESPACE: &str = "flows";
 
@@ -24,6 +28,28 @@ pub enum Flow {
     Login2FA {
         user_id: UserId,
     },
+    /// 高风险登录需要通过短信验证码确认
+    LoginStepUp {
+        user_id: UserId,
+    },
+    /// 异常登录提醒邮件中的“这不是我”链接
+    LoginAlert {
+        user_id: UserId,
+    },
+    WebauthnRegistration {
+        user_id: UserId,
+        name: String,
+        state: PasskeyRegistration,
+    },
+    WebauthnLogin {
+        state: DiscoverableAuthentication,
+    },
+    WebauthnLogin2FA {
+        user_id: UserId,
+        /// 对应的 `Login2FA` 流程
+        login_flow: String,
+        state: PasskeyAuthentication,
+    },
     Initialize2FA {
         user_id: UserId,
         secret: String,
@@ -43,6 +69,10 @@ pub enum Flow {
         scopes: Scopes,
         redirect_uris: OAuthRedirectUris,
         state: Option<String>,
+        #[serde(default)]
+        code_challenge: Option<CodeChallenge>,
+        #[serde(default)]
+        nonce: Option<String>,
     },
     OAuthAuthorizationCodeSupplied {
         user_id: UserId,
@@ -50,6 +80,10 @@ pub enum Flow {
         authorization_id: OAuthClientAuthorizationId,
         scopes: Scopes,
         original_redirect_uri: Option<String>, // Needed for https://datatracker.ietf.org/doc/html/rfc6749#section-4.1.3
+        #[serde(default)]
+        code_challenge: Option<CodeChallenge>, // Needed for https://datatracker.ietf.org/doc/html/rfc7636#section-4.6
+        #[serde(default)]
+        nonce: Option<String>, // Needed for https://openid.net/specs/openid-connect-core-1_0.html#IDToken
     },
 }
 
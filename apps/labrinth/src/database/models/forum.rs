@@ -26,6 +26,9 @@ pub struct PostQuery {
     pub reply_to_deleted: bool,
     pub replies: Vec<Replay>,
     pub deleted: bool,
+    // 兼容缓存中没有该字段的旧数据
+    #[serde(default)]
+    pub edited_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -63,6 +66,11 @@ pub struct Discussion {
     pub deleted_at: Option<DateTime<Utc>>,
     pub last_post_time: DateTime<Utc>,
     pub project_id: Option<ProjectId>,
+    // 兼容缓存中没有该字段的旧数据
+    #[serde(default)]
+    pub edited_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub accepted_post_id: Option<PostId>,
 }
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct QueryDiscussion {
@@ -70,6 +78,19 @@ pub struct QueryDiscussion {
     pub posts: Vec<PostIndex>,
 }
 
+/// 论坛编辑记录，保存每次编辑前的版本
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ForumEditHistory {
+    pub id: i64,
+    pub discussion_id: DiscussionId,
+    pub post_id: Option<PostId>,
+    pub title: Option<String>,
+    pub content: String,
+    pub edited_by: UserId,
+    pub editor_name: Option<String>,
+    pub edited_at: DateTime<Utc>,
+}
+
 impl Discussion {
     pub async fn clear_cache(
         ids: &[DiscussionId],
@@ -130,10 +151,11 @@ impl Discussion {
         content: String,
         transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
         sqlx::query!(
-            "update discussions set content=$1,updated_at=$2 where id=$3",
+            "update discussions set content=$1,updated_at=$2,edited_at=$2 where id=$3",
             content,
-            Utc::now(),
+            now,
             self.id.0
         )
         .execute(&mut **transaction)
@@ -146,10 +168,11 @@ impl Discussion {
         title: String,
         transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
         sqlx::query!(
-            "update discussions set title=$1,updated_at=$2 where id=$3",
+            "update discussions set title=$1,updated_at=$2,edited_at=$2 where id=$3",
             title,
-            Utc::now(),
+            now,
             self.id.0
         )
         .execute(&mut **transaction)
@@ -172,6 +195,20 @@ impl Discussion {
 
         Ok(())
     }
+    pub async fn update_accepted_post(
+        &self,
+        post_id: Option<PostId>,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE discussions SET accepted_post_id = $1 WHERE id = $2",
+            post_id.map(|x| x.0),
+            self.id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+        Ok(())
+    }
     pub async fn update_last_post_time(
         &self,
         transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
@@ -330,6 +367,8 @@ impl Discussion {
                            d.deleted as "deleted!",
                            d.deleted_at,
                            d.last_post_time,
+                           d.edited_at,
+                           d.accepted_post_id,
                            u.username as "user_name?",
                            u.avatar_url as "avatar_url?",
                            (SELECT m.id FROM mods m WHERE m.forum = d.id LIMIT 1) as project_id
@@ -374,6 +413,8 @@ impl Discussion {
                                     deleted_at: m.deleted_at,
                                     last_post_time: m.last_post_time.unwrap_or_else(chrono::Utc::now),
                                     project_id,
+                                    edited_at: m.edited_at,
+                                    accepted_post_id: m.accepted_post_id.map(PostId),
                                 },
                             },
                         );
@@ -456,6 +497,24 @@ impl PostQuery {
         Ok(())
     }
 
+    pub async fn update_content(
+        id: PostId,
+        content: &str,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
+        sqlx::query!(
+            "UPDATE posts SET content = $1, updated_at = $2, edited_at = $2 WHERE id = $3",
+            content,
+            now,
+            id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn get_many<'a, E>(
         ids: &[i64],
         discussion_id: &DiscussionId,
@@ -620,6 +679,7 @@ impl PostQuery {
                             reply_content,
                             reply_to_deleted,
                             deleted: w.deleted,
+                            edited_at: w.edited_at,
                         },
                     );
                 }
@@ -631,3 +691,102 @@ impl PostQuery {
         Ok(posts)
     }
 }
+
+impl ForumEditHistory {
+    pub async fn insert(
+        discussion_id: DiscussionId,
+        post_id: Option<PostId>,
+        title: Option<&str>,
+        content: &str,
+        edited_by: UserId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "
+            INSERT INTO forum_edit_history (discussion_id, post_id, title, content, edited_by)
+            VALUES ($1, $2, $3, $4, $5)
+            ",
+            discussion_id.0,
+            post_id.map(|x| x.0),
+            title,
+            content,
+            edited_by.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 获取讨论主题本身的编辑记录（不包含回复），按时间倒序
+    pub async fn get_for_discussion<'a, E>(
+        discussion_id: DiscussionId,
+        exec: E,
+    ) -> Result<Vec<ForumEditHistory>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let history = sqlx::query!(
+            "
+            SELECT h.id, h.discussion_id, h.post_id, h.title, h.content, h.edited_by, h.edited_at,
+                   u.username as \"editor_name?\"
+            FROM forum_edit_history h
+            LEFT JOIN users u ON h.edited_by = u.id
+            WHERE h.discussion_id = $1 AND h.post_id IS NULL
+            ORDER BY h.edited_at DESC
+            ",
+            discussion_id.0
+        )
+        .fetch(exec)
+        .map_ok(|r| ForumEditHistory {
+            id: r.id,
+            discussion_id: DiscussionId(r.discussion_id),
+            post_id: r.post_id.map(PostId),
+            title: r.title,
+            content: r.content,
+            edited_by: UserId(r.edited_by),
+            editor_name: r.editor_name,
+            edited_at: r.edited_at,
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(history)
+    }
+
+    /// 获取单条回复的编辑记录，按时间倒序
+    pub async fn get_for_post<'a, E>(
+        post_id: PostId,
+        exec: E,
+    ) -> Result<Vec<ForumEditHistory>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let history = sqlx::query!(
+            "
+            SELECT h.id, h.discussion_id, h.post_id, h.title, h.content, h.edited_by, h.edited_at,
+                   u.username as \"editor_name?\"
+            FROM forum_edit_history h
+            LEFT JOIN users u ON h.edited_by = u.id
+            WHERE h.post_id = $1
+            ORDER BY h.edited_at DESC
+            ",
+            post_id.0
+        )
+        .fetch(exec)
+        .map_ok(|r| ForumEditHistory {
+            id: r.id,
+            discussion_id: DiscussionId(r.discussion_id),
+            post_id: r.post_id.map(PostId),
+            title: r.title,
+            content: r.content,
+            edited_by: UserId(r.edited_by),
+            editor_name: r.editor_name,
+            edited_at: r.edited_at,
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(history)
+    }
+}
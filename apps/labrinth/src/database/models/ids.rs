@@ -256,6 +256,14 @@ generate_ids!(
     OAuthAccessTokenId
 );
 
+generate_ids!(
+    pub generate_oauth_refresh_token_id,
+    OAuthRefreshTokenId,
+    8,
+    "SELECT EXISTS(SELECT 1 FROM oauth_refresh_tokens WHERE id=$1)",
+    OAuthRefreshTokenId
+);
+
 generate_ids!(
     pub generate_payout_id,
     PayoutId,
@@ -360,6 +368,14 @@ generate_ids!(
     PaymentOrderId
 );
 
+generate_ids!(
+    pub generate_webauthn_credential_id,
+    WebauthnCredentialId,
+    8,
+    "SELECT EXISTS(SELECT 1 FROM user_webauthn_credentials WHERE id=$1)",
+    WebauthnCredentialId
+);
+
 #[derive(
     Copy, Clone, Debug, PartialEq, Eq, Type, Hash, Serialize, Deserialize,
 )]
@@ -586,6 +602,12 @@ pub struct OAuthRedirectUriId(pub i64);
 #[sqlx(transparent)]
 pub struct OAuthAccessTokenId(pub i64);
 
+#[derive(
+    Copy, Clone, Debug, Type, Serialize, Deserialize, Eq, PartialEq, Hash,
+)]
+#[sqlx(transparent)]
+pub struct OAuthRefreshTokenId(pub i64);
+
 #[derive(
     Copy, Clone, Debug, Type, Serialize, Deserialize, Eq, PartialEq, Hash,
 )]
@@ -967,3 +989,20 @@ pub struct UserPurchaseId(pub i64);
 )]
 #[sqlx(transparent)]
 pub struct PaymentOrderId(pub i64);
+
+#[derive(
+    Copy, Clone, Debug, Type, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[sqlx(transparent)]
+pub struct WebauthnCredentialId(pub i64);
+
+impl From<ids::WebauthnCredentialId> for WebauthnCredentialId {
+    fn from(id: ids::WebauthnCredentialId) -> Self {
+        WebauthnCredentialId(id.0 as i64)
+    }
+}
+impl From<WebauthnCredentialId> for ids::WebauthnCredentialId {
+    fn from(id: WebauthnCredentialId) -> Self {
+        ids::WebauthnCredentialId(id.0 as u64)
+    }
+}
@@ -1,5 +1,5 @@
 use crate::database::models::{
-    DatabaseError, IssuesCommentsId, IssuesId, ProjectId, UserId,
+    DatabaseError, IssuesCommentsId, IssuesId, ProjectId, UserId, VersionId,
 };
 use crate::database::redis::RedisPool;
 use chrono::{DateTime, Utc};
@@ -15,6 +15,8 @@ pub const ISSUE_LABELS_NAMESPACE: &str = "issue_labels";
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct IssueLabel {
     pub id: i32,
+    /// 为空时是全站通用标签
+    pub project_id: Option<ProjectId>,
     pub name: String,
     pub color: String,
     pub description: Option<String>,
@@ -102,6 +104,9 @@ pub struct Issue {
     pub deleted_at: Option<DateTime<Utc>>,
     pub labels: Vec<IssueLabel>,
     pub assignees: Vec<IssueAssignee>,
+    pub accepted_comment_id: Option<IssuesCommentsId>,
+    pub milestone_id: Option<i32>,
+    pub template_id: Option<i32>,
 }
 
 // 查询Issue结构
@@ -133,7 +138,7 @@ impl Issue {
         transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), sqlx::Error> {
         sqlx::query!(
-            "INSERT INTO issues(id, mod_id, title, body, state, created_at, updated_at, closed_at, author_id, locked, deleted, deleted_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)",
+            "INSERT INTO issues(id, mod_id, title, body, state, created_at, updated_at, closed_at, author_id, locked, deleted, deleted_at, milestone_id, template_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)",
             self.id.0,
             self.mod_id.0,
             self.title,
@@ -145,7 +150,9 @@ impl Issue {
             self.author_id.0,
             self.locked,
             self.deleted,
-            self.deleted_at
+            self.deleted_at,
+            self.milestone_id,
+            self.template_id
         )
         .execute(&mut **transaction)
         .await?;
@@ -251,6 +258,24 @@ impl Issue {
         Ok(())
     }
 
+    // 更新Issue里程碑
+    pub async fn update_milestone(
+        &self,
+        milestone_id: Option<i32>,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE issues SET milestone_id=$1, updated_at=$2 WHERE id=$3",
+            milestone_id,
+            Utc::now(),
+            self.id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
     // 锁定/解锁Issue
     pub async fn update_locked(
         &self,
@@ -269,6 +294,24 @@ impl Issue {
         Ok(())
     }
 
+    // 设置被采纳的回答，传入 None 取消采纳
+    pub async fn update_accepted_comment(
+        &self,
+        comment_id: Option<IssuesCommentsId>,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE issues SET accepted_comment_id=$1, updated_at=$2 WHERE id=$3",
+            comment_id.map(|id| id.0),
+            Utc::now(),
+            self.id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
     // 删除Issue
     pub async fn delete_issue(
         &self,
@@ -408,6 +451,7 @@ impl Issue {
                             i.updated_at as "updated_at!", i.closed_at,
                             i.author_id as "author_id!", i.locked as "locked!",
                             i.deleted as "deleted!", i.deleted_at,
+                            i.accepted_comment_id, i.milestone_id, i.template_id,
                             u.username as "author_name?", u.avatar_url as "author_avatar?"
                      FROM issues i
                      LEFT JOIN users u ON i.author_id = u.id
@@ -445,6 +489,9 @@ impl Issue {
                                     deleted_at: m.deleted_at,
                                     labels: Vec::new(), // 稍后填充
                                     assignees: Vec::new(), // 稍后填充
+                                    accepted_comment_id: m.accepted_comment_id.map(IssuesCommentsId),
+                                    milestone_id: m.milestone_id,
+                                    template_id: m.template_id,
                                 },
                             },
                         );
@@ -455,7 +502,7 @@ impl Issue {
 
                 // 获取标签数据
                 let labels_data = sqlx::query!(
-                    "SELECT ila.issue_id, il.id, il.name, il.color, il.description, il.created_at
+                    "SELECT ila.issue_id, il.id, il.mod_id, il.name, il.color, il.description, il.created_at
                      FROM issue_label_associations ila
                      JOIN issue_labels il ON ila.label_id = il.id
                      WHERE ila.issue_id = ANY($1)",
@@ -469,6 +516,7 @@ impl Issue {
                     if let Some(mut issue) = issues.get_mut(&label_row.issue_id) {
                         issue.inner.labels.push(IssueLabel {
                             id: label_row.id,
+                            project_id: label_row.mod_id.map(ProjectId),
                             name: label_row.name,
                             color: label_row.color,
                             description: Some(label_row.description.unwrap_or_default()),
@@ -551,12 +599,13 @@ impl IssueLabel {
                     let mut exec = exec.acquire().await?;
 
                     let labels: Vec<IssueLabel> = sqlx::query!(
-                        "SELECT id, name, color, description, created_at FROM issue_labels ORDER BY id ASC"
+                        "SELECT id, mod_id, name, color, description, created_at FROM issue_labels WHERE mod_id IS NULL ORDER BY id ASC"
                     )
                     .fetch(&mut *exec)
                     .try_fold(Vec::new(), |mut acc, row| {
                         acc.push(IssueLabel {
                             id: row.id,
+                            project_id: row.mod_id.map(ProjectId),
                             name: row.name,
                             color: row.color,
                             description: Some(row.description.unwrap_or_default()),
@@ -573,6 +622,508 @@ impl IssueLabel {
 
         Ok(labels)
     }
+
+    // 获取项目可用的标签（全站通用标签 + 项目自定义标签）
+    pub async fn get_project<'a, E>(
+        project_id: ProjectId,
+        exec: E,
+        redis: &RedisPool,
+    ) -> Result<Vec<IssueLabel>, DatabaseError>
+    where
+        E: sqlx::Acquire<'a, Database = sqlx::Postgres>,
+    {
+        let labels = redis
+            .get_cached_key(
+                ISSUE_LABELS_NAMESPACE,
+                format!("project_{}", project_id.0),
+                || async move {
+                    let mut exec = exec.acquire().await?;
+
+                    let labels: Vec<IssueLabel> = sqlx::query!(
+                        "
+                        SELECT id, mod_id, name, color, description, created_at
+                        FROM issue_labels
+                        WHERE mod_id IS NULL OR mod_id = $1
+                        ORDER BY mod_id NULLS FIRST, id ASC
+                        ",
+                        project_id.0
+                    )
+                    .fetch(&mut *exec)
+                    .try_fold(Vec::new(), |mut acc, row| {
+                        acc.push(IssueLabel {
+                            id: row.id,
+                            project_id: row.mod_id.map(ProjectId),
+                            name: row.name,
+                            color: row.color,
+                            description: Some(
+                                row.description.unwrap_or_default(),
+                            ),
+                            created_at: row.created_at,
+                        });
+                        async move { Ok(acc) }
+                    })
+                    .await?;
+
+                    Ok(labels)
+                },
+            )
+            .await?;
+
+        Ok(labels)
+    }
+
+    pub async fn clear_project_cache(
+        project_id: ProjectId,
+        redis: &RedisPool,
+    ) -> Result<(), DatabaseError> {
+        let mut redis = redis.connect().await?;
+        redis
+            .delete(ISSUE_LABELS_NAMESPACE, format!("project_{}", project_id.0))
+            .await?;
+        Ok(())
+    }
+
+    // 创建项目标签
+    pub async fn insert(
+        project_id: ProjectId,
+        name: &str,
+        color: &str,
+        description: &str,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<i32, sqlx::Error> {
+        let id = sqlx::query!(
+            "
+            INSERT INTO issue_labels (mod_id, name, color, description)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id
+            ",
+            project_id.0,
+            name,
+            color,
+            description
+        )
+        .fetch_one(&mut **transaction)
+        .await?
+        .id;
+
+        Ok(id)
+    }
+
+    // 修改项目标签，只能修改属于该项目的标签
+    pub async fn update(
+        id: i32,
+        project_id: ProjectId,
+        name: &str,
+        color: &str,
+        description: &str,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "
+            UPDATE issue_labels SET name = $1, color = $2, description = $3
+            WHERE id = $4 AND mod_id = $5
+            ",
+            name,
+            color,
+            description,
+            id,
+            project_id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // 使用该标签的Issue，用于修改标签后清除缓存
+    pub async fn get_issue_ids<'a, E>(
+        id: i32,
+        exec: E,
+    ) -> Result<Vec<IssuesId>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let issues = sqlx::query!(
+            "SELECT issue_id FROM issue_label_associations WHERE label_id = $1",
+            id
+        )
+        .fetch(exec)
+        .map_ok(|x| IssuesId(x.issue_id))
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(issues)
+    }
+
+    // 删除项目标签，返回受影响的Issue用于清除缓存
+    pub async fn remove(
+        id: i32,
+        project_id: ProjectId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<IssuesId>, sqlx::Error> {
+        let issues = sqlx::query!(
+            "
+            DELETE FROM issue_label_associations
+            WHERE label_id = (SELECT id FROM issue_labels WHERE id = $1 AND mod_id = $2)
+            RETURNING issue_id
+            ",
+            id,
+            project_id.0
+        )
+        .fetch_all(&mut **transaction)
+        .await?
+        .into_iter()
+        .map(|x| IssuesId(x.issue_id))
+        .collect();
+
+        sqlx::query!(
+            "DELETE FROM issue_labels WHERE id = $1 AND mod_id = $2",
+            id,
+            project_id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(issues)
+    }
+}
+
+// Issue里程碑
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IssueMilestone {
+    pub id: i32,
+    pub project_id: ProjectId,
+    pub title: String,
+    pub description: String,
+    pub version_number: Option<String>,
+    pub version_id: Option<VersionId>,
+    pub state: String,
+    pub due_date: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub open_issues: i64,
+    pub closed_issues: i64,
+}
+
+impl IssueMilestone {
+    pub async fn get_project<'a, E>(
+        project_id: ProjectId,
+        exec: E,
+    ) -> Result<Vec<IssueMilestone>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let milestones = sqlx::query!(
+            r#"
+            SELECT m.id, m.mod_id, m.title, m.description, m.version_number, m.version_id,
+                   m.state, m.due_date, m.created_at, m.closed_at,
+                   COUNT(i.id) FILTER (WHERE i.state = 'open') as "open_issues!",
+                   COUNT(i.id) FILTER (WHERE i.state = 'closed') as "closed_issues!"
+            FROM issue_milestones m
+            LEFT JOIN issues i ON i.milestone_id = m.id AND i.deleted = false
+            WHERE m.mod_id = $1
+            GROUP BY m.id
+            ORDER BY m.state DESC, m.due_date ASC NULLS LAST, m.id ASC
+            "#,
+            project_id.0
+        )
+        .fetch(exec)
+        .map_ok(|row| IssueMilestone {
+            id: row.id,
+            project_id: ProjectId(row.mod_id),
+            title: row.title,
+            description: row.description,
+            version_number: row.version_number,
+            version_id: row.version_id.map(VersionId),
+            state: row.state,
+            due_date: row.due_date,
+            created_at: row.created_at,
+            closed_at: row.closed_at,
+            open_issues: row.open_issues,
+            closed_issues: row.closed_issues,
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(milestones)
+    }
+
+    pub async fn insert(
+        project_id: ProjectId,
+        title: &str,
+        description: &str,
+        version_number: Option<&str>,
+        due_date: Option<DateTime<Utc>>,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<i32, sqlx::Error> {
+        let id = sqlx::query!(
+            "
+            INSERT INTO issue_milestones (mod_id, title, description, version_number, due_date)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id
+            ",
+            project_id.0,
+            title,
+            description,
+            version_number,
+            due_date
+        )
+        .fetch_one(&mut **transaction)
+        .await?
+        .id;
+
+        Ok(id)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update(
+        id: i32,
+        project_id: ProjectId,
+        title: &str,
+        description: &str,
+        version_number: Option<&str>,
+        due_date: Option<DateTime<Utc>>,
+        state: &str,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "
+            UPDATE issue_milestones
+            SET title = $1, description = $2, version_number = $3, due_date = $4, state = $5::varchar,
+                closed_at = CASE WHEN $5::varchar = 'closed' THEN COALESCE(closed_at, NOW()) ELSE NULL END
+            WHERE id = $6 AND mod_id = $7
+            ",
+            title,
+            description,
+            version_number,
+            due_date,
+            state,
+            id,
+            project_id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // 删除里程碑，返回受影响的Issue用于清除缓存
+    pub async fn remove(
+        id: i32,
+        project_id: ProjectId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<IssuesId>, sqlx::Error> {
+        let issues = sqlx::query!(
+            "
+            UPDATE issues SET milestone_id = NULL
+            WHERE milestone_id = (SELECT id FROM issue_milestones WHERE id = $1 AND mod_id = $2)
+            RETURNING id
+            ",
+            id,
+            project_id.0
+        )
+        .fetch_all(&mut **transaction)
+        .await?
+        .into_iter()
+        .map(|x| IssuesId(x.id))
+        .collect();
+
+        sqlx::query!(
+            "DELETE FROM issue_milestones WHERE id = $1 AND mod_id = $2",
+            id,
+            project_id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(issues)
+    }
+
+    // 检查里程碑是否属于该项目
+    pub async fn exists_in_project<'a, E>(
+        id: i32,
+        project_id: ProjectId,
+        exec: E,
+    ) -> Result<bool, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let exists = sqlx::query!(
+            "SELECT EXISTS(SELECT 1 FROM issue_milestones WHERE id = $1 AND mod_id = $2)",
+            id,
+            project_id.0
+        )
+        .fetch_one(exec)
+        .await?
+        .exists
+        .unwrap_or(false);
+
+        Ok(exists)
+    }
+
+    // 版本发布后关闭关联该版本号的里程碑
+    pub async fn close_for_version(
+        project_id: ProjectId,
+        version_number: &str,
+        version_id: VersionId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "
+            UPDATE issue_milestones
+            SET state = 'closed', closed_at = NOW(), version_id = $3
+            WHERE mod_id = $1 AND version_number = $2 AND state = 'open'
+            ",
+            project_id.0,
+            version_number,
+            version_id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+}
+
+// 问题模板中的单个字段
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IssueTemplateField {
+    pub id: String,
+    pub label: String,
+    /// input / textarea / dropdown
+    pub field_type: String,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// dropdown 的可选项
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+// 问题模板
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IssueTemplate {
+    pub id: i32,
+    pub project_id: ProjectId,
+    pub name: String,
+    pub template_type: String,
+    pub description: String,
+    pub fields: Vec<IssueTemplateField>,
+    pub sort_order: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl IssueTemplate {
+    pub async fn get_project<'a, E>(
+        project_id: ProjectId,
+        exec: E,
+    ) -> Result<Vec<IssueTemplate>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let templates = sqlx::query!(
+            "
+            SELECT id, mod_id, name, template_type, description, fields, sort_order, created_at
+            FROM issue_templates
+            WHERE mod_id = $1
+            ORDER BY sort_order ASC, id ASC
+            ",
+            project_id.0
+        )
+        .fetch(exec)
+        .map_ok(|row| IssueTemplate {
+            id: row.id,
+            project_id: ProjectId(row.mod_id),
+            name: row.name,
+            template_type: row.template_type,
+            description: row.description,
+            fields: serde_json::from_value(row.fields).unwrap_or_default(),
+            sort_order: row.sort_order,
+            created_at: row.created_at,
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(templates)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert(
+        project_id: ProjectId,
+        name: &str,
+        template_type: &str,
+        description: &str,
+        fields: &[IssueTemplateField],
+        sort_order: i32,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<i32, DatabaseError> {
+        let id = sqlx::query!(
+            "
+            INSERT INTO issue_templates (mod_id, name, template_type, description, fields, sort_order)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id
+            ",
+            project_id.0,
+            name,
+            template_type,
+            description,
+            serde_json::to_value(fields)?,
+            sort_order
+        )
+        .fetch_one(&mut **transaction)
+        .await?
+        .id;
+
+        Ok(id)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update(
+        id: i32,
+        project_id: ProjectId,
+        name: &str,
+        template_type: &str,
+        description: &str,
+        fields: &[IssueTemplateField],
+        sort_order: i32,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            UPDATE issue_templates
+            SET name = $1, template_type = $2, description = $3, fields = $4, sort_order = $5
+            WHERE id = $6 AND mod_id = $7
+            ",
+            name,
+            template_type,
+            description,
+            serde_json::to_value(fields)?,
+            sort_order,
+            id,
+            project_id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn remove(
+        id: i32,
+        project_id: ProjectId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "DELETE FROM issue_templates WHERE id = $1 AND mod_id = $2",
+            id,
+            project_id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
 }
 
 impl IssueCommentQuery {
@@ -0,0 +1,105 @@
+use super::ids::*;
+use crate::database::models::DatabaseError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 一次成功登录的记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginHistory {
+    pub id: i64,
+    pub user_id: UserId,
+    pub session_id: Option<SessionId>,
+    pub ip: String,
+    pub country: Option<String>,
+    pub city: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub device: String,
+    pub risk_flags: Vec<String>,
+    pub created: DateTime<Utc>,
+}
+
+pub struct LoginHistoryBuilder {
+    pub user_id: UserId,
+    pub session_id: Option<SessionId>,
+    pub ip: String,
+    pub country: Option<String>,
+    pub city: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub device: String,
+    pub risk_flags: Vec<String>,
+}
+
+impl LoginHistoryBuilder {
+    pub async fn insert(
+        &self,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<i64, DatabaseError> {
+        let id = sqlx::query!(
+            "
+            INSERT INTO user_login_history (
+                user_id, session_id, ip, country, city, latitude, longitude,
+                device, risk_flags
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id
+            ",
+            self.user_id as UserId,
+            self.session_id.map(|x| x.0),
+            self.ip,
+            self.country,
+            self.city,
+            self.latitude,
+            self.longitude,
+            self.device,
+            &self.risk_flags,
+        )
+        .fetch_one(&mut **transaction)
+        .await?
+        .id;
+
+        Ok(id)
+    }
+}
+
+impl LoginHistory {
+    /// 用户最近的登录记录，按时间倒序
+    pub async fn get_recent(
+        user_id: UserId,
+        limit: i64,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<Vec<LoginHistory>, DatabaseError> {
+        let history = sqlx::query!(
+            "
+            SELECT id, user_id, session_id, ip, country, city, latitude,
+                longitude, device, risk_flags, created
+            FROM user_login_history
+            WHERE user_id = $1
+            ORDER BY created DESC
+            LIMIT $2
+            ",
+            user_id as UserId,
+            limit,
+        )
+        .fetch_all(exec)
+        .await?;
+
+        Ok(history
+            .into_iter()
+            .map(|x| LoginHistory {
+                id: x.id,
+                user_id: UserId(x.user_id),
+                session_id: x.session_id.map(SessionId),
+                ip: x.ip,
+                country: x.country,
+                city: x.city,
+                latitude: x.latitude,
+                longitude: x.longitude,
+                device: x.device,
+                risk_flags: x.risk_flags,
+                created: x.created,
+            })
+            .collect())
+    }
+}
@@ -1,5 +1,6 @@
 use thiserror::Error;
 
+pub mod analytics_report_item;
 pub mod categories;
 pub mod charge_item;
 pub mod collection_item;
@@ -9,22 +10,33 @@ pub mod ids;
 pub mod image_item;
 pub mod legacy_loader_fields;
 pub mod loader_fields;
+pub mod login_history_item;
+pub mod modpack_file_item;
 pub mod notification_item;
 pub mod oauth_client_authorization_item;
 pub mod oauth_client_item;
+pub mod oauth_device_code_item;
 pub mod oauth_token_item;
+pub mod oidc_key_item;
 pub mod organization_item;
 pub mod pat_item;
 pub mod payout_item;
 pub mod product_item;
 pub mod project_item;
+pub mod reaction_item;
 pub mod report_item;
 pub mod session_item;
+pub mod sms_send_log_item;
+pub mod subscription_item;
 pub mod team_item;
 pub mod thread_item;
+pub mod translation_coverage_item;
+pub mod translation_task_item;
+pub mod upload_session_item;
 pub mod user_item;
 pub mod user_subscription_item;
 pub mod version_item;
+pub mod webauthn_credential_item;
 pub mod wiki_item;
 
 pub mod creator_application_item;
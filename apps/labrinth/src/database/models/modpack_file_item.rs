@@ -0,0 +1,180 @@
+use super::DatabaseError;
+use super::ids::*;
+use futures::TryStreamExt;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+/// 整合包中文件的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModpackFileKind {
+    /// `modrinth.index.json` 中列出的文件
+    Index,
+    /// 覆盖目录中的模组、资源包、光影
+    Override,
+    /// 其他覆盖文件，主要是配置文件
+    Config,
+}
+
+impl ModpackFileKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModpackFileKind::Index => "index",
+            ModpackFileKind::Override => "override",
+            ModpackFileKind::Config => "config",
+        }
+    }
+
+    pub fn from_string(string: &str) -> ModpackFileKind {
+        match string {
+            "index" => ModpackFileKind::Index,
+            "override" => ModpackFileKind::Override,
+            _ => ModpackFileKind::Config,
+        }
+    }
+
+    /// 根据覆盖文件的路径判断类型
+    pub fn from_override_path(path: &str) -> ModpackFileKind {
+        let path = path.split_once('/').map(|(_, rest)| rest).unwrap_or(path);
+
+        if (path.ends_with(".jar") || path.ends_with(".zip"))
+            && (path.starts_with("mods/")
+                || path.starts_with("resourcepacks/")
+                || path.starts_with("shaderpacks/"))
+        {
+            ModpackFileKind::Override
+        } else {
+            ModpackFileKind::Config
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ModpackFileBuilder {
+    pub path: String,
+    pub kind: ModpackFileKind,
+    /// 十六进制 SHA1 字符串的字节，与 hashes 表一致
+    pub sha1: Vec<u8>,
+}
+
+impl ModpackFileBuilder {
+    pub async fn insert_many(
+        files: Vec<ModpackFileBuilder>,
+        version_id: VersionId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        if files.is_empty() {
+            return Ok(());
+        }
+
+        let (paths, kinds, hashes): (Vec<_>, Vec<_>, Vec<_>) = files
+            .into_iter()
+            .map(|x| (x.path, x.kind.as_str().to_string(), x.sha1))
+            .multiunzip();
+
+        sqlx::query!(
+            "
+            INSERT INTO modpack_files (version_id, path, kind, sha1)
+            SELECT $1, * FROM UNNEST($2::varchar[], $3::varchar[], $4::bytea[])
+            ON CONFLICT (version_id, path) DO NOTHING
+            ",
+            version_id as VersionId,
+            &paths[..],
+            &kinds[..],
+            &hashes[..],
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// 整合包版本中的文件，模组文件会通过哈希解析到站内的项目和版本
+#[derive(Clone, Debug)]
+pub struct ModpackFile {
+    pub path: String,
+    pub kind: ModpackFileKind,
+    pub sha1: String,
+    pub project_id: Option<ProjectId>,
+    pub project_name: Option<String>,
+    pub version_id: Option<VersionId>,
+    pub version_number: Option<String>,
+}
+
+impl ModpackFile {
+    pub async fn get_version<'a, E>(
+        version_id: VersionId,
+        exec: E,
+    ) -> Result<Vec<ModpackFile>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let files = sqlx::query!(
+            r#"
+            SELECT mf.path, mf.kind, mf.sha1,
+                r.mod_id as "mod_id?", r.name as "name?",
+                r.version_id as "version_id?", r.version_number as "version_number?"
+            FROM modpack_files mf
+            LEFT JOIN LATERAL (
+                SELECT m.id mod_id, m.name, v.id version_id, v.version_number
+                FROM hashes h
+                INNER JOIN files f ON f.id = h.file_id
+                INNER JOIN versions v ON v.id = f.version_id
+                INNER JOIN mods m ON m.id = v.mod_id
+                WHERE h.algorithm = 'sha1' AND h.hash = mf.sha1
+                AND mf.kind != 'config'
+                ORDER BY v.date_published
+                LIMIT 1
+            ) r ON true
+            WHERE mf.version_id = $1
+            ORDER BY mf.path
+            "#,
+            version_id as VersionId
+        )
+        .fetch(exec)
+        .map_ok(|row| ModpackFile {
+            path: row.path,
+            kind: ModpackFileKind::from_string(&row.kind),
+            sha1: String::from_utf8_lossy(&row.sha1).to_string(),
+            project_id: row.mod_id.map(ProjectId),
+            project_name: row.name,
+            version_id: row.version_id.map(VersionId),
+            version_number: row.version_number,
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(files)
+    }
+
+    /// 同一项目中在该版本之前发布、且记录了文件列表的最近一个版本
+    pub async fn get_previous_version<'a, E>(
+        version_id: VersionId,
+        exec: E,
+    ) -> Result<Option<VersionId>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let previous = sqlx::query!(
+            "
+            SELECT pv.id
+            FROM versions v
+            INNER JOIN versions pv ON pv.mod_id = v.mod_id
+            WHERE v.id = $1 AND pv.id != v.id
+            AND pv.date_published <= v.date_published
+            AND EXISTS (
+                SELECT 1 FROM modpack_files mf WHERE mf.version_id = pv.id
+            )
+            ORDER BY pv.date_published DESC
+            LIMIT 1
+            ",
+            version_id as VersionId
+        )
+        .fetch_optional(exec)
+        .await?
+        .map(|x| VersionId(x.id));
+
+        Ok(previous)
+    }
+}
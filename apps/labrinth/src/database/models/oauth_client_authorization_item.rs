@@ -13,6 +13,9 @@ pub struct OAuthClientAuthorization {
     pub user_id: UserId,
     pub scopes: Scopes,
     pub created: DateTime<Utc>,
+    /// 已通过 ID 令牌或 userinfo 发放给应用的声明
+    pub released_claims: Vec<String>,
+    pub claims_released: Option<DateTime<Utc>>,
 }
 
 struct AuthorizationQueryResult {
@@ -21,6 +24,8 @@ struct AuthorizationQueryResult {
     user_id: i64,
     scopes: i64,
     created: DateTime<Utc>,
+    released_claims: Vec<String>,
+    claims_released: Option<DateTime<Utc>>,
 }
 
 impl From<AuthorizationQueryResult> for OAuthClientAuthorization {
@@ -31,6 +36,8 @@ impl From<AuthorizationQueryResult> for OAuthClientAuthorization {
             user_id: UserId(value.user_id),
             scopes: Scopes::from_postgres(value.scopes),
             created: value.created,
+            released_claims: value.released_claims,
+            claims_released: value.claims_released,
         }
     }
 }
@@ -44,7 +51,8 @@ impl OAuthClientAuthorization {
         let value = sqlx::query_as!(
             AuthorizationQueryResult,
             "
-            SELECT id, client_id, user_id, scopes, created
+            SELECT id, client_id, user_id, scopes, created,
+                released_claims, claims_released
             FROM oauth_client_authorizations
             WHERE client_id=$1 AND user_id=$2
             ",
@@ -64,7 +72,8 @@ impl OAuthClientAuthorization {
         let results = sqlx::query_as!(
             AuthorizationQueryResult,
             "
-            SELECT id, client_id, user_id, scopes, created
+            SELECT id, client_id, user_id, scopes, created,
+                released_claims, claims_released
             FROM oauth_client_authorizations
             WHERE user_id=$1
             ",
@@ -105,6 +114,31 @@ impl OAuthClientAuthorization {
         Ok(())
     }
 
+    /// 记录发放给应用的声明，与之前发放过的声明合并
+    pub async fn record_released_claims(
+        id: OAuthClientAuthorizationId,
+        claims: &[String],
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE oauth_client_authorizations
+            SET released_claims = ARRAY(
+                SELECT DISTINCT unnest(released_claims || $2::text[])
+                ORDER BY 1
+            ),
+            claims_released = CURRENT_TIMESTAMP
+            WHERE id = $1
+            ",
+            id.0,
+            claims,
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn remove(
         client_id: OAuthClientId,
         user_id: UserId,
@@ -21,6 +21,10 @@ pub struct OAuthClient {
     pub raw_icon_url: Option<String>,
     pub max_scopes: Scopes,
     pub secret_hash: String,
+    /// 公开客户端无法保存密钥，换取令牌时必须使用 PKCE
+    pub is_public: bool,
+    /// ID 令牌的签名算法，`RS256` 或 `ES256`
+    pub id_token_signed_response_alg: String,
     pub redirect_uris: Vec<OAuthRedirectUri>,
     pub created: DateTime<Utc>,
     pub created_by: UserId,
@@ -35,6 +39,8 @@ struct ClientQueryResult {
     raw_icon_url: Option<String>,
     max_scopes: i64,
     secret_hash: String,
+    is_public: bool,
+    id_token_signed_response_alg: String,
     created: DateTime<Utc>,
     created_by: i64,
     url: Option<String>,
@@ -58,6 +64,8 @@ macro_rules! select_clients_with_predicate {
                 clients.raw_icon_url as "raw_icon_url?",
                 clients.max_scopes as "max_scopes!",
                 clients.secret_hash as "secret_hash!",
+                clients.is_public as "is_public!",
+                clients.id_token_signed_response_alg as "id_token_signed_response_alg!",
                 clients.created as "created!",
                 clients.created_by as "created_by!",
                 clients.url as "url?",
@@ -141,10 +149,11 @@ impl OAuthClient {
         sqlx::query!(
             "
             INSERT INTO oauth_clients (
-                id, name, icon_url, raw_icon_url, max_scopes, secret_hash, created_by
+                id, name, icon_url, raw_icon_url, max_scopes, secret_hash, is_public,
+                id_token_signed_response_alg, created_by
             )
             VALUES (
-                $1, $2, $3, $4, $5, $6, $7
+                $1, $2, $3, $4, $5, $6, $7, $8, $9
             )
             ",
             self.id.0,
@@ -153,6 +162,8 @@ impl OAuthClient {
             self.raw_icon_url,
             self.max_scopes.to_postgres(),
             self.secret_hash,
+            self.is_public,
+            self.id_token_signed_response_alg,
             self.created_by.0
         )
         .execute(&mut **transaction)
@@ -171,8 +182,9 @@ impl OAuthClient {
         sqlx::query!(
             "
             UPDATE oauth_clients
-            SET name = $1, icon_url = $2, raw_icon_url = $3, max_scopes = $4, url = $5, description = $6
-            WHERE (id = $7)
+            SET name = $1, icon_url = $2, raw_icon_url = $3, max_scopes = $4, url = $5, description = $6,
+                id_token_signed_response_alg = $7
+            WHERE (id = $8)
             ",
             self.name,
             self.icon_url,
@@ -180,6 +192,7 @@ impl OAuthClient {
             self.max_scopes.to_postgres(),
             self.url,
             self.description,
+            self.id_token_signed_response_alg,
             self.id.0,
         )
         .execute(exec)
@@ -259,6 +272,8 @@ impl From<ClientQueryResult> for OAuthClient {
             raw_icon_url: r.raw_icon_url,
             max_scopes: Scopes::from_postgres(r.max_scopes),
             secret_hash: r.secret_hash,
+            is_public: r.is_public,
+            id_token_signed_response_alg: r.id_token_signed_response_alg,
             redirect_uris: redirects,
             created: r.created,
             created_by: UserId(r.created_by),
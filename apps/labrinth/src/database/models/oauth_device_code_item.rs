@@ -0,0 +1,216 @@
+use super::ids::*;
+use crate::database::models::DatabaseError;
+use crate::database::redis::RedisPool;
+use crate::models::pats::Scopes;
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use rand::distributions::Alphanumeric;
+use rand_chacha::ChaCha20Rng;
+use rand_chacha::rand_core::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+const DEVICE_CODES_NAMESPACE: &str = "oauth_device_codes";
+const USER_CODES_NAMESPACE: &str = "oauth_device_user_codes";
+const POLL_STATES_NAMESPACE: &str = "oauth_device_code_polls";
+
+// IETF RFC 8628 Section 6.1 建议的字符集：只含辅音，避免拼出单词和易混淆字符
+const USER_CODE_CHARSET: &[u8] = b"BCDFGHJKLMNPQRSTVWXZ";
+const USER_CODE_LENGTH: usize = 8;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum DeviceCodeStatus {
+    Pending,
+    Approved {
+        user_id: UserId,
+        authorization_id: OAuthClientAuthorizationId,
+    },
+    Denied,
+}
+
+/// 设备授权请求，保存在 Redis 中直到过期或被换取令牌
+///
+/// 参见：IETF RFC 8628 (https://datatracker.ietf.org/doc/html/rfc8628)
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct OAuthDeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub client_id: OAuthClientId,
+    pub scopes: Scopes,
+    pub status: DeviceCodeStatus,
+    pub expires: DateTime<Utc>,
+    /// 客户端两次轮询之间的初始最短间隔（秒）
+    pub interval: i64,
+}
+
+/// 客户端的轮询状态，与授权状态分开保存，避免轮询覆盖用户的确认结果
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct DevicePollState {
+    /// 当前的最短轮询间隔（秒），收到 slow_down 后会增加
+    pub interval: i64,
+    pub last_polled: DateTime<Utc>,
+}
+
+impl OAuthDeviceCode {
+    pub fn new(
+        client_id: OAuthClientId,
+        scopes: Scopes,
+        expires: DateTime<Utc>,
+        interval: i64,
+    ) -> Self {
+        let mut rng = ChaCha20Rng::from_entropy();
+
+        let device_code = (&mut rng)
+            .sample_iter(&Alphanumeric)
+            .take(40)
+            .map(char::from)
+            .collect::<String>();
+        let user_code = (0..USER_CODE_LENGTH)
+            .map(|_| {
+                USER_CODE_CHARSET[rng.gen_range(0..USER_CODE_CHARSET.len())]
+                    as char
+            })
+            .collect::<String>();
+
+        Self {
+            device_code,
+            user_code,
+            client_id,
+            scopes,
+            status: DeviceCodeStatus::Pending,
+            expires,
+            interval,
+        }
+    }
+
+    /// 将用户输入的代码规范化：忽略大小写、空格和连字符
+    pub fn normalize_user_code(user_code: &str) -> String {
+        user_code
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_uppercase())
+            .collect()
+    }
+
+    /// 展示给用户的代码，如 `BCDF-GHJK`
+    pub fn display_user_code(&self) -> String {
+        let (first, second) = self.user_code.split_at(USER_CODE_LENGTH / 2);
+        format!("{first}-{second}")
+    }
+
+    /// 写入（或更新）设备授权请求，过期时间与请求本身一致
+    pub async fn upsert(&self, redis: &RedisPool) -> Result<(), DatabaseError> {
+        let expiry = (self.expires - Utc::now()).num_seconds();
+        if expiry <= 0 {
+            return Ok(());
+        }
+
+        let mut redis = redis.connect().await?;
+        redis
+            .set_serialized_to_json(
+                DEVICE_CODES_NAMESPACE,
+                &self.device_code,
+                self,
+                Some(expiry),
+            )
+            .await?;
+        redis
+            .set(
+                USER_CODES_NAMESPACE,
+                &self.user_code,
+                &self.device_code,
+                Some(expiry),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get(
+        device_code: &str,
+        redis: &RedisPool,
+    ) -> Result<Option<Self>, DatabaseError> {
+        let mut redis = redis.connect().await?;
+
+        redis
+            .get_deserialized_from_json(DEVICE_CODES_NAMESPACE, device_code)
+            .await
+    }
+
+    pub async fn get_by_user_code(
+        user_code: &str,
+        redis: &RedisPool,
+    ) -> Result<Option<Self>, DatabaseError> {
+        let user_code = Self::normalize_user_code(user_code);
+
+        let device_code = {
+            let mut redis = redis.connect().await?;
+            redis.get(USER_CODES_NAMESPACE, &user_code).await?
+        };
+
+        match device_code {
+            Some(device_code) => Self::get(&device_code, redis).await,
+            None => Ok(None),
+        }
+    }
+
+    /// 原子地取出并删除设备授权请求，保证设备码只能被换取一次
+    pub async fn take(
+        device_code: &str,
+        redis: &RedisPool,
+    ) -> Result<Option<Self>, DatabaseError> {
+        let mut redis = redis.connect().await?;
+
+        let Some(value) = redis
+            .get_and_delete(DEVICE_CODES_NAMESPACE, device_code)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let device_code = serde_json::from_str::<Self>(&value)?;
+
+        redis
+            .delete_many([
+                (USER_CODES_NAMESPACE, Some(device_code.user_code.clone())),
+                (POLL_STATES_NAMESPACE, Some(device_code.device_code.clone())),
+            ])
+            .await?;
+
+        Ok(Some(device_code))
+    }
+
+    pub async fn get_poll_state(
+        &self,
+        redis: &RedisPool,
+    ) -> Result<Option<DevicePollState>, DatabaseError> {
+        let mut redis = redis.connect().await?;
+
+        redis
+            .get_deserialized_from_json(
+                POLL_STATES_NAMESPACE,
+                &self.device_code,
+            )
+            .await
+    }
+
+    pub async fn set_poll_state(
+        &self,
+        state: &DevicePollState,
+        redis: &RedisPool,
+    ) -> Result<(), DatabaseError> {
+        let expiry = (self.expires - Utc::now()).num_seconds();
+        if expiry <= 0 {
+            return Ok(());
+        }
+
+        let mut redis = redis.connect().await?;
+        redis
+            .set_serialized_to_json(
+                POLL_STATES_NAMESPACE,
+                &self.device_code,
+                state,
+                Some(expiry),
+            )
+            .await
+    }
+}
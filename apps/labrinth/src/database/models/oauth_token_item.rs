@@ -1,6 +1,6 @@
 use super::{
     DatabaseError, OAuthAccessTokenId, OAuthClientAuthorizationId,
-    OAuthClientId, UserId,
+    OAuthClientId, OAuthRefreshTokenId, UserId,
 };
 use crate::models::pats::Scopes;
 use chrono::{DateTime, Utc};
@@ -92,7 +92,209 @@ impl OAuthAccessToken {
         Ok(time_until_expiration)
     }
 
+    pub async fn remove(
+        id: OAuthAccessTokenId,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            DELETE FROM oauth_access_tokens
+            WHERE id = $1
+            ",
+            id.0
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 删除某次授权下签发的所有访问令牌和刷新令牌
+    pub async fn remove_all_for_authorization(
+        authorization_id: OAuthClientAuthorizationId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            DELETE FROM oauth_refresh_tokens
+            WHERE authorization_id = $1
+            ",
+            authorization_id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        sqlx::query!(
+            "
+            DELETE FROM oauth_access_tokens
+            WHERE authorization_id = $1
+            ",
+            authorization_id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 删除用户所有授权下签发的访问令牌和刷新令牌，授权记录本身保留
+    pub async fn remove_all_for_user(
+        user_id: UserId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            DELETE FROM oauth_refresh_tokens
+            WHERE authorization_id IN (
+                SELECT id FROM oauth_client_authorizations
+                WHERE user_id = $1
+            )
+            ",
+            user_id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        sqlx::query!(
+            "
+            DELETE FROM oauth_access_tokens
+            WHERE authorization_id IN (
+                SELECT id FROM oauth_client_authorizations
+                WHERE user_id = $1
+            )
+            ",
+            user_id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
     pub fn hash_token(token: &str) -> String {
         format!("{:x}", sha2::Sha512::digest(token.as_bytes()))
     }
 }
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct OAuthRefreshToken {
+    pub id: OAuthRefreshTokenId,
+    pub authorization_id: OAuthClientAuthorizationId,
+    pub access_token_id: Option<OAuthAccessTokenId>,
+    pub token_hash: String,
+    pub scopes: Scopes,
+    pub created: DateTime<Utc>,
+    pub expires: DateTime<Utc>,
+    /// 已被轮换的刷新令牌不能再次使用
+    pub rotated: Option<DateTime<Utc>>,
+
+    // Stored separately inside oauth_client_authorizations table
+    pub client_id: OAuthClientId,
+    pub user_id: UserId,
+}
+
+impl OAuthRefreshToken {
+    pub async fn get(
+        token_hash: String,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<Option<OAuthRefreshToken>, DatabaseError> {
+        let value = sqlx::query!(
+            "
+            SELECT
+                tokens.id,
+                tokens.authorization_id,
+                tokens.access_token_id,
+                tokens.token_hash,
+                tokens.scopes,
+                tokens.created,
+                tokens.expires,
+                tokens.rotated,
+                auths.client_id,
+                auths.user_id
+            FROM oauth_refresh_tokens tokens
+            JOIN oauth_client_authorizations auths
+            ON tokens.authorization_id = auths.id
+            WHERE tokens.token_hash = $1
+            ",
+            token_hash
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(value.map(|r| OAuthRefreshToken {
+            id: OAuthRefreshTokenId(r.id),
+            authorization_id: OAuthClientAuthorizationId(r.authorization_id),
+            access_token_id: r.access_token_id.map(OAuthAccessTokenId),
+            token_hash: r.token_hash,
+            scopes: Scopes::from_postgres(r.scopes),
+            created: r.created,
+            expires: r.expires,
+            rotated: r.rotated,
+            client_id: OAuthClientId(r.client_id),
+            user_id: UserId(r.user_id),
+        }))
+    }
+
+    /// Inserts and returns the time until the token expires
+    pub async fn insert(
+        &self,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<chrono::Duration, DatabaseError> {
+        let r = sqlx::query!(
+            "
+            INSERT INTO oauth_refresh_tokens (
+                id, authorization_id, access_token_id, token_hash, scopes
+            )
+            VALUES (
+                $1, $2, $3, $4, $5
+            )
+            RETURNING created, expires
+            ",
+            self.id.0,
+            self.authorization_id.0,
+            self.access_token_id.map(|x| x.0),
+            self.token_hash,
+            self.scopes.to_postgres(),
+        )
+        .fetch_one(exec)
+        .await?;
+
+        Ok(r.expires - r.created)
+    }
+
+    /// 标记刷新令牌已被轮换，返回 false 表示令牌已被其他请求使用
+    pub async fn mark_rotated(
+        id: OAuthRefreshTokenId,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            UPDATE oauth_refresh_tokens
+            SET rotated = CURRENT_TIMESTAMP
+            WHERE id = $1 AND rotated IS NULL
+            ",
+            id.0
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    pub async fn remove(
+        id: OAuthRefreshTokenId,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            DELETE FROM oauth_refresh_tokens
+            WHERE id = $1
+            ",
+            id.0
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+}
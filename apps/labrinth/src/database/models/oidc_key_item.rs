@@ -0,0 +1,114 @@
+use super::DatabaseError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// ID 令牌签名密钥
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct OidcSigningKey {
+    pub kid: String,
+    /// `RS256` 或 `ES256`
+    pub algorithm: String,
+    /// 加密后的 PKCS#8 私钥
+    pub private_key: String,
+    pub public_jwk: serde_json::Value,
+    pub created: DateTime<Utc>,
+    pub retired: Option<DateTime<Utc>>,
+}
+
+impl OidcSigningKey {
+    pub async fn insert(
+        &self,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            INSERT INTO oidc_signing_keys (
+                kid, algorithm, private_key, public_jwk
+            )
+            VALUES (
+                $1, $2, $3, $4
+            )
+            ON CONFLICT (kid) DO UPDATE SET retired = NULL
+            ",
+            self.kid,
+            self.algorithm,
+            self.private_key,
+            self.public_jwk,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        // 同一算法只保留一个签名密钥
+        sqlx::query!(
+            "
+            UPDATE oidc_signing_keys
+            SET retired = CURRENT_TIMESTAMP
+            WHERE algorithm = $1 AND kid != $2 AND retired IS NULL
+            ",
+            self.algorithm,
+            self.kid,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 当前用于签名的密钥
+    pub async fn get_active(
+        algorithm: &str,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<Option<OidcSigningKey>, DatabaseError> {
+        let key = sqlx::query_as!(
+            OidcSigningKey,
+            "
+            SELECT kid, algorithm, private_key, public_jwk, created, retired
+            FROM oidc_signing_keys
+            WHERE algorithm = $1 AND retired IS NULL
+            ORDER BY created DESC
+            LIMIT 1
+            ",
+            algorithm
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(key)
+    }
+
+    /// 所有已发布的密钥，包括尚未过保留期的退役密钥
+    pub async fn get_published(
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<Vec<OidcSigningKey>, DatabaseError> {
+        let keys = sqlx::query_as!(
+            OidcSigningKey,
+            "
+            SELECT kid, algorithm, private_key, public_jwk, created, retired
+            FROM oidc_signing_keys
+            ORDER BY created DESC
+            "
+        )
+        .fetch_all(exec)
+        .await?;
+
+        Ok(keys)
+    }
+
+    /// 删除在指定时间之前退役的密钥
+    pub async fn remove_retired_before(
+        before: DateTime<Utc>,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<u64, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            DELETE FROM oidc_signing_keys
+            WHERE retired IS NOT NULL AND retired < $1
+            ",
+            before
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     database::redis::RedisPool, models::ids::base62_impl::parse_base62,
+    models::teams::ProjectPermissions,
 };
 use dashmap::DashMap;
 use futures::TryStreamExt;
@@ -34,6 +35,10 @@ pub struct Organization {
     pub icon_url: Option<String>,
     pub raw_icon_url: Option<String>,
     pub color: Option<u32>,
+
+    /// 持有敏感权限的成员是否必须使用通行密钥作为第二因素
+    #[serde(default)]
+    pub require_passkey_2fa: bool,
 }
 
 impl Organization {
@@ -43,8 +48,8 @@ impl Organization {
     ) -> Result<(), super::DatabaseError> {
         sqlx::query!(
             "
-            INSERT INTO organizations (id, slug, name, team_id, description, icon_url, raw_icon_url, color)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            INSERT INTO organizations (id, slug, name, team_id, description, icon_url, raw_icon_url, color, require_passkey_2fa)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
             ",
             self.id.0,
             self.slug,
@@ -54,6 +59,7 @@ impl Organization {
             self.icon_url,
             self.raw_icon_url,
             self.color.map(|x| x as i32),
+            self.require_passkey_2fa,
         )
         .execute(&mut **transaction)
         .await?;
@@ -133,7 +139,7 @@ impl Organization {
 
                     let organizations = sqlx::query!(
                         "
-                        SELECT o.id, o.slug, o.name, o.team_id, o.description, o.icon_url, o.raw_icon_url, o.color
+                        SELECT o.id, o.slug, o.name, o.team_id, o.description, o.icon_url, o.raw_icon_url, o.color, o.require_passkey_2fa
                         FROM organizations o
                         WHERE o.id = ANY($1) OR LOWER(o.slug) = ANY($2)
                         GROUP BY o.id;
@@ -152,6 +158,7 @@ impl Organization {
                             icon_url: m.icon_url,
                             raw_icon_url: m.raw_icon_url,
                             color: m.color.map(|x| x as u32),
+                            require_passkey_2fa: m.require_passkey_2fa,
                         };
 
                         acc.insert(m.id, (Some(m.slug), org));
@@ -177,7 +184,7 @@ impl Organization {
     {
         let result = sqlx::query!(
             "
-            SELECT o.id, o.slug, o.name, o.team_id, o.description, o.icon_url, o.raw_icon_url, o.color
+            SELECT o.id, o.slug, o.name, o.team_id, o.description, o.icon_url, o.raw_icon_url, o.color, o.require_passkey_2fa
             FROM organizations o
             LEFT JOIN mods m ON m.organization_id = o.id
             WHERE m.id = $1
@@ -198,12 +205,83 @@ impl Organization {
                 icon_url: result.icon_url,
                 raw_icon_url: result.raw_icon_url,
                 color: result.color.map(|x| x as u32),
+                require_passkey_2fa: result.require_passkey_2fa,
             }))
         } else {
             Ok(None)
         }
     }
 
+    /// 用户是否在任一要求通行密钥的组织（或其项目）中持有敏感权限
+    pub async fn is_passkey_2fa_required<'a, E>(
+        user_id: UserId,
+        permissions: ProjectPermissions,
+        exec: E,
+    ) -> Result<bool, super::DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT EXISTS(
+                SELECT 1
+                FROM team_members tm
+                INNER JOIN organizations o ON o.team_id = tm.team_id
+                WHERE tm.user_id = $1 AND tm.accepted AND o.require_passkey_2fa
+                    AND (tm.is_owner OR tm.permissions & $2 != 0)
+                UNION ALL
+                SELECT 1
+                FROM team_members tm
+                INNER JOIN mods m ON m.team_id = tm.team_id
+                INNER JOIN organizations o ON o.id = m.organization_id
+                WHERE tm.user_id = $1 AND tm.accepted AND o.require_passkey_2fa
+                    AND (tm.is_owner OR tm.permissions & $2 != 0)
+            )
+            ",
+            user_id as UserId,
+            permissions.bits() as i64,
+        )
+        .fetch_one(exec)
+        .await?;
+
+        Ok(result.exists.unwrap_or(false))
+    }
+
+    /// 组织及其项目中持有敏感权限的成员
+    pub async fn get_sensitive_members<'a, E>(
+        id: OrganizationId,
+        permissions: ProjectPermissions,
+        exec: E,
+    ) -> Result<Vec<UserId>, super::DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let user_ids = sqlx::query!(
+            "
+            SELECT tm.user_id
+            FROM team_members tm
+            INNER JOIN organizations o ON o.team_id = tm.team_id
+            WHERE o.id = $1 AND tm.accepted
+                AND (tm.is_owner OR tm.permissions & $2 != 0)
+            UNION
+            SELECT tm.user_id
+            FROM team_members tm
+            INNER JOIN mods m ON m.team_id = tm.team_id
+            WHERE m.organization_id = $1 AND tm.accepted
+                AND (tm.is_owner OR tm.permissions & $2 != 0)
+            ",
+            id as OrganizationId,
+            permissions.bits() as i64,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .filter_map(|x| x.user_id.map(UserId))
+        .collect();
+
+        Ok(user_ids)
+    }
+
     pub async fn remove(
         id: OrganizationId,
         transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
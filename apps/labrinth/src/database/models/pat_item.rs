@@ -24,22 +24,46 @@ pub struct PersonalAccessToken {
     pub created: DateTime<Utc>,
     pub expires: DateTime<Utc>,
     pub last_used: Option<DateTime<Utc>>,
+
+    /// 限定可以操作的项目，None 表示不限制
+    #[serde(default)]
+    pub project_ids: Option<Vec<ProjectId>>,
+    /// 限定可以操作的组织（含组织下的项目），None 表示不限制
+    #[serde(default)]
+    pub organization_ids: Option<Vec<OrganizationId>>,
+    /// 允许使用令牌的 IP 范围，None 表示不限制
+    #[serde(default)]
+    pub allowed_ips: Option<Vec<String>>,
 }
 
 impl PersonalAccessToken {
+    /// 是否限定了可以操作的项目或组织
+    pub fn is_resource_restricted(&self) -> bool {
+        self.project_ids.is_some() || self.organization_ids.is_some()
+    }
+
     pub async fn insert(
         &self,
         transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), DatabaseError> {
+        let project_ids = self
+            .project_ids
+            .as_ref()
+            .map(|x| x.iter().map(|x| x.0).collect::<Vec<_>>());
+        let organization_ids = self
+            .organization_ids
+            .as_ref()
+            .map(|x| x.iter().map(|x| x.0).collect::<Vec<_>>());
+
         sqlx::query!(
             "
             INSERT INTO pats (
                 id, name, access_token, scopes, user_id,
-                expires
+                expires, project_ids, organization_ids, allowed_ips
             )
             VALUES (
                 $1, $2, $3, $4, $5,
-                $6
+                $6, $7, $8, $9
             )
             ",
             self.id as PatId,
@@ -47,7 +71,10 @@ impl PersonalAccessToken {
             self.access_token,
             self.scopes.bits() as i64,
             self.user_id as UserId,
-            self.expires
+            self.expires,
+            project_ids.as_deref(),
+            organization_ids.as_deref(),
+            self.allowed_ips.as_deref(),
         )
         .execute(&mut **transaction)
         .await?;
@@ -115,7 +142,8 @@ impl PersonalAccessToken {
 
                     let pats = sqlx::query!(
                         "
-                        SELECT id, name, access_token, scopes, user_id, created, expires, last_used
+                        SELECT id, name, access_token, scopes, user_id, created, expires, last_used,
+                            project_ids, organization_ids, allowed_ips
                         FROM pats
                         WHERE id = ANY($1) OR access_token = ANY($2)
                         ORDER BY created DESC
@@ -134,6 +162,9 @@ impl PersonalAccessToken {
                             created: x.created,
                             expires: x.expires,
                             last_used: x.last_used,
+                            project_ids: x.project_ids.map(|x| x.into_iter().map(ProjectId).collect()),
+                            organization_ids: x.organization_ids.map(|x| x.into_iter().map(OrganizationId).collect()),
+                            allowed_ips: x.allowed_ips,
                         };
 
                         acc.insert(x.id, (Some(x.access_token), pat));
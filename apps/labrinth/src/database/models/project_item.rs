@@ -13,6 +13,7 @@ use dashmap::{DashMap, DashSet};
 use futures::TryStreamExt;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
 
@@ -218,7 +219,7 @@ impl ProjectBuilder {
             issues_type: 0,
             forum: None,
             translation_tracking: false,
-            translation_tracker: None,
+            translation_tracker: HashMap::new(),
             translation_source: None,
             is_paid: self.is_paid,
         };
@@ -297,8 +298,9 @@ pub struct Project {
     pub issues_type: i32,
     pub forum: Option<DiscussionId>,
     pub translation_tracking: bool,
-    pub translation_tracker: Option<String>,
-    /// 汉化来源：哪个项目将当前项目作为汉化目标（通过反向查询 translation_tracker 获取）
+    /// 各语言的本地化资源 slug，键为语言代码（例如 zh_cn）
+    pub translation_tracker: HashMap<String, String>,
+    /// 汉化来源：哪个项目将当前项目作为本地化目标（通过反向查询 mod_translation_trackers 获取）
     pub translation_source: Option<String>,
     pub is_paid: bool, // 是否为付费资源
 }
@@ -934,8 +936,9 @@ impl Project {
                     m.updated updated, m.approved approved, m.queued, m.status status, m.requested_status requested_status,
                     m.license_url license_url, m.issues_type as issues_type,
                     m.team_id team_id, m.organization_id organization_id, m.license license, m.slug slug, m.moderation_message moderation_message, m.moderation_message_body moderation_message_body,
-                    m.webhook_sent, m.color, m.wiki_open, m.forum, m.translation_tracking, m.translation_tracker,
-                    (SELECT slug FROM mods WHERE translation_tracker = m.slug AND m.slug IS NOT NULL LIMIT 1) as translation_source,
+                    m.webhook_sent, m.color, m.wiki_open, m.forum, m.translation_tracking,
+                    (SELECT jsonb_object_agg(mtt.language_code, mtt.translation_slug) FROM mod_translation_trackers mtt WHERE mtt.mod_id = m.id) as translation_tracker,
+                    (SELECT src.slug FROM mod_translation_trackers mtt INNER JOIN mods src ON src.id = mtt.mod_id WHERE LOWER(mtt.translation_slug) = LOWER(m.slug) LIMIT 1) as translation_source,
                     t.id thread_id, m.monetization_status monetization_status, m.is_paid,
                     ARRAY_AGG(DISTINCT c.category) filter (where c.category is not null and mc.is_additional is false) categories,
                     ARRAY_AGG(DISTINCT c.category) filter (where c.category is not null and mc.is_additional is true) additional_categories
@@ -1009,7 +1012,11 @@ impl Project {
                                 loaders,
                                 forum: m.forum.map(DiscussionId),
                                 translation_tracking: m.translation_tracking,
-                                translation_tracker: m.translation_tracker.clone(),
+                                translation_tracker: m
+                                    .translation_tracker
+                                    .clone()
+                                    .and_then(|x| serde_json::from_value(x).ok())
+                                    .unwrap_or_default(),
                                 translation_source: m.translation_source.clone(),
                                 is_paid: m.is_paid,
                             },
@@ -0,0 +1,161 @@
+use crate::database::models::{DatabaseError, UserId};
+use crate::util::env::parse_strings_from_var;
+use futures_util::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+// 未配置 REACTION_TYPES 时使用的默认表情集合
+const DEFAULT_REACTIONS: &[&str] = &[
+    "+1", "-1", "laugh", "hooray", "confused", "heart", "rocket", "eyes",
+];
+
+/// 可以添加表情回应的对象类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReactionTarget {
+    Post,
+    IssueComment,
+}
+
+impl ReactionTarget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReactionTarget::Post => "post",
+            ReactionTarget::IssueComment => "issue_comment",
+        }
+    }
+}
+
+/// 单个表情在某条回复上的汇总
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReactionSummary {
+    pub reaction: String,
+    pub count: i64,
+    /// 当前用户是否添加过该表情
+    pub reacted: bool,
+}
+
+// 启动后首次使用时解析一次 REACTION_TYPES
+static ALLOWED_REACTIONS: LazyLock<Vec<String>> = LazyLock::new(|| {
+    parse_strings_from_var("REACTION_TYPES").unwrap_or_else(|| {
+        DEFAULT_REACTIONS.iter().map(|x| x.to_string()).collect()
+    })
+});
+
+/// 允许使用的表情集合，可通过 `REACTION_TYPES`（JSON 字符串数组）配置
+pub fn allowed_reactions() -> &'static [String] {
+    &ALLOWED_REACTIONS
+}
+
+pub fn is_allowed_reaction(reaction: &str) -> bool {
+    allowed_reactions().iter().any(|x| x == reaction)
+}
+
+pub struct Reaction;
+
+impl Reaction {
+    pub async fn insert(
+        target: ReactionTarget,
+        target_id: i64,
+        user_id: UserId,
+        reaction: &str,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "
+            INSERT INTO reactions (target_type, target_id, user_id, reaction)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT DO NOTHING
+            ",
+            target.as_str(),
+            target_id,
+            user_id.0,
+            reaction
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove(
+        target: ReactionTarget,
+        target_id: i64,
+        user_id: UserId,
+        reaction: &str,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "
+            DELETE FROM reactions
+            WHERE target_type = $1 AND target_id = $2 AND user_id = $3 AND reaction = $4
+            ",
+            target.as_str(),
+            target_id,
+            user_id.0,
+            reaction
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 批量统计表情回应。结果不缓存，因为其中包含当前用户的状态
+    pub async fn get_summaries<'a, E>(
+        target: ReactionTarget,
+        target_ids: &[i64],
+        user_id: Option<UserId>,
+        exec: E,
+    ) -> Result<HashMap<i64, Vec<ReactionSummary>>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let allowed = allowed_reactions();
+
+        let summaries = sqlx::query!(
+            r#"
+            SELECT target_id, reaction, COUNT(*) as "count!",
+                   BOOL_OR(user_id = $3) as "reacted!"
+            FROM reactions
+            WHERE target_type = $1 AND target_id = ANY($2)
+            GROUP BY target_id, reaction
+            "#,
+            target.as_str(),
+            target_ids,
+            user_id.map(|x| x.0).unwrap_or(0)
+        )
+        .fetch(exec)
+        .try_fold(
+            HashMap::<i64, Vec<ReactionSummary>>::new(),
+            |mut acc, row| {
+                // 已从配置中移除的表情不再展示
+                if allowed.contains(&row.reaction) {
+                    acc.entry(row.target_id).or_default().push(
+                        ReactionSummary {
+                            reaction: row.reaction,
+                            count: row.count,
+                            reacted: row.reacted,
+                        },
+                    );
+                }
+                async move { Ok(acc) }
+            },
+        )
+        .await?;
+
+        // 按配置中的顺序输出
+        let summaries = summaries
+            .into_iter()
+            .map(|(id, mut list)| {
+                list.sort_by_key(|x| {
+                    allowed.iter().position(|r| r == &x.reaction)
+                });
+                (id, list)
+            })
+            .collect();
+
+        Ok(summaries)
+    }
+}
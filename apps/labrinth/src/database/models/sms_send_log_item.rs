@@ -0,0 +1,121 @@
+use crate::database::models::{DatabaseError, UserId};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 短信发送结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmsSendStatus {
+    Sent,
+    Failed,
+    RateLimited,
+}
+
+impl SmsSendStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SmsSendStatus::Sent => "sent",
+            SmsSendStatus::Failed => "failed",
+            SmsSendStatus::RateLimited => "rate_limited",
+        }
+    }
+
+    pub fn from_string(string: &str) -> SmsSendStatus {
+        match string {
+            "sent" => SmsSendStatus::Sent,
+            "rate_limited" => SmsSendStatus::RateLimited,
+            _ => SmsSendStatus::Failed,
+        }
+    }
+}
+
+/// 一次短信发送的记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmsSendLog {
+    pub id: i64,
+    pub user_id: Option<UserId>,
+    pub phone_number: String,
+    pub ip: Option<String>,
+    pub provider: String,
+    pub status: SmsSendStatus,
+    pub provider_message_id: Option<String>,
+    pub error: Option<String>,
+    pub created: DateTime<Utc>,
+}
+
+impl SmsSendLog {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert(
+        user_id: Option<UserId>,
+        phone_number: &str,
+        ip: Option<&str>,
+        provider: &str,
+        status: SmsSendStatus,
+        provider_message_id: Option<&str>,
+        error: Option<&str>,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<i64, DatabaseError> {
+        let id = sqlx::query!(
+            "
+            INSERT INTO sms_send_logs (
+                user_id, phone_number, ip, provider, status,
+                provider_message_id, error
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id
+            ",
+            user_id.map(|x| x.0),
+            phone_number,
+            ip,
+            provider,
+            status.as_str(),
+            provider_message_id,
+            error,
+        )
+        .fetch_one(exec)
+        .await?
+        .id;
+
+        Ok(id)
+    }
+
+    /// 按手机号和/或用户筛选最近的发送记录，按时间倒序
+    pub async fn get_recent(
+        phone_number: Option<&str>,
+        user_id: Option<UserId>,
+        limit: i64,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<Vec<SmsSendLog>, DatabaseError> {
+        let logs = sqlx::query!(
+            "
+            SELECT id, user_id, phone_number, ip, provider, status,
+                provider_message_id, error, created
+            FROM sms_send_logs
+            WHERE ($1::varchar IS NULL OR phone_number = $1)
+                AND ($2::bigint IS NULL OR user_id = $2)
+            ORDER BY created DESC
+            LIMIT $3
+            ",
+            phone_number,
+            user_id.map(|x| x.0),
+            limit,
+        )
+        .fetch_all(exec)
+        .await?;
+
+        Ok(logs
+            .into_iter()
+            .map(|x| SmsSendLog {
+                id: x.id,
+                user_id: x.user_id.map(UserId),
+                phone_number: x.phone_number,
+                ip: x.ip,
+                provider: x.provider,
+                status: SmsSendStatus::from_string(&x.status),
+                provider_message_id: x.provider_message_id,
+                error: x.error,
+                created: x.created,
+            })
+            .collect())
+    }
+}
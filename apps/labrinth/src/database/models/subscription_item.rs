@@ -0,0 +1,394 @@
+use crate::database::models::{DatabaseError, UserId};
+use chrono::{DateTime, Utc};
+use futures_util::TryStreamExt;
+use serde::{Deserialize, Serialize};
+
+/// 可以关注的对象类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionTarget {
+    Discussion,
+    Issue,
+    ProjectIssues,
+    Wiki,
+}
+
+impl SubscriptionTarget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriptionTarget::Discussion => "discussion",
+            SubscriptionTarget::Issue => "issue",
+            SubscriptionTarget::ProjectIssues => "project_issues",
+            SubscriptionTarget::Wiki => "wiki",
+        }
+    }
+
+    pub fn from_string(string: &str) -> Option<SubscriptionTarget> {
+        match string {
+            "discussion" => Some(SubscriptionTarget::Discussion),
+            "issue" => Some(SubscriptionTarget::Issue),
+            "project_issues" => Some(SubscriptionTarget::ProjectIssues),
+            "wiki" => Some(SubscriptionTarget::Wiki),
+            _ => None,
+        }
+    }
+}
+
+/// 触发关注通知的事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionEventType {
+    NewPost,
+    NewIssue,
+    NewComment,
+    StateChange,
+    WikiEditAccepted,
+}
+
+impl SubscriptionEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriptionEventType::NewPost => "new_post",
+            SubscriptionEventType::NewIssue => "new_issue",
+            SubscriptionEventType::NewComment => "new_comment",
+            SubscriptionEventType::StateChange => "state_change",
+            SubscriptionEventType::WikiEditAccepted => "wiki_edit_accepted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subscription {
+    pub user_id: UserId,
+    pub target_type: SubscriptionTarget,
+    pub target_id: i64,
+    pub automatic: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SubscriptionEvent {
+    pub id: i64,
+    pub user_id: UserId,
+    pub target_type: String,
+    pub target_id: i64,
+    pub event_type: String,
+    pub actor_name: Option<String>,
+    pub title: String,
+    pub link: String,
+}
+
+impl Subscription {
+    /// 用户主动关注，会重新启用之前取消的关注
+    pub async fn subscribe(
+        user_id: UserId,
+        target: SubscriptionTarget,
+        target_id: i64,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "
+            INSERT INTO subscriptions (user_id, target_type, target_id, active, automatic)
+            VALUES ($1, $2, $3, true, false)
+            ON CONFLICT (user_id, target_type, target_id)
+            DO UPDATE SET active = true, automatic = false
+            ",
+            user_id.0,
+            target.as_str(),
+            target_id
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 参与讨论时自动关注，用户取消过的关注不会被重新启用
+    pub async fn subscribe_automatic(
+        user_id: UserId,
+        target: SubscriptionTarget,
+        target_id: i64,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "
+            INSERT INTO subscriptions (user_id, target_type, target_id, active, automatic)
+            VALUES ($1, $2, $3, true, true)
+            ON CONFLICT (user_id, target_type, target_id) DO NOTHING
+            ",
+            user_id.0,
+            target.as_str(),
+            target_id
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn unsubscribe(
+        user_id: UserId,
+        target: SubscriptionTarget,
+        target_id: i64,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "
+            INSERT INTO subscriptions (user_id, target_type, target_id, active, automatic)
+            VALUES ($1, $2, $3, false, false)
+            ON CONFLICT (user_id, target_type, target_id)
+            DO UPDATE SET active = false
+            ",
+            user_id.0,
+            target.as_str(),
+            target_id
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_user<'a, E>(
+        user_id: UserId,
+        exec: E,
+    ) -> Result<Vec<Subscription>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let subscriptions = sqlx::query!(
+            "
+            SELECT user_id, target_type, target_id, automatic, created_at
+            FROM subscriptions
+            WHERE user_id = $1 AND active = true
+            ORDER BY created_at DESC
+            ",
+            user_id.0
+        )
+        .fetch(exec)
+        .try_filter_map(|row| async move {
+            Ok(SubscriptionTarget::from_string(&row.target_type).map(
+                |target_type| Subscription {
+                    user_id: UserId(row.user_id),
+                    target_type,
+                    target_id: row.target_id,
+                    automatic: row.automatic,
+                    created_at: row.created_at,
+                },
+            ))
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(subscriptions)
+    }
+
+    /// 获取对象的全部关注者
+    pub async fn get_subscribers<'a, E>(
+        target: SubscriptionTarget,
+        target_id: i64,
+        exec: E,
+    ) -> Result<Vec<UserId>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let subscribers = sqlx::query!(
+            "
+            SELECT user_id FROM subscriptions
+            WHERE target_type = $1 AND target_id = $2 AND active = true
+            ",
+            target.as_str(),
+            target_id
+        )
+        .fetch(exec)
+        .map_ok(|row| UserId(row.user_id))
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(subscribers)
+    }
+
+    pub async fn get_muted<'a, E>(
+        user_id: UserId,
+        exec: E,
+    ) -> Result<Vec<SubscriptionTarget>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let muted = sqlx::query!(
+            "SELECT target_type FROM subscription_mutes WHERE user_id = $1",
+            user_id.0
+        )
+        .fetch(exec)
+        .try_filter_map(|row| async move {
+            Ok(SubscriptionTarget::from_string(&row.target_type))
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(muted)
+    }
+
+    pub async fn set_muted(
+        user_id: UserId,
+        muted: &[SubscriptionTarget],
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "DELETE FROM subscription_mutes WHERE user_id = $1",
+            user_id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        let types = muted
+            .iter()
+            .map(|x| x.as_str().to_string())
+            .collect::<Vec<_>>();
+        sqlx::query!(
+            "
+            INSERT INTO subscription_mutes (user_id, target_type)
+            SELECT $1, * FROM UNNEST($2::varchar[])
+            ON CONFLICT DO NOTHING
+            ",
+            user_id.0,
+            &types[..]
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl SubscriptionEvent {
+    /// 为关注者生成待发送的事件。
+    /// `parent` 用于同时通知上级对象的关注者（例如项目的问题列表），
+    /// 触发事件的用户本人和屏蔽了该类型通知的用户不会收到事件。
+    #[allow(clippy::too_many_arguments)]
+    pub async fn queue(
+        target: SubscriptionTarget,
+        target_id: i64,
+        parent: Option<(SubscriptionTarget, i64)>,
+        event_type: SubscriptionEventType,
+        actor_id: Option<UserId>,
+        title: &str,
+        link: &str,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "
+            INSERT INTO subscription_events (user_id, target_type, target_id, event_type, actor_id, title, link)
+            SELECT DISTINCT s.user_id, $1::varchar, $2::bigint, $3::varchar, $4::bigint, $5::varchar, $6::varchar
+            FROM subscriptions s
+            WHERE s.active = true
+            AND ((s.target_type = $1::varchar AND s.target_id = $2::bigint) OR (s.target_type = $7::varchar AND s.target_id = $8::bigint))
+            AND ($4::bigint IS NULL OR s.user_id <> $4)
+            AND NOT EXISTS (
+                SELECT 1 FROM subscription_mutes sm
+                WHERE sm.user_id = s.user_id AND sm.target_type = s.target_type
+            )
+            ",
+            target.as_str(),
+            target_id,
+            event_type.as_str(),
+            actor_id.map(|x| x.0),
+            title,
+            link,
+            parent.map(|x| x.0.as_str()),
+            parent.map(|x| x.1)
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 与 `queue` 相同，但只为 `user_ids` 中的关注者生成事件，
+    /// 用于对象并非所有人可见的情况
+    #[allow(clippy::too_many_arguments)]
+    pub async fn queue_for_users(
+        target: SubscriptionTarget,
+        target_id: i64,
+        event_type: SubscriptionEventType,
+        actor_id: Option<UserId>,
+        title: &str,
+        link: &str,
+        user_ids: &[UserId],
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        let user_ids = user_ids.iter().map(|x| x.0).collect::<Vec<_>>();
+
+        sqlx::query!(
+            "
+            INSERT INTO subscription_events (user_id, target_type, target_id, event_type, actor_id, title, link)
+            SELECT s.user_id, $1::varchar, $2::bigint, $3::varchar, $4::bigint, $5::varchar, $6::varchar
+            FROM subscriptions s
+            WHERE s.active = true
+            AND s.target_type = $1::varchar AND s.target_id = $2::bigint
+            AND s.user_id = ANY($7::bigint[])
+            AND ($4::bigint IS NULL OR s.user_id <> $4)
+            AND NOT EXISTS (
+                SELECT 1 FROM subscription_mutes sm
+                WHERE sm.user_id = s.user_id AND sm.target_type = s.target_type
+            )
+            ",
+            target.as_str(),
+            target_id,
+            event_type.as_str(),
+            actor_id.map(|x| x.0),
+            title,
+            link,
+            &user_ids[..]
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_pending<'a, E>(
+        limit: i64,
+        exec: E,
+    ) -> Result<Vec<SubscriptionEvent>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let events = sqlx::query!(
+            r#"
+            SELECT e.id, e.user_id, e.target_type, e.target_id, e.event_type, e.title, e.link,
+                   u.username as "actor_name?"
+            FROM subscription_events e
+            LEFT JOIN users u ON e.actor_id = u.id
+            ORDER BY e.id ASC
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch(exec)
+        .map_ok(|row| SubscriptionEvent {
+            id: row.id,
+            user_id: UserId(row.user_id),
+            target_type: row.target_type,
+            target_id: row.target_id,
+            event_type: row.event_type,
+            actor_name: row.actor_name,
+            title: row.title,
+            link: row.link,
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(events)
+    }
+
+    pub async fn remove_many(
+        ids: &[i64],
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!("DELETE FROM subscription_events WHERE id = ANY($1)", ids)
+            .execute(&mut **transaction)
+            .await?;
+
+        Ok(())
+    }
+}
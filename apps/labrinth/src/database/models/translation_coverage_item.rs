@@ -0,0 +1,346 @@
+use super::ids::*;
+use crate::database::models::DatabaseError;
+use chrono::{DateTime, Utc};
+use futures_util::TryStreamExt;
+use serde::{Deserialize, Serialize};
+
+/// 覆盖率报告状态
+pub const COVERAGE_COMPLETE: &str = "complete";
+pub const COVERAGE_FAILED: &str = "failed";
+/// 版本没有可以下载分析的文件（例如网盘版本、付费私有文件）
+pub const COVERAGE_UNAVAILABLE: &str = "unavailable";
+
+/// 单个整合包版本的汉化覆盖率报告
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TranslationCoverage {
+    pub version_id: VersionId,
+    /// 目标语言代码，例如 zh_cn
+    pub language_code: String,
+    pub project_id: ProjectId,
+    pub translation_version_id: Option<VersionId>,
+    pub previous_version_id: Option<VersionId>,
+    pub status: String,
+    pub error: Option<String>,
+    pub source_keys: Vec<String>,
+    pub new_keys: Vec<String>,
+    pub removed_keys: Vec<String>,
+    pub untranslated_keys: Vec<String>,
+    pub total_keys: i32,
+    pub translated_keys: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 不含键列表的报告摘要
+#[derive(Clone, Debug)]
+pub struct TranslationCoverageSummary {
+    pub version_id: VersionId,
+    pub version_number: String,
+    pub language_code: String,
+    pub translation_version_id: Option<VersionId>,
+    pub previous_version_id: Option<VersionId>,
+    pub status: String,
+    pub total_keys: i32,
+    pub translated_keys: i32,
+    pub new_keys: i32,
+    pub removed_keys: i32,
+    pub untranslated_keys: i32,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 需要生成（或重新生成）覆盖率报告的版本
+#[derive(Clone, Debug)]
+pub struct PendingCoverage {
+    pub version_id: VersionId,
+    pub language_code: String,
+    pub project_id: ProjectId,
+    /// 当前已批准的汉化版本
+    pub translation_version_id: Option<VersionId>,
+    /// 已有报告（任一语言）时可以复用其中的原文键，只需重新读取汉化版本
+    pub existing_source_keys: Option<Vec<String>>,
+}
+
+impl TranslationCoverage {
+    pub async fn get<'a, E>(
+        version_id: VersionId,
+        language_code: &str,
+        exec: E,
+    ) -> Result<Option<TranslationCoverage>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let row = sqlx::query!(
+            "
+            SELECT version_id, language_code, mod_id, translation_version_id, previous_version_id,
+                status, error, source_keys, new_keys, removed_keys, untranslated_keys,
+                total_keys, translated_keys, created_at, updated_at
+            FROM translation_coverage
+            WHERE version_id = $1 AND language_code = $2
+            ",
+            version_id.0,
+            language_code
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(row.map(|row| TranslationCoverage {
+            version_id: VersionId(row.version_id),
+            language_code: row.language_code,
+            project_id: ProjectId(row.mod_id),
+            translation_version_id: row.translation_version_id.map(VersionId),
+            previous_version_id: row.previous_version_id.map(VersionId),
+            status: row.status,
+            error: row.error,
+            source_keys: serde_json::from_value(row.source_keys)
+                .unwrap_or_default(),
+            new_keys: serde_json::from_value(row.new_keys).unwrap_or_default(),
+            removed_keys: serde_json::from_value(row.removed_keys)
+                .unwrap_or_default(),
+            untranslated_keys: serde_json::from_value(row.untranslated_keys)
+                .unwrap_or_default(),
+            total_keys: row.total_keys,
+            translated_keys: row.translated_keys,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }))
+    }
+
+    /// 获取项目所有版本、所有语言的报告摘要，按版本发布时间倒序
+    pub async fn get_project_summaries<'a, E>(
+        project_id: ProjectId,
+        exec: E,
+    ) -> Result<Vec<TranslationCoverageSummary>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let summaries = sqlx::query!(
+            r#"
+            SELECT tc.version_id, v.version_number, tc.language_code, tc.translation_version_id,
+                tc.previous_version_id, tc.status, tc.total_keys, tc.translated_keys,
+                jsonb_array_length(tc.new_keys) as "new_keys!",
+                jsonb_array_length(tc.removed_keys) as "removed_keys!",
+                jsonb_array_length(tc.untranslated_keys) as "untranslated_keys!",
+                tc.updated_at
+            FROM translation_coverage tc
+            INNER JOIN versions v ON v.id = tc.version_id
+            WHERE tc.mod_id = $1
+            ORDER BY v.date_published DESC, tc.language_code
+            "#,
+            project_id.0
+        )
+        .fetch(exec)
+        .map_ok(|row| TranslationCoverageSummary {
+            version_id: VersionId(row.version_id),
+            version_number: row.version_number,
+            language_code: row.language_code,
+            translation_version_id: row.translation_version_id.map(VersionId),
+            previous_version_id: row.previous_version_id.map(VersionId),
+            status: row.status,
+            total_keys: row.total_keys,
+            translated_keys: row.translated_keys,
+            new_keys: row.new_keys,
+            removed_keys: row.removed_keys,
+            untranslated_keys: row.untranslated_keys,
+            updated_at: row.updated_at,
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(summaries)
+    }
+
+    /// 获取指定版本所有语言的报告摘要
+    pub async fn get_version_summaries<'a, E>(
+        version_ids: &[VersionId],
+        exec: E,
+    ) -> Result<Vec<TranslationCoverageSummary>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let summaries = sqlx::query!(
+            r#"
+            SELECT tc.version_id, v.version_number, tc.language_code, tc.translation_version_id,
+                tc.previous_version_id, tc.status, tc.total_keys, tc.translated_keys,
+                jsonb_array_length(tc.new_keys) as "new_keys!",
+                jsonb_array_length(tc.removed_keys) as "removed_keys!",
+                jsonb_array_length(tc.untranslated_keys) as "untranslated_keys!",
+                tc.updated_at
+            FROM translation_coverage tc
+            INNER JOIN versions v ON v.id = tc.version_id
+            WHERE tc.version_id = ANY($1)
+            ORDER BY tc.language_code
+            "#,
+            &version_ids.iter().map(|x| x.0).collect::<Vec<_>>()
+        )
+        .fetch(exec)
+        .map_ok(|row| TranslationCoverageSummary {
+            version_id: VersionId(row.version_id),
+            version_number: row.version_number,
+            language_code: row.language_code,
+            translation_version_id: row.translation_version_id.map(VersionId),
+            previous_version_id: row.previous_version_id.map(VersionId),
+            status: row.status,
+            total_keys: row.total_keys,
+            translated_keys: row.translated_keys,
+            new_keys: row.new_keys,
+            removed_keys: row.removed_keys,
+            untranslated_keys: row.untranslated_keys,
+            updated_at: row.updated_at,
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(summaries)
+    }
+
+    /// 获取同一项目中早于该版本发布、且已成功分析的上一个版本及其原文键。
+    /// 原文键与目标语言无关，取任一语言的报告即可
+    pub async fn get_previous<'a, E>(
+        version_id: VersionId,
+        exec: E,
+    ) -> Result<Option<(VersionId, Vec<String>)>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let row = sqlx::query!(
+            "
+            SELECT tc.version_id, tc.source_keys
+            FROM versions cur
+            INNER JOIN versions prev ON prev.mod_id = cur.mod_id
+                AND prev.date_published < cur.date_published
+            INNER JOIN translation_coverage tc ON tc.version_id = prev.id
+            WHERE cur.id = $1 AND tc.status = $2
+            ORDER BY prev.date_published DESC
+            LIMIT 1
+            ",
+            version_id.0,
+            COVERAGE_COMPLETE
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(row.map(|row| {
+            (
+                VersionId(row.version_id),
+                serde_json::from_value(row.source_keys).unwrap_or_default(),
+            )
+        }))
+    }
+
+    /// 查找需要分析的版本与语言：每个汉化追踪项目最近 `history` 个公开版本中，
+    /// 对项目追踪的每种语言还没有报告、绑定的汉化版本发生变化，或上次分析失败超过一天的
+    pub async fn get_pending<'a, E>(
+        history: i64,
+        limit: i64,
+        exec: E,
+    ) -> Result<Vec<PendingCoverage>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let pending = sqlx::query!(
+            r#"
+            WITH recent_versions AS (
+                SELECT v.id, v.mod_id, v.date_published,
+                    ROW_NUMBER() OVER (PARTITION BY v.mod_id ORDER BY v.date_published DESC) as rn
+                FROM versions v
+                INNER JOIN mods m ON m.id = v.mod_id
+                WHERE m.translation_tracking = true
+                AND m.status = 'approved'
+                AND v.status = 'listed'
+            ),
+            approved_translations AS (
+                SELECT DISTINCT ON (vlv.joining_version_id, LOWER(vlv.language_code))
+                    vlv.joining_version_id, LOWER(vlv.language_code) as language_code, vlv.version_id
+                FROM version_link_version vlv
+                WHERE vlv.approval_status = 'approved'
+                AND vlv.link_type = 'translation'
+                ORDER BY vlv.joining_version_id, LOWER(vlv.language_code), vlv.created_at DESC
+            )
+            SELECT rv.id, rv.mod_id, mtt.language_code,
+                at.version_id as "translation_version_id?",
+                (
+                    SELECT source.source_keys FROM translation_coverage source
+                    WHERE source.version_id = rv.id AND source.status = $2
+                    LIMIT 1
+                ) as "source_keys?"
+            FROM recent_versions rv
+            INNER JOIN mod_translation_trackers mtt ON mtt.mod_id = rv.mod_id
+            LEFT JOIN approved_translations at
+                ON at.joining_version_id = rv.id AND at.language_code = mtt.language_code
+            LEFT JOIN translation_coverage tc
+                ON tc.version_id = rv.id AND tc.language_code = mtt.language_code
+            WHERE rv.rn <= $1
+            AND (
+                tc.version_id IS NULL
+                OR tc.translation_version_id IS DISTINCT FROM at.version_id
+                OR (tc.status = $3 AND tc.updated_at < NOW() - INTERVAL '1 day')
+            )
+            ORDER BY rv.date_published DESC
+            LIMIT $4
+            "#,
+            history,
+            COVERAGE_COMPLETE,
+            COVERAGE_FAILED,
+            limit
+        )
+        .fetch(exec)
+        .map_ok(|row| PendingCoverage {
+            version_id: VersionId(row.id),
+            language_code: row.language_code,
+            project_id: ProjectId(row.mod_id),
+            translation_version_id: row.translation_version_id.map(VersionId),
+            existing_source_keys: row
+                .source_keys
+                .and_then(|x| serde_json::from_value(x).ok()),
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(pending)
+    }
+
+    pub async fn upsert(
+        &self,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            INSERT INTO translation_coverage (
+                version_id, mod_id, translation_version_id, previous_version_id,
+                status, error, source_keys, new_keys, removed_keys, untranslated_keys,
+                total_keys, translated_keys, language_code
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            ON CONFLICT (version_id, language_code) DO UPDATE SET
+                translation_version_id = EXCLUDED.translation_version_id,
+                previous_version_id = EXCLUDED.previous_version_id,
+                status = EXCLUDED.status,
+                error = EXCLUDED.error,
+                source_keys = EXCLUDED.source_keys,
+                new_keys = EXCLUDED.new_keys,
+                removed_keys = EXCLUDED.removed_keys,
+                untranslated_keys = EXCLUDED.untranslated_keys,
+                total_keys = EXCLUDED.total_keys,
+                translated_keys = EXCLUDED.translated_keys,
+                updated_at = NOW()
+            ",
+            self.version_id.0,
+            self.project_id.0,
+            self.translation_version_id.map(|x| x.0),
+            self.previous_version_id.map(|x| x.0),
+            self.status,
+            self.error,
+            serde_json::to_value(&self.source_keys)?,
+            serde_json::to_value(&self.new_keys)?,
+            serde_json::to_value(&self.removed_keys)?,
+            serde_json::to_value(&self.untranslated_keys)?,
+            self.total_keys,
+            self.translated_keys,
+            self.language_code
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+}
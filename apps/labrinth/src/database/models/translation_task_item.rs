@@ -0,0 +1,235 @@
+use super::ids::*;
+use crate::database::models::DatabaseError;
+use crate::database::models::issues::{ISSUE_NAMESPACE, Issue};
+use crate::database::redis::RedisPool;
+use chrono::{DateTime, Utc};
+use futures_util::TryStreamExt;
+
+/// 还没有已批准汉化、也还没有创建任务的整合包版本
+#[derive(Clone, Debug)]
+pub struct OutdatedVersion {
+    pub version_id: VersionId,
+    pub language_code: String,
+    pub project_id: ProjectId,
+    pub project_name: String,
+    pub project_slug: Option<String>,
+    pub version_number: String,
+    pub changelog: String,
+    pub date_published: DateTime<Utc>,
+    /// 该语言的本地化资源（例如 `{slug}-cn`）
+    pub translation_project_id: ProjectId,
+    pub translation_project_slug: String,
+}
+
+/// 汉化过期任务：整合包新版本在每种追踪语言的本地化资源上对应一个问题
+pub struct TranslationTask;
+
+impl TranslationTask {
+    /// 每个汉化追踪项目的最新公开版本中，指定语言没有已批准汉化绑定且还没有任务的版本。
+    /// 等待覆盖率报告生成（或发布超过一小时）后再创建，以便任务中附带键变化
+    pub async fn get_outdated_versions<'a, E>(
+        language_code: &str,
+        exec: E,
+    ) -> Result<Vec<OutdatedVersion>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let versions = sqlx::query!(
+            r#"
+            WITH latest_versions AS (
+                SELECT DISTINCT ON (v.mod_id)
+                    v.id, v.mod_id, v.version_number, v.changelog, v.date_published
+                FROM versions v
+                INNER JOIN mods m ON m.id = v.mod_id
+                WHERE m.translation_tracking = true
+                AND m.status = 'approved'
+                AND v.status = 'listed'
+                ORDER BY v.mod_id, v.date_published DESC
+            )
+            SELECT lv.id, lv.mod_id, m.name, m.slug, lv.version_number, lv.changelog,
+                lv.date_published, mtt.language_code, loc.id as loc_id, loc.slug as "loc_slug!"
+            FROM latest_versions lv
+            INNER JOIN mods m ON m.id = lv.mod_id
+            INNER JOIN mod_translation_trackers mtt
+                ON mtt.mod_id = lv.mod_id AND mtt.language_code = $1
+            INNER JOIN mods loc ON LOWER(loc.slug) = LOWER(mtt.translation_slug)
+            WHERE NOT EXISTS (
+                SELECT 1 FROM version_link_version vlv
+                WHERE vlv.joining_version_id = lv.id
+                AND vlv.link_type = 'translation'
+                AND vlv.approval_status = 'approved'
+                AND LOWER(vlv.language_code) = $1
+            )
+            AND NOT EXISTS (
+                SELECT 1 FROM translation_tasks tt
+                WHERE tt.version_id = lv.id AND tt.language_code = $1
+            )
+            AND (
+                EXISTS (
+                    SELECT 1 FROM translation_coverage tc
+                    WHERE tc.version_id = lv.id AND tc.language_code = $1
+                )
+                OR lv.date_published < NOW() - INTERVAL '1 hour'
+            )
+            "#,
+            language_code
+        )
+        .fetch(exec)
+        .map_ok(|row| OutdatedVersion {
+            version_id: VersionId(row.id),
+            language_code: row.language_code,
+            project_id: ProjectId(row.mod_id),
+            project_name: row.name,
+            project_slug: row.slug,
+            version_number: row.version_number,
+            changelog: row.changelog,
+            date_published: row.date_published,
+            translation_project_id: ProjectId(row.loc_id),
+            translation_project_slug: row.loc_slug,
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(versions)
+    }
+
+    /// 同一版本的每种语言只能有一个任务，重复插入会报错并回滚连同问题在内的整个事务
+    pub async fn insert(
+        version_id: VersionId,
+        language_code: &str,
+        project_id: ProjectId,
+        issue_id: IssuesId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            INSERT INTO translation_tasks (version_id, language_code, mod_id, issue_id)
+            VALUES ($1, $2, $3, $4)
+            ",
+            version_id.0,
+            language_code,
+            project_id.0,
+            issue_id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 有汉化版本绑定并批准后，关闭该语言下该版本以及同项目更早版本的任务，
+    /// 返回被关闭的问题及其所属项目
+    pub async fn close_for_version(
+        version_id: VersionId,
+        language_code: &str,
+        translation_version_id: VersionId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<(IssuesId, ProjectId)>, DatabaseError> {
+        let issue_ids = sqlx::query!(
+            "
+            UPDATE translation_tasks tt
+            SET state = 'closed', closed_at = NOW(), translation_version_id = $2
+            FROM versions cur, versions v
+            WHERE cur.id = $1
+            AND v.id = tt.version_id
+            AND v.mod_id = cur.mod_id
+            AND v.date_published <= cur.date_published
+            AND tt.language_code = $3
+            AND tt.state = 'open'
+            RETURNING tt.issue_id
+            ",
+            version_id.0,
+            translation_version_id.0,
+            language_code
+        )
+        .fetch(&mut **transaction)
+        .map_ok(|row| row.issue_id)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        if issue_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let closed = sqlx::query!(
+            "
+            UPDATE issues
+            SET state = 'closed', closed_at = NOW(), updated_at = NOW()
+            WHERE id = ANY($1) AND state = 'open'
+            RETURNING id, mod_id
+            ",
+            &issue_ids[..]
+        )
+        .fetch(&mut **transaction)
+        .map_ok(|row| (IssuesId(row.id), ProjectId(row.mod_id)))
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(closed)
+    }
+
+    /// 仍然打开、但对应版本已经有已批准汉化的任务（例如汉化在上传时被自动批准）
+    pub async fn get_resolved<'a, E>(
+        exec: E,
+    ) -> Result<Vec<(VersionId, String, VersionId)>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let resolved = sqlx::query!(
+            "
+            SELECT DISTINCT ON (tt.version_id, tt.language_code)
+                tt.version_id, tt.language_code, vlv.version_id as translation_version_id
+            FROM translation_tasks tt
+            INNER JOIN version_link_version vlv
+                ON vlv.joining_version_id = tt.version_id
+                AND LOWER(vlv.language_code) = tt.language_code
+            WHERE tt.state = 'open'
+            AND vlv.link_type = 'translation'
+            AND vlv.approval_status = 'approved'
+            ORDER BY tt.version_id, tt.language_code, vlv.created_at DESC
+            "
+        )
+        .fetch(exec)
+        .map_ok(|row| {
+            (
+                VersionId(row.version_id),
+                row.language_code,
+                VersionId(row.translation_version_id),
+            )
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        Ok(resolved)
+    }
+
+    /// 清除任务问题的缓存以及所属项目的问题列表缓存
+    pub async fn clear_issue_cache(
+        issues: &[(IssuesId, ProjectId)],
+        redis: &RedisPool,
+    ) -> Result<(), DatabaseError> {
+        if issues.is_empty() {
+            return Ok(());
+        }
+
+        Issue::clear_cache(
+            &issues.iter().map(|x| x.0).collect::<Vec<_>>(),
+            redis,
+        )
+        .await?;
+
+        let mut redis = redis.connect().await?;
+        redis
+            .delete_many(issues.iter().flat_map(|(_, project_id)| {
+                ["all", "open", "closed"].map(|state| {
+                    (
+                        ISSUE_NAMESPACE,
+                        Some(format!("project_{}_{}", project_id.0, state)),
+                    )
+                })
+            }))
+            .await?;
+
+        Ok(())
+    }
+}
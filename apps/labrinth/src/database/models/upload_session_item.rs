@@ -0,0 +1,249 @@
+use super::ids::*;
+use crate::database::models::DatabaseError;
+use crate::database::redis::{RedisLock, RedisPool};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use rand::distributions::Alphanumeric;
+use rand_chacha::ChaCha20Rng;
+use rand_chacha::rand_core::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const UPLOAD_SESSIONS_NAMESPACE: &str = "upload_sessions";
+const UPLOAD_SESSIONS_LOCK_NAMESPACE: &str = "upload_sessions_lock";
+const UPLOAD_SESSIONS_USER_NAMESPACE: &str = "upload_sessions_user";
+const UPLOAD_SESSIONS_USER_LOCK_NAMESPACE: &str = "upload_sessions_user_lock";
+// 未完成的上传会话保留 24 小时，超时后暂存文件由定时任务清理
+pub const UPLOAD_SESSION_EXPIRY: i64 = 60 * 60 * 24;
+const UPLOAD_SESSION_LOCK_EXPIRY: i64 = 60 * 10;
+const UPLOAD_SESSION_USER_LOCK_EXPIRY: i64 = 60;
+
+/// 可续传的分块上传会话，分块暂存在本机磁盘上
+#[derive(Deserialize, Serialize, Clone)]
+pub struct UploadSession {
+    pub id: String,
+    pub user_id: UserId,
+    pub file_name: String,
+    pub size: u64,
+    /// 已接收的字节数，下一个分块必须从此偏移开始
+    pub offset: u64,
+    pub completed: bool,
+    pub sha1: Option<String>,
+    pub sha512: Option<String>,
+    pub created: DateTime<Utc>,
+}
+
+impl UploadSession {
+    pub fn new(user_id: UserId, file_name: String, size: u64) -> Self {
+        let id = ChaCha20Rng::from_entropy()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect::<String>();
+
+        UploadSession {
+            id,
+            user_id,
+            file_name,
+            size,
+            offset: 0,
+            completed: false,
+            sha1: None,
+            sha512: None,
+            created: Utc::now(),
+        }
+    }
+
+    /// 暂存目录，默认位于系统临时目录下
+    pub fn staging_dir() -> PathBuf {
+        dotenvy::var("UPLOAD_STAGING_PATH")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| std::env::temp_dir().join("labrinth-uploads"))
+    }
+
+    pub fn staging_path(&self) -> PathBuf {
+        Self::staging_dir().join(format!("{}.part", self.id))
+    }
+
+    pub async fn upsert(&self, redis: &RedisPool) -> Result<(), DatabaseError> {
+        let mut redis = redis.connect().await?;
+
+        redis
+            .set_serialized_to_json(
+                UPLOAD_SESSIONS_NAMESPACE,
+                &self.id,
+                self,
+                Some(UPLOAD_SESSION_EXPIRY),
+            )
+            .await
+    }
+
+    /// 写入新建的会话，并加入用户的会话索引
+    pub async fn insert(&self, redis: &RedisPool) -> Result<(), DatabaseError> {
+        let mut ids = Self::get_user_sessions(self.user_id, redis)
+            .await?
+            .into_iter()
+            .map(|x| x.id)
+            .collect::<Vec<_>>();
+        ids.push(self.id.clone());
+
+        self.upsert(redis).await?;
+
+        let mut redis = redis.connect().await?;
+        redis
+            .set_serialized_to_json(
+                UPLOAD_SESSIONS_USER_NAMESPACE,
+                self.user_id.0,
+                ids,
+                Some(UPLOAD_SESSION_EXPIRY),
+            )
+            .await
+    }
+
+    pub async fn get(
+        id: &str,
+        redis: &RedisPool,
+    ) -> Result<Option<UploadSession>, DatabaseError> {
+        let mut redis = redis.connect().await?;
+
+        redis
+            .get_deserialized_from_json(UPLOAD_SESSIONS_NAMESPACE, id)
+            .await
+    }
+
+    /// 获取用户所有未过期的会话，已删除或过期的会话会被跳过
+    pub async fn get_user_sessions(
+        user_id: UserId,
+        redis: &RedisPool,
+    ) -> Result<Vec<UploadSession>, DatabaseError> {
+        let mut redis = redis.connect().await?;
+
+        let ids: Vec<String> = redis
+            .get_deserialized_from_json(
+                UPLOAD_SESSIONS_USER_NAMESPACE,
+                &user_id.0.to_string(),
+            )
+            .await?
+            .unwrap_or_default();
+
+        let mut sessions = Vec::new();
+        for id in ids {
+            if let Some(session) = redis
+                .get_deserialized_from_json::<UploadSession>(
+                    UPLOAD_SESSIONS_NAMESPACE,
+                    &id,
+                )
+                .await?
+                && session.user_id == user_id
+            {
+                sessions.push(session);
+            }
+        }
+
+        Ok(sessions)
+    }
+
+    /// 删除会话及其暂存文件
+    pub async fn remove(&self, redis: &RedisPool) -> Result<(), DatabaseError> {
+        let mut redis = redis.connect().await?;
+
+        redis.delete(UPLOAD_SESSIONS_NAMESPACE, &self.id).await?;
+
+        let path = self.staging_path();
+        let result =
+            actix_web::web::block(move || match std::fs::remove_file(&path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    Err((path, err))
+                }
+                _ => Ok(()),
+            })
+            .await;
+        match result {
+            Ok(Err((path, err))) => {
+                log::warn!("删除上传暂存文件 {} 失败: {err}", path.display());
+            }
+            Err(err) => {
+                log::warn!("删除上传暂存文件 {} 失败: {err}", self.id);
+            }
+            Ok(Ok(())) => {}
+        }
+
+        Ok(())
+    }
+
+    /// 对会话加锁，防止同一偏移的分块被并发写入；返回的锁在 drop 时自动释放
+    pub async fn lock(
+        id: &str,
+        redis: &RedisPool,
+    ) -> Result<Option<RedisLock>, DatabaseError> {
+        RedisLock::acquire(
+            redis,
+            UPLOAD_SESSIONS_LOCK_NAMESPACE,
+            id,
+            UPLOAD_SESSION_LOCK_EXPIRY,
+        )
+        .await
+    }
+
+    /// 对用户的会话索引加锁，保证并发创建会话时配额检查不被绕过
+    pub async fn lock_user(
+        user_id: UserId,
+        redis: &RedisPool,
+    ) -> Result<Option<RedisLock>, DatabaseError> {
+        RedisLock::acquire(
+            redis,
+            UPLOAD_SESSIONS_USER_LOCK_NAMESPACE,
+            &user_id.0.to_string(),
+            UPLOAD_SESSION_USER_LOCK_EXPIRY,
+        )
+        .await
+    }
+
+    /// 清理超过会话有效期的暂存文件，返回删除的文件数
+    pub fn remove_stale_files() -> std::io::Result<usize> {
+        let dir = Self::staging_dir();
+        if !dir.exists() {
+            return Ok(0);
+        }
+
+        let expiry =
+            std::time::Duration::from_secs(UPLOAD_SESSION_EXPIRY as u64);
+        let mut removed = 0;
+        // 单个文件处理失败时记录日志并继续清理其余文件
+        for entry in std::fs::read_dir(dir)? {
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(err) => {
+                    log::warn!("读取上传暂存目录失败: {err}");
+                    continue;
+                }
+            };
+            match remove_if_stale(&path, expiry) {
+                Ok(true) => removed += 1,
+                Ok(false) => {}
+                Err(err) => {
+                    log::warn!(
+                        "删除上传暂存文件 {} 失败: {err}",
+                        path.display()
+                    );
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+}
+
+/// 文件最后修改时间超过 `expiry` 时删除，返回是否删除
+fn remove_if_stale(
+    path: &std::path::Path,
+    expiry: std::time::Duration,
+) -> std::io::Result<bool> {
+    let modified = std::fs::metadata(path)?.modified()?;
+    if !modified.elapsed().is_ok_and(|x| x > expiry) {
+        return Ok(false);
+    }
+
+    std::fs::remove_file(path)?;
+    Ok(true)
+}
@@ -4,6 +4,7 @@ use super::loader_fields::VersionField;
 use crate::database::models::loader_fields::{
     QueryLoaderField, QueryLoaderFieldEnumValue, QueryVersionField,
 };
+use crate::database::models::modpack_file_item::ModpackFileBuilder;
 use crate::database::redis::RedisPool;
 use crate::models::projects::{FileType, VersionStatus};
 use chrono::{DateTime, Utc};
@@ -186,6 +187,8 @@ pub struct VersionFileBuilder {
     pub size: u32,
     pub file_type: Option<FileType>,
     pub is_private: bool, // 是否存储在私有桶（付费资源）
+    // 整合包文件解析出的文件列表
+    pub pack_files: Vec<ModpackFileBuilder>,
 }
 
 impl VersionFileBuilder {
@@ -227,6 +230,13 @@ impl VersionFileBuilder {
             .await?;
         }
 
+        ModpackFileBuilder::insert_many(
+            self.pack_files,
+            version_id,
+            &mut *transaction,
+        )
+        .await?;
+
         Ok(file_id)
     }
 }
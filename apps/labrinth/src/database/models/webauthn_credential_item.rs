@@ -0,0 +1,217 @@
+use super::ids::*;
+use crate::database::models::DatabaseError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use webauthn_rs::prelude::Passkey;
+
+/// 用户注册的 WebAuthn 通行密钥
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct WebauthnCredential {
+    pub id: WebauthnCredentialId,
+    pub user_id: UserId,
+    /// base64url 编码的凭据 ID
+    pub credential_id: String,
+    pub name: String,
+    pub passkey: Passkey,
+    pub created: DateTime<Utc>,
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+impl WebauthnCredential {
+    pub async fn insert(
+        &self,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            INSERT INTO user_webauthn_credentials (
+                id, user_id, credential_id, name, passkey
+            )
+            VALUES (
+                $1, $2, $3, $4, $5
+            )
+            ",
+            self.id as WebauthnCredentialId,
+            self.user_id as UserId,
+            self.credential_id,
+            self.name,
+            serde_json::to_value(&self.passkey)?,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get(
+        id: WebauthnCredentialId,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<Option<WebauthnCredential>, DatabaseError> {
+        let credential = sqlx::query!(
+            "
+            SELECT id, user_id, credential_id, name, passkey, created, last_used
+            FROM user_webauthn_credentials
+            WHERE id = $1
+            ",
+            id as WebauthnCredentialId,
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        credential
+            .map(|x| {
+                Ok(WebauthnCredential {
+                    id: WebauthnCredentialId(x.id),
+                    user_id: UserId(x.user_id),
+                    credential_id: x.credential_id,
+                    name: x.name,
+                    passkey: serde_json::from_value(x.passkey)?,
+                    created: x.created,
+                    last_used: x.last_used,
+                })
+            })
+            .transpose()
+    }
+
+    pub async fn get_all_user(
+        user_id: UserId,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<Vec<WebauthnCredential>, DatabaseError> {
+        let credentials = sqlx::query!(
+            "
+            SELECT id, user_id, credential_id, name, passkey, created, last_used
+            FROM user_webauthn_credentials
+            WHERE user_id = $1
+            ORDER BY created
+            ",
+            user_id as UserId,
+        )
+        .fetch_all(exec)
+        .await?;
+
+        credentials
+            .into_iter()
+            .map(|x| {
+                Ok(WebauthnCredential {
+                    id: WebauthnCredentialId(x.id),
+                    user_id: UserId(x.user_id),
+                    credential_id: x.credential_id,
+                    name: x.name,
+                    passkey: serde_json::from_value(x.passkey)?,
+                    created: x.created,
+                    last_used: x.last_used,
+                })
+            })
+            .collect()
+    }
+
+    /// 通过凭据 ID 查找，用于无密码登录时识别用户
+    pub async fn get_by_credential_id(
+        credential_id: &str,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<Option<WebauthnCredential>, DatabaseError> {
+        let credential = sqlx::query!(
+            "
+            SELECT id, user_id, credential_id, name, passkey, created, last_used
+            FROM user_webauthn_credentials
+            WHERE credential_id = $1
+            ",
+            credential_id,
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        credential
+            .map(|x| {
+                Ok(WebauthnCredential {
+                    id: WebauthnCredentialId(x.id),
+                    user_id: UserId(x.user_id),
+                    credential_id: x.credential_id,
+                    name: x.name,
+                    passkey: serde_json::from_value(x.passkey)?,
+                    created: x.created,
+                    last_used: x.last_used,
+                })
+            })
+            .transpose()
+    }
+
+    /// 返回给定用户中至少注册了一个通行密钥的用户
+    pub async fn get_users_with_credentials(
+        user_ids: &[UserId],
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<Vec<UserId>, DatabaseError> {
+        let user_ids = sqlx::query!(
+            "
+            SELECT DISTINCT user_id
+            FROM user_webauthn_credentials
+            WHERE user_id = ANY($1)
+            ",
+            &user_ids.iter().map(|x| x.0).collect::<Vec<_>>(),
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| UserId(x.user_id))
+        .collect();
+
+        Ok(user_ids)
+    }
+
+    /// 认证成功后保存更新的签名计数并记录使用时间
+    pub async fn update_after_use(
+        &self,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE user_webauthn_credentials
+            SET passkey = $1, last_used = CURRENT_TIMESTAMP
+            WHERE id = $2
+            ",
+            serde_json::to_value(&self.passkey)?,
+            self.id as WebauthnCredentialId,
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn rename(
+        id: WebauthnCredentialId,
+        name: &str,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE user_webauthn_credentials
+            SET name = $1
+            WHERE id = $2
+            ",
+            name,
+            id as WebauthnCredentialId,
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove(
+        id: WebauthnCredentialId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            DELETE FROM user_webauthn_credentials
+            WHERE id = $1
+            ",
+            id as WebauthnCredentialId,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -3,6 +3,10 @@ use crate::models::ids::base62_impl::{parse_base62, to_base62};
 use chrono::{TimeZone, Utc};
 use dashmap::DashMap;
 use deadpool_redis::{Config, Runtime};
+use rand::Rng;
+use rand::distributions::Alphanumeric;
+use rand_chacha::ChaCha20Rng;
+use rand_chacha::rand_core::SeedableRng;
 use redis::{
     Cmd, ConnectionInfo, ExistenceCheck, IntoConnectionInfo, SetExpiry,
     SetOptions, cmd,
@@ -613,6 +617,31 @@ impl RedisConnection {
         Ok(())
     }
 
+    /// 仅在键不存在时写入，返回是否写入成功（SET NX）
+    pub async fn set_if_absent(
+        &mut self,
+        namespace: &str,
+        id: &str,
+        data: &str,
+        expiry: i64,
+    ) -> Result<bool, DatabaseError> {
+        let mut cmd = cmd("SET");
+        redis_args(
+            &mut cmd,
+            vec![
+                format!("{}_{}:{}", self.meta_namespace, namespace, id),
+                data.to_string(),
+                "NX".to_string(),
+                "EX".to_string(),
+                expiry.to_string(),
+            ]
+            .as_slice(),
+        );
+        let res: Option<String> =
+            redis_execute(&mut cmd, &mut self.connection).await?;
+        Ok(res.is_some())
+    }
+
     pub async fn set_serialized_to_json<Id, D>(
         &mut self,
         namespace: &str,
@@ -648,6 +677,44 @@ impl RedisConnection {
         Ok(res)
     }
 
+    /// 原子地读取并删除键，多个调用方并发读取时只有一个能取到值
+    pub async fn get_and_delete(
+        &mut self,
+        namespace: &str,
+        id: &str,
+    ) -> Result<Option<String>, DatabaseError> {
+        let mut cmd = cmd("GETDEL");
+        redis_args(
+            &mut cmd,
+            vec![format!("{}_{}:{}", self.meta_namespace, namespace, id)]
+                .as_slice(),
+        );
+        let res = redis_execute(&mut cmd, &mut self.connection).await?;
+        Ok(res)
+    }
+
+    /// 计数器自增并返回新值，首次创建时设置过期时间（固定窗口计数）
+    pub async fn increment(
+        &mut self,
+        namespace: &str,
+        id: &str,
+        expiry: i64,
+    ) -> Result<i64, DatabaseError> {
+        let key = format!("{}_{}:{}", self.meta_namespace, namespace, id);
+
+        let mut incr = cmd("INCR");
+        redis_args(&mut incr, vec![key.clone()].as_slice());
+        let count: i64 = redis_execute(&mut incr, &mut self.connection).await?;
+
+        if count == 1 {
+            let mut expire = cmd("EXPIRE");
+            redis_args(&mut expire, vec![key, expiry.to_string()].as_slice());
+            redis_execute::<()>(&mut expire, &mut self.connection).await?;
+        }
+
+        Ok(count)
+    }
+
     pub async fn get_deserialized_from_json<R>(
         &mut self,
         namespace: &str,
@@ -680,6 +747,28 @@ impl RedisConnection {
         Ok(())
     }
 
+    /// 仅在键的值与 `data` 相同时删除，返回是否删除成功
+    pub async fn delete_if_equals(
+        &mut self,
+        namespace: &str,
+        id: &str,
+        data: &str,
+    ) -> Result<bool, DatabaseError> {
+        let mut cmd = cmd("EVAL");
+        redis_args(
+            &mut cmd,
+            vec![
+                "if redis.call('GET', KEYS[1]) == ARGV[1] then return redis.call('DEL', KEYS[1]) else return 0 end".to_string(),
+                "1".to_string(),
+                format!("{}_{}:{}", self.meta_namespace, namespace, id),
+                data.to_string(),
+            ]
+            .as_slice(),
+        );
+        let res: i64 = redis_execute(&mut cmd, &mut self.connection).await?;
+        Ok(res == 1)
+    }
+
     pub async fn delete_many(
         &mut self,
         iter: impl IntoIterator<Item = (&str, Option<String>)>,
@@ -751,6 +840,83 @@ impl RedisConnection {
     }
 }
 
+/// 基于 `SET NX` 的互斥锁
+///
+/// 锁的值是随机生成的持有者标识，释放时只删除仍由自己持有的锁，避免锁过期
+/// 被其他请求获取后误删对方的锁。调用 [`RedisLock::release`] 释放；若持有锁的
+/// future 被丢弃（例如客户端断开连接），会在 drop 时后台释放，避免锁一直保留到过期
+pub struct RedisLock {
+    pool: RedisPool,
+    namespace: &'static str,
+    id: String,
+    token: String,
+    released: bool,
+}
+
+impl RedisLock {
+    /// 尝试获取锁，已被占用时返回 `None`
+    pub async fn acquire(
+        pool: &RedisPool,
+        namespace: &'static str,
+        id: &str,
+        expiry: i64,
+    ) -> Result<Option<RedisLock>, DatabaseError> {
+        let token = ChaCha20Rng::from_entropy()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect::<String>();
+
+        let mut redis = pool.connect().await?;
+        if !redis.set_if_absent(namespace, id, &token, expiry).await? {
+            return Ok(None);
+        }
+
+        Ok(Some(RedisLock {
+            pool: pool.clone(),
+            namespace,
+            id: id.to_string(),
+            token,
+            released: false,
+        }))
+    }
+
+    pub async fn release(mut self) -> Result<(), DatabaseError> {
+        self.released = true;
+        let mut redis = self.pool.connect().await?;
+        redis
+            .delete_if_equals(self.namespace, &self.id, &self.token)
+            .await?;
+        Ok(())
+    }
+}
+
+impl Drop for RedisLock {
+    fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+
+        let pool = self.pool.clone();
+        let namespace = self.namespace;
+        let id = std::mem::take(&mut self.id);
+        let token = std::mem::take(&mut self.token);
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(async move {
+                let result = match pool.connect().await {
+                    Ok(mut redis) => {
+                        redis.delete_if_equals(namespace, &id, &token).await
+                    }
+                    Err(err) => Err(err),
+                };
+                if let Err(err) = result {
+                    log::warn!("释放锁 {namespace}:{id} 失败: {err}");
+                }
+            });
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct RedisValue<T, K, S> {
     key: K,
@@ -13,8 +13,6 @@ use queue::{
 use sqlx::Postgres;
 use tokio::sync::RwLock;
 
-extern crate clickhouse as clickhouse_crate;
-use clickhouse_crate::Client;
 use governor::middleware::StateInformationMiddleware;
 use governor::{Quota, RateLimiter};
 use log::{info, warn};
@@ -36,7 +34,9 @@ use crate::{
     util::env::{parse_strings_from_var, parse_var},
 };
 
+pub mod analytics;
 pub mod auth;
+pub mod captcha;
 pub mod clickhouse;
 pub mod database;
 pub mod file_hosting;
@@ -45,6 +45,7 @@ pub mod queue;
 pub mod routes;
 pub mod scheduler;
 pub mod search;
+pub mod sms;
 pub mod util;
 pub mod validate;
 
@@ -57,10 +58,12 @@ pub struct Pepper {
 pub struct LabrinthConfig {
     pub pool: sqlx::Pool<Postgres>,
     pub redis_pool: RedisPool,
-    pub clickhouse: Client,
+    pub analytics: Arc<dyn analytics::AnalyticsStorage + Send + Sync>,
     pub file_host: Arc<dyn file_hosting::FileHost + Send + Sync>,
     /// 私有桶文件存储（用于付费插件），可选
     pub private_file_host: Option<Arc<file_hosting::S3PrivateHost>>,
+    pub sms_sender: Arc<dyn sms::SmsSender + Send + Sync>,
+    pub captcha: Arc<captcha::Captcha>,
     pub scheduler: Arc<scheduler::Scheduler>,
     pub ip_salt: Pepper,
     pub search_config: search::SearchConfig,
@@ -73,13 +76,16 @@ pub struct LabrinthConfig {
     // pub stripe_client: stripe::Client,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn app_setup(
     pool: sqlx::Pool<Postgres>,
     redis_pool: RedisPool,
     search_config: search::SearchConfig,
-    clickhouse: &mut Client,
+    analytics: Arc<dyn analytics::AnalyticsStorage + Send + Sync>,
     file_host: Arc<dyn file_hosting::FileHost + Send + Sync>,
     private_file_host: Option<Arc<file_hosting::S3PrivateHost>>,
+    sms_sender: Arc<dyn sms::SmsSender + Send + Sync>,
+    captcha: Arc<captcha::Captcha>,
 ) -> LabrinthConfig {
     info!("启动 Labrinth 于 {}", dotenvy::var("BIND_ADDR").unwrap());
 
@@ -193,6 +199,27 @@ pub fn app_setup(
         redis_pool.clone(),
     );
 
+    scheduler::schedule_translation_coverage(
+        &mut scheduler,
+        pool.clone(),
+        redis_pool.clone(),
+    );
+
+    scheduler::schedule_subscription_notifications(
+        &mut scheduler,
+        pool.clone(),
+        redis_pool.clone(),
+    );
+
+    scheduler::schedule_analytics_reports(
+        &mut scheduler,
+        pool.clone(),
+        redis_pool.clone(),
+        analytics.clone(),
+    );
+
+    scheduler::schedule_oidc_key_rotation(&mut scheduler, pool.clone());
+
     // 每 5 分钟清理超过 12 小时未付款的待支付订单
     let pool_ref = pool.clone();
     scheduler.run(std::time::Duration::from_secs(60 * 5), move || {
@@ -214,6 +241,26 @@ pub fn app_setup(
         }
     });
 
+    // 每小时清理过期的分块上传暂存文件
+    scheduler.run(std::time::Duration::from_secs(60 * 60), move || async {
+        match actix_web::web::block(
+            database::models::upload_session_item::UploadSession::remove_stale_files,
+        )
+        .await
+        {
+            Ok(Ok(count)) if count > 0 => {
+                info!("已清理 {} 个过期的上传暂存文件", count);
+            }
+            Ok(Err(e)) => {
+                log::error!("清理上传暂存文件失败: {:?}", e);
+            }
+            Err(e) => {
+                log::error!("清理上传暂存文件失败: {:?}", e);
+            }
+            _ => {}
+        }
+    });
+
     let session_queue = web::Data::new(AuthQueue::new());
 
     let pool_ref = pool.clone();
@@ -236,12 +283,12 @@ pub fn app_setup(
 
     let analytics_queue = Arc::new(AnalyticsQueue::new());
     {
-        let client_ref = clickhouse.clone();
+        let analytics_ref = analytics.clone();
         let analytics_queue_ref = analytics_queue.clone();
         let pool_ref = pool.clone();
         let redis_ref = redis_pool.clone();
         scheduler.run(std::time::Duration::from_secs(15), move || {
-            let client_ref = client_ref.clone();
+            let analytics_ref = analytics_ref.clone();
             let analytics_queue_ref = analytics_queue_ref.clone();
             let pool_ref = pool_ref.clone();
             let redis_ref = redis_ref.clone();
@@ -249,7 +296,7 @@ pub fn app_setup(
             async move {
                 info!("开始索引分析服务");
                 let result = analytics_queue_ref
-                    .index(client_ref, &redis_ref, &pool_ref)
+                    .index(&*analytics_ref, &redis_ref, &pool_ref)
                     .await;
                 if let Err(e) = result {
                     warn!("分析服务索引失败: {:?}", e);
@@ -285,8 +332,9 @@ pub fn app_setup(
                                     m.updated updated, m.approved approved, m.queued, m.status status, m.requested_status requested_status,
                                     m.license_url license_url,
                                     m.team_id team_id, m.organization_id organization_id, m.license license, m.slug slug, m.moderation_message moderation_message, m.moderation_message_body moderation_message_body,
-                                    m.webhook_sent, m.color, m.wiki_open,m.issues_type issues_type, m.translation_tracking, m.translation_tracker, m.is_paid,
-                                    (SELECT slug FROM mods WHERE translation_tracker = m.slug AND m.slug IS NOT NULL LIMIT 1) as translation_source,
+                                    m.webhook_sent, m.color, m.wiki_open,m.issues_type issues_type, m.translation_tracking,
+                                    (SELECT jsonb_object_agg(mtt.language_code, mtt.translation_slug) FROM mod_translation_trackers mtt WHERE mtt.mod_id = m.id) as translation_tracker, m.is_paid,
+                                    (SELECT src.slug FROM mod_translation_trackers mtt INNER JOIN mods src ON src.id = mtt.mod_id WHERE LOWER(mtt.translation_slug) = LOWER(m.slug) LIMIT 1) as translation_source,
                                     t.id thread_id, m.monetization_status monetization_status,
                                     ARRAY_AGG(DISTINCT c.category) filter (where c.category is not null and mc.is_additional is false) categories,
                                     ARRAY_AGG(DISTINCT c.category) filter (where c.category is not null and mc.is_additional is true) additional_categories
@@ -337,7 +385,11 @@ pub fn app_setup(
                                             issues_type: m.issues_type,
                                             forum: None,
                                             translation_tracking: m.translation_tracking,
-                                            translation_tracker: m.translation_tracker.clone(),
+                                            translation_tracker: m
+                                    .translation_tracker
+                                    .clone()
+                                    .and_then(|x| serde_json::from_value(x).ok())
+                                    .unwrap_or_default(),
                                             translation_source: m.translation_source.clone(),
                                             is_paid: m.is_paid,
                                         };
@@ -697,9 +749,11 @@ pub fn app_setup(
     LabrinthConfig {
         pool,
         redis_pool,
-        clickhouse: clickhouse.clone(),
+        analytics,
         file_host,
         private_file_host,
+        sms_sender,
+        captcha,
         scheduler: Arc::new(scheduler),
         ip_salt,
         search_config,
@@ -732,12 +786,14 @@ pub fn app_config(
     .app_data(web::Data::new(labrinth_config.pool.clone()))
     .app_data(web::Data::new(labrinth_config.file_host.clone()))
     .app_data(web::Data::new(labrinth_config.private_file_host.clone()))
+    .app_data(web::Data::new(labrinth_config.sms_sender.clone()))
+    .app_data(web::Data::new(labrinth_config.captcha.clone()))
     .app_data(web::Data::new(labrinth_config.search_config.clone()))
     .app_data(labrinth_config.session_queue.clone())
     .app_data(labrinth_config.payouts_queue.clone())
     .app_data(web::Data::new(labrinth_config.ip_salt.clone()))
     .app_data(web::Data::new(labrinth_config.analytics_queue.clone()))
-    .app_data(web::Data::new(labrinth_config.clickhouse.clone()))
+    .app_data(web::Data::new(labrinth_config.analytics.clone()))
     .app_data(labrinth_config.active_sockets.clone())
     .app_data(labrinth_config.automated_moderation_queue.clone())
     // .app_data(web::Data::new(labrinth_config.stripe_client.clone()))
@@ -849,7 +905,37 @@ pub fn check_env_vars() -> bool {
     failed |= check_var::<String>("PAYPAL_CLIENT_ID");
     failed |= check_var::<String>("PAYPAL_CLIENT_SECRET");
 
-    failed |= check_var::<String>("HCAPTCHA_SECRET");
+    let captcha_providers = captcha::CaptchaAction::ALL
+        .iter()
+        .map(|x| x.provider_name())
+        .collect::<std::collections::HashSet<_>>();
+    for provider in captcha_providers {
+        match provider.as_str() {
+            "tac" => {
+                failed |= check_var::<String>("TAC_URL");
+            }
+            "hcaptcha" => {
+                failed |= check_var::<String>("HCAPTCHA_SITE_KEY");
+                failed |= check_var::<String>("HCAPTCHA_SECRET");
+            }
+            "turnstile" => {
+                failed |= check_var::<String>("TURNSTILE_SITE_KEY");
+                failed |= check_var::<String>("TURNSTILE_SECRET");
+            }
+            "geetest" => {
+                failed |= check_var::<String>("GEETEST_CAPTCHA_ID");
+                failed |= check_var::<String>("GEETEST_CAPTCHA_KEY");
+            }
+            "test" => {}
+            backend => {
+                warn!(
+                    "人机验证服务包含无效值：{}。预期值为 \"tac\"、\"hcaptcha\"、\"turnstile\"、\"geetest\" 或 \"test\"。",
+                    backend
+                );
+                failed |= true;
+            }
+        }
+    }
 
     failed |= check_var::<String>("SMTP_USERNAME");
     failed |= check_var::<String>("SMTP_PASSWORD");
@@ -869,10 +955,54 @@ pub fn check_env_vars() -> bool {
         failed |= true;
     }
 
-    failed |= check_var::<String>("CLICKHOUSE_URL");
-    failed |= check_var::<String>("CLICKHOUSE_USER");
-    failed |= check_var::<String>("CLICKHOUSE_PASSWORD");
-    failed |= check_var::<String>("CLICKHOUSE_DATABASE");
+    let analytics_backend = dotenvy::var("ANALYTICS_BACKEND").ok();
+    match analytics_backend.as_deref() {
+        None | Some("clickhouse") => {
+            failed |= check_var::<String>("CLICKHOUSE_URL");
+            failed |= check_var::<String>("CLICKHOUSE_USER");
+            failed |= check_var::<String>("CLICKHOUSE_PASSWORD");
+            failed |= check_var::<String>("CLICKHOUSE_DATABASE");
+        }
+        Some("postgres") => {}
+        Some(backend) => {
+            warn!(
+                "变量 `ANALYTICS_BACKEND` 包含无效值：{}。预期值为 \"clickhouse\" 或 \"postgres\"。",
+                backend
+            );
+            failed |= true;
+        }
+    }
+
+    let sms_backend = dotenvy::var("SMS_BACKEND").ok();
+    match sms_backend.as_deref() {
+        Some("local") => {}
+        Some("aliyun") => {
+            failed |= check_var::<String>("ALIYUN_SMS_ACCESS_KEYID");
+            failed |= check_var::<String>("ALIYUN_SMS_ACCESS_KEY_SECRET");
+            failed |= check_var::<String>("ALIYUN_SMS_REGION");
+            failed |= check_var::<String>("ALIYUN_SMS_REPORT_TEMPLETE_CODE");
+            failed |= check_var::<String>("ALIYUN_SMS_SIGN_NAME");
+        }
+        Some("tencent") => {
+            failed |= check_var::<String>("TENCENT_SMS_SECRET_ID");
+            failed |= check_var::<String>("TENCENT_SMS_SECRET_KEY");
+            failed |= check_var::<String>("TENCENT_SMS_REGION");
+            failed |= check_var::<String>("TENCENT_SMS_SDK_APP_ID");
+            failed |= check_var::<String>("TENCENT_SMS_SIGN_NAME");
+            failed |= check_var::<String>("TENCENT_SMS_TEMPLATE_ID");
+        }
+        Some(backend) => {
+            warn!(
+                "变量 `SMS_BACKEND` 包含无效值：{}。预期值为 \"aliyun\"、\"tencent\" 或 \"local\"。",
+                backend
+            );
+            failed |= true;
+        }
+        None => {
+            warn!("变量 `SMS_BACKEND` 未设置！");
+            failed |= true;
+        }
+    }
 
     failed |= check_var::<String>("FLAME_ANVIL_URL");
 
@@ -4,7 +4,9 @@ use labrinth::database::redis::RedisPool;
 use labrinth::file_hosting::{S3Host, S3PrivateHost};
 use labrinth::search;
 use labrinth::util::ratelimit::RateLimit;
-use labrinth::{check_env_vars, clickhouse, database, file_hosting};
+use labrinth::{
+    analytics, captcha, check_env_vars, clickhouse, database, file_hosting, sms,
+};
 use std::sync::Arc;
 use tracing::{error, info};
 
@@ -120,8 +122,61 @@ async fn main() -> std::io::Result<()> {
             None
         };
 
-    info!("初始化 clickhouse 连接");
-    let mut clickhouse = clickhouse::init_client().await.unwrap();
+    let analytics_backend = dotenvy::var("ANALYTICS_BACKEND")
+        .unwrap_or_else(|_| "clickhouse".to_string());
+
+    let analytics: Arc<dyn analytics::AnalyticsStorage + Send + Sync> =
+        match analytics_backend.as_str() {
+            "clickhouse" => {
+                info!("初始化 clickhouse 连接");
+                Arc::new(analytics::ClickhouseAnalytics::new(
+                    clickhouse::init_client().await.unwrap(),
+                ))
+            }
+            "postgres" => {
+                info!("使用 Postgres 存储分析数据");
+                Arc::new(analytics::PostgresAnalytics::new(pool.clone()))
+            }
+            _ => panic!("指定了无效的分析数据后端。启动中止！"),
+        };
+
+    // 未配置短信后端时拒绝启动，避免生产环境误用只写日志的 local 后端
+    let sms_backend = dotenvy::var("SMS_BACKEND")
+        .expect("未设置短信后端 `SMS_BACKEND`。启动中止！");
+
+    let sms_sender: Arc<dyn sms::SmsSender + Send + Sync> = match sms_backend
+        .as_str()
+    {
+        "aliyun" => Arc::new(sms::AliyunSms::new(
+            &dotenvy::var("ALIYUN_SMS_REGION").unwrap(),
+            &dotenvy::var("ALIYUN_SMS_ACCESS_KEYID").unwrap(),
+            &dotenvy::var("ALIYUN_SMS_ACCESS_KEY_SECRET").unwrap(),
+            &dotenvy::var("ALIYUN_SMS_SIGN_NAME").unwrap(),
+            &dotenvy::var("ALIYUN_SMS_REPORT_TEMPLETE_CODE").unwrap(),
+        )),
+        "tencent" => Arc::new(sms::TencentSms::new(
+            &dotenvy::var("TENCENT_SMS_REGION").unwrap(),
+            &dotenvy::var("TENCENT_SMS_SECRET_ID").unwrap(),
+            &dotenvy::var("TENCENT_SMS_SECRET_KEY").unwrap(),
+            &dotenvy::var("TENCENT_SMS_SDK_APP_ID").unwrap(),
+            &dotenvy::var("TENCENT_SMS_SIGN_NAME").unwrap(),
+            &dotenvy::var("TENCENT_SMS_TEMPLATE_ID").unwrap(),
+        )),
+        "local" => {
+            info!("短信不会实际发送，验证码仅写入日志");
+            Arc::new(sms::LocalSms::new(dotenvy::var("SMS_LOCAL_PATH").ok()))
+        }
+        _ => panic!("指定了无效的短信后端。启动中止！"),
+    };
+
+    let captcha = match captcha::Captcha::from_env() {
+        Ok(captcha) => Arc::new(captcha),
+        Err(err) => {
+            error!("人机验证配置无效: {err}");
+            return Err(std::io::Error::other(err));
+        }
+    };
+
     let prometheus = PrometheusMetricsBuilder::new("labrinth")
         .endpoint("/metrics")
         .exclude_regex("^/v[23]/project/[^/]+(/.*)?$") // 排除所有 /project/{id} 相关路由
@@ -135,9 +190,11 @@ async fn main() -> std::io::Result<()> {
         pool.clone(),
         redis_pool.clone(),
         search_config.clone(),
-        &mut clickhouse,
+        analytics,
         file_host.clone(),
         private_file_host,
+        sms_sender,
+        captcha,
     );
 
     info!("启动 Actix HTTP 服务器！");
@@ -17,6 +17,9 @@ pub use v3::payouts;
 pub use v3::projects;
 pub use v3::reports;
 pub use v3::sessions;
+pub use v3::subscriptions;
 pub use v3::teams;
 pub use v3::threads;
+pub use v3::translation_coverage;
 pub use v3::users;
+pub use v3::webauthn;
@@ -149,6 +149,29 @@ pub enum LegacyNotificationBody {
         status: String,
         review_notes: Option<String>,
     },
+    Subscription {
+        target_type: String,
+        target_id: String,
+        title: String,
+        link: String,
+        event_types: Vec<String>,
+        event_count: u32,
+        actors: Vec<String>,
+    },
+    Mention {
+        sender: String,
+        source_type: String,
+        title: String,
+        link: String,
+        excerpt: String,
+    },
+    TranslationOutdated {
+        project_id: ProjectId,
+        project_title: String,
+        version_id: VersionId,
+        version_number: String,
+        link: String,
+    },
     Unknown,
 }
 
@@ -204,6 +227,13 @@ impl LegacyNotification {
             NotificationBody::ImageReviewResult { .. } => {
                 Some("image_review_result".to_string())
             }
+            NotificationBody::Subscription { .. } => {
+                Some("subscription".to_string())
+            }
+            NotificationBody::Mention { .. } => Some("mention".to_string()),
+            NotificationBody::TranslationOutdated { .. } => {
+                Some("translation_outdated".to_string())
+            }
             NotificationBody::LegacyMarkdown {
                 notification_type, ..
             } => notification_type.clone(),
@@ -391,6 +421,49 @@ impl LegacyNotification {
                 status,
                 review_notes,
             },
+            NotificationBody::Subscription {
+                target_type,
+                target_id,
+                title,
+                link,
+                event_types,
+                event_count,
+                actors,
+            } => LegacyNotificationBody::Subscription {
+                target_type,
+                target_id,
+                title,
+                link,
+                event_types,
+                event_count,
+                actors,
+            },
+            NotificationBody::Mention {
+                sender,
+                source_type,
+                title,
+                link,
+                excerpt,
+            } => LegacyNotificationBody::Mention {
+                sender,
+                source_type,
+                title,
+                link,
+                excerpt,
+            },
+            NotificationBody::TranslationOutdated {
+                project_id,
+                project_title,
+                version_id,
+                version_number,
+                link,
+            } => LegacyNotificationBody::TranslationOutdated {
+                project_id,
+                project_title,
+                version_id,
+                version_number,
+                link,
+            },
             NotificationBody::Unknown => LegacyNotificationBody::Unknown,
         };
 
@@ -74,7 +74,7 @@ pub struct LegacyProject {
     pub forum: Option<DiscussionId>,
     /// 汉化追踪标记
     pub translation_tracking: bool,
-    /// 汉化资源 slug
+    /// 简体中文汉化资源 slug，其他语言见 v3 的 translation_tracker
     pub translation_tracker: Option<String>,
     /// 汉化来源：哪个项目将当前项目作为汉化目标
     pub translation_source: Option<String>,
@@ -280,7 +280,7 @@ impl LegacyProject {
             game_versions,
             forum: data.forum,
             translation_tracking: data.translation_tracking,
-            translation_tracker: data.translation_tracker.clone(),
+            translation_tracker: data.translation_tracker.get("zh_cn").cloned(),
             translation_source: data.translation_source.clone(),
             is_paid: data.is_paid,
             user_has_purchased: data.user_has_purchased,
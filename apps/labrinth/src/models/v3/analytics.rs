@@ -22,6 +22,14 @@ pub struct Download {
     pub country: String,
     pub user_agent: String,
     pub headers: Vec<(String, String)>,
+
+    // 来源页面的域名，未知时为空
+    pub referrer: String,
+    // 根据 User-Agent 识别的启动器，例如 hmcl、pcl、browser
+    pub launcher: String,
+    // 下载版本支持的加载器和游戏版本
+    pub loaders: Vec<String>,
+    pub game_versions: Vec<String>,
 }
 
 #[derive(Row, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
@@ -43,6 +51,9 @@ pub struct PageView {
     pub country: String,
     pub user_agent: String,
     pub headers: Vec<(String, String)>,
+
+    // 来源页面的域名，未知时为空
+    pub referrer: String,
 }
 
 #[derive(Row, Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Hash)]
@@ -62,3 +73,24 @@ pub struct Playtime {
     /// Parent modpack this playtime was recorded in
     pub parent: u64,
 }
+
+/// 被刷量检测判定为可疑、未计入下载量的下载
+#[derive(Row, Serialize, Deserialize, Clone, Debug)]
+pub struct SuspiciousDownload {
+    pub recorded: i64,
+
+    pub user_id: u64,
+    pub project_id: u64,
+    pub version_id: u64,
+
+    pub ip: Ipv6Addr,
+    // 0 if unknown
+    pub asn: u32,
+    pub user_agent: String,
+
+    /// 本批次内同一 IP 对该项目的请求次数
+    pub attempts: u32,
+    pub score: u32,
+    /// 命中的检测规则，例如 ip_velocity、headless_user_agent
+    pub reasons: Vec<String>,
+}
@@ -1,6 +1,9 @@
 use super::ids::Base62Id;
-use crate::database::models::forum::{PostQuery, QueryDiscussion};
-use crate::models::ids::ProjectId;
+use crate::database::models::forum::{
+    ForumEditHistory, PostQuery, QueryDiscussion,
+};
+use crate::database::models::reaction_item::ReactionSummary;
+use crate::models::ids::{ProjectId, UserId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -36,6 +39,9 @@ pub struct PostResponse {
     pub reply_to_deleted: bool,
     pub replies: Vec<Replay>,
     pub deleted: bool,
+    pub edited: bool,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub reactions: Vec<ReactionSummary>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -57,6 +63,21 @@ pub struct ForumResponse {
     pub last_post_time: DateTime<Utc>,
     pub replies: i32,
     pub project_id: Option<ProjectId>,
+    pub edited: bool,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub accepted_post_id: Option<PostId>,
+}
+
+/// 编辑记录，仅对版主可见
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ForumEditHistoryResponse {
+    pub discussion_id: DiscussionId,
+    pub post_id: Option<PostId>,
+    pub title: Option<String>,
+    pub content: String,
+    pub edited_by: UserId,
+    pub editor_name: Option<String>,
+    pub edited_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -99,6 +120,9 @@ impl From<PostQuery> for PostResponse {
             reply_to_deleted: post.reply_to_deleted,
             replies: post.replies,
             deleted: post.deleted,
+            edited: post.edited_at.is_some(),
+            edited_at: post.edited_at,
+            reactions: Vec::new(),
         }
     }
 }
@@ -128,6 +152,26 @@ impl From<QueryDiscussion> for ForumResponse {
             last_post_time: discussion.inner.last_post_time,
             replies: discussion.posts.len() as i32,
             project_id: discussion.inner.project_id.map(|id| id.into()),
+            edited: discussion.inner.edited_at.is_some(),
+            edited_at: discussion.inner.edited_at,
+            accepted_post_id: discussion
+                .inner
+                .accepted_post_id
+                .map(|id| id.into()),
+        }
+    }
+}
+
+impl From<ForumEditHistory> for ForumEditHistoryResponse {
+    fn from(history: ForumEditHistory) -> Self {
+        ForumEditHistoryResponse {
+            discussion_id: history.discussion_id.into(),
+            post_id: history.post_id.map(|id| id.into()),
+            title: history.title,
+            content: history.content,
+            edited_by: history.edited_by.into(),
+            editor_name: history.editor_name,
+            edited_at: history.edited_at,
         }
     }
 }
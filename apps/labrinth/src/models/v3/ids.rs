@@ -16,6 +16,7 @@ pub use super::teams::TeamId;
 pub use super::threads::ThreadId;
 pub use super::threads::ThreadMessageId;
 pub use super::users::UserId;
+pub use super::webauthn::WebauthnCredentialId;
 pub use crate::models::billing::{
     ChargeId, ProductId, ProductPriceId, UserSubscriptionId,
 };
@@ -154,6 +155,7 @@ base62_id_impl!(IssuesCommentsId, IssuesCommentsId);
 base62_id_impl!(UserBanId, UserBanId);
 base62_id_impl!(BanHistoryId, BanHistoryId);
 base62_id_impl!(BanAppealId, BanAppealId);
+base62_id_impl!(WebauthnCredentialId, WebauthnCredentialId);
 
 pub mod base62_impl {
     use serde::de::{self, Deserializer, Visitor};
@@ -1,10 +1,13 @@
 use super::ids::Base62Id;
 use crate::database::models::issues::{
-    IssueAssignee, IssueCommentQuery, IssueLabel, IssueReply, QueryIssue,
+    IssueAssignee, IssueCommentQuery, IssueLabel, IssueMilestone, IssueReply,
+    IssueTemplate, IssueTemplateField, QueryIssue,
 };
-use crate::models::ids::{ProjectId, UserId};
+use crate::database::models::reaction_item::ReactionSummary;
+use crate::models::ids::{ProjectId, UserId, VersionId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Debug, Hash)]
 #[serde(from = "Base62Id")]
@@ -51,6 +54,9 @@ pub struct IssueResponse {
     pub labels: Vec<LabelResponse>,
     pub assignees: Vec<AssigneeResponse>,
     pub comments_count: i32,
+    pub accepted_comment_id: Option<IssuesCommentsId>,
+    pub milestone_id: Option<i32>,
+    pub template_id: Option<i32>,
 }
 
 // 评论响应结构
@@ -68,17 +74,49 @@ pub struct CommentResponse {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub replies: Vec<ReplyResponse>,
+    pub reactions: Vec<ReactionSummary>,
 }
 
 // 标签响应结构
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LabelResponse {
     pub id: i32,
+    /// 为空时是全站通用标签
+    pub project_id: Option<ProjectId>,
     pub name: String,
     pub color: String,
     pub description: Option<String>,
 }
 
+// 里程碑响应结构
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MilestoneResponse {
+    pub id: i32,
+    pub project_id: ProjectId,
+    pub title: String,
+    pub description: String,
+    pub version_number: Option<String>,
+    pub version_id: Option<VersionId>,
+    pub state: String,
+    pub due_date: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub open_issues: i64,
+    pub closed_issues: i64,
+}
+
+// 问题模板响应结构
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TemplateResponse {
+    pub id: i32,
+    pub project_id: ProjectId,
+    pub name: String,
+    pub template_type: String,
+    pub description: String,
+    pub fields: Vec<IssueTemplateField>,
+    pub sort_order: i32,
+}
+
 // 指派人响应结构
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AssigneeResponse {
@@ -119,6 +157,11 @@ pub struct CreateIssueRequest {
     pub body: String,
     pub labels: Option<Vec<i32>>,
     pub assignees: Option<Vec<UserId>>,
+    /// 使用的问题模板
+    pub template_id: Option<i32>,
+    /// 模板字段的填写内容，键为字段 id
+    #[serde(default)]
+    pub fields: HashMap<String, String>,
 }
 
 // 更新Issue请求
@@ -126,6 +169,44 @@ pub struct CreateIssueRequest {
 pub struct UpdateIssueRequest {
     pub state: Option<String>,
     pub labels: Option<Vec<i32>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub milestone_id: Option<Option<i32>>,
+}
+
+// 创建/修改项目标签请求
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LabelRequest {
+    pub name: String,
+    pub color: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+// 创建/修改里程碑请求
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MilestoneRequest {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub version_number: Option<String>,
+    pub due_date: Option<DateTime<Utc>>,
+    pub state: Option<String>,
+}
+
+// 创建/修改问题模板请求
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TemplateRequest {
+    pub name: String,
+    pub template_type: String,
+    #[serde(default)]
+    pub description: String,
+    pub fields: Vec<IssueTemplateField>,
+    #[serde(default)]
+    pub sort_order: i32,
 }
 
 // 创建评论请求
@@ -136,6 +217,18 @@ pub struct CreateCommentRequest {
     pub reply_to_id: Option<IssuesCommentsId>,
 }
 
+// 表情回应请求
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReactionRequest {
+    pub reaction: String,
+}
+
+// 采纳回答请求，comment_id 为空时取消采纳
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AcceptCommentRequest {
+    pub comment_id: Option<IssuesCommentsId>,
+}
+
 // 更新评论请求
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateCommentRequest {
@@ -178,6 +271,12 @@ impl From<QueryIssue> for IssueResponse {
                 .map(|assignee| assignee.into())
                 .collect(),
             comments_count: issue.comments.len() as i32,
+            accepted_comment_id: issue
+                .inner
+                .accepted_comment_id
+                .map(|id| id.into()),
+            milestone_id: issue.inner.milestone_id,
+            template_id: issue.inner.template_id,
         }
     }
 }
@@ -210,6 +309,7 @@ impl From<IssueCommentQuery> for CommentResponse {
                 .into_iter()
                 .map(|reply| reply.into())
                 .collect(),
+            reactions: Vec::new(),
         }
     }
 }
@@ -218,6 +318,7 @@ impl From<IssueLabel> for LabelResponse {
     fn from(label: IssueLabel) -> Self {
         LabelResponse {
             id: label.id,
+            project_id: label.project_id.map(|id| id.into()),
             name: label.name,
             color: label.color,
             description: label.description,
@@ -225,6 +326,39 @@ impl From<IssueLabel> for LabelResponse {
     }
 }
 
+impl From<IssueMilestone> for MilestoneResponse {
+    fn from(milestone: IssueMilestone) -> Self {
+        MilestoneResponse {
+            id: milestone.id,
+            project_id: milestone.project_id.into(),
+            title: milestone.title,
+            description: milestone.description,
+            version_number: milestone.version_number,
+            version_id: milestone.version_id.map(|id| id.into()),
+            state: milestone.state,
+            due_date: milestone.due_date,
+            created_at: milestone.created_at,
+            closed_at: milestone.closed_at,
+            open_issues: milestone.open_issues,
+            closed_issues: milestone.closed_issues,
+        }
+    }
+}
+
+impl From<IssueTemplate> for TemplateResponse {
+    fn from(template: IssueTemplate) -> Self {
+        TemplateResponse {
+            id: template.id,
+            project_id: template.project_id.into(),
+            name: template.name,
+            template_type: template.template_type,
+            description: template.description,
+            fields: template.fields,
+            sort_order: template.sort_order,
+        }
+    }
+}
+
 impl From<IssueAssignee> for AssigneeResponse {
     fn from(assignee: IssueAssignee) -> Self {
         let mut user_avatar = assignee.user_avatar.unwrap_or_default();
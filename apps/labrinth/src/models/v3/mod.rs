@@ -15,6 +15,9 @@ pub mod payouts;
 pub mod projects;
 pub mod reports;
 pub mod sessions;
+pub mod subscriptions;
 pub mod teams;
 pub mod threads;
+pub mod translation_coverage;
 pub mod users;
+pub mod webauthn;
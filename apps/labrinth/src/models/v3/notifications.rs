@@ -146,6 +146,32 @@ pub enum NotificationBody {
         status: String,
         review_notes: Option<String>,
     },
+    /// 关注的讨论、问题或百科页面有新动态（合并发送）
+    Subscription {
+        target_type: String,
+        target_id: String,
+        title: String,
+        link: String,
+        event_types: Vec<String>,
+        event_count: u32,
+        actors: Vec<String>,
+    },
+    /// 在帖子、问题、消息线程或百科审核留言中被 @ 提及
+    Mention {
+        sender: String,
+        source_type: String,
+        title: String,
+        link: String,
+        excerpt: String,
+    },
+    /// 汉化追踪的整合包发布了新版本，但还没有已批准的汉化
+    TranslationOutdated {
+        project_id: ProjectId,
+        project_title: String,
+        version_id: VersionId,
+        version_number: String,
+        link: String,
+    },
     Unknown,
 }
 
@@ -477,6 +503,79 @@ impl From<DBNotification> for Notification {
                         vec![],
                     )
                 }
+                NotificationBody::Subscription {
+                    target_type,
+                    target_id,
+                    title,
+                    link,
+                    event_count,
+                    actors,
+                    ..
+                } => {
+                    let actors_text = match actors.len() {
+                        0 => "有人".to_string(),
+                        1..=3 => actors.join("、"),
+                        n => format!("{} 等 {} 人", actors[..3].join("、"), n),
+                    };
+                    (
+                        "您关注的内容有新动态".to_string(),
+                        format!(
+                            "{} 在 {} 中产生了 {} 条新动态",
+                            actors_text, title, event_count
+                        ),
+                        link.clone(),
+                        vec![NotificationAction {
+                            name: "取消关注".to_string(),
+                            action_route: (
+                                "DELETE".to_string(),
+                                format!(
+                                    "subscriptions/{target_type}/{target_id}"
+                                ),
+                            ),
+                        }],
+                    )
+                }
+                NotificationBody::Mention {
+                    sender,
+                    source_type,
+                    title,
+                    link,
+                    excerpt,
+                } => {
+                    let source = match source_type.as_str() {
+                        "forum" | "forum_post" => "论坛帖子",
+                        "issue" | "issue_comment" => "问题",
+                        "thread_message" => "消息线程",
+                        "wiki_review" => "百科审核",
+                        _ => "内容",
+                    };
+                    let text = if title.is_empty() {
+                        format!(
+                            "{} 在{}中提到了您：{}",
+                            sender, source, excerpt
+                        )
+                    } else {
+                        format!(
+                            "{} 在{}「{}」中提到了您：{}",
+                            sender, source, title, excerpt
+                        )
+                    };
+                    (format!("{} 提到了您", sender), text, link.clone(), vec![])
+                }
+                NotificationBody::TranslationOutdated {
+                    project_title,
+                    version_number,
+                    link,
+                    ..
+                } => (
+                    "整合包汉化已过期".to_string(),
+                    format!(
+                        "{} 发布了新版本 {}，需要更新汉化",
+                        project_title, version_number
+                    ),
+                    link.clone(),
+                    vec![],
+                ),
                 NotificationBody::Unknown => {
                     ("".to_string(), "".to_string(), "#".to_string(), vec![])
                 }
@@ -49,6 +49,12 @@ pub struct OAuthClient {
     // The maximum scopes the client can request for OAuth
     pub max_scopes: Scopes,
 
+    // Public clients cannot keep a secret and must use PKCE
+    pub is_public: bool,
+
+    // The algorithm used to sign OpenID Connect ID tokens
+    pub id_token_signed_response_alg: String,
+
     // The valid URIs that can be redirected to during an authorization request
     pub redirect_uris: Vec<OAuthRedirectUri>,
 
@@ -70,6 +76,9 @@ pub struct OAuthClientAuthorization {
     pub user_id: UserId,
     pub scopes: Scopes,
     pub created: DateTime<Utc>,
+    // OpenID Connect claims that have been released to the app
+    pub released_claims: Vec<String>,
+    pub claims_released: Option<DateTime<Utc>>,
 }
 
 #[serde_as]
@@ -93,6 +102,8 @@ impl From<DBOAuthClient> for OAuthClient {
             name: value.name,
             icon_url: value.icon_url,
             max_scopes: value.max_scopes,
+            is_public: value.is_public,
+            id_token_signed_response_alg: value.id_token_signed_response_alg,
             redirect_uris: value
                 .redirect_uris
                 .into_iter()
@@ -124,6 +135,8 @@ impl From<DBOAuthClientAuthorization> for OAuthClientAuthorization {
             user_id: value.user_id.into(),
             scopes: value.scopes,
             created: value.created,
+            released_claims: value.released_claims,
+            claims_released: value.claims_released,
         }
     }
 }
@@ -28,6 +28,8 @@ pub struct Organization {
     pub icon_url: Option<String>,
     /// The color of the organization (picked from the icon)
     pub color: Option<u32>,
+    /// 持有敏感权限的成员是否必须使用通行密钥作为第二因素
+    pub require_passkey_2fa: bool,
 
     /// A list of the members of the organization
     pub members: Vec<TeamMember>,
@@ -47,6 +49,7 @@ impl Organization {
             members: team_members,
             icon_url: data.icon_url,
             color: data.color,
+            require_passkey_2fa: data.require_passkey_2fa,
         }
     }
 }
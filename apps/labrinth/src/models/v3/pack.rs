@@ -1,8 +1,13 @@
+use crate::database::models::modpack_file_item::{
+    ModpackFile, ModpackFileKind,
+};
+use crate::models::ids::{ProjectId, VersionId};
 use crate::{
     models::v2::projects::LegacySideType, util::env::parse_strings_from_var,
     util::safe_path::SafeRelativePath,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use validator::Validate;
 
 #[derive(Serialize, Deserialize, Validate, Eq, PartialEq, Debug)]
@@ -115,3 +120,242 @@ impl PackDependency {
         }
     }
 }
+
+/// 整合包版本中的文件
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PackFileEntry {
+    pub path: String,
+    pub kind: ModpackFileKind,
+    pub sha1: String,
+    /// 通过哈希解析到的站内项目，未收录的文件为空
+    pub project_id: Option<ProjectId>,
+    pub project_title: Option<String>,
+    pub version_id: Option<VersionId>,
+    pub version_number: Option<String>,
+}
+
+impl From<ModpackFile> for PackFileEntry {
+    fn from(file: ModpackFile) -> Self {
+        PackFileEntry {
+            path: file.path,
+            kind: file.kind,
+            sha1: file.sha1,
+            project_id: file.project_id.map(ProjectId::from),
+            project_title: file.project_name,
+            version_id: file.version_id.map(VersionId::from),
+            version_number: file.version_number,
+        }
+    }
+}
+
+/// 整合包中一个模组的变化
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PackModChange {
+    /// 项目名称，未收录的文件为文件名
+    pub name: String,
+    pub project_id: Option<ProjectId>,
+    /// 旧版本号，未收录的文件为文件名
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+}
+
+/// 同一整合包两个版本之间的文件差异
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PackDiff {
+    pub base_version_id: VersionId,
+    pub version_id: VersionId,
+    pub added: Vec<PackModChange>,
+    pub removed: Vec<PackModChange>,
+    pub updated: Vec<PackModChange>,
+    pub configs_added: Vec<String>,
+    pub configs_removed: Vec<String>,
+    pub configs_changed: Vec<String>,
+}
+
+impl PackFileEntry {
+    fn file_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    /// 对比时用于识别同一个模组：已收录的按项目，未收录的按文件名
+    fn mod_key(&self) -> String {
+        match self.project_id {
+            Some(project_id) => project_id.to_string(),
+            None => self.file_name().to_string(),
+        }
+    }
+
+    fn mod_name(&self) -> String {
+        self.project_title
+            .clone()
+            .unwrap_or_else(|| self.file_name().to_string())
+    }
+
+    fn mod_version(&self) -> String {
+        self.version_number
+            .clone()
+            .unwrap_or_else(|| self.file_name().to_string())
+    }
+}
+
+impl PackDiff {
+    pub fn new(
+        base_version_id: VersionId,
+        base: Vec<PackFileEntry>,
+        version_id: VersionId,
+        target: Vec<PackFileEntry>,
+    ) -> Self {
+        let (base_mods, base_configs): (Vec<_>, Vec<_>) = base
+            .into_iter()
+            .partition(|x| x.kind != ModpackFileKind::Config);
+        let (target_mods, target_configs): (Vec<_>, Vec<_>) = target
+            .into_iter()
+            .partition(|x| x.kind != ModpackFileKind::Config);
+
+        let base_mods = base_mods
+            .into_iter()
+            .map(|x| (x.mod_key(), x))
+            .collect::<BTreeMap<_, _>>();
+        let target_mods = target_mods
+            .into_iter()
+            .map(|x| (x.mod_key(), x))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut added = Vec::new();
+        let mut updated = Vec::new();
+        for (key, new) in &target_mods {
+            match base_mods.get(key) {
+                None => added.push(PackModChange {
+                    name: new.mod_name(),
+                    project_id: new.project_id,
+                    old_version: None,
+                    new_version: Some(new.mod_version()),
+                }),
+                Some(old) if old.sha1 != new.sha1 => {
+                    updated.push(PackModChange {
+                        name: new.mod_name(),
+                        project_id: new.project_id,
+                        old_version: Some(old.mod_version()),
+                        new_version: Some(new.mod_version()),
+                    })
+                }
+                _ => {}
+            }
+        }
+        let removed = base_mods
+            .iter()
+            .filter(|(key, _)| !target_mods.contains_key(*key))
+            .map(|(_, old)| PackModChange {
+                name: old.mod_name(),
+                project_id: old.project_id,
+                old_version: Some(old.mod_version()),
+                new_version: None,
+            })
+            .collect();
+
+        let base_configs = base_configs
+            .into_iter()
+            .map(|x| (x.path, x.sha1))
+            .collect::<BTreeMap<_, _>>();
+        let target_configs = target_configs
+            .into_iter()
+            .map(|x| (x.path, x.sha1))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut configs_added = Vec::new();
+        let mut configs_changed = Vec::new();
+        for (path, sha1) in &target_configs {
+            match base_configs.get(path) {
+                None => configs_added.push(path.clone()),
+                Some(old) if old != sha1 => configs_changed.push(path.clone()),
+                _ => {}
+            }
+        }
+        let configs_removed = base_configs
+            .into_keys()
+            .filter(|x| !target_configs.contains_key(x))
+            .collect();
+
+        PackDiff {
+            base_version_id,
+            version_id,
+            added,
+            removed,
+            updated,
+            configs_added,
+            configs_removed,
+            configs_changed,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.updated.is_empty()
+            && self.configs_added.is_empty()
+            && self.configs_removed.is_empty()
+            && self.configs_changed.is_empty()
+    }
+
+    /// 生成 Markdown 格式的更新日志段落，`base_version` 为对比的旧版本号
+    pub fn to_markdown(&self, base_version: &str) -> String {
+        let mut sections = vec![format!("## 与 {base_version} 相比的文件变化")];
+
+        if !self.added.is_empty() {
+            sections.push(format!(
+                "### 新增模组\n{}",
+                self.added
+                    .iter()
+                    .map(|x| format!(
+                        "- {} {}",
+                        x.name,
+                        x.new_version.as_deref().unwrap_or_default()
+                    ))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ));
+        }
+        if !self.updated.is_empty() {
+            sections.push(format!(
+                "### 更新模组\n{}",
+                self.updated
+                    .iter()
+                    .map(|x| format!(
+                        "- {}：{} → {}",
+                        x.name,
+                        x.old_version.as_deref().unwrap_or_default(),
+                        x.new_version.as_deref().unwrap_or_default()
+                    ))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ));
+        }
+        if !self.removed.is_empty() {
+            sections.push(format!(
+                "### 移除模组\n{}",
+                self.removed
+                    .iter()
+                    .map(|x| format!(
+                        "- {} {}",
+                        x.name,
+                        x.old_version.as_deref().unwrap_or_default()
+                    ))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ));
+        }
+
+        let configs = self
+            .configs_added
+            .iter()
+            .map(|x| format!("- 新增 `{x}`"))
+            .chain(self.configs_changed.iter().map(|x| format!("- 修改 `{x}`")))
+            .chain(self.configs_removed.iter().map(|x| format!("- 删除 `{x}`")))
+            .collect::<Vec<_>>();
+        if !configs.is_empty() {
+            sections.push(format!("### 配置文件\n{}", configs.join("\n")));
+        }
+
+        sections.join("\n\n")
+    }
+}
@@ -1,6 +1,6 @@
 use super::ids::Base62Id;
 use crate::bitflags_serde_impl;
-use crate::models::ids::UserId;
+use crate::models::ids::{OrganizationId, ProjectId, UserId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -109,6 +109,9 @@ bitflags::bitflags! {
         // 写入wiki
         const WIKI_WRITE = 1 << 40;
 
+        // OpenID Connect 登录，签发 ID 令牌
+        const OPENID = 1 << 41;
+
         const NONE = 0b0;
     }
 }
@@ -134,10 +137,36 @@ impl Scopes {
         self.intersects(Self::restricted())
     }
 
+    // 限定了项目或组织的令牌只能使用这些权限
+    pub fn resource_scoped() -> Scopes {
+        Scopes::USER_READ
+            | Scopes::PROJECT_READ
+            | Scopes::PROJECT_WRITE
+            | Scopes::PROJECT_DELETE
+            | Scopes::VERSION_CREATE
+            | Scopes::VERSION_READ
+            | Scopes::VERSION_WRITE
+            | Scopes::VERSION_DELETE
+            | Scopes::ORGANIZATION_READ
+            | Scopes::ORGANIZATION_WRITE
+    }
+
     pub fn parse_from_oauth_scopes(
         scopes: &str,
     ) -> Result<Scopes, bitflags::parser::ParseError> {
-        let scopes = scopes.replace(['+', ' '], "|").replace("%20", "|");
+        // OpenID Connect 的标准 scope 映射到对应的权限
+        let scopes = scopes
+            .replace("%20", " ")
+            .split(['+', ' '])
+            .filter(|x| !x.is_empty())
+            .map(|x| match x {
+                "openid" => "OPENID",
+                "profile" => "USER_READ",
+                "email" => "USER_READ_EMAIL",
+                x => x,
+            })
+            .collect::<Vec<_>>()
+            .join("|");
         bitflags::parser::from_str(&scopes)
     }
 
@@ -160,6 +189,13 @@ pub struct PersonalAccessToken {
     pub created: DateTime<Utc>,
     pub expires: DateTime<Utc>,
     pub last_used: Option<DateTime<Utc>>,
+
+    /// 限定可以操作的项目，None 表示不限制
+    pub projects: Option<Vec<ProjectId>>,
+    /// 限定可以操作的组织（含组织下的项目），None 表示不限制
+    pub organizations: Option<Vec<OrganizationId>>,
+    /// 允许使用令牌的 IP 范围，None 表示不限制
+    pub allowed_ips: Option<Vec<String>>,
 }
 
 impl PersonalAccessToken {
@@ -180,6 +216,13 @@ impl PersonalAccessToken {
             created: data.created,
             expires: data.expires,
             last_used: data.last_used,
+            projects: data
+                .project_ids
+                .map(|x| x.into_iter().map(|x| x.into()).collect()),
+            organizations: data
+                .organization_ids
+                .map(|x| x.into_iter().map(|x| x.into()).collect()),
+            allowed_ips: data.allowed_ips,
         }
     }
 }
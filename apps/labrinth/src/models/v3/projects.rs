@@ -120,8 +120,8 @@ pub struct Project {
     /// 汉化追踪标记
     pub translation_tracking: bool,
 
-    /// 汉化资源 slug（当 translation_tracking 为 true 时有值）
-    pub translation_tracker: Option<String>,
+    /// 各语言的本地化资源 slug，键为语言代码（例如 zh_cn）
+    pub translation_tracker: HashMap<String, String>,
 
     /// 汉化来源：哪个项目将当前项目作为汉化目标
     pub translation_source: Option<String>,
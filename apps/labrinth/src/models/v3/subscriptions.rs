@@ -0,0 +1,32 @@
+use crate::database::models::subscription_item::{
+    Subscription, SubscriptionTarget,
+};
+use crate::models::ids::base62_impl::to_base62;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SubscriptionResponse {
+    pub target_type: SubscriptionTarget,
+    pub target_id: String,
+    /// 是否为参与讨论时自动关注
+    pub automatic: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SubscriptionSettings {
+    /// 不接收通知的对象类型
+    pub muted: Vec<SubscriptionTarget>,
+}
+
+impl From<Subscription> for SubscriptionResponse {
+    fn from(subscription: Subscription) -> Self {
+        SubscriptionResponse {
+            target_type: subscription.target_type,
+            target_id: to_base62(subscription.target_id as u64),
+            automatic: subscription.automatic,
+            created_at: subscription.created_at,
+        }
+    }
+}
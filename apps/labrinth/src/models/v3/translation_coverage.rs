@@ -0,0 +1,86 @@
+use crate::database::models::translation_coverage_item::{
+    TranslationCoverage, TranslationCoverageSummary,
+};
+use crate::models::ids::VersionId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 版本汉化覆盖率摘要
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TranslationCoverageSummaryResponse {
+    pub version_id: VersionId,
+    pub version_number: String,
+    /// 目标语言代码，例如 zh_cn
+    pub language_code: String,
+    /// 对比时使用的汉化版本，没有绑定汉化时为空
+    pub translation_version_id: Option<VersionId>,
+    /// 计算新增/移除键时对比的上一个版本
+    pub previous_version_id: Option<VersionId>,
+    /// complete / failed / unavailable
+    pub status: String,
+    pub total_keys: i32,
+    pub translated_keys: i32,
+    pub new_keys: i32,
+    pub removed_keys: i32,
+    pub untranslated_keys: i32,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 版本汉化覆盖率报告，键的格式为 `命名空间:键`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TranslationCoverageResponse {
+    pub version_id: VersionId,
+    pub language_code: String,
+    pub translation_version_id: Option<VersionId>,
+    pub previous_version_id: Option<VersionId>,
+    pub status: String,
+    pub error: Option<String>,
+    pub total_keys: i32,
+    pub translated_keys: i32,
+    pub new_keys: Vec<String>,
+    pub removed_keys: Vec<String>,
+    pub untranslated_keys: Vec<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<TranslationCoverageSummary> for TranslationCoverageSummaryResponse {
+    fn from(summary: TranslationCoverageSummary) -> Self {
+        TranslationCoverageSummaryResponse {
+            version_id: summary.version_id.into(),
+            version_number: summary.version_number,
+            language_code: summary.language_code,
+            translation_version_id: summary
+                .translation_version_id
+                .map(|x| x.into()),
+            previous_version_id: summary.previous_version_id.map(|x| x.into()),
+            status: summary.status,
+            total_keys: summary.total_keys,
+            translated_keys: summary.translated_keys,
+            new_keys: summary.new_keys,
+            removed_keys: summary.removed_keys,
+            untranslated_keys: summary.untranslated_keys,
+            updated_at: summary.updated_at,
+        }
+    }
+}
+
+impl From<TranslationCoverage> for TranslationCoverageResponse {
+    fn from(coverage: TranslationCoverage) -> Self {
+        TranslationCoverageResponse {
+            version_id: coverage.version_id.into(),
+            language_code: coverage.language_code,
+            translation_version_id: coverage
+                .translation_version_id
+                .map(|x| x.into()),
+            previous_version_id: coverage.previous_version_id.map(|x| x.into()),
+            status: coverage.status,
+            error: coverage.error,
+            total_keys: coverage.total_keys,
+            translated_keys: coverage.translated_keys,
+            new_keys: coverage.new_keys,
+            removed_keys: coverage.removed_keys,
+            untranslated_keys: coverage.untranslated_keys,
+            updated_at: coverage.updated_at,
+        }
+    }
+}
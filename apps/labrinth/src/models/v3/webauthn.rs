@@ -0,0 +1,32 @@
+use super::ids::Base62Id;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Debug, Hash)]
+#[serde(from = "Base62Id")]
+#[serde(into = "Base62Id")]
+pub struct WebauthnCredentialId(pub u64);
+
+/// 通行密钥，不包含公钥等凭据数据
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WebauthnCredential {
+    pub id: WebauthnCredentialId,
+    pub name: String,
+    pub created: DateTime<Utc>,
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+impl From<crate::database::models::webauthn_credential_item::WebauthnCredential>
+    for WebauthnCredential
+{
+    fn from(
+        data: crate::database::models::webauthn_credential_item::WebauthnCredential,
+    ) -> Self {
+        WebauthnCredential {
+            id: data.id.into(),
+            name: data.name,
+            created: data.created,
+            last_used: data.last_used,
+        }
+    }
+}
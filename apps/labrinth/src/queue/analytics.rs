@@ -1,3 +1,4 @@
+use crate::analytics::AnalyticsStorage;
 use crate::database::models::DatabaseError;
 use crate::database::redis::RedisPool;
 use crate::models::analytics::{Download, PageView, Playtime};
@@ -12,7 +13,8 @@ const VIEWS_NAMESPACE: &str = "views";
 
 pub struct AnalyticsQueue {
     views_queue: DashMap<(u64, u64), Vec<PageView>>,
-    downloads_queue: DashMap<(u64, u64), Download>,
+    // 值为最近一次下载以及本批次内的请求次数
+    downloads_queue: DashMap<(u64, u64), (Download, u32)>,
     playtime_queue: DashSet<Playtime>,
 }
 
@@ -42,8 +44,12 @@ impl AnalyticsQueue {
     }
     pub fn add_download(&self, download: Download) {
         let ip_stripped = crate::util::ip::strip_ip(download.ip);
-        self.downloads_queue
-            .insert((ip_stripped, download.project_id), download);
+        let mut entry = self
+            .downloads_queue
+            .entry((ip_stripped, download.project_id))
+            .or_insert_with(|| (download.clone(), 0));
+        entry.0 = download;
+        entry.1 += 1;
     }
 
     pub fn add_playtime(&self, playtime: Playtime) {
@@ -52,7 +58,7 @@ impl AnalyticsQueue {
 
     pub async fn index(
         &self,
-        client: clickhouse::Client,
+        storage: &(dyn AnalyticsStorage + Send + Sync),
         redis: &RedisPool,
         pool: &PgPool,
     ) -> Result<(), ApiError> {
@@ -66,13 +72,9 @@ impl AnalyticsQueue {
         self.playtime_queue.clear();
 
         if !playtime_queue.is_empty() {
-            let mut playtimes = client.insert::<Playtime>("playtime").await?;
-
-            for playtime in playtime_queue {
-                playtimes.write(&playtime).await?;
-            }
-
-            playtimes.end().await?;
+            storage
+                .insert_playtimes(playtime_queue.into_iter().collect())
+                .await?;
         }
 
         if !views_queue.is_empty() {
@@ -132,19 +134,18 @@ impl AnalyticsQueue {
                 .await
                 .map_err(DatabaseError::CacheError)?;
 
-            let mut views = client.insert::<PageView>("views").await?;
-
+            let mut views = Vec::new();
             for (all_views, monetized) in raw_views {
                 for (idx, mut view) in all_views.into_iter().enumerate() {
                     if idx != 0 || !monetized {
                         view.monetized = false;
                     }
 
-                    views.write(&view).await?;
+                    views.push(view);
                 }
             }
 
-            views.end().await?;
+            storage.insert_views(views).await?;
         }
 
         if !downloads_queue.is_empty() {
@@ -158,6 +159,7 @@ impl AnalyticsQueue {
                 raw_downloads.insert(index, download);
             }
 
+            let redis_pool = redis;
             let mut redis =
                 redis.pool.get().await.map_err(DatabaseError::RedisPool)?;
 
@@ -199,21 +201,28 @@ impl AnalyticsQueue {
                 .await
                 .map_err(DatabaseError::CacheError)?;
 
+            // 刷量检测：可疑下载单独记录，不计入下载量
+            let (raw_downloads, suspicious) =
+                crate::queue::download_fraud::score_downloads(
+                    raw_downloads.into_iter().map(|x| x.1).collect(),
+                    redis_pool,
+                )
+                .await?;
+
+            storage.insert_suspicious_downloads(suspicious).await?;
+
             let mut transaction = pool.begin().await?;
-            let mut downloads = client.insert::<Download>("downloads").await?;
 
             let mut version_downloads: HashMap<i64, i32> = HashMap::new();
             let mut project_downloads: HashMap<i64, i32> = HashMap::new();
 
-            for (_, download) in raw_downloads {
+            for download in &raw_downloads {
                 *version_downloads
                     .entry(download.version_id as i64)
                     .or_default() += 1;
                 *project_downloads
                     .entry(download.project_id as i64)
                     .or_default() += 1;
-
-                downloads.write(&download).await?;
             }
 
             sqlx::query(
@@ -243,7 +252,7 @@ impl AnalyticsQueue {
             .await?;
 
             transaction.commit().await?;
-            downloads.end().await?;
+            storage.insert_downloads(raw_downloads).await?;
         }
 
         Ok(())
@@ -0,0 +1,204 @@
+//! 下载刷量检测
+//!
+//! 下载在计入 `versions.downloads` / `mods.downloads` 之前先经过打分：
+//! 按 IP、用户、ASN 统计一小时内的下载速度，检查同一 IP 重复下载同一版本，
+//! 以及无头浏览器、脚本等自动化 User-Agent。
+//! 分数达到阈值的下载写入 ClickHouse 的 `download_fraud` 表，不计入下载量。
+
+use crate::database::models::DatabaseError;
+use crate::database::redis::RedisPool;
+use crate::models::analytics::{Download, SuspiciousDownload};
+use chrono::Utc;
+
+const FRAUD_NAMESPACE: &str = "download_fraud";
+
+// 统计窗口（秒），计数按固定窗口分桶
+const WINDOW_SECONDS: i64 = 60 * 60;
+
+/// 达到该分数的下载视为可疑
+pub const SUSPICIOUS_SCORE: u32 = 50;
+
+// 单个 IP（IPv6 按 /64）每小时的下载次数上限
+const IP_LIMIT: u32 = 60;
+// 单个用户每小时的下载次数上限
+const USER_LIMIT: u32 = 120;
+// 单个 ASN 每小时对同一项目的下载次数上限
+const ASN_PROJECT_LIMIT: u32 = 300;
+// 同一 IP 每小时重复下载同一版本的次数上限
+const REPEAT_LIMIT: u32 = 5;
+
+const VELOCITY_SCORE: u32 = 40;
+const ASN_SCORE: u32 = 30;
+const REPEAT_SCORE: u32 = 40;
+
+// 浏览器自动化工具，单独出现即视为可疑
+const HEADLESS_AGENTS: &[&str] = &[
+    "headlesschrome",
+    "phantomjs",
+    "puppeteer",
+    "playwright",
+    "selenium",
+    "scrapy",
+];
+const HEADLESS_SCORE: u32 = 50;
+
+// 脚本 HTTP 客户端，服主也可能直接用它们下载，需要配合其他信号
+const SCRIPT_AGENTS: &[&str] = &[
+    "python-requests",
+    "python-urllib",
+    "aiohttp",
+    "go-http-client",
+    "curl/",
+    "wget/",
+];
+const SCRIPT_SCORE: u32 = 30;
+
+/// 单次下载的打分结果
+struct FraudSignals {
+    score: u32,
+    reasons: Vec<String>,
+}
+
+impl FraudSignals {
+    fn add(&mut self, score: u32, reason: &str) {
+        self.score += score;
+        self.reasons.push(reason.to_string());
+    }
+}
+
+/// 从下载请求头中读取 ASN，请求头名称由 `DOWNLOAD_ASN_HEADER` 配置，未配置时为 0
+fn download_asn(download: &Download, header: Option<&str>) -> u32 {
+    let Some(header) = header else {
+        return 0;
+    };
+
+    download
+        .headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(header))
+        .and_then(|(_, value)| {
+            value.trim().trim_start_matches("AS").parse().ok()
+        })
+        .unwrap_or(0)
+}
+
+/// 根据 User-Agent 打分
+fn score_user_agent(user_agent: &str, signals: &mut FraudSignals) {
+    let user_agent = user_agent.to_lowercase();
+
+    if user_agent.trim().is_empty() {
+        signals.add(SCRIPT_SCORE, "empty_user_agent");
+    } else if HEADLESS_AGENTS.iter().any(|x| user_agent.contains(x)) {
+        signals.add(HEADLESS_SCORE, "headless_user_agent");
+    } else if SCRIPT_AGENTS.iter().any(|x| user_agent.contains(x)) {
+        signals.add(SCRIPT_SCORE, "script_user_agent");
+    }
+}
+
+/// 为一批下载打分，`attempts` 为该下载在本批次内被请求的次数。
+/// 返回可以计入下载量的下载以及可疑下载
+pub async fn score_downloads(
+    downloads: Vec<(Download, u32)>,
+    redis: &RedisPool,
+) -> Result<(Vec<Download>, Vec<SuspiciousDownload>), DatabaseError> {
+    if downloads.is_empty() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+
+    let asn_header = dotenvy::var("DOWNLOAD_ASN_HEADER")
+        .ok()
+        .filter(|x| !x.is_empty());
+    let bucket = Utc::now().timestamp() / WINDOW_SECONDS;
+
+    // 每次下载依次累加 IP、重复下载、用户、ASN 计数器（后两者可能没有）
+    let mut pipe = redis::pipe();
+    let mut counters = Vec::with_capacity(downloads.len());
+    for (download, attempts) in &downloads {
+        let ip = crate::util::ip::strip_ip(download.ip);
+        let asn = download_asn(download, asn_header.as_deref());
+
+        let mut keys = vec![
+            format!("{FRAUD_NAMESPACE}:ip:{bucket}:{ip}"),
+            format!(
+                "{FRAUD_NAMESPACE}:repeat:{bucket}:{ip}-{}",
+                download.version_id
+            ),
+        ];
+        if download.user_id != 0 {
+            keys.push(format!(
+                "{FRAUD_NAMESPACE}:user:{bucket}:{}",
+                download.user_id
+            ));
+        }
+        if asn != 0 {
+            keys.push(format!(
+                "{FRAUD_NAMESPACE}:asn:{bucket}:{asn}-{}",
+                download.project_id
+            ));
+        }
+
+        for key in &keys {
+            pipe.cmd("INCRBY").arg(key).arg(*attempts);
+            pipe.cmd("EXPIRE").arg(key).arg(WINDOW_SECONDS * 2).ignore();
+        }
+        counters.push((asn, keys.len()));
+    }
+
+    let mut conn = redis.pool.get().await.map_err(DatabaseError::RedisPool)?;
+    let counts = pipe
+        .query_async::<Vec<u32>>(&mut *conn)
+        .await
+        .map_err(DatabaseError::CacheError)?;
+
+    let mut legitimate = Vec::new();
+    let mut suspicious = Vec::new();
+    let mut offset = 0;
+    for ((download, attempts), (asn, len)) in
+        downloads.into_iter().zip(counters)
+    {
+        let values = &counts[offset..offset + len];
+        offset += len;
+
+        let mut signals = FraudSignals {
+            score: 0,
+            reasons: Vec::new(),
+        };
+
+        if values[0] > IP_LIMIT {
+            signals.add(VELOCITY_SCORE, "ip_velocity");
+        }
+        if values[1] > REPEAT_LIMIT {
+            signals.add(REPEAT_SCORE, "repeat_download");
+        }
+        let mut next = 2;
+        if download.user_id != 0 {
+            if values[next] > USER_LIMIT {
+                signals.add(VELOCITY_SCORE, "user_velocity");
+            }
+            next += 1;
+        }
+        if asn != 0 && values[next] > ASN_PROJECT_LIMIT {
+            signals.add(ASN_SCORE, "asn_velocity");
+        }
+        score_user_agent(&download.user_agent, &mut signals);
+
+        if signals.score >= SUSPICIOUS_SCORE {
+            suspicious.push(SuspiciousDownload {
+                recorded: download.recorded,
+                user_id: download.user_id,
+                project_id: download.project_id,
+                version_id: download.version_id,
+                ip: download.ip,
+                asn,
+                user_agent: download.user_agent,
+                attempts,
+                score: signals.score,
+                reasons: signals.reasons,
+            });
+        } else {
+            legitimate.push(download);
+        }
+    }
+
+    Ok((legitimate, suspicious))
+}
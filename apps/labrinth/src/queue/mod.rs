@@ -1,4 +1,5 @@
 pub mod analytics;
+pub mod download_fraud;
 pub mod moderation;
 pub mod payouts;
 pub mod session;
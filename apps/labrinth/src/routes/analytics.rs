@@ -41,6 +41,60 @@ pub const FILTERED_HEADERS: &[&str] = &[
 #[derive(Deserialize)]
 pub struct UrlInput {
     url: String,
+    /// 页面的 document.referrer
+    referrer: Option<String>,
+}
+
+// 启动器 User-Agent 关键字及其名称，按顺序匹配
+const LAUNCHERS: &[(&str, &str)] = &[
+    ("bbsmc", "bbsmc"),
+    ("modrinth/theseus", "modrinth"),
+    ("hmcl", "hmcl"),
+    ("pcl", "pcl"),
+    ("bakaxl", "bakaxl"),
+    ("prismlauncher", "prism"),
+    ("multimc", "multimc"),
+    ("atlauncher", "atlauncher"),
+    ("foldcraftlauncher", "fcl"),
+    ("pojavlauncher", "pojav"),
+    ("mozilla", "browser"),
+];
+
+/// 根据 User-Agent 识别启动器，无法识别时为 `other`，为空时为空字符串
+pub fn classify_launcher(user_agent: &str) -> String {
+    let user_agent = user_agent.to_lowercase();
+    if user_agent.trim().is_empty() {
+        return String::new();
+    }
+
+    LAUNCHERS
+        .iter()
+        .find(|(key, _)| user_agent.contains(key))
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| "other".to_string())
+}
+
+/// 来源页面的域名
+pub fn referrer_host(referrer: Option<&str>) -> String {
+    referrer
+        .and_then(|x| Url::parse(x).ok())
+        .and_then(|x| x.host_str().map(|x| x.to_lowercase()))
+        .unwrap_or_default()
+}
+
+/// 版本支持的加载器和游戏版本，用于下载统计
+pub fn version_dimensions(
+    version: &crate::database::models::version_item::QueryVersion,
+) -> (Vec<String>, Vec<String>) {
+    let game_versions = version
+        .version_fields
+        .iter()
+        .find(|x| x.field_name == "game_versions")
+        .map(|x| x.value.serialize_internal())
+        .and_then(|x| serde_json::from_value::<Vec<String>>(x).ok())
+        .unwrap_or_default();
+
+    (version.loaders.clone(), game_versions)
 }
 
 // 这个路由应该在 cloudflare WAF 后面，以防止非浏览器调用它
@@ -116,6 +170,7 @@ pub async fn page_view_ingest(
             .filter(|x| !FILTERED_HEADERS.contains(&&*x.0))
             .collect(),
         monetized: true,
+        referrer: referrer_host(url_input.referrer.as_deref()),
     };
 
     if let Some(segments) = url.path_segments() {
@@ -109,6 +109,20 @@ pub async fn count_download(
     let ip = crate::util::ip::convert_to_ip_v6(&download_body.ip)
         .unwrap_or_else(|_| Ipv4Addr::new(127, 0, 0, 1).to_ipv6_mapped());
 
+    let (loaders, game_versions) = crate::database::models::Version::get(
+        crate::database::models::ids::VersionId(version_id),
+        &**pool,
+        &redis,
+    )
+    .await?
+    .map(|x| crate::routes::analytics::version_dimensions(&x))
+    .unwrap_or_default();
+    let user_agent = download_body
+        .headers
+        .get("user-agent")
+        .cloned()
+        .unwrap_or_default();
+
     analytics_queue.add_download(Download {
         recorded: get_current_tenths_of_ms(),
         domain: url.host_str().unwrap_or_default().to_string(),
@@ -126,11 +140,8 @@ pub async fn count_download(
         version_id: version_id as u64,
         ip,
         country: String::new(), // MaxMind 功能已移除
-        user_agent: download_body
-            .headers
-            .get("user-agent")
-            .cloned()
-            .unwrap_or_default(),
+        launcher: crate::routes::analytics::classify_launcher(&user_agent),
+        user_agent,
         headers: download_body
             .headers
             .clone()
@@ -140,6 +151,15 @@ pub async fn count_download(
                     .contains(&&*x.0.to_lowercase())
             })
             .collect(),
+        referrer: crate::routes::analytics::referrer_host(
+            download_body
+                .headers
+                .iter()
+                .find(|x| x.0.eq_ignore_ascii_case("referer"))
+                .map(|x| x.1.as_str()),
+        ),
+        loaders,
+        game_versions,
     });
 
     Ok(HttpResponse::NoContent().body(""))
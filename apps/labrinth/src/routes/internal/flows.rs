@@ -1,7 +1,14 @@
 use crate::auth::email::send_email;
 use crate::auth::validate::get_user_record_from_bearer_token;
-use crate::auth::{AuthProvider, AuthenticationError, get_user_from_headers};
+use crate::auth::webauthn::{passkey_2fa_enforced, second_factor_methods};
+use crate::auth::{
+    AuthProvider, AuthenticationError, get_user_from_headers, login_risk,
+};
+use crate::captcha::{Captcha, CaptchaAction};
 use crate::database::models::flow_item::Flow;
+use crate::database::models::oauth_token_item::OAuthAccessToken;
+use crate::database::models::pat_item::PersonalAccessToken;
+use crate::database::models::session_item::Session as DBSession;
 use crate::database::redis::RedisPool;
 use crate::file_hosting::FileHost;
 use crate::models::ids::base62_impl::{parse_base62, to_base62};
@@ -11,12 +18,13 @@ use crate::models::users::{Badges, Role};
 use crate::queue::session::AuthQueue;
 use crate::queue::socket::ActiveSockets;
 use crate::routes::ApiError;
-use crate::routes::internal::session::issue_session;
-use crate::util::captcha::check_hcaptcha;
+use crate::routes::internal::session::issue_login_session;
+use crate::sms::SmsSender;
 use crate::util::env::parse_strings_from_var;
 use crate::util::ext::get_image_ext;
 use crate::util::img::upload_image_optimized;
 use crate::util::phone::send_phone_number_code;
+use crate::util::ratelimit::client_ip;
 use crate::util::validate::{RE_URL_SAFE, validation_errors_to_string};
 use actix_web::web::{Data, Payload, Query, ServiceConfig, scope};
 use actix_web::{HttpRequest, HttpResponse, delete, get, patch, post, web};
@@ -46,6 +54,9 @@ pub fn config(cfg: &mut ServiceConfig) {
             .service(create_account_with_password)
             .service(login_password)
             .service(login_2fa)
+            .service(login_step_up_send)
+            .service(login_step_up)
+            .service(login_revoke)
             .service(begin_2fa_flow)
             .service(finish_2fa_flow)
             .service(remove_2fa)
@@ -56,7 +67,9 @@ pub fn config(cfg: &mut ServiceConfig) {
             .service(verify_email)
             .service(subscribe_newsletter)
             .service(phone_number_code)
-            .service(phone_number_bind),
+            .service(phone_number_bind)
+            .service(captcha_config)
+            .configure(super::webauthn::config),
     );
 }
 
@@ -1711,17 +1724,26 @@ pub async fn auth_callback(
                         .await?
                         .ok_or_else(|| AuthenticationError::InvalidCredentials)?;
 
-                    if user.totp_secret.is_some() {
-                        let flow = Flow::Login2FA { user_id: user.id }
+                    let methods = second_factor_methods(&user, &client).await?;
+                    let challenge = if !methods.is_empty() {
+                        Some((Flow::Login2FA { user_id: user.id }, methods))
+                    } else {
+                        login_risk::step_up_method(&req, &user, &client)
+                            .await?
+                            .map(|method| (Flow::LoginStepUp { user_id: user.id }, vec![method]))
+                    };
+                    if let Some((flow, methods)) = challenge {
+                        let flow = flow
                             .insert(Duration::minutes(30), &redis)
                             .await?;
 
                         if let Some(url) = url {
                             let redirect_url = format!(
-                                "{}{}error=2fa_required&flow={}",
+                                "{}{}error=2fa_required&flow={}&methods={}",
                                 url,
                                 if url.contains('?') { "&" } else { "?" },
-                                flow
+                                flow,
+                                methods.join(",")
                             );
 
                             return Ok(HttpResponse::TemporaryRedirect()
@@ -1744,6 +1766,7 @@ pub async fn auth_callback(
                                     serde_json::json!({
                                         "error": "2fa_required",
                                         "flow": flow,
+                                        "methods": methods,
                                     }).to_string()
                                 )
                                 .await.map_err(|_| AuthenticationError::SocketError)?;
@@ -1762,7 +1785,7 @@ pub async fn auth_callback(
                     oauth_user.create_account(provider, &mut transaction, &client, &file_host, &redis).await?
                 };
 
-                let session = issue_session(req, user_id, &mut transaction, &redis).await?;
+                let session = issue_login_session(req, user_id, &mut transaction, &redis).await?;
                 transaction.commit().await?;
 
                 if let Some(url) = url {
@@ -1952,14 +1975,21 @@ pub async fn create_account_with_password(
     pool: Data<PgPool>,
     redis: Data<RedisPool>,
     new_account: web::Json<NewAccount>,
+    captcha: Data<Arc<Captcha>>,
 ) -> Result<HttpResponse, ApiError> {
     new_account.0.validate().map_err(|err| {
         ApiError::InvalidInput(validation_errors_to_string(err, None))
     })?;
 
-    if !check_hcaptcha(&new_account.challenge).await? {
-        return Err(ApiError::Turnstile);
-    }
+    captcha
+        .check(
+            CaptchaAction::Register,
+            &new_account.challenge,
+            &req,
+            None,
+            &redis,
+        )
+        .await?;
 
     if crate::database::models::User::get(
         &new_account.username,
@@ -2058,7 +2088,8 @@ pub async fn create_account_with_password(
     .insert(&mut transaction)
     .await?;
 
-    let session = issue_session(req, user_id, &mut transaction, &redis).await?;
+    let session =
+        issue_login_session(req, user_id, &mut transaction, &redis).await?;
     let res = crate::models::sessions::Session::from(session, true, None);
 
     let flow = Flow::ConfirmEmail {
@@ -2105,10 +2136,11 @@ pub async fn login_password(
     pool: Data<PgPool>,
     redis: Data<RedisPool>,
     login: web::Json<Login>,
+    captcha: Data<Arc<Captcha>>,
 ) -> Result<HttpResponse, ApiError> {
-    if !check_hcaptcha(&login.challenge).await? {
-        return Err(ApiError::Turnstile);
-    }
+    captcha
+        .check(CaptchaAction::Login, &login.challenge, &req, None, &redis)
+        .await?;
 
     let user = if let Some(user) =
         crate::database::models::User::get(&login.username, &**pool, &redis)
@@ -2131,14 +2163,15 @@ pub async fn login_password(
         .verify_password(
             login.password.as_bytes(),
             &PasswordHash::new(
-                &user
-                    .password
+                user.password
+                    .as_deref()
                     .ok_or_else(|| AuthenticationError::InvalidCredentials)?,
             )?,
         )
         .map_err(|_| AuthenticationError::InvalidCredentials)?;
 
-    if user.totp_secret.is_some() {
+    let methods = second_factor_methods(&user, &pool).await?;
+    if !methods.is_empty() {
         let flow = Flow::Login2FA { user_id: user.id }
             .insert(Duration::minutes(30), &redis)
             .await?;
@@ -2147,11 +2180,25 @@ pub async fn login_password(
             "error": "2fa_required",
             "description": "需要 2FA 才能完成此操作。",
             "flow": flow,
+            "methods": methods,
+        })))
+    } else if let Some(method) =
+        login_risk::step_up_method(&req, &user, &pool).await?
+    {
+        let flow = Flow::LoginStepUp { user_id: user.id }
+            .insert(Duration::minutes(30), &redis)
+            .await?;
+
+        Ok(HttpResponse::Ok().json(serde_json::json!({
+            "error": "2fa_required",
+            "description": "检测到异常登录，需要验证身份才能继续。",
+            "flow": flow,
+            "methods": [method],
         })))
     } else {
         let mut transaction = pool.begin().await?;
         let session =
-            issue_session(req, user.id, &mut transaction, &redis).await?;
+            issue_login_session(req, user.id, &mut transaction, &redis).await?;
         let res = crate::models::sessions::Session::from(session, true, None);
         transaction.commit().await?;
 
@@ -2159,6 +2206,14 @@ pub async fn login_password(
     }
 }
 
+/// 各入口使用的人机验证服务，前端据此加载对应的组件
+#[get("captcha")]
+pub async fn captcha_config(
+    captcha: Data<Arc<Captcha>>,
+) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(captcha.config()))
+}
+
 #[derive(Deserialize, Validate)]
 pub struct PhoneNumberCode {
     pub phone_number: String,
@@ -2172,10 +2227,18 @@ pub async fn phone_number_code(
     redis: Data<RedisPool>,
     phone_number_code: web::Json<PhoneNumberCode>,
     session_queue: Data<AuthQueue>,
+    sms_sender: Data<Arc<dyn SmsSender + Send + Sync>>,
+    captcha: Data<Arc<Captcha>>,
 ) -> Result<HttpResponse, ApiError> {
-    if !check_hcaptcha(&phone_number_code.challenge).await? {
-        return Err(ApiError::Turnstile);
-    }
+    captcha
+        .check(
+            CaptchaAction::PhoneCode,
+            &phone_number_code.challenge,
+            &req,
+            None,
+            &redis,
+        )
+        .await?;
     let user = get_user_from_headers(
         &req,
         &**pool,
@@ -2235,8 +2298,18 @@ pub async fn phone_number_code(
         Some(300),
     )
     .await?;
-    send_phone_number_code(&phone_number_code.phone_number, &code.to_string())
-        .await?;
+
+    let ip = client_ip(&req);
+    send_phone_number_code(
+        &***sms_sender,
+        &phone_number_code.phone_number,
+        &code.to_string(),
+        Some(user.id.into()),
+        Some(&ip),
+        &pool,
+        &redis,
+    )
+    .await?;
 
     //  记录90秒内发送过短信
     conn.set(&namespace, &user.id.to_string(), &token, Some(90))
@@ -2431,6 +2504,13 @@ pub async fn login_2fa(
                 .await?
                 .ok_or_else(|| AuthenticationError::InvalidCredentials)?;
 
+        // 所在组织要求使用通行密钥时不接受 TOTP 和备用码
+        if passkey_2fa_enforced(user.id, &pool).await? {
+            return Err(ApiError::CustomAuthentication(
+                "您所在的组织要求使用通行密钥完成两步验证".to_string(),
+            ));
+        }
+
         let mut transaction = pool.begin().await?;
         if !validate_2fa_code(
             login.code.clone(),
@@ -2451,7 +2531,7 @@ pub async fn login_2fa(
         Flow::remove(&login.flow, &redis).await?;
 
         let session =
-            issue_session(req, user_id, &mut transaction, &redis).await?;
+            issue_login_session(req, user_id, &mut transaction, &redis).await?;
         let res = crate::models::sessions::Session::from(session, true, None);
         transaction.commit().await?;
 
@@ -2463,6 +2543,271 @@ pub async fn login_2fa(
     }
 }
 
+const LOGIN_STEP_UP_CODE_NAMESPACE: &str = "login_step_up_code";
+const LOGIN_STEP_UP_ATTEMPTS_NAMESPACE: &str = "login_step_up_attempts";
+const LOGIN_STEP_UP_SENDS_NAMESPACE: &str = "login_step_up_sends";
+/// 同一个验证流程允许的最大尝试次数
+const LOGIN_STEP_UP_MAX_ATTEMPTS: i64 = 5;
+/// 同一个验证流程最多发送验证码的次数
+const LOGIN_STEP_UP_MAX_SENDS: i64 = 3;
+
+#[derive(Deserialize)]
+pub struct LoginStepUpSend {
+    pub flow: String,
+}
+
+/// 发送异常登录验证码，已绑定手机号时发送短信，否则发送到邮箱
+#[post("login/step-up/send")]
+pub async fn login_step_up_send(
+    req: HttpRequest,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+    body: web::Json<LoginStepUpSend>,
+    sms_sender: Data<Arc<dyn SmsSender + Send + Sync>>,
+) -> Result<HttpResponse, ApiError> {
+    let Some(Flow::LoginStepUp { user_id }) =
+        Flow::get(&body.flow, &redis).await?
+    else {
+        return Err(ApiError::Authentication(
+            AuthenticationError::InvalidCredentials,
+        ));
+    };
+
+    let user = crate::database::models::User::get_id(user_id, &**pool, &redis)
+        .await?
+        .ok_or_else(|| AuthenticationError::InvalidCredentials)?;
+
+    let mut conn = redis.connect().await?;
+    let sends = conn
+        .increment(LOGIN_STEP_UP_SENDS_NAMESPACE, &body.flow, 1800)
+        .await?;
+    if sends > LOGIN_STEP_UP_MAX_SENDS {
+        return Err(ApiError::InvalidInput(
+            "验证码发送次数过多，请重新登录".to_string(),
+        ));
+    }
+
+    let code = rand::thread_rng().gen_range(100000..999999).to_string();
+
+    let response = if let Some(phone_number) = &user.phone_number {
+        send_phone_number_code(
+            &***sms_sender,
+            phone_number,
+            &code,
+            Some(user_id),
+            Some(&client_ip(&req)),
+            &pool,
+            &redis,
+        )
+        .await?;
+
+        // 只返回脱敏后的手机号
+        let masked = if phone_number.chars().count() > 7 {
+            let chars = phone_number.chars().collect::<Vec<_>>();
+            format!(
+                "{}****{}",
+                chars[..3].iter().collect::<String>(),
+                chars[chars.len() - 4..].iter().collect::<String>()
+            )
+        } else {
+            "****".to_string()
+        };
+
+        serde_json::json!({
+            "method": login_risk::STEP_UP_PHONE,
+            "phone_number": masked,
+        })
+    } else if let Some(email) = &user.email {
+        send_email(
+            email.clone(),
+            "异常登录验证码",
+            &format!(
+                "我们检测到您的账号正在进行异常登录，验证码为 {code}，5 分钟内有效。"
+            ),
+            "如果这不是您本人的操作，请立即修改密码并启用两步验证。",
+            None,
+        )?;
+
+        // 只返回脱敏后的邮箱
+        let masked = match email.split_once('@') {
+            Some((name, domain)) => format!(
+                "{}****@{domain}",
+                name.chars().next().map(String::from).unwrap_or_default()
+            ),
+            None => "****".to_string(),
+        };
+
+        serde_json::json!({
+            "method": login_risk::STEP_UP_EMAIL,
+            "email": masked,
+        })
+    } else {
+        return Err(ApiError::Authentication(
+            AuthenticationError::LoginBlocked,
+        ));
+    };
+
+    conn.set(LOGIN_STEP_UP_CODE_NAMESPACE, &body.flow, &code, Some(300))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[derive(Deserialize)]
+pub struct LoginStepUp {
+    pub flow: String,
+    pub code: String,
+}
+
+/// 校验异常登录的验证码并完成登录
+#[post("login/step-up")]
+pub async fn login_step_up(
+    req: HttpRequest,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+    body: web::Json<LoginStepUp>,
+) -> Result<HttpResponse, ApiError> {
+    let Some(Flow::LoginStepUp { user_id }) =
+        Flow::get(&body.flow, &redis).await?
+    else {
+        return Err(ApiError::Authentication(
+            AuthenticationError::InvalidCredentials,
+        ));
+    };
+
+    let mut conn = redis.connect().await?;
+    let attempts = conn
+        .increment(LOGIN_STEP_UP_ATTEMPTS_NAMESPACE, &body.flow, 1800)
+        .await?;
+    if attempts > LOGIN_STEP_UP_MAX_ATTEMPTS {
+        Flow::remove(&body.flow, &redis).await?;
+        conn.delete(LOGIN_STEP_UP_CODE_NAMESPACE, &body.flow)
+            .await?;
+        return Err(ApiError::InvalidInput(
+            "验证码错误次数过多，请重新登录".to_string(),
+        ));
+    }
+
+    let code = conn.get(LOGIN_STEP_UP_CODE_NAMESPACE, &body.flow).await?;
+    if code.as_deref() != Some(body.code.as_str()) {
+        return Err(ApiError::InvalidInput("验证码错误或已过期".to_string()));
+    }
+
+    Flow::remove(&body.flow, &redis).await?;
+    conn.delete(LOGIN_STEP_UP_CODE_NAMESPACE, &body.flow)
+        .await?;
+    conn.delete(LOGIN_STEP_UP_ATTEMPTS_NAMESPACE, &body.flow)
+        .await?;
+    conn.delete(LOGIN_STEP_UP_SENDS_NAMESPACE, &body.flow)
+        .await?;
+
+    let mut transaction = pool.begin().await?;
+    let session =
+        issue_login_session(req, user_id, &mut transaction, &redis).await?;
+    let res = crate::models::sessions::Session::from(session, true, None);
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(res))
+}
+
+#[derive(Deserialize)]
+pub struct LoginRevoke {
+    pub flow: String,
+}
+
+/// 登录提醒邮件中的“这不是我”：退出所有会话、撤销所有访问令牌并要求重置密码
+#[post("login/revoke")]
+pub async fn login_revoke(
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+    body: web::Json<LoginRevoke>,
+) -> Result<HttpResponse, ApiError> {
+    let Some(Flow::LoginAlert { user_id }) =
+        Flow::get(&body.flow, &redis).await?
+    else {
+        return Err(ApiError::InvalidInput("链接无效或已过期".to_string()));
+    };
+    Flow::remove(&body.flow, &redis).await?;
+
+    let user = crate::database::models::User::get_id(user_id, &**pool, &redis)
+        .await?
+        .ok_or_else(|| AuthenticationError::InvalidCredentials)?;
+
+    let session_ids =
+        DBSession::get_user_sessions(user.id, &**pool, &redis).await?;
+    let sessions =
+        DBSession::get_many_ids(&session_ids, &**pool, &redis).await?;
+
+    let pat_ids =
+        PersonalAccessToken::get_user_pats(user.id, &**pool, &redis).await?;
+    let pats =
+        PersonalAccessToken::get_many_ids(&pat_ids, &**pool, &redis).await?;
+
+    let mut transaction = pool.begin().await?;
+    for session in &sessions {
+        DBSession::remove(session.id, &mut transaction).await?;
+    }
+    for pat in &pats {
+        PersonalAccessToken::remove(pat.id, &mut transaction).await?;
+    }
+    OAuthAccessToken::remove_all_for_user(user.id, &mut transaction).await?;
+
+    sqlx::query!(
+        "
+        UPDATE users
+        SET password = NULL
+        WHERE (id = $1)
+        ",
+        user.id as crate::database::models::ids::UserId,
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    DBSession::clear_cache(
+        sessions
+            .into_iter()
+            .map(|x| (Some(x.id), Some(x.session), Some(x.user_id)))
+            .collect(),
+        &redis,
+    )
+    .await?;
+    PersonalAccessToken::clear_cache(
+        pats.into_iter()
+            .map(|x| (Some(x.id), Some(x.access_token), Some(x.user_id)))
+            .collect(),
+        &redis,
+    )
+    .await?;
+    crate::database::models::User::clear_caches(&[(user.id, None)], &redis)
+        .await?;
+
+    if let Some(email) = user.email {
+        let flow = Flow::ForgotPassword { user_id: user.id }
+            .insert(Duration::hours(24), &redis)
+            .await?;
+
+        send_email(
+            email,
+            "请重置您的密码",
+            "我们已退出您账号的所有登录，撤销了所有访问令牌并清除了原密码。请访问以下链接设置新密码，如果按钮无法使用，您可以复制链接并将其粘贴到浏览器中。",
+            "建议同时检查账号绑定的手机号和第三方登录方式，并启用两步验证。",
+            Some((
+                "重置密码",
+                &format!(
+                    "{}/{}?flow={}",
+                    dotenvy::var("SITE_URL")?,
+                    dotenvy::var("SITE_RESET_PASSWORD_PATH")?,
+                    flow
+                ),
+            )),
+        )?;
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
 #[post("2fa/get_secret")]
 pub async fn begin_2fa_flow(
     req: HttpRequest,
@@ -2719,13 +3064,21 @@ pub struct ResetPassword {
 
 #[post("password/reset")]
 pub async fn reset_password_begin(
+    req: HttpRequest,
     pool: Data<PgPool>,
     redis: Data<RedisPool>,
     reset_password: web::Json<ResetPassword>,
+    captcha: Data<Arc<Captcha>>,
 ) -> Result<HttpResponse, ApiError> {
-    if !check_hcaptcha(&reset_password.challenge).await? {
-        return Err(ApiError::Turnstile);
-    }
+    captcha
+        .check(
+            CaptchaAction::PasswordReset,
+            &reset_password.challenge,
+            &req,
+            None,
+            &redis,
+        )
+        .await?;
 
     let user = if let Some(user_id) = crate::database::models::User::get_email(
         &reset_password.username,
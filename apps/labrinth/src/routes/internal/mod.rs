@@ -7,6 +7,7 @@ pub mod moderation;
 pub mod pats;
 pub mod payment;
 pub mod session;
+pub mod webauthn;
 
 pub use super::ApiError;
 use super::v3::oauth_clients;
@@ -1,8 +1,12 @@
 use super::ApiError;
+use crate::analytics::AnalyticsStorage;
 use crate::database;
+use crate::database::models::sms_send_log_item::{SmsSendLog, SmsSendStatus};
+use crate::database::models::translation_coverage_item::TranslationCoverage;
 use crate::database::redis::RedisPool;
 use crate::models::ids::random_base62;
 use crate::models::projects::ProjectStatus;
+use crate::models::translation_coverage::TranslationCoverageSummaryResponse;
 use crate::queue::moderation::{ApprovalType, IdentifiedFile, MissingMetadata};
 use crate::queue::session::AuthQueue;
 use crate::{auth::check_is_moderator_from_headers, models::pats::Scopes};
@@ -10,6 +14,7 @@ use actix_web::{HttpRequest, HttpResponse, web};
 use serde::Deserialize;
 use sqlx::PgPool;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.route("moderation/projects", web::get().to(get_projects));
@@ -19,6 +24,11 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         "moderation/pending-counts",
         web::get().to(get_pending_counts),
     );
+    cfg.route(
+        "moderation/download-fraud",
+        web::get().to(get_download_fraud_report),
+    );
+    cfg.route("moderation/sms-logs", web::get().to(get_sms_logs));
     cfg.route(
         "moderation/translation-tracking-status",
         web::get().to(get_translation_tracking_status),
@@ -363,22 +373,26 @@ pub struct TranslationTrackingItem {
     pub project_name: String,
     /// 项目图标
     pub project_icon: Option<String>,
-    /// 汉化包 slug
-    pub translation_pack_slug: Option<String>,
+    /// 各语言本地化资源的 slug，键为语言代码
+    pub translation_packs: HashMap<String, String>,
     /// 最新版本 ID
     pub latest_version_id: Option<String>,
     /// 最新版本号
     pub latest_version_number: Option<String>,
     /// 最新版本发布时间
     pub latest_version_published: Option<chrono::DateTime<chrono::Utc>>,
-    /// 是否有已批准的汉化绑定
+    /// 是否有已批准的汉化绑定（任意语言）
     pub has_approved_translation: bool,
+    /// 已有已批准汉化绑定的语言
+    pub approved_languages: Vec<String>,
     /// 已批准的汉化版本 ID
     pub approved_translation_version_id: Option<String>,
     /// 已批准的汉化版本号
     pub approved_translation_version_number: Option<String>,
     /// 版本发布后经过的秒数
     pub seconds_since_published: Option<i64>,
+    /// 最新版本各语言的汉化覆盖率，键为语言代码，尚未分析的语言不包含在内
+    pub coverage: HashMap<String, TranslationCoverageSummaryResponse>,
 }
 
 /// 汉化追踪状态响应
@@ -421,7 +435,11 @@ pub async fn get_translation_tracking_status(
                 m.slug,
                 m.name,
                 m.icon_url,
-                m.translation_tracker
+                (
+                    SELECT jsonb_object_agg(mtt.language_code, mtt.translation_slug)
+                    FROM mod_translation_trackers mtt
+                    WHERE mtt.mod_id = m.id
+                ) as translation_tracker
             FROM mods m
             WHERE m.translation_tracking = true
             AND m.status = 'approved'
@@ -460,7 +478,14 @@ pub async fn get_translation_tracking_status(
             lv.version_number as "latest_version_number?",
             lv.date_published as "latest_version_published?",
             at.translation_version_id as "translation_version_id?",
-            at.translation_version_number as "translation_version_number?"
+            at.translation_version_number as "translation_version_number?",
+            ARRAY(
+                SELECT DISTINCT LOWER(vlv.language_code)
+                FROM version_link_version vlv
+                WHERE vlv.joining_version_id = lv.version_id
+                AND vlv.approval_status = 'approved'
+                AND vlv.link_type = 'translation'
+            ) as "approved_languages!"
         FROM tracked_projects tp
         LEFT JOIN latest_versions lv ON lv.mod_id = tp.id
         LEFT JOIN approved_translations at ON at.original_version_id = lv.version_id
@@ -471,6 +496,26 @@ pub async fn get_translation_tracking_status(
     .try_collect::<Vec<_>>()
     .await?;
 
+    // 最新版本各语言的覆盖率报告
+    let mut coverages: HashMap<
+        i64,
+        HashMap<String, TranslationCoverageSummaryResponse>,
+    > = HashMap::new();
+    let latest_version_ids = rows
+        .iter()
+        .filter_map(|row| row.latest_version_id)
+        .map(database::models::ids::VersionId)
+        .collect::<Vec<_>>();
+    for summary in
+        TranslationCoverage::get_version_summaries(&latest_version_ids, &**pool)
+            .await?
+    {
+        coverages
+            .entry(summary.version_id.0)
+            .or_default()
+            .insert(summary.language_code.clone(), summary.into());
+    }
+
     let now = Utc::now();
     let items: Vec<TranslationTrackingItem> = rows
         .into_iter()
@@ -479,6 +524,11 @@ pub async fn get_translation_tracking_status(
                 .latest_version_published
                 .map(|pub_time| (now - pub_time).num_seconds());
 
+            let coverage = row
+                .latest_version_id
+                .and_then(|id| coverages.remove(&id))
+                .unwrap_or_default();
+
             TranslationTrackingItem {
                 project_id: crate::models::ids::ProjectId::from(
                     database::models::ids::ProjectId(row.project_id),
@@ -487,7 +537,10 @@ pub async fn get_translation_tracking_status(
                 project_slug: row.project_slug,
                 project_name: row.project_name,
                 project_icon: row.project_icon,
-                translation_pack_slug: row.translation_tracker,
+                translation_packs: row
+                    .translation_tracker
+                    .and_then(|x| serde_json::from_value(x).ok())
+                    .unwrap_or_default(),
                 latest_version_id: row.latest_version_id.map(|id| {
                     crate::models::ids::VersionId::from(
                         database::models::ids::VersionId(id),
@@ -497,6 +550,7 @@ pub async fn get_translation_tracking_status(
                 latest_version_number: row.latest_version_number,
                 latest_version_published: row.latest_version_published,
                 has_approved_translation: row.translation_version_id.is_some(),
+                approved_languages: row.approved_languages,
                 approved_translation_version_id: row
                     .translation_version_id
                     .map(|id| {
@@ -508,6 +562,7 @@ pub async fn get_translation_tracking_status(
                 approved_translation_version_number: row
                     .translation_version_number,
                 seconds_since_published,
+                coverage,
             }
         })
         .collect();
@@ -521,6 +576,184 @@ pub async fn get_translation_tracking_status(
     }))
 }
 
+// ==================== 下载刷量报告 ====================
+
+#[derive(Deserialize)]
+pub struct DownloadFraudQuery {
+    /// 统计最近多少天，默认 7 天，最多 90 天
+    pub days: Option<u32>,
+    /// 返回的项目数量，默认 50，最多 200
+    pub limit: Option<u32>,
+}
+
+/// 下载模式异常的项目
+#[derive(serde::Serialize)]
+pub struct DownloadFraudItem {
+    pub project_id: crate::models::ids::ProjectId,
+    pub project_slug: Option<String>,
+    pub project_name: Option<String>,
+    pub project_icon: Option<String>,
+    /// 被判定为可疑、未计入下载量的下载次数
+    pub suspicious_downloads: u64,
+    /// 同期计入下载量的下载次数
+    pub counted_downloads: u64,
+    /// 可疑下载占全部下载的比例
+    pub suspicious_ratio: f64,
+    pub unique_ips: u64,
+    pub unique_users: u64,
+    pub max_score: u32,
+    /// 命中的检测规则
+    pub reasons: Vec<String>,
+    pub last_seen: chrono::DateTime<chrono::Utc>,
+}
+
+/// 获取下载模式异常的项目报告
+pub async fn get_download_fraud_report(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    analytics: web::Data<Arc<dyn AnalyticsStorage + Send + Sync>>,
+    query: web::Query<DownloadFraudQuery>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    check_is_moderator_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ANALYTICS]),
+    )
+    .await?;
+
+    let days = query.days.unwrap_or(7).clamp(1, 90);
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let end_date = chrono::Utc::now();
+    let start_date = end_date - chrono::Duration::days(days as i64);
+    let rows = analytics
+        .fetch_download_fraud_projects(start_date, end_date, limit)
+        .await?;
+    if rows.is_empty() {
+        return Ok(HttpResponse::Ok().json(Vec::<DownloadFraudItem>::new()));
+    }
+
+    let project_ids = rows
+        .iter()
+        .map(|x| crate::models::ids::ProjectId(x.id))
+        .collect::<Vec<_>>();
+    let counted = analytics
+        .fetch_downloads_total(project_ids.clone(), start_date, end_date)
+        .await?
+        .into_iter()
+        .map(|x| (x.id, x.total))
+        .collect::<HashMap<_, _>>();
+
+    let projects = database::Project::get_many_ids(
+        &project_ids
+            .iter()
+            .map(|x| database::models::ProjectId::from(*x))
+            .collect::<Vec<_>>(),
+        &**pool,
+        &redis,
+    )
+    .await?
+    .into_iter()
+    .map(|x| (x.inner.id.0 as u64, x.inner))
+    .collect::<HashMap<_, _>>();
+
+    let items = rows
+        .into_iter()
+        .map(|row| {
+            let project = projects.get(&row.id);
+            let counted_downloads = counted.get(&row.id).copied().unwrap_or(0);
+
+            DownloadFraudItem {
+                project_id: crate::models::ids::ProjectId(row.id),
+                project_slug: project.and_then(|x| x.slug.clone()),
+                project_name: project.map(|x| x.name.clone()),
+                project_icon: project.and_then(|x| x.icon_url.clone()),
+                suspicious_downloads: row.suspicious,
+                counted_downloads,
+                suspicious_ratio: row.suspicious as f64
+                    / (row.suspicious + counted_downloads) as f64,
+                unique_ips: row.unique_ips,
+                unique_users: row.unique_users,
+                max_score: row.max_score,
+                reasons: row.reasons,
+                last_seen: chrono::DateTime::from_timestamp(
+                    row.last_seen as i64,
+                    0,
+                )
+                .unwrap_or_default(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(items))
+}
+
+// ==================== 短信发送记录 ====================
+
+#[derive(Deserialize)]
+pub struct SmsLogsQuery {
+    pub phone_number: Option<String>,
+    pub user_id: Option<crate::models::ids::UserId>,
+    pub limit: Option<i64>,
+}
+
+#[derive(serde::Serialize)]
+pub struct SmsLogItem {
+    pub user_id: Option<crate::models::ids::UserId>,
+    pub phone_number: String,
+    pub ip: Option<String>,
+    pub provider: String,
+    pub status: SmsSendStatus,
+    pub provider_message_id: Option<String>,
+    pub error: Option<String>,
+    pub created: chrono::DateTime<chrono::Utc>,
+}
+
+/// 查询短信发送记录，用于排查用户收不到验证码的问题
+///
+/// GET /_internal/moderation/sms-logs
+pub async fn get_sms_logs(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    query: web::Query<SmsLogsQuery>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    check_is_moderator_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_READ]),
+    )
+    .await?;
+
+    let logs = SmsSendLog::get_recent(
+        query.phone_number.as_deref(),
+        query.user_id.map(|x| x.into()),
+        query.limit.unwrap_or(50).clamp(1, 200),
+        &**pool,
+    )
+    .await?
+    .into_iter()
+    .map(|x| SmsLogItem {
+        user_id: x.user_id.map(|x| x.into()),
+        phone_number: x.phone_number,
+        ip: x.ip,
+        provider: x.provider,
+        status: x.status,
+        provider_message_id: x.provider_message_id,
+        error: x.error,
+        created: x.created,
+    })
+    .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(logs))
+}
+
 // ==================== 待处理数量统计 ====================
 
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
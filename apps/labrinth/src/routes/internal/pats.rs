@@ -1,7 +1,7 @@
 use crate::database;
 use crate::database::models::generate_pat_id;
 
-use crate::auth::get_user_from_headers;
+use crate::auth::{filter_enlisted_projects_ids, get_user_from_headers};
 use crate::routes::ApiError;
 
 use crate::database::redis::RedisPool;
@@ -13,15 +13,19 @@ use rand::distributions::Alphanumeric;
 use rand_chacha::ChaCha20Rng;
 use rand_chacha::rand_core::SeedableRng;
 
+use crate::models::ids::{OrganizationId, ProjectId};
 use crate::models::pats::{PersonalAccessToken, Scopes};
+use crate::models::users::User;
 use crate::queue::session::AuthQueue;
+use crate::util::ip::parse_ip_range;
 use crate::util::validate::validation_errors_to_string;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use sqlx::postgres::PgPool;
 use validator::Validate;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(get_pats);
+    cfg.service(get_pat_access);
     cfg.service(create_pat);
     cfg.service(edit_pat);
     cfg.service(delete_pat);
@@ -63,12 +67,202 @@ pub async fn get_pats(
     ))
 }
 
+#[derive(Serialize)]
+pub struct PatAccessProject {
+    pub id: ProjectId,
+    pub slug: Option<String>,
+    pub name: String,
+    pub organization: Option<OrganizationId>,
+}
+
+#[derive(Serialize)]
+pub struct PatAccessOrganization {
+    pub id: OrganizationId,
+    pub slug: String,
+    pub name: String,
+}
+
+/// 令牌实际可以操作的资源，组织限制会展开为组织下的所有项目
+#[derive(Serialize)]
+pub struct PatAccess {
+    pub scopes: Scopes,
+    pub expires: DateTime<Utc>,
+    /// 未限定项目或组织时为 true，此时令牌可以操作用户有权限的所有资源
+    pub unrestricted: bool,
+    pub projects: Vec<PatAccessProject>,
+    pub organizations: Vec<PatAccessOrganization>,
+    pub allowed_ips: Option<Vec<String>>,
+}
+
+#[get("pat/{id}/access")]
+pub async fn get_pat_access(
+    req: HttpRequest,
+    id: web::Path<(String,)>,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+    session_queue: Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PAT_READ]),
+    )
+    .await?
+    .1;
+
+    let id = id.into_inner().0;
+    let pat = database::models::pat_item::PersonalAccessToken::get(
+        &id, &**pool, &redis,
+    )
+    .await?
+    .filter(|x| x.user_id == user.id.into())
+    .ok_or(ApiError::NotFound)?;
+
+    let organization_ids = pat.organization_ids.clone().unwrap_or_default();
+    let organizations = database::models::Organization::get_many_ids(
+        &organization_ids,
+        &**pool,
+        &redis,
+    )
+    .await?;
+
+    let mut project_ids = pat.project_ids.clone().unwrap_or_default();
+    if !organization_ids.is_empty() {
+        let organization_projects = sqlx::query!(
+            "
+            SELECT id FROM mods
+            WHERE organization_id = ANY($1)
+            ",
+            &organization_ids.iter().map(|x| x.0).collect::<Vec<_>>(),
+        )
+        .fetch_all(&**pool)
+        .await?;
+
+        for project in organization_projects {
+            let project_id = database::models::ProjectId(project.id);
+            if !project_ids.contains(&project_id) {
+                project_ids.push(project_id);
+            }
+        }
+    }
+    let projects =
+        database::models::Project::get_many_ids(&project_ids, &**pool, &redis)
+            .await?;
+
+    Ok(HttpResponse::Ok().json(PatAccess {
+        scopes: if pat.is_resource_restricted() {
+            pat.scopes & Scopes::resource_scoped()
+        } else {
+            pat.scopes
+        },
+        expires: pat.expires,
+        unrestricted: !pat.is_resource_restricted(),
+        projects: projects
+            .into_iter()
+            .map(|x| PatAccessProject {
+                id: x.inner.id.into(),
+                slug: x.inner.slug,
+                name: x.inner.name,
+                organization: x.inner.organization_id.map(|x| x.into()),
+            })
+            .collect(),
+        organizations: organizations
+            .into_iter()
+            .map(|x| PatAccessOrganization {
+                id: x.id.into(),
+                slug: x.slug,
+                name: x.name,
+            })
+            .collect(),
+        allowed_ips: pat.allowed_ips,
+    }))
+}
+
+/// 检查令牌的项目、组织和 IP 限制。
+/// 限定的项目和组织必须是用户所在团队的，且令牌只能使用与资源相关的权限
+async fn validate_restrictions(
+    user: &User,
+    scopes: Scopes,
+    projects: &Option<Vec<ProjectId>>,
+    organizations: &Option<Vec<OrganizationId>>,
+    allowed_ips: &Option<Vec<String>>,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(), ApiError> {
+    if (projects.is_some() || organizations.is_some())
+        && !Scopes::resource_scoped().contains(scopes)
+    {
+        return Err(ApiError::InvalidInput(
+            "限定项目或组织的令牌只能使用项目、版本和组织相关的权限！"
+                .to_string(),
+        ));
+    }
+
+    if let Some(projects) = projects {
+        let project_ids = projects
+            .iter()
+            .map(|x| database::models::ProjectId::from(*x))
+            .collect::<Vec<_>>();
+        let db_projects =
+            database::models::Project::get_many_ids(&project_ids, pool, redis)
+                .await?;
+        let enlisted = filter_enlisted_projects_ids(
+            db_projects.iter().map(|x| &x.inner).collect(),
+            &Some(user.clone()),
+            pool,
+        )
+        .await?;
+
+        if project_ids.iter().any(|x| !enlisted.contains(x)) {
+            return Err(ApiError::InvalidInput(
+                "只能限定为您所在团队的项目！".to_string(),
+            ));
+        }
+    }
+
+    if let Some(organizations) = organizations {
+        let user_organizations =
+            database::models::User::get_organizations(user.id.into(), pool)
+                .await?;
+
+        if organizations
+            .iter()
+            .any(|x| !user_organizations.contains(&(*x).into()))
+        {
+            return Err(ApiError::InvalidInput(
+                "只能限定为您所在的组织！".to_string(),
+            ));
+        }
+    }
+
+    if let Some(allowed_ips) = allowed_ips
+        && allowed_ips.iter().any(|x| parse_ip_range(x).is_none())
+    {
+        return Err(ApiError::InvalidInput(
+            "IP 范围格式无效，请使用如 203.0.113.0/24 的格式！".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
 #[derive(Deserialize, Validate)]
 pub struct NewPersonalAccessToken {
     pub scopes: Scopes,
     #[validate(length(min = 3, max = 255))]
     pub name: String,
     pub expires: DateTime<Utc>,
+    /// 限定可以操作的项目
+    #[validate(length(min = 1, max = 64))]
+    pub projects: Option<Vec<ProjectId>>,
+    /// 限定可以操作的组织（含组织下的项目）
+    #[validate(length(min = 1, max = 64))]
+    pub organizations: Option<Vec<OrganizationId>>,
+    /// 允许使用令牌的 IP 范围
+    #[validate(length(min = 1, max = 32))]
+    pub allowed_ips: Option<Vec<String>>,
 }
 
 #[post("pat")]
@@ -100,6 +294,17 @@ pub async fn create_pat(
     .await?
     .1;
 
+    validate_restrictions(
+        &user,
+        info.scopes,
+        &info.projects,
+        &info.organizations,
+        &info.allowed_ips,
+        &pool,
+        &redis,
+    )
+    .await?;
+
     let mut transaction = pool.begin().await?;
 
     let id = generate_pat_id(&mut transaction).await?;
@@ -121,6 +326,15 @@ pub async fn create_pat(
         created: Utc::now(),
         expires: info.expires,
         last_used: None,
+        project_ids: info
+            .projects
+            .as_ref()
+            .map(|x| x.iter().map(|x| (*x).into()).collect()),
+        organization_ids: info
+            .organizations
+            .as_ref()
+            .map(|x| x.iter().map(|x| (*x).into()).collect()),
+        allowed_ips: info.allowed_ips.clone(),
     }
     .insert(&mut transaction)
     .await?;
@@ -141,6 +355,9 @@ pub async fn create_pat(
         created: Utc::now(),
         expires: info.expires,
         last_used: None,
+        projects: info.projects.clone(),
+        organizations: info.organizations.clone(),
+        allowed_ips: info.allowed_ips.clone(),
     }))
 }
 
@@ -150,6 +367,27 @@ pub struct ModifyPersonalAccessToken {
     #[validate(length(min = 3, max = 255))]
     pub name: Option<String>,
     pub expires: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    #[validate(length(min = 1, max = 64))]
+    pub projects: Option<Option<Vec<ProjectId>>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    #[validate(length(min = 1, max = 64))]
+    pub organizations: Option<Option<Vec<OrganizationId>>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    #[validate(length(min = 1, max = 32))]
+    pub allowed_ips: Option<Option<Vec<String>>>,
 }
 
 #[patch("pat/{id}")]
@@ -184,6 +422,32 @@ pub async fn edit_pat(
     if let Some(pat) = pat
         && pat.user_id == user.id.into()
     {
+        // 与未修改的字段合并后再检查限制条件
+        let projects = info.projects.clone().unwrap_or_else(|| {
+            pat.project_ids
+                .as_ref()
+                .map(|x| x.iter().map(|x| (*x).into()).collect())
+        });
+        let organizations = info.organizations.clone().unwrap_or_else(|| {
+            pat.organization_ids
+                .as_ref()
+                .map(|x| x.iter().map(|x| (*x).into()).collect())
+        });
+        let allowed_ips = info
+            .allowed_ips
+            .clone()
+            .unwrap_or_else(|| pat.allowed_ips.clone());
+        validate_restrictions(
+            &user,
+            info.scopes.unwrap_or(pat.scopes),
+            &projects,
+            &organizations,
+            &allowed_ips,
+            &pool,
+            &redis,
+        )
+        .await?;
+
         let mut transaction = pool.begin().await?;
 
         if let Some(scopes) = &info.scopes {
@@ -238,6 +502,33 @@ pub async fn edit_pat(
             .await?;
         }
 
+        if info.projects.is_some()
+            || info.organizations.is_some()
+            || info.allowed_ips.is_some()
+        {
+            let project_ids = projects
+                .as_ref()
+                .map(|x| x.iter().map(|x| x.0 as i64).collect::<Vec<_>>());
+            let organization_ids = organizations
+                .as_ref()
+                .map(|x| x.iter().map(|x| x.0 as i64).collect::<Vec<_>>());
+
+            sqlx::query!(
+                "
+                    UPDATE pats
+                    SET project_ids = $1, organization_ids = $2,
+                        allowed_ips = $3
+                    WHERE id = $4
+                    ",
+                project_ids.as_deref(),
+                organization_ids.as_deref(),
+                allowed_ips.as_deref(),
+                pat.id.0
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
         transaction.commit().await?;
         database::models::pat_item::PersonalAccessToken::clear_cache(
             vec![(Some(pat.id), Some(pat.access_token), Some(pat.user_id))],
@@ -1,4 +1,4 @@
-use crate::auth::{AuthenticationError, get_user_from_headers};
+use crate::auth::{AuthenticationError, get_user_from_headers, login_risk};
 use crate::database::models::UserId;
 use crate::database::models::session_item::Session as DBSession;
 use crate::database::models::session_item::SessionBuilder;
@@ -7,7 +7,7 @@ use crate::models::pats::Scopes;
 use crate::models::sessions::Session;
 use crate::queue::session::AuthQueue;
 use crate::routes::ApiError;
-use crate::util::env::parse_var;
+use crate::util::ratelimit::client_ip;
 use actix_web::http::header::AUTHORIZATION;
 use actix_web::web::{Data, ServiceConfig, scope};
 use actix_web::{HttpRequest, HttpResponse, delete, get, post, web};
@@ -31,6 +31,9 @@ pub struct SessionMetadata {
     pub city: Option<String>,
     pub country: Option<String>,
     pub ip: String,
+    /// Cloudflare 提供的 IP 大致坐标，用于判断异地登录
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
 
     pub os: Option<String>,
     pub platform: Option<String>,
@@ -40,22 +43,22 @@ pub struct SessionMetadata {
 pub async fn get_session_metadata(
     req: &HttpRequest,
 ) -> Result<SessionMetadata, AuthenticationError> {
-    let conn_info = req.connection_info().clone();
-    let ip_addr = if parse_var("CLOUDFLARE_INTEGRATION").unwrap_or(false) {
-        if let Some(header) = req.headers().get("x-real-ip") {
-            header.to_str().ok()
-        } else {
-            conn_info.peer_addr()
-        }
-    } else {
-        conn_info.peer_addr()
-    };
+    let ip_addr = client_ip(req);
+    if ip_addr.is_empty() {
+        return Err(AuthenticationError::InvalidCredentials);
+    }
 
     let country = req
         .headers()
         .get("cf-ipcountry")
         .and_then(|x| x.to_str().ok());
     let city = req.headers().get("cf-ipcity").and_then(|x| x.to_str().ok());
+    let coordinate = |header: &str| {
+        req.headers()
+            .get(header)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.parse::<f64>().ok())
+    };
 
     let user_agent = req
         .headers()
@@ -76,9 +79,9 @@ pub async fn get_session_metadata(
         platform: os.map(|x| x.1.to_string()),
         city: city.map(|x| x.to_string()),
         country: country.map(|x| x.to_string()),
-        ip: ip_addr
-            .ok_or_else(|| AuthenticationError::InvalidCredentials)?
-            .to_string(),
+        latitude: coordinate("cf-iplatitude"),
+        longitude: coordinate("cf-iplongitude"),
+        ip: ip_addr,
         user_agent: user_agent.to_string(),
     })
 }
@@ -91,6 +94,41 @@ pub async fn issue_session(
 ) -> Result<DBSession, AuthenticationError> {
     let metadata = get_session_metadata(&req).await?;
 
+    create_session(&metadata, user_id, transaction, redis).await
+}
+
+/// 用户登录时创建会话，记录登录历史并在出现异常时邮件提醒用户
+pub async fn issue_login_session(
+    req: HttpRequest,
+    user_id: UserId,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<DBSession, AuthenticationError> {
+    let metadata = get_session_metadata(&req).await?;
+    let risk = login_risk::assess_login(user_id, &metadata, &mut **transaction)
+        .await?;
+
+    let session =
+        create_session(&metadata, user_id, transaction, redis).await?;
+    login_risk::record_login(
+        user_id,
+        session.id,
+        &metadata,
+        &risk,
+        transaction,
+        redis,
+    )
+    .await?;
+
+    Ok(session)
+}
+
+async fn create_session(
+    metadata: &SessionMetadata,
+    user_id: UserId,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<DBSession, AuthenticationError> {
     let session = ChaCha20Rng::from_entropy()
         .sample_iter(&Alphanumeric)
         .take(60)
@@ -102,12 +140,12 @@ pub async fn issue_session(
     let id = SessionBuilder {
         session,
         user_id,
-        os: metadata.os,
-        platform: metadata.platform,
-        city: metadata.city,
-        country: metadata.country,
-        ip: metadata.ip,
-        user_agent: metadata.user_agent,
+        os: metadata.os.clone(),
+        platform: metadata.platform.clone(),
+        city: metadata.city.clone(),
+        country: metadata.country.clone(),
+        ip: metadata.ip.clone(),
+        user_agent: metadata.user_agent.clone(),
     }
     .insert(transaction)
     .await?;
@@ -0,0 +1,497 @@
+use crate::auth::email::send_email;
+use crate::auth::webauthn::{
+    encode_credential_id, passkey_2fa_enforced, user_handle,
+    user_id_from_handle, webauthn,
+};
+use crate::auth::{AuthenticationError, get_user_from_headers};
+use crate::database::models::flow_item::Flow;
+use crate::database::models::generate_webauthn_credential_id;
+use crate::database::models::webauthn_credential_item::WebauthnCredential;
+use crate::database::redis::RedisPool;
+use crate::models::ids::WebauthnCredentialId;
+use crate::models::pats::Scopes;
+use crate::queue::session::AuthQueue;
+use crate::routes::ApiError;
+use crate::routes::internal::session::issue_login_session;
+use crate::util::validate::validation_errors_to_string;
+use actix_web::web::{Data, ServiceConfig, scope};
+use actix_web::{HttpRequest, HttpResponse, delete, get, patch, post, web};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use validator::Validate;
+use webauthn_rs::prelude::{
+    CreationChallengeResponse, DiscoverableKey, PublicKeyCredential,
+    RegisterPublicKeyCredential, RequestChallengeResponse,
+};
+
+/// 每个用户最多可注册的通行密钥数量
+const MAX_CREDENTIALS: usize = 20;
+/// 浏览器完成一次 WebAuthn 仪式的时限
+const CEREMONY_EXPIRY_MINUTES: i64 = 5;
+
+pub fn config(cfg: &mut ServiceConfig) {
+    cfg.service(
+        scope("webauthn")
+            .service(list_credentials)
+            .service(rename_credential)
+            .service(delete_credential)
+            .service(begin_registration)
+            .service(finish_registration)
+            .service(begin_login)
+            .service(finish_login)
+            .service(begin_login_2fa)
+            .service(finish_login_2fa),
+    );
+}
+
+#[derive(Serialize)]
+pub struct RegistrationChallenge {
+    pub flow: String,
+    pub options: CreationChallengeResponse,
+}
+
+#[derive(Serialize)]
+pub struct AuthenticationChallenge {
+    pub flow: String,
+    pub options: RequestChallengeResponse,
+}
+
+#[get("credentials")]
+pub async fn list_credentials(
+    req: HttpRequest,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+    session_queue: Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_AUTH_WRITE]),
+    )
+    .await?
+    .1;
+
+    let credentials = WebauthnCredential::get_all_user(user.id.into(), &**pool)
+        .await?
+        .into_iter()
+        .map(crate::models::webauthn::WebauthnCredential::from)
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(credentials))
+}
+
+#[derive(Deserialize, Validate)]
+pub struct CredentialName {
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+}
+
+#[patch("credentials/{id}")]
+pub async fn rename_credential(
+    req: HttpRequest,
+    info: web::Path<(WebauthnCredentialId,)>,
+    edit: web::Json<CredentialName>,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+    session_queue: Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_AUTH_WRITE]),
+    )
+    .await?
+    .1;
+
+    edit.validate().map_err(|err| {
+        ApiError::Validation(validation_errors_to_string(err, None))
+    })?;
+
+    let credential =
+        WebauthnCredential::get(info.into_inner().0.into(), &**pool)
+            .await?
+            .filter(|x| x.user_id == user.id.into())
+            .ok_or(ApiError::NotFound)?;
+
+    WebauthnCredential::rename(credential.id, edit.name.trim(), &**pool)
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[delete("credentials/{id}")]
+pub async fn delete_credential(
+    req: HttpRequest,
+    info: web::Path<(WebauthnCredentialId,)>,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+    session_queue: Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_AUTH_WRITE]),
+    )
+    .await?
+    .1;
+
+    let credentials =
+        WebauthnCredential::get_all_user(user.id.into(), &**pool).await?;
+    let id = info.into_inner().0.into();
+    let credential = credentials
+        .iter()
+        .find(|x| x.id == id)
+        .ok_or(ApiError::NotFound)?;
+
+    // 组织要求使用通行密钥时不能删除最后一个通行密钥
+    if credentials.len() == 1
+        && passkey_2fa_enforced(user.id.into(), &pool).await?
+    {
+        return Err(ApiError::InvalidInput(
+            "您所在的组织要求使用通行密钥进行两步验证，不能删除最后一个通行密钥".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+    WebauthnCredential::remove(credential.id, &mut transaction).await?;
+
+    if let Some(email) = user.email {
+        send_email(
+            email,
+            "通行密钥已移除",
+            &format!(
+                "通行密钥「{}」已从您的 BBSMC 账号中移除，今后无法再使用它登录。",
+                credential.name
+            ),
+            "如果不是您进行的更改，请立即通过电子邮件 (support@bbsmc.net) 联系我们。",
+            None,
+        )?;
+    }
+
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[post("register")]
+pub async fn begin_registration(
+    req: HttpRequest,
+    body: web::Json<CredentialName>,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+    session_queue: Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_AUTH_WRITE]),
+    )
+    .await?
+    .1;
+
+    body.validate().map_err(|err| {
+        ApiError::Validation(validation_errors_to_string(err, None))
+    })?;
+
+    let credentials =
+        WebauthnCredential::get_all_user(user.id.into(), &**pool).await?;
+    if credentials.len() >= MAX_CREDENTIALS {
+        return Err(ApiError::InvalidInput(format!(
+            "最多只能注册 {MAX_CREDENTIALS} 个通行密钥"
+        )));
+    }
+
+    // 排除已注册的凭据，避免同一个认证器重复注册
+    let (options, state) = webauthn()?
+        .start_passkey_registration(
+            user_handle(user.id.into()),
+            &user.username,
+            &user.username,
+            Some(
+                credentials
+                    .iter()
+                    .map(|x| x.passkey.cred_id().clone())
+                    .collect(),
+            ),
+        )
+        .map_err(AuthenticationError::from)?;
+
+    let flow = Flow::WebauthnRegistration {
+        user_id: user.id.into(),
+        name: body.name.trim().to_string(),
+        state,
+    }
+    .insert(Duration::minutes(CEREMONY_EXPIRY_MINUTES), &redis)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(RegistrationChallenge { flow, options }))
+}
+
+#[derive(Deserialize)]
+pub struct FinishRegistration {
+    pub flow: String,
+    pub credential: RegisterPublicKeyCredential,
+}
+
+#[post("register/finish")]
+pub async fn finish_registration(
+    req: HttpRequest,
+    body: web::Json<FinishRegistration>,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+    session_queue: Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_AUTH_WRITE]),
+    )
+    .await?
+    .1;
+
+    let user_id = user.id.into();
+    let flow = Flow::take_if(
+        &body.flow,
+        |x| matches!(x, Flow::WebauthnRegistration { user_id: id, .. } if *id == user_id),
+        &redis,
+    )
+    .await?;
+
+    let Some(Flow::WebauthnRegistration { name, state, .. }) = flow else {
+        return Err(ApiError::Authentication(
+            AuthenticationError::InvalidCredentials,
+        ));
+    };
+
+    let passkey = webauthn()?
+        .finish_passkey_registration(&body.credential, &state)
+        .map_err(AuthenticationError::from)?;
+
+    let mut transaction = pool.begin().await?;
+
+    let credential = WebauthnCredential {
+        id: generate_webauthn_credential_id(&mut transaction).await?,
+        user_id,
+        credential_id: encode_credential_id(passkey.cred_id().as_ref()),
+        name,
+        passkey,
+        created: Utc::now(),
+        last_used: None,
+    };
+    credential.insert(&mut transaction).await?;
+
+    if let Some(email) = user.email {
+        send_email(
+            email,
+            "已添加通行密钥",
+            &format!(
+                "通行密钥「{}」已添加到您的 BBSMC 账号，今后可以使用它登录或完成两步验证。",
+                credential.name
+            ),
+            "如果不是您进行的更改，请立即通过电子邮件 (support@bbsmc.net) 联系我们。",
+            None,
+        )?;
+    }
+
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(
+        crate::models::webauthn::WebauthnCredential::from(credential),
+    ))
+}
+
+#[post("login")]
+/// 开始无密码登录，由浏览器选择可发现的通行密钥
+pub async fn begin_login(
+    redis: Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    let (options, state) = webauthn()?
+        .start_discoverable_authentication()
+        .map_err(AuthenticationError::from)?;
+
+    let flow = Flow::WebauthnLogin { state }
+        .insert(Duration::minutes(CEREMONY_EXPIRY_MINUTES), &redis)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(AuthenticationChallenge { flow, options }))
+}
+
+#[derive(Deserialize)]
+pub struct FinishAuthentication {
+    pub flow: String,
+    pub credential: PublicKeyCredential,
+}
+
+#[post("login/finish")]
+pub async fn finish_login(
+    req: HttpRequest,
+    body: web::Json<FinishAuthentication>,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    let flow = Flow::take_if(
+        &body.flow,
+        |x| matches!(x, Flow::WebauthnLogin { .. }),
+        &redis,
+    )
+    .await?;
+
+    let Some(Flow::WebauthnLogin { state }) = flow else {
+        return Err(ApiError::Authentication(
+            AuthenticationError::InvalidCredentials,
+        ));
+    };
+
+    let webauthn = webauthn()?;
+    let (handle, credential_id) = webauthn
+        .identify_discoverable_authentication(&body.credential)
+        .map_err(AuthenticationError::from)?;
+
+    let mut credential = WebauthnCredential::get_by_credential_id(
+        &encode_credential_id(credential_id),
+        &**pool,
+    )
+    .await?
+    .filter(|x| x.user_id == user_id_from_handle(handle))
+    .ok_or(AuthenticationError::InvalidCredentials)?;
+
+    let result = webauthn
+        .finish_discoverable_authentication(
+            &body.credential,
+            state,
+            &[DiscoverableKey::from(&credential.passkey)],
+        )
+        .map_err(AuthenticationError::from)?;
+    credential.passkey.update_credential(&result);
+
+    let mut transaction = pool.begin().await?;
+    credential.update_after_use(&mut *transaction).await?;
+
+    let session =
+        issue_login_session(req, credential.user_id, &mut transaction, &redis)
+            .await?;
+    let res = crate::models::sessions::Session::from(session, true, None);
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(res))
+}
+
+#[derive(Deserialize)]
+pub struct BeginLogin2FA {
+    pub flow: String,
+}
+
+#[post("login/2fa")]
+/// 密码或第三方登录后，使用通行密钥代替 TOTP 完成两步验证
+pub async fn begin_login_2fa(
+    body: web::Json<BeginLogin2FA>,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    let Some(Flow::Login2FA { user_id }) =
+        Flow::get(&body.flow, &redis).await?
+    else {
+        return Err(ApiError::Authentication(
+            AuthenticationError::InvalidCredentials,
+        ));
+    };
+
+    let passkeys = WebauthnCredential::get_all_user(user_id, &**pool)
+        .await?
+        .into_iter()
+        .map(|x| x.passkey)
+        .collect::<Vec<_>>();
+    if passkeys.is_empty() {
+        return Err(ApiError::InvalidInput(
+            "该账号尚未注册通行密钥".to_string(),
+        ));
+    }
+
+    let (options, state) = webauthn()?
+        .start_passkey_authentication(&passkeys)
+        .map_err(AuthenticationError::from)?;
+
+    let flow = Flow::WebauthnLogin2FA {
+        user_id,
+        login_flow: body.flow.clone(),
+        state,
+    }
+    .insert(Duration::minutes(CEREMONY_EXPIRY_MINUTES), &redis)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(AuthenticationChallenge { flow, options }))
+}
+
+#[post("login/2fa/finish")]
+pub async fn finish_login_2fa(
+    req: HttpRequest,
+    body: web::Json<FinishAuthentication>,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    let flow = Flow::take_if(
+        &body.flow,
+        |x| matches!(x, Flow::WebauthnLogin2FA { .. }),
+        &redis,
+    )
+    .await?;
+
+    let Some(Flow::WebauthnLogin2FA {
+        user_id,
+        login_flow,
+        state,
+    }) = flow
+    else {
+        return Err(ApiError::Authentication(
+            AuthenticationError::InvalidCredentials,
+        ));
+    };
+
+    // 第一步登录流程只能使用一次
+    let login_flow = Flow::take_if(
+        &login_flow,
+        |x| matches!(x, Flow::Login2FA { user_id: id } if *id == user_id),
+        &redis,
+    )
+    .await?;
+    if !matches!(login_flow, Some(Flow::Login2FA { user_id: id }) if id == user_id)
+    {
+        return Err(ApiError::Authentication(
+            AuthenticationError::InvalidCredentials,
+        ));
+    }
+
+    let result = webauthn()?
+        .finish_passkey_authentication(&body.credential, &state)
+        .map_err(AuthenticationError::from)?;
+
+    let mut credential = WebauthnCredential::get_by_credential_id(
+        &encode_credential_id(result.cred_id().as_ref()),
+        &**pool,
+    )
+    .await?
+    .filter(|x| x.user_id == user_id)
+    .ok_or(AuthenticationError::InvalidCredentials)?;
+    credential.passkey.update_credential(&result);
+
+    let mut transaction = pool.begin().await?;
+    credential.update_after_use(&mut *transaction).await?;
+
+    let session =
+        issue_login_session(req, user_id, &mut transaction, &redis).await?;
+    let res = crate::models::sessions::Session::from(session, true, None);
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(res))
+}
@@ -1,19 +1,33 @@
 #![allow(non_local_definitions)]
 
-use crate::auth::checks::{is_visible_project, is_visible_version};
+use crate::auth::checks::{
+    check_pat_project, is_visible_project, is_visible_version,
+};
+use crate::auth::validate::get_user_record_from_bearer_token;
 use crate::database::models::legacy_loader_fields::MinecraftGameVersion;
 use crate::database::models::loader_fields::Loader;
 use crate::database::models::project_item::QueryProject;
 use crate::database::models::version_item::{QueryFile, QueryVersion};
 use crate::database::redis::RedisPool;
+use crate::file_hosting::S3PrivateHost;
 use crate::models::pats::Scopes;
 use crate::models::projects::{ProjectId, VersionId};
+use crate::models::users::User;
 use crate::queue::session::AuthQueue;
 use crate::routes::ApiError;
+use crate::routes::v3::version_file::{
+    check_private_file_access, presign_private_file,
+};
 use crate::{auth::get_user_from_headers, database};
+use actix_web::http::header::AUTHORIZATION;
 use actix_web::{HttpRequest, HttpResponse, get, route, web};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde_json::json;
+use sha2::Digest;
 use sqlx::PgPool;
 use std::collections::HashSet;
+use std::sync::Arc;
 use yaserde_derive::YaSerialize;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
@@ -68,6 +82,182 @@ pub struct MavenPom {
     version: String,
     name: String,
     description: String,
+    dependencies: PomDependencies,
+}
+
+#[derive(Default, Debug, Clone, YaSerialize)]
+#[yaserde(rename = "dependencies", namespaces = { "" = "http://maven.apache.org/POM/4.0.0" })]
+pub struct PomDependencies {
+    #[yaserde(rename = "dependency")]
+    dependencies: Vec<PomDependency>,
+}
+
+#[derive(Default, Debug, Clone, YaSerialize)]
+#[yaserde(rename = "dependency", namespaces = { "" = "http://maven.apache.org/POM/4.0.0" })]
+pub struct PomDependency {
+    #[yaserde(rename = "groupId")]
+    group_id: String,
+    #[yaserde(rename = "artifactId")]
+    artifact_id: String,
+    version: String,
+    scope: String,
+    optional: Option<String>,
+}
+
+const MAVEN_GROUP_ID: &str = "maven.modrinth";
+
+/// Gradle 看到该标记后会改为读取同目录下的 `.module` 文件
+const GRADLE_METADATA_MARKER: &str =
+    "<!-- do_not_remove: published-with-gradle-metadata -->";
+
+/// 附加文件支持的 classifier，文件名形如 `xxx-sources.jar`
+const CLASSIFIERS: [&str; 3] = ["sources", "javadoc", "dev"];
+
+/// 获取 Maven 请求的用户
+///
+/// Gradle 和 Maven 只能通过 Basic 认证传递凭据，此时密码即为个人访问令牌，
+/// 用户名会被忽略。其他情况按普通 API 请求处理。
+async fn get_maven_user(
+    req: &HttpRequest,
+    pool: &PgPool,
+    redis: &RedisPool,
+    session_queue: &AuthQueue,
+) -> Option<User> {
+    let basic_token = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.strip_prefix("Basic "))
+        .and_then(|x| STANDARD.decode(x.trim()).ok())
+        .and_then(|x| String::from_utf8(x).ok())
+        .and_then(|x| x.split_once(':').map(|(_, token)| token.to_string()));
+
+    let Some(token) = basic_token else {
+        return get_user_from_headers(
+            req,
+            pool,
+            redis,
+            session_queue,
+            Some(&[Scopes::PROJECT_READ]),
+        )
+        .await
+        .map(|x| x.1)
+        .ok();
+    };
+
+    let (scopes, user) = get_user_record_from_bearer_token(
+        req,
+        Some(&token),
+        pool,
+        redis,
+        session_queue,
+    )
+    .await
+    .ok()
+    .flatten()?;
+
+    scopes
+        .contains(Scopes::PROJECT_READ)
+        .then(|| User::from_full(user))
+}
+
+/// 查找请求的项目和版本，并检查可见性
+async fn get_artifact(
+    req: &HttpRequest,
+    project_id: &str,
+    vnum: &String,
+    user_option: &Option<User>,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(QueryProject, QueryVersion), ApiError> {
+    let Some(project) =
+        database::models::Project::get(project_id, pool, redis).await?
+    else {
+        return Err(ApiError::NotFound);
+    };
+
+    // 受限令牌无权访问该项目时按未登录处理，只能获取公开的构件
+    let anonymous = None;
+    let user_option = if check_pat_project(req, &project.inner).is_ok() {
+        user_option
+    } else {
+        &anonymous
+    };
+
+    if !is_visible_project(&project.inner, user_option, pool, false).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    let Some(version) = find_version(&project, vnum, pool, redis).await? else {
+        return Err(ApiError::NotFound);
+    };
+
+    if !is_visible_version(&version.inner, user_option, pool, redis).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok((project, version))
+}
+
+/// 版本依赖对应的构件
+struct ArtifactDependency {
+    artifact_id: String,
+    /// 只依赖项目时为空，表示任意版本
+    version: Option<String>,
+    optional: bool,
+}
+
+async fn get_artifact_dependencies(
+    version: &QueryVersion,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<Vec<ArtifactDependency>, ApiError> {
+    // 不兼容和内置的依赖不需要下载
+    let dependencies = version
+        .dependencies
+        .iter()
+        .filter(|x| {
+            x.dependency_type == "required" || x.dependency_type == "optional"
+        })
+        .collect::<Vec<_>>();
+
+    let version_ids = dependencies
+        .iter()
+        .filter_map(|x| x.version_id)
+        .collect::<Vec<_>>();
+    let versions =
+        database::models::Version::get_many(&version_ids, pool, redis).await?;
+
+    let mut artifacts = Vec::new();
+    let mut seen = HashSet::new();
+
+    for dependency in dependencies {
+        let (project_id, version_number) = if let Some(version) = dependency
+            .version_id
+            .and_then(|id| versions.iter().find(|x| x.inner.id == id))
+        {
+            (
+                version.inner.project_id,
+                Some(version.inner.version_number.clone()),
+            )
+        } else if let Some(project_id) = dependency.project_id {
+            (project_id, None)
+        } else {
+            continue;
+        };
+
+        if !seen.insert(project_id) {
+            continue;
+        }
+
+        artifacts.push(ArtifactDependency {
+            artifact_id: ProjectId::from(project_id).to_string(),
+            version: version_number,
+            optional: dependency.dependency_type == "optional",
+        });
+    }
+
+    Ok(artifacts)
 }
 
 #[get("maven/modrinth/{id}/maven-metadata.xml")]
@@ -85,16 +275,11 @@ pub async fn maven_metadata(
         return Err(ApiError::NotFound);
     };
 
-    let user_option = get_user_from_headers(
-        &req,
-        &**pool,
-        &redis,
-        &session_queue,
-        Some(&[Scopes::PROJECT_READ]),
-    )
-    .await
-    .map(|x| x.1)
-    .ok();
+    let mut user_option =
+        get_maven_user(&req, &pool, &redis, &session_queue).await;
+    if check_pat_project(&req, &project.inner).is_err() {
+        user_option = None;
+    }
 
     if !is_visible_project(&project.inner, &user_option, &pool, false).await? {
         return Err(ApiError::NotFound);
@@ -138,7 +323,7 @@ pub async fn maven_metadata(
     let project_id: ProjectId = project.inner.id.into();
 
     let respdata = Metadata {
-        group_id: "maven.modrinth".to_string(),
+        group_id: MAVEN_GROUP_ID.to_string(),
         artifact_id: project_id.to_string(),
         versioning: Versioning {
             latest: new_versions
@@ -237,6 +422,30 @@ async fn find_version(
         .cloned())
 }
 
+fn primary_file(version: &QueryVersion) -> Option<&QueryFile> {
+    version
+        .files
+        .iter()
+        .find(|x| x.primary)
+        .or_else(|| version.files.iter().last())
+}
+
+fn file_extension(filename: &str) -> Option<&str> {
+    filename.rsplit_once('.').map(|(_, ext)| ext)
+}
+
+/// 附加文件的 classifier，例如 `xxx-1.0-sources.jar` 为 `sources`
+fn file_classifier(file: &QueryFile) -> Option<&'static str> {
+    if file.primary {
+        return None;
+    }
+
+    let (stem, _) = file.filename.rsplit_once('.')?;
+    CLASSIFIERS
+        .into_iter()
+        .find(|classifier| stem.ends_with(&format!("-{classifier}")))
+}
+
 fn find_file<'a>(
     project_id: &str,
     vcoords: &str,
@@ -249,27 +458,220 @@ fn find_file<'a>(
         return Some(selected_file);
     }
 
-    // Minecraft mods are not going to be both a mod and a modpack, so this minecraft-specific handling is fine
-    // 由于可能有多种项目类型，返回第一个允许的匹配
-    let mut fileexts = vec![];
-    for project_type in version.project_types.iter() {
-        match project_type.as_str() {
-            "mod" => fileexts.push("jar"),
-            "modpack" => fileexts.push("mrpack"),
-            _ => (),
+    let rest = file.strip_prefix(&format!("{}-{}", project_id, vcoords))?;
+
+    // 主文件：{id}-{version}.{ext}，扩展名与上传的主文件一致，适用于所有项目类型
+    if let Some(ext) = rest.strip_prefix('.') {
+        return primary_file(version)
+            .filter(|x| file_extension(&x.filename) == Some(ext));
+    }
+
+    // 附加文件：{id}-{version}-{classifier}.{ext}
+    let (classifier, ext) = rest.strip_prefix('-')?.rsplit_once('.')?;
+    version.files.iter().find(|x| {
+        file_classifier(x) == Some(classifier)
+            && file_extension(&x.filename) == Some(ext)
+    })
+}
+
+/// Maven 坐标下的文件名，附加文件带上 classifier
+fn artifact_file_name(
+    project_id: &str,
+    vcoords: &str,
+    file: &QueryFile,
+) -> String {
+    let ext = file_extension(&file.filename).unwrap_or("jar");
+    match file_classifier(file) {
+        Some(classifier) => {
+            format!("{project_id}-{vcoords}-{classifier}.{ext}")
         }
+        None => format!("{project_id}-{vcoords}.{ext}"),
     }
+}
 
-    for fileext in fileexts {
-        if file == format!("{}-{}.{}", &project_id, &vcoords, fileext) {
-            return version
-                .files
-                .iter()
-                .find(|x| x.primary)
-                .or_else(|| version.files.iter().last());
+fn module_file(
+    project_id: &str,
+    vcoords: &str,
+    file: &QueryFile,
+) -> serde_json::Value {
+    let name = artifact_file_name(project_id, vcoords, file);
+    json!({
+        "name": name,
+        "url": name,
+        "size": file.size,
+        "sha1": file.hashes.get("sha1"),
+        "sha512": file.hashes.get("sha512"),
+    })
+}
+
+/// 生成 Gradle 模块元数据（`.module`）
+///
+/// 除默认的 `runtimeElements` 变体外，每个加载器和游戏版本的组合对应一个变体，
+/// Gradle 可以通过 `net.bbsmc.loader` 和 `net.bbsmc.game_version` 属性选择合适的变体。
+fn gradle_module(
+    project_id: &str,
+    vcoords: &str,
+    version: &QueryVersion,
+    dependencies: &[ArtifactDependency],
+) -> serde_json::Value {
+    let status = match version.inner.version_type.as_str() {
+        "release" => "release",
+        "beta" => "milestone",
+        _ => "integration",
+    };
+
+    let module_dependencies = dependencies
+        .iter()
+        .filter(|x| !x.optional)
+        .map(|x| {
+            json!({
+                "group": MAVEN_GROUP_ID,
+                "module": x.artifact_id,
+                "version": {
+                    "requires": x.version.as_deref().unwrap_or("+"),
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let game_versions = version
+        .version_fields
+        .iter()
+        .find_map(|v| MinecraftGameVersion::try_from_version_field(v).ok())
+        .map(|x| x.into_iter().map(|x| x.version).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let mut variants = Vec::new();
+
+    if let Some(file) = primary_file(version) {
+        let ext = file_extension(&file.filename).unwrap_or("jar");
+        let files = vec![module_file(project_id, vcoords, file)];
+
+        let attributes = json!({
+            "org.gradle.category": "library",
+            "org.gradle.dependency.bundling": "external",
+            "org.gradle.libraryelements": ext,
+            "org.gradle.usage": "java-runtime",
+        });
+
+        // 默认变体不带加载器和游戏版本属性，未声明这些属性的普通消费者会选中它
+        variants.push(json!({
+            "name": "runtimeElements",
+            "attributes": attributes,
+            "dependencies": module_dependencies,
+            "files": files,
+        }));
+
+        for loader in &version.loaders {
+            for game_version in &game_versions {
+                let mut attributes = attributes.clone();
+                attributes["net.bbsmc.loader"] = json!(loader);
+                attributes["net.bbsmc.game_version"] = json!(game_version);
+                let name = format!("{loader}-{game_version}-runtimeElements");
+
+                variants.push(json!({
+                    "name": name,
+                    "attributes": attributes,
+                    "dependencies": module_dependencies,
+                    "files": files,
+                }));
+            }
         }
     }
-    None
+
+    for file in &version.files {
+        let docs_type = match file_classifier(file) {
+            Some("sources") => "sources",
+            Some("javadoc") => "javadoc",
+            _ => continue,
+        };
+
+        variants.push(json!({
+            "name": format!("{docs_type}Elements"),
+            "attributes": {
+                "org.gradle.category": "documentation",
+                "org.gradle.dependency.bundling": "external",
+                "org.gradle.docstype": docs_type,
+                "org.gradle.usage": "java-runtime",
+            },
+            "files": [module_file(project_id, vcoords, file)],
+        }));
+    }
+
+    json!({
+        "formatVersion": "1.1",
+        "component": {
+            "group": MAVEN_GROUP_ID,
+            "module": project_id,
+            "version": vcoords,
+            "attributes": {
+                "org.gradle.status": status,
+            },
+        },
+        "variants": variants,
+    })
+}
+
+/// 动态生成的 `.pom` 与 `.module` 文件，返回内容类型和文件内容
+async fn metadata_file(
+    project_id: &str,
+    vnum: &str,
+    project: &QueryProject,
+    version: &QueryVersion,
+    file: &str,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<Option<(&'static str, String)>, ApiError> {
+    if file == format!("{project_id}-{vnum}.pom") {
+        let dependencies =
+            get_artifact_dependencies(version, pool, redis).await?;
+
+        let respdata = MavenPom {
+            schema_location:
+                "http://maven.apache.org/POM/4.0.0 http://maven.apache.org/xsd/maven-4.0.0.xsd"
+                    .to_string(),
+            xsi: "http://www.w3.org/2001/XMLSchema-instance".to_string(),
+            model_version: "4.0.0".to_string(),
+            group_id: MAVEN_GROUP_ID.to_string(),
+            artifact_id: project_id.to_string(),
+            version: vnum.to_string(),
+            name: project.inner.name.clone(),
+            description: project.inner.description.clone(),
+            dependencies: PomDependencies {
+                dependencies: dependencies
+                    .into_iter()
+                    .map(|x| PomDependency {
+                        group_id: MAVEN_GROUP_ID.to_string(),
+                        artifact_id: x.artifact_id,
+                        version: x.version.unwrap_or_else(|| "[0,)".to_string()),
+                        scope: "runtime".to_string(),
+                        optional: x.optional.then(|| "true".to_string()),
+                    })
+                    .collect(),
+            },
+        };
+
+        let pom = yaserde::ser::to_string(&respdata).map_err(ApiError::Xml)?;
+        let pom = match pom.split_once("?>") {
+            Some((declaration, body)) => {
+                format!("{declaration}?>\n{GRADLE_METADATA_MARKER}{body}")
+            }
+            None => format!("{GRADLE_METADATA_MARKER}\n{pom}"),
+        };
+
+        Ok(Some(("text/xml", pom)))
+    } else if file == format!("{project_id}-{vnum}.module") {
+        let dependencies =
+            get_artifact_dependencies(version, pool, redis).await?;
+        let module = gradle_module(project_id, vnum, version, &dependencies);
+
+        Ok(Some((
+            "application/vnd.org.gradle.module+json",
+            serde_json::to_string(&module)?,
+        )))
+    } else {
+        Ok(None)
+    }
 }
 
 #[route(
@@ -283,59 +685,65 @@ pub async fn version_file(
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
     session_queue: web::Data<AuthQueue>,
+    private_file_host: web::Data<Option<Arc<S3PrivateHost>>>,
 ) -> Result<HttpResponse, ApiError> {
     let (project_id, vnum, file) = params.into_inner();
-    let Some(project) =
-        database::models::Project::get(&project_id, &**pool, &redis).await?
-    else {
-        return Err(ApiError::NotFound);
-    };
+    let user_option = get_maven_user(&req, &pool, &redis, &session_queue).await;
+    let (project, version) =
+        get_artifact(&req, &project_id, &vnum, &user_option, &pool, &redis)
+            .await?;
 
-    let user_option = get_user_from_headers(
-        &req,
-        &**pool,
+    if let Some((content_type, body)) = metadata_file(
+        &project_id,
+        &vnum,
+        &project,
+        &version,
+        &file,
+        &pool,
         &redis,
-        &session_queue,
-        Some(&[Scopes::PROJECT_READ]),
     )
-    .await
-    .map(|x| x.1)
-    .ok();
+    .await?
+    {
+        return Ok(HttpResponse::Ok().content_type(content_type).body(body));
+    } else if let Some(selected_file) =
+        find_file(&project_id, &vnum, &version, &file)
+    {
+        if !selected_file.is_private {
+            return Ok(HttpResponse::TemporaryRedirect()
+                .append_header(("location", &*selected_file.url))
+                .body(""));
+        }
 
-    if !is_visible_project(&project.inner, &user_option, &pool, false).await? {
-        return Err(ApiError::NotFound);
-    }
+        // 付费资源：未登录时要求 Basic 认证，Gradle 收到质询后才会发送凭据
+        if user_option.is_none() {
+            return Ok(HttpResponse::Unauthorized()
+                .append_header(("WWW-Authenticate", "Basic realm=\"BBSMC\""))
+                .body(""));
+        }
 
-    let Some(version) = find_version(&project, &vnum, &pool, &redis).await?
-    else {
-        return Err(ApiError::NotFound);
-    };
+        check_pat_project(&req, &project.inner)?;
 
-    if !is_visible_version(&version.inner, &user_option, &pool, &redis).await? {
-        return Err(ApiError::NotFound);
-    }
+        if !check_private_file_access(
+            user_option.as_ref(),
+            project.inner.id,
+            &pool,
+        )
+        .await?
+        {
+            return Err(ApiError::CustomAuthentication(
+                "您需要购买此资源才能下载文件".to_string(),
+            ));
+        }
+
+        let presigned_url = presign_private_file(
+            &selected_file.url,
+            &selected_file.filename,
+            &private_file_host,
+        )
+        .await?;
 
-    if file == format!("{}-{}.pom", &project_id, &vnum) {
-        let respdata = MavenPom {
-            schema_location:
-                "http://maven.apache.org/POM/4.0.0 http://maven.apache.org/xsd/maven-4.0.0.xsd"
-                    .to_string(),
-            xsi: "http://www.w3.org/2001/XMLSchema-instance".to_string(),
-            model_version: "4.0.0".to_string(),
-            group_id: "maven.modrinth".to_string(),
-            artifact_id: project_id,
-            version: vnum,
-            name: project.inner.name,
-            description: project.inner.description,
-        };
-        return Ok(HttpResponse::Ok()
-            .content_type("text/xml")
-            .body(yaserde::ser::to_string(&respdata).map_err(ApiError::Xml)?));
-    } else if let Some(selected_file) =
-        find_file(&project_id, &vnum, &version, &file)
-    {
         return Ok(HttpResponse::TemporaryRedirect()
-            .append_header(("location", &*selected_file.url))
+            .append_header(("location", presigned_url))
             .body(""));
     }
 
@@ -351,34 +759,24 @@ pub async fn version_file_sha1(
     session_queue: web::Data<AuthQueue>,
 ) -> Result<HttpResponse, ApiError> {
     let (project_id, vnum, file) = params.into_inner();
-    let Some(project) =
-        database::models::Project::get(&project_id, &**pool, &redis).await?
-    else {
-        return Err(ApiError::NotFound);
-    };
+    let user_option = get_maven_user(&req, &pool, &redis, &session_queue).await;
+    let (project, version) =
+        get_artifact(&req, &project_id, &vnum, &user_option, &pool, &redis)
+            .await?;
 
-    let user_option = get_user_from_headers(
-        &req,
-        &**pool,
+    if let Some((_, body)) = metadata_file(
+        &project_id,
+        &vnum,
+        &project,
+        &version,
+        &file,
+        &pool,
         &redis,
-        &session_queue,
-        Some(&[Scopes::PROJECT_READ]),
     )
-    .await
-    .map(|x| x.1)
-    .ok();
-
-    if !is_visible_project(&project.inner, &user_option, &pool, false).await? {
-        return Err(ApiError::NotFound);
-    }
-
-    let Some(version) = find_version(&project, &vnum, &pool, &redis).await?
-    else {
-        return Err(ApiError::NotFound);
-    };
-
-    if !is_visible_version(&version.inner, &user_option, &pool, &redis).await? {
-        return Err(ApiError::NotFound);
+    .await?
+    {
+        return Ok(HttpResponse::Ok()
+            .body(format!("{:x}", sha1::Sha1::digest(body.as_bytes()))));
     }
 
     Ok(find_file(&project_id, &vnum, &version, &file)
@@ -396,34 +794,24 @@ pub async fn version_file_sha512(
     session_queue: web::Data<AuthQueue>,
 ) -> Result<HttpResponse, ApiError> {
     let (project_id, vnum, file) = params.into_inner();
-    let Some(project) =
-        database::models::Project::get(&project_id, &**pool, &redis).await?
-    else {
-        return Err(ApiError::NotFound);
-    };
+    let user_option = get_maven_user(&req, &pool, &redis, &session_queue).await;
+    let (project, version) =
+        get_artifact(&req, &project_id, &vnum, &user_option, &pool, &redis)
+            .await?;
 
-    let user_option = get_user_from_headers(
-        &req,
-        &**pool,
+    if let Some((_, body)) = metadata_file(
+        &project_id,
+        &vnum,
+        &project,
+        &version,
+        &file,
+        &pool,
         &redis,
-        &session_queue,
-        Some(&[Scopes::PROJECT_READ]),
     )
-    .await
-    .map(|x| x.1)
-    .ok();
-
-    if !is_visible_project(&project.inner, &user_option, &pool, false).await? {
-        return Err(ApiError::NotFound);
-    }
-
-    let Some(version) = find_version(&project, &vnum, &pool, &redis).await?
-    else {
-        return Err(ApiError::NotFound);
-    };
-
-    if !is_visible_version(&version.inner, &user_option, &pool, &redis).await? {
-        return Err(ApiError::NotFound);
+    .await?
+    {
+        return Ok(HttpResponse::Ok()
+            .body(format!("{:x}", sha2::Sha512::digest(body.as_bytes()))));
     }
 
     Ok(find_file(&project_id, &vnum, &version, &file)
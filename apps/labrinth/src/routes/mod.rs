@@ -23,6 +23,11 @@ mod updates;
 pub use self::not_found::not_found;
 
 pub fn root_config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope(".well-known")
+            .wrap(default_cors())
+            .configure(crate::auth::oauth::oidc::well_known_config),
+    );
     cfg.service(
         web::scope("maven")
             .wrap(default_cors())
@@ -146,8 +151,8 @@ pub enum ApiError {
     RateLimitError(u128, u32),
     #[error("与支付处理器交互时出错: {0}")]
     Stripe(#[from] stripe::StripeError),
-    #[error("阿里云短信服务错误: {0}")]
-    AliyunSms(#[from] alibaba_cloud_sdk_rust::error::AliyunSDKError),
+    #[error("短信服务错误: {0}")]
+    Sms(#[from] crate::sms::SmsError),
     #[error("您已达到上传图片的限制 ({0}/{1})")]
     ImageLimit(u32, u32),
     #[error(
@@ -190,7 +195,7 @@ impl ApiError {
                 ApiError::Io(..) => "io_error",
                 ApiError::RateLimitError(..) => "ratelimit_error",
                 ApiError::Stripe(..) => "stripe_error",
-                ApiError::AliyunSms(..) => "aliyun_sms_error",
+                ApiError::Sms(..) => "sms_error",
                 ApiError::ImageLimit(..) => "image_limit",
                 ApiError::RiskLimit(..) => "risk_limit",
             },
@@ -231,7 +236,7 @@ impl actix_web::ResponseError for ApiError {
             ApiError::Io(..) => StatusCode::BAD_REQUEST,
             ApiError::RateLimitError(..) => StatusCode::TOO_MANY_REQUESTS,
             ApiError::Stripe(..) => StatusCode::FAILED_DEPENDENCY,
-            ApiError::AliyunSms(..) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::Sms(..) => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::WikiBan(..) => StatusCode::BAD_REQUEST,
             ApiError::ImageLimit(..) => StatusCode::BAD_REQUEST,
             ApiError::RiskLimit(..) => StatusCode::BAD_REQUEST,
@@ -1,4 +1,5 @@
 use super::ApiError;
+use crate::auth::checks::check_pat_version_id;
 use crate::auth::get_user_from_headers;
 use crate::models::ids::VersionId;
 use crate::models::ids::base62_impl::parse_base62;
@@ -269,6 +270,9 @@ pub async fn admin_approve_link(
     // 转换ID并调用v3的approve函数
     let translation_version_id = VersionId(parse_base62(&info.0)?);
     let target_version_id = VersionId(parse_base62(&info.1)?);
+    // 受限令牌需要能同时操作翻译版本和目标版本所属的项目
+    check_pat_version_id(&req, translation_version_id, &pool, &redis).await?;
+    check_pat_version_id(&req, target_version_id, &pool, &redis).await?;
 
     v3::versions::approve_version_link(
         req,
@@ -310,6 +314,9 @@ pub async fn admin_reject_link(
     // 转换ID并调用v3的reject函数
     let translation_version_id = VersionId(parse_base62(&info.0)?);
     let target_version_id = VersionId(parse_base62(&info.1)?);
+    // 受限令牌需要能同时操作翻译版本和目标版本所属的项目
+    check_pat_version_id(&req, translation_version_id, &pool, &redis).await?;
+    check_pat_version_id(&req, target_version_id, &pool, &redis).await?;
 
     v3::versions::reject_version_link(
         req,
@@ -351,6 +358,9 @@ pub async fn admin_revoke_link(
     // 转换ID并调用v3的revoke函数
     let translation_version_id = VersionId(parse_base62(&info.0)?);
     let target_version_id = VersionId(parse_base62(&info.1)?);
+    // 受限令牌需要能同时操作翻译版本和目标版本所属的项目
+    check_pat_version_id(&req, translation_version_id, &pool, &redis).await?;
+    check_pat_version_id(&req, target_version_id, &pool, &redis).await?;
 
     v3::versions::revoke_version_link(
         req,
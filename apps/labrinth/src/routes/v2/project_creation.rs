@@ -188,6 +188,7 @@ pub async fn project_create(
                     v3::version_creation::InitialVersionData {
                         project_id: v.project_id,
                         file_parts: v.file_parts,
+                        upload_sessions: Vec::new(),
                         version_number: v.version_number,
                         version_title: v.version_title,
                         version_body: v.version_body,
@@ -204,6 +205,7 @@ pub async fn project_create(
                         fields,
                         disk_only: v.disk_only,
                         disk_urls: v.disk_urls,
+                        generate_pack_changelog: false,
                     }
                 })
                 .collect();
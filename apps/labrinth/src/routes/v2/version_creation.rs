@@ -78,6 +78,9 @@ pub struct InitialVersionData {
     pub software: bool,
     #[serde(default)]
     pub disk_only: bool,
+    // 根据整合包文件变化自动生成更新日志段落
+    #[serde(default)]
+    pub generate_pack_changelog: bool,
     pub disk_urls: Option<Vec<QueryDisk>>,
 }
 
@@ -262,6 +265,7 @@ pub async fn version_create(
                 Ok(v3::version_creation::InitialVersionData {
                     project_id: legacy_create.project_id,
                     file_parts: legacy_create.file_parts,
+                    upload_sessions: Vec::new(),
                     version_number: legacy_create.version_number,
                     version_title: legacy_create.version_title,
                     version_body: legacy_create.version_body,
@@ -278,6 +282,8 @@ pub async fn version_create(
                     fields,
                     disk_only: legacy_create.disk_only,
                     disk_urls: legacy_create.disk_urls,
+                    generate_pack_changelog: legacy_create
+                        .generate_pack_changelog,
                 })
             }
         },
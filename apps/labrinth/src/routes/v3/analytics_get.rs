@@ -1,5 +1,9 @@
 use super::ApiError;
+use crate::analytics::{AnalyticsBreakdown, AnalyticsStorage};
 use crate::database;
+use crate::database::models::analytics_report_item::{
+    AnalyticsReportSubscription, ReportFrequency,
+};
 use crate::database::redis::RedisPool;
 use crate::models::teams::ProjectPermissions;
 use crate::{
@@ -18,6 +22,7 @@ use sqlx::PgPool;
 use sqlx::postgres::types::PgInterval;
 use std::collections::HashMap;
 use std::convert::TryInto;
+use std::sync::Arc;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
@@ -30,7 +35,26 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                 "countries/downloads",
                 web::get().to(countries_downloads_get),
             )
-            .route("countries/views", web::get().to(countries_views_get)),
+            .route("countries/views", web::get().to(countries_views_get))
+            .route("downloads/versions", web::get().to(downloads_versions_get))
+            .route(
+                "downloads/game_versions",
+                web::get().to(downloads_game_versions_get),
+            )
+            .route("downloads/loaders", web::get().to(downloads_loaders_get))
+            .route(
+                "downloads/referrers",
+                web::get().to(downloads_referrers_get),
+            )
+            .route(
+                "downloads/launchers",
+                web::get().to(downloads_launchers_get),
+            )
+            .route("views/referrers", web::get().to(views_referrers_get))
+            .route("reports", web::get().to(reports_get))
+            .route("reports", web::post().to(report_create))
+            .route("reports/{id}", web::patch().to(report_edit))
+            .route("reports/{id}", web::delete().to(report_delete)),
     );
 }
 
@@ -69,7 +93,7 @@ pub struct FetchedPlaytime {
 }
 pub async fn playtimes_get(
     req: HttpRequest,
-    clickhouse: web::Data<clickhouse::Client>,
+    analytics: web::Data<Arc<dyn AnalyticsStorage + Send + Sync>>,
     data: web::Query<GetData>,
     session_queue: web::Data<AuthQueue>,
     pool: web::Data<PgPool>,
@@ -102,14 +126,14 @@ pub async fn playtimes_get(
         filter_allowed_ids(project_ids, user, &pool, &redis, None).await?;
 
     // 获取游玩时间
-    let playtimes = crate::clickhouse::fetch_playtimes(
-        project_ids.unwrap_or_default(),
-        start_date,
-        end_date,
-        resolution_minutes,
-        clickhouse.into_inner(),
-    )
-    .await?;
+    let playtimes = analytics
+        .fetch_playtimes(
+            project_ids.unwrap_or_default(),
+            start_date,
+            end_date,
+            resolution_minutes,
+        )
+        .await?;
 
     let mut hm = HashMap::new();
     for playtime in playtimes {
@@ -136,7 +160,7 @@ pub async fn playtimes_get(
 /// 可以使用项目 ID 列表或版本 ID 列表，但不能同时使用。未经授权的项目/版本将被过滤掉。
 pub async fn views_get(
     req: HttpRequest,
-    clickhouse: web::Data<clickhouse::Client>,
+    analytics: web::Data<Arc<dyn AnalyticsStorage + Send + Sync>>,
     data: web::Query<GetData>,
     session_queue: web::Data<AuthQueue>,
     pool: web::Data<PgPool>,
@@ -169,14 +193,14 @@ pub async fn views_get(
         filter_allowed_ids(project_ids, user, &pool, &redis, None).await?;
 
     // 获取浏览量
-    let views = crate::clickhouse::fetch_views(
-        project_ids.unwrap_or_default(),
-        start_date,
-        end_date,
-        resolution_minutes,
-        clickhouse.into_inner(),
-    )
-    .await?;
+    let views = analytics
+        .fetch_views(
+            project_ids.unwrap_or_default(),
+            start_date,
+            end_date,
+            resolution_minutes,
+        )
+        .await?;
 
     let mut hm = HashMap::new();
     for views in views {
@@ -203,7 +227,7 @@ pub async fn views_get(
 /// 可以使用项目 ID 列表或版本 ID 列表，但不能同时使用。未经授权的项目/版本将被过滤掉。
 pub async fn downloads_get(
     req: HttpRequest,
-    clickhouse: web::Data<clickhouse::Client>,
+    analytics: web::Data<Arc<dyn AnalyticsStorage + Send + Sync>>,
     data: web::Query<GetData>,
     session_queue: web::Data<AuthQueue>,
     pool: web::Data<PgPool>,
@@ -237,14 +261,14 @@ pub async fn downloads_get(
             .await?;
 
     // 获取下载量
-    let downloads = crate::clickhouse::fetch_downloads(
-        project_ids.unwrap_or_default(),
-        start_date,
-        end_date,
-        resolution_minutes,
-        clickhouse.into_inner(),
-    )
-    .await?;
+    let downloads = analytics
+        .fetch_downloads(
+            project_ids.unwrap_or_default(),
+            start_date,
+            end_date,
+            resolution_minutes,
+        )
+        .await?;
 
     let mut hm = HashMap::new();
     for downloads in downloads {
@@ -408,7 +432,7 @@ pub async fn revenue_get(
 /// 对于此端点，提供的日期是要聚合的范围，而不是要获取的特定日期
 pub async fn countries_downloads_get(
     req: HttpRequest,
-    clickhouse: web::Data<clickhouse::Client>,
+    analytics: web::Data<Arc<dyn AnalyticsStorage + Send + Sync>>,
     data: web::Query<GetData>,
     session_queue: web::Data<AuthQueue>,
     pool: web::Data<PgPool>,
@@ -440,13 +464,13 @@ pub async fn countries_downloads_get(
         filter_allowed_ids(project_ids, user, &pool, &redis, None).await?;
 
     // 获取国家数据
-    let countries = crate::clickhouse::fetch_countries_downloads(
-        project_ids.unwrap_or_default(),
-        start_date,
-        end_date,
-        clickhouse.into_inner(),
-    )
-    .await?;
+    let countries = analytics
+        .fetch_countries_downloads(
+            project_ids.unwrap_or_default(),
+            start_date,
+            end_date,
+        )
+        .await?;
 
     let mut hm = HashMap::new();
     for views in countries {
@@ -481,7 +505,7 @@ pub async fn countries_downloads_get(
 /// 对于此端点，提供的日期是要聚合的范围，而不是要获取的特定日期
 pub async fn countries_views_get(
     req: HttpRequest,
-    clickhouse: web::Data<clickhouse::Client>,
+    analytics: web::Data<Arc<dyn AnalyticsStorage + Send + Sync>>,
     data: web::Query<GetData>,
     session_queue: web::Data<AuthQueue>,
     pool: web::Data<PgPool>,
@@ -513,13 +537,13 @@ pub async fn countries_views_get(
         filter_allowed_ids(project_ids, user, &pool, &redis, None).await?;
 
     // 获取国家数据
-    let countries = crate::clickhouse::fetch_countries_views(
-        project_ids.unwrap_or_default(),
-        start_date,
-        end_date,
-        clickhouse.into_inner(),
-    )
-    .await?;
+    let countries = analytics
+        .fetch_countries_views(
+            project_ids.unwrap_or_default(),
+            start_date,
+            end_date,
+        )
+        .await?;
 
     let mut hm = HashMap::new();
     for views in countries {
@@ -540,6 +564,419 @@ pub async fn countries_views_get(
     Ok(HttpResponse::Ok().json(hm))
 }
 
+/// 获取一组项目按细分维度统计的下载量或浏览量
+/// 数据以哈希映射的形式返回，项目 ID 映射到维度值，再映射到每个时间段的数量。
+/// 例如（按游戏版本统计下载量）:
+/// {
+///     "4N1tEhnO": {
+///         "1.20.1": {
+///             "1692835200": 32
+///         }
+///    }
+///}
+/// 版本维度的键为版本 ID，来源维度的键为来源域名，未知的维度值为 ""。
+/// 参数与其他分析端点相同，未经授权的项目将被过滤掉。
+async fn breakdown_get(
+    breakdown: AnalyticsBreakdown,
+    req: HttpRequest,
+    analytics: web::Data<Arc<dyn AnalyticsStorage + Send + Sync>>,
+    data: web::Query<GetData>,
+    session_queue: web::Data<AuthQueue>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ANALYTICS]),
+    )
+    .await
+    .map(|x| x.1)?;
+
+    let project_ids = data
+        .project_ids
+        .as_ref()
+        .map(|ids| serde_json::from_str::<Vec<String>>(ids))
+        .transpose()?;
+
+    let start_date = data.start_date.unwrap_or(Utc::now() - Duration::weeks(2));
+    let end_date = data.end_date.unwrap_or(Utc::now());
+    let resolution_minutes = data.resolution_minutes.unwrap_or(60 * 24);
+
+    let project_ids =
+        filter_allowed_ids(project_ids, user, &pool, &redis, None).await?;
+
+    let rows = analytics
+        .fetch_breakdown(
+            breakdown,
+            project_ids.unwrap_or_default(),
+            start_date,
+            end_date,
+            resolution_minutes,
+        )
+        .await?;
+
+    let mut hm: HashMap<String, HashMap<String, HashMap<u32, u64>>> =
+        HashMap::new();
+    for row in rows {
+        let key = if breakdown == AnalyticsBreakdown::DownloadVersion {
+            row.key.parse::<u64>().map(to_base62).unwrap_or(row.key)
+        } else {
+            row.key
+        };
+
+        *hm.entry(to_base62(row.id))
+            .or_default()
+            .entry(key)
+            .or_default()
+            .entry(row.time)
+            .or_default() += row.total;
+    }
+
+    Ok(HttpResponse::Ok().json(hm))
+}
+
+/// 按版本统计下载量
+pub async fn downloads_versions_get(
+    req: HttpRequest,
+    analytics: web::Data<Arc<dyn AnalyticsStorage + Send + Sync>>,
+    data: web::Query<GetData>,
+    session_queue: web::Data<AuthQueue>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    breakdown_get(
+        AnalyticsBreakdown::DownloadVersion,
+        req,
+        analytics,
+        data,
+        session_queue,
+        pool,
+        redis,
+    )
+    .await
+}
+
+/// 按游戏版本统计下载量，支持多个游戏版本的下载会计入每个游戏版本
+pub async fn downloads_game_versions_get(
+    req: HttpRequest,
+    analytics: web::Data<Arc<dyn AnalyticsStorage + Send + Sync>>,
+    data: web::Query<GetData>,
+    session_queue: web::Data<AuthQueue>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    breakdown_get(
+        AnalyticsBreakdown::DownloadGameVersion,
+        req,
+        analytics,
+        data,
+        session_queue,
+        pool,
+        redis,
+    )
+    .await
+}
+
+/// 按加载器统计下载量，支持多个加载器的下载会计入每个加载器
+pub async fn downloads_loaders_get(
+    req: HttpRequest,
+    analytics: web::Data<Arc<dyn AnalyticsStorage + Send + Sync>>,
+    data: web::Query<GetData>,
+    session_queue: web::Data<AuthQueue>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    breakdown_get(
+        AnalyticsBreakdown::DownloadLoader,
+        req,
+        analytics,
+        data,
+        session_queue,
+        pool,
+        redis,
+    )
+    .await
+}
+
+/// 按来源域名统计下载量
+pub async fn downloads_referrers_get(
+    req: HttpRequest,
+    analytics: web::Data<Arc<dyn AnalyticsStorage + Send + Sync>>,
+    data: web::Query<GetData>,
+    session_queue: web::Data<AuthQueue>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    breakdown_get(
+        AnalyticsBreakdown::DownloadReferrer,
+        req,
+        analytics,
+        data,
+        session_queue,
+        pool,
+        redis,
+    )
+    .await
+}
+
+/// 按启动器统计下载量
+pub async fn downloads_launchers_get(
+    req: HttpRequest,
+    analytics: web::Data<Arc<dyn AnalyticsStorage + Send + Sync>>,
+    data: web::Query<GetData>,
+    session_queue: web::Data<AuthQueue>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    breakdown_get(
+        AnalyticsBreakdown::DownloadLauncher,
+        req,
+        analytics,
+        data,
+        session_queue,
+        pool,
+        redis,
+    )
+    .await
+}
+
+/// 按来源域名统计浏览量
+pub async fn views_referrers_get(
+    req: HttpRequest,
+    analytics: web::Data<Arc<dyn AnalyticsStorage + Send + Sync>>,
+    data: web::Query<GetData>,
+    session_queue: web::Data<AuthQueue>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    breakdown_get(
+        AnalyticsBreakdown::ViewReferrer,
+        req,
+        analytics,
+        data,
+        session_queue,
+        pool,
+        redis,
+    )
+    .await
+}
+
+/// 分析报告邮件订阅
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AnalyticsReportResponse {
+    pub id: i64,
+    pub frequency: ReportFrequency,
+    pub project_ids: Vec<ProjectId>,
+    pub last_sent: Option<DateTime<Utc>>,
+    pub created: DateTime<Utc>,
+}
+
+impl From<AnalyticsReportSubscription> for AnalyticsReportResponse {
+    fn from(subscription: AnalyticsReportSubscription) -> Self {
+        AnalyticsReportResponse {
+            id: subscription.id,
+            frequency: subscription.frequency,
+            project_ids: subscription
+                .project_ids
+                .into_iter()
+                .map(ProjectId::from)
+                .collect(),
+            last_sent: subscription.last_sent,
+            created: subscription.created,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CreateAnalyticsReport {
+    pub frequency: ReportFrequency,
+    pub project_ids: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EditAnalyticsReport {
+    pub frequency: Option<ReportFrequency>,
+    pub project_ids: Option<Vec<String>>,
+}
+
+// 单个用户最多可以创建的报告订阅数量
+const MAX_REPORTS_PER_USER: usize = 10;
+
+/// 过滤出用户有权查看分析数据的项目，一个都没有时返回错误
+async fn report_project_ids(
+    project_ids: Vec<String>,
+    user: crate::models::users::User,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<Vec<database::models::ProjectId>, ApiError> {
+    let project_ids =
+        filter_allowed_ids(Some(project_ids), user, pool, redis, Some(true))
+            .await?
+            .unwrap_or_default();
+
+    if project_ids.is_empty() {
+        return Err(ApiError::InvalidInput(
+            "至少需要选择一个有分析数据查看权限的项目".to_string(),
+        ));
+    }
+
+    Ok(project_ids.into_iter().map(|x| x.into()).collect())
+}
+
+/// 获取当前用户的分析报告邮件订阅
+pub async fn reports_get(
+    req: HttpRequest,
+    session_queue: web::Data<AuthQueue>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ANALYTICS]),
+    )
+    .await
+    .map(|x| x.1)?;
+
+    let reports =
+        AnalyticsReportSubscription::get_user(user.id.into(), &**pool)
+            .await?
+            .into_iter()
+            .map(AnalyticsReportResponse::from)
+            .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(reports))
+}
+
+/// 创建分析报告邮件订阅，按周或按月发送所选项目的数据汇总
+pub async fn report_create(
+    req: HttpRequest,
+    body: web::Json<CreateAnalyticsReport>,
+    session_queue: web::Data<AuthQueue>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ANALYTICS, Scopes::USER_WRITE]),
+    )
+    .await
+    .map(|x| x.1)?;
+
+    let existing =
+        AnalyticsReportSubscription::get_user(user.id.into(), &**pool).await?;
+    if existing.len() >= MAX_REPORTS_PER_USER {
+        return Err(ApiError::InvalidInput(format!(
+            "最多只能创建 {MAX_REPORTS_PER_USER} 个分析报告订阅"
+        )));
+    }
+
+    let body = body.into_inner();
+    let project_ids =
+        report_project_ids(body.project_ids, user.clone(), &pool, &redis)
+            .await?;
+
+    let mut transaction = pool.begin().await?;
+    let id = AnalyticsReportSubscription::insert(
+        user.id.into(),
+        body.frequency,
+        &project_ids,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    let report = AnalyticsReportSubscription::get(id, &**pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(HttpResponse::Ok().json(AnalyticsReportResponse::from(report)))
+}
+
+/// 修改分析报告邮件订阅的频率或项目
+pub async fn report_edit(
+    req: HttpRequest,
+    info: web::Path<(i64,)>,
+    body: web::Json<EditAnalyticsReport>,
+    session_queue: web::Data<AuthQueue>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ANALYTICS, Scopes::USER_WRITE]),
+    )
+    .await
+    .map(|x| x.1)?;
+
+    let report = AnalyticsReportSubscription::get(info.into_inner().0, &**pool)
+        .await?
+        .filter(|x| x.user_id == user.id.into())
+        .ok_or(ApiError::NotFound)?;
+
+    let body = body.into_inner();
+    let frequency = body.frequency.unwrap_or(report.frequency);
+    let project_ids = match body.project_ids {
+        Some(project_ids) => {
+            report_project_ids(project_ids, user, &pool, &redis).await?
+        }
+        None => report.project_ids,
+    };
+
+    let mut transaction = pool.begin().await?;
+    AnalyticsReportSubscription::update(
+        report.id,
+        frequency,
+        &project_ids,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+/// 删除分析报告邮件订阅
+pub async fn report_delete(
+    req: HttpRequest,
+    info: web::Path<(i64,)>,
+    session_queue: web::Data<AuthQueue>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ANALYTICS, Scopes::USER_WRITE]),
+    )
+    .await
+    .map(|x| x.1)?;
+
+    let report = AnalyticsReportSubscription::get(info.into_inner().0, &**pool)
+        .await?
+        .filter(|x| x.user_id == user.id.into())
+        .ok_or(ApiError::NotFound)?;
+
+    let mut transaction = pool.begin().await?;
+    AnalyticsReportSubscription::remove(report.id, &mut transaction).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
 fn condense_countries(countries: HashMap<String, u64>) -> HashMap<String, u64> {
     // 每个国家（视图或下载）低于 '15' 的应缩减为 'XX'
     let mut hm = HashMap::new();
@@ -557,17 +994,17 @@ fn condense_countries(countries: HashMap<String, u64>) -> HashMap<String, u64> {
     hm
 }
 
-async fn filter_allowed_ids(
+pub(crate) async fn filter_allowed_ids(
     mut project_ids: Option<Vec<String>>,
     user: crate::models::users::User,
-    pool: &web::Data<PgPool>,
+    pool: &PgPool,
     redis: &RedisPool,
     remove_defaults: Option<bool>,
 ) -> Result<Option<Vec<ProjectId>>, ApiError> {
     // 如果未提供项目 ID 或版本 ID，则默认使用用户有权访问的所有项目
     if project_ids.is_none() && !remove_defaults.unwrap_or(false) {
         project_ids = Some(
-            user_item::User::get_projects(user.id.into(), &***pool, redis)
+            user_item::User::get_projects(user.id.into(), pool, redis)
                 .await?
                 .into_iter()
                 .map(|x| ProjectId::from(x).to_string())
@@ -578,12 +1015,9 @@ async fn filter_allowed_ids(
     // 将字符串列表转换为项目 ID 或版本 ID 列表
     // - 过滤掉未经授权的项目/版本
     let project_ids = if let Some(project_strings) = project_ids {
-        let projects_data = database::models::Project::get_many(
-            &project_strings,
-            &***pool,
-            redis,
-        )
-        .await?;
+        let projects_data =
+            database::models::Project::get_many(&project_strings, pool, redis)
+                .await?;
 
         let team_ids = projects_data
             .iter()
@@ -591,7 +1025,7 @@ async fn filter_allowed_ids(
             .collect::<Vec<database::models::TeamId>>();
         let team_members =
             database::models::TeamMember::get_from_team_full_many(
-                &team_ids, &***pool, redis,
+                &team_ids, pool, redis,
             )
             .await?;
 
@@ -601,7 +1035,7 @@ async fn filter_allowed_ids(
             .collect::<Vec<database::models::OrganizationId>>();
         let organizations = database::models::Organization::get_many_ids(
             &organization_ids,
-            &***pool,
+            pool,
             redis,
         )
         .await?;
@@ -613,7 +1047,7 @@ async fn filter_allowed_ids(
         let organization_team_members =
             database::models::TeamMember::get_from_team_full_many(
                 &organization_team_ids,
-                &***pool,
+                pool,
                 redis,
             )
             .await?;
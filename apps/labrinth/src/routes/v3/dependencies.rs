@@ -0,0 +1,734 @@
+//! 依赖解析
+//!
+//! 根据目标游戏版本和加载器递归解析项目或版本的必需依赖，
+//! 为每个项目选择兼容的版本，生成类似锁文件的结果，
+//! 并报告缺少版本、不兼容、版本冲突和循环依赖等问题。
+
+use super::ApiError;
+use super::version_file::check_private_file_access;
+use crate::auth::checks::{is_visible_project, is_visible_version};
+use crate::auth::get_user_from_headers;
+use crate::database;
+use crate::database::models::version_item::QueryVersion;
+use crate::database::redis::RedisPool;
+use crate::models::ids::{ProjectId, VersionId};
+use crate::models::pats::Scopes;
+use crate::models::projects::{DependencyType, VersionType};
+use crate::models::users::User;
+use crate::queue::session::AuthQueue;
+use crate::util::validate::validation_errors_to_string;
+use actix_web::{HttpRequest, HttpResponse, web};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet, VecDeque};
+use validator::Validate;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("dependencies")
+            .route("resolve", web::post().to(resolve_dependencies)),
+    );
+}
+
+// 单次解析最多包含的项目数量
+const MAX_RESOLVED_PROJECTS: usize = 512;
+
+#[derive(Serialize, Deserialize, Validate)]
+pub struct ResolveDependencies {
+    /// 项目 ID 或 slug，会为其选择最新的兼容版本
+    #[validate(length(max = 256))]
+    #[serde(default)]
+    pub projects: Vec<String>,
+    /// 指定的版本，不会替换为其他版本
+    #[validate(length(max = 256))]
+    #[serde(default)]
+    pub versions: Vec<VersionId>,
+    pub game_version: String,
+    pub loader: String,
+    /// 可以选择的版本类型，默认全部
+    pub version_types: Option<Vec<VersionType>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// 项目或版本不存在、不可见，或者是无法解析的外部文件
+    NotFound,
+    /// 项目没有支持目标游戏版本和加载器的版本
+    MissingVersion,
+    /// 指定的版本不支持目标环境，或与其他依赖声明了不兼容
+    Incompatible,
+    /// 同一项目被要求使用不同的版本
+    VersionConflict,
+    /// 依赖之间存在循环
+    Cycle,
+    /// 付费资源尚未购买
+    PurchaseRequired,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DependencyConflict {
+    pub kind: ConflictKind,
+    pub project_id: Option<ProjectId>,
+    pub version_id: Option<VersionId>,
+    pub file_name: Option<String>,
+    pub required_by: Option<ProjectId>,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LockedFile {
+    pub filename: String,
+    /// 付费文件为需要登录的下载接口，无权访问时为空
+    pub url: Option<String>,
+    pub hashes: HashMap<String, String>,
+    pub size: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LockedDependency {
+    pub project_id: ProjectId,
+    pub project_slug: Option<String>,
+    pub project_title: String,
+    pub version_id: VersionId,
+    pub version_number: String,
+    /// 依赖该项目的项目，为空表示由请求直接指定
+    pub required_by: Vec<ProjectId>,
+    pub file: Option<LockedFile>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ResolvedDependencies {
+    pub game_version: String,
+    pub loader: String,
+    pub dependencies: Vec<LockedDependency>,
+    pub conflicts: Vec<DependencyConflict>,
+}
+
+#[derive(Clone, Copy)]
+enum Requirement {
+    Project(database::models::ProjectId),
+    Version(database::models::VersionId),
+}
+
+struct Resolved {
+    project: database::models::project_item::QueryProject,
+    version: QueryVersion,
+    /// 是否使用的是指定的版本
+    pinned: bool,
+    required_by: Vec<database::models::ProjectId>,
+}
+
+/// 一轮解析的结果
+struct Resolution {
+    resolved: Vec<Resolved>,
+    resolved_index: HashMap<database::models::ProjectId, usize>,
+    /// 依赖图：项目 -> 它必需的项目
+    edges:
+        HashMap<database::models::ProjectId, Vec<database::models::ProjectId>>,
+    conflicts: Vec<DependencyConflict>,
+    /// 项目已按最新版本解析后才遇到的指定版本
+    late_pins: HashMap<database::models::ProjectId, QueryVersion>,
+}
+
+impl ResolveDependencies {
+    /// 版本是否支持目标加载器、游戏版本和版本类型
+    fn is_compatible(&self, version: &QueryVersion) -> bool {
+        if !version.loaders.contains(&self.loader) {
+            return false;
+        }
+
+        if let Some(version_types) = &self.version_types
+            && !version_types
+                .iter()
+                .any(|x| x.as_str() == version.inner.version_type)
+        {
+            return false;
+        }
+
+        let game_version = serde_json::Value::String(self.game_version.clone());
+        version
+            .version_fields
+            .iter()
+            .find(|x| x.field_name == "game_versions")
+            .is_none_or(|x| x.value.contains_json_value(&game_version))
+    }
+}
+
+/// 解析请求中项目和版本的必需依赖，返回锁文件结构
+pub async fn resolve_dependencies(
+    req: HttpRequest,
+    body: web::Json<ResolveDependencies>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    body.validate().map_err(|err| {
+        ApiError::Validation(validation_errors_to_string(err, None))
+    })?;
+
+    let user_option = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ, Scopes::VERSION_READ]),
+    )
+    .await
+    .map(|x| x.1)
+    .ok();
+
+    let mut conflicts = Vec::new();
+    let mut roots = Vec::new();
+
+    let projects =
+        database::models::Project::get_many(&body.projects, &**pool, &redis)
+            .await?;
+    for requested in &body.projects {
+        let found = projects.iter().find(|x| {
+            ProjectId::from(x.inner.id).to_string() == *requested
+                || x.inner
+                    .slug
+                    .as_deref()
+                    .is_some_and(|slug| slug.eq_ignore_ascii_case(requested))
+        });
+
+        match found {
+            Some(project) => roots.push(Requirement::Project(project.inner.id)),
+            None => conflicts.push(DependencyConflict {
+                kind: ConflictKind::NotFound,
+                project_id: None,
+                version_id: None,
+                file_name: None,
+                required_by: None,
+                message: format!("项目 {requested} 不存在"),
+            }),
+        }
+    }
+    for version_id in &body.versions {
+        roots.push(Requirement::Version((*version_id).into()));
+    }
+
+    // 指定的版本优先于按最新版本选择的结果。项目已按最新版本解析后才遇到
+    // 指定版本时，记录该版本并重新解析，使结果与遍历顺序无关
+    let mut pins = HashMap::new();
+    let Resolution {
+        resolved,
+        resolved_index,
+        edges,
+        conflicts: resolution_conflicts,
+        ..
+    } = loop {
+        let resolution =
+            resolve_pass(&body, &roots, &pins, &user_option, &pool, &redis)
+                .await?;
+        if resolution.late_pins.is_empty() {
+            break resolution;
+        }
+        pins.extend(resolution.late_pins);
+    };
+    conflicts.extend(resolution_conflicts);
+
+    conflicts.extend(find_incompatibilities(&resolved, &resolved_index));
+    conflicts.extend(find_cycles(&edges, &resolved, &resolved_index));
+
+    let self_addr = dotenvy::var("SELF_ADDR")?;
+    let mut dependencies = Vec::with_capacity(resolved.len());
+    for entry in resolved {
+        let file = entry
+            .version
+            .files
+            .iter()
+            .find(|x| x.primary)
+            .or_else(|| entry.version.files.first());
+
+        let file = match file {
+            Some(file) => {
+                let url = if file.is_private {
+                    locked_private_url(
+                        file,
+                        &entry,
+                        user_option.as_ref(),
+                        &self_addr,
+                        &pool,
+                        &mut conflicts,
+                    )
+                    .await?
+                } else {
+                    Some(file.url.clone())
+                };
+
+                Some(LockedFile {
+                    filename: file.filename.clone(),
+                    url,
+                    hashes: file.hashes.clone(),
+                    size: file.size,
+                })
+            }
+            None => None,
+        };
+
+        dependencies.push(LockedDependency {
+            project_id: entry.project.inner.id.into(),
+            project_slug: entry.project.inner.slug.clone(),
+            project_title: entry.project.inner.name.clone(),
+            version_id: entry.version.inner.id.into(),
+            version_number: entry.version.inner.version_number.clone(),
+            required_by: entry
+                .required_by
+                .into_iter()
+                .map(ProjectId::from)
+                .collect(),
+            file,
+        });
+    }
+
+    Ok(HttpResponse::Ok().json(ResolvedDependencies {
+        game_version: body.game_version.clone(),
+        loader: body.loader.clone(),
+        dependencies,
+        conflicts,
+    }))
+}
+
+/// 按广度优先解析一轮依赖，`pins` 中的项目使用记录的版本
+async fn resolve_pass(
+    body: &ResolveDependencies,
+    roots: &[Requirement],
+    pins: &HashMap<database::models::ProjectId, QueryVersion>,
+    user_option: &Option<User>,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<Resolution, ApiError> {
+    let mut conflicts = Vec::new();
+    let mut resolved: Vec<Resolved> = Vec::new();
+    let mut resolved_index: HashMap<database::models::ProjectId, usize> =
+        HashMap::new();
+    let mut edges: HashMap<
+        database::models::ProjectId,
+        Vec<database::models::ProjectId>,
+    > = HashMap::new();
+    let mut late_pins = HashMap::new();
+
+    let mut queue = roots.iter().map(|x| (*x, None)).collect::<VecDeque<_>>();
+
+    while let Some((requirement, required_by)) = queue.pop_front() {
+        let required_by_id = required_by.map(ProjectId::from);
+
+        let (project_id, pinned) = match requirement {
+            Requirement::Project(project_id) => (project_id, None),
+            Requirement::Version(version_id) => {
+                let version = match database::models::Version::get(
+                    version_id, pool, redis,
+                )
+                .await?
+                {
+                    Some(version)
+                        if is_visible_version(
+                            &version.inner,
+                            user_option,
+                            pool,
+                            redis,
+                        )
+                        .await? =>
+                    {
+                        version
+                    }
+                    _ => {
+                        conflicts.push(DependencyConflict {
+                            kind: ConflictKind::NotFound,
+                            project_id: None,
+                            version_id: Some(version_id.into()),
+                            file_name: None,
+                            required_by: required_by_id,
+                            message: format!(
+                                "版本 {} 不存在",
+                                VersionId::from(version_id)
+                            ),
+                        });
+                        continue;
+                    }
+                };
+                (version.inner.project_id, Some(version))
+            }
+        };
+
+        if let Some(required_by) = required_by {
+            edges.entry(required_by).or_default().push(project_id);
+        }
+
+        // 项目已经解析过，只记录依赖关系，并检查指定的版本是否一致
+        if let Some(index) = resolved_index.get(&project_id) {
+            let existing = &mut resolved[*index];
+            if let Some(required_by) = required_by
+                && !existing.required_by.contains(&required_by)
+            {
+                existing.required_by.push(required_by);
+            }
+            if let Some(pinned) = pinned
+                && pinned.inner.id != existing.version.inner.id
+            {
+                if existing.pinned {
+                    conflicts.push(version_conflict(
+                        &existing.project.inner.name,
+                        &pinned,
+                        &existing.version,
+                        required_by_id,
+                    ));
+                } else {
+                    late_pins.entry(project_id).or_insert(pinned);
+                }
+            }
+            continue;
+        }
+
+        let project =
+            match database::models::Project::get_id(project_id, pool, redis)
+                .await?
+            {
+                Some(project)
+                    if is_visible_project(
+                        &project.inner,
+                        user_option,
+                        pool,
+                        false,
+                    )
+                    .await? =>
+                {
+                    project
+                }
+                _ => {
+                    conflicts.push(DependencyConflict {
+                        kind: ConflictKind::NotFound,
+                        project_id: Some(project_id.into()),
+                        version_id: None,
+                        file_name: None,
+                        required_by: required_by_id,
+                        message: format!(
+                            "项目 {} 不存在",
+                            ProjectId::from(project_id)
+                        ),
+                    });
+                    continue;
+                }
+            };
+
+        // 已记录指定版本的项目始终使用记录的版本
+        let pinned = match (pins.get(&project_id), pinned) {
+            (Some(pin), Some(version)) => {
+                if pin.inner.id != version.inner.id {
+                    conflicts.push(version_conflict(
+                        &project.inner.name,
+                        &version,
+                        pin,
+                        required_by_id,
+                    ));
+                }
+                Some(pin.clone())
+            }
+            (Some(pin), None) => Some(pin.clone()),
+            (None, pinned) => pinned,
+        };
+        let is_pinned = pinned.is_some();
+
+        let version = match pinned {
+            Some(version) => {
+                if !body.is_compatible(&version) {
+                    conflicts.push(DependencyConflict {
+                        kind: ConflictKind::Incompatible,
+                        project_id: Some(project_id.into()),
+                        version_id: Some(version.inner.id.into()),
+                        file_name: None,
+                        required_by: required_by_id,
+                        message: format!(
+                            "{} {} 不支持 {} {}",
+                            project.inner.name,
+                            version.inner.version_number,
+                            body.loader,
+                            body.game_version
+                        ),
+                    });
+                    continue;
+                }
+                version
+            }
+            None => {
+                let mut candidates = database::models::Version::get_many(
+                    &project.versions,
+                    pool,
+                    redis,
+                )
+                .await?
+                .into_iter()
+                .filter(|x| body.is_compatible(x))
+                .collect::<Vec<_>>();
+                candidates.sort();
+
+                let mut selected = None;
+                while let Some(candidate) = candidates.pop() {
+                    if is_visible_version(
+                        &candidate.inner,
+                        user_option,
+                        pool,
+                        redis,
+                    )
+                    .await?
+                    {
+                        selected = Some(candidate);
+                        break;
+                    }
+                }
+
+                match selected {
+                    Some(version) => version,
+                    None => {
+                        conflicts.push(DependencyConflict {
+                            kind: ConflictKind::MissingVersion,
+                            project_id: Some(project_id.into()),
+                            version_id: None,
+                            file_name: None,
+                            required_by: required_by_id,
+                            message: format!(
+                                "{} 没有支持 {} {} 的版本",
+                                project.inner.name,
+                                body.loader,
+                                body.game_version
+                            ),
+                        });
+                        continue;
+                    }
+                }
+            }
+        };
+
+        if resolved.len() >= MAX_RESOLVED_PROJECTS {
+            return Err(ApiError::InvalidInput(format!(
+                "依赖数量超过了 {MAX_RESOLVED_PROJECTS} 个项目的上限"
+            )));
+        }
+
+        for dependency in &version.dependencies {
+            if dependency.dependency_type != DependencyType::Required.as_str() {
+                continue;
+            }
+
+            if let Some(version_id) = dependency.version_id {
+                queue.push_back((
+                    Requirement::Version(version_id),
+                    Some(project_id),
+                ));
+            } else if let Some(dependency_project_id) = dependency.project_id {
+                queue.push_back((
+                    Requirement::Project(dependency_project_id),
+                    Some(project_id),
+                ));
+            } else if let Some(file_name) = &dependency.file_name {
+                conflicts.push(DependencyConflict {
+                    kind: ConflictKind::NotFound,
+                    project_id: None,
+                    version_id: None,
+                    file_name: Some(file_name.clone()),
+                    required_by: Some(project_id.into()),
+                    message: format!(
+                        "{} 依赖的外部文件 {} 无法解析",
+                        project.inner.name, file_name
+                    ),
+                });
+            }
+        }
+
+        resolved_index.insert(project_id, resolved.len());
+        resolved.push(Resolved {
+            project,
+            version,
+            pinned: is_pinned,
+            required_by: required_by.into_iter().collect(),
+        });
+    }
+
+    Ok(Resolution {
+        resolved,
+        resolved_index,
+        edges,
+        conflicts,
+        late_pins,
+    })
+}
+
+/// 同一项目被指定了与已选版本不同的版本
+fn version_conflict(
+    project_name: &str,
+    pinned: &QueryVersion,
+    selected: &QueryVersion,
+    required_by: Option<ProjectId>,
+) -> DependencyConflict {
+    DependencyConflict {
+        kind: ConflictKind::VersionConflict,
+        project_id: Some(pinned.inner.project_id.into()),
+        version_id: Some(pinned.inner.id.into()),
+        file_name: None,
+        required_by,
+        message: format!(
+            "{} 需要版本 {}，但已选择版本 {}",
+            project_name,
+            pinned.inner.version_number,
+            selected.inner.version_number
+        ),
+    }
+}
+
+/// 付费文件只向有权访问的用户提供下载接口地址，下载时再生成临时链接
+async fn locked_private_url(
+    file: &database::models::version_item::QueryFile,
+    entry: &Resolved,
+    user: Option<&User>,
+    self_addr: &str,
+    pool: &PgPool,
+    conflicts: &mut Vec<DependencyConflict>,
+) -> Result<Option<String>, ApiError> {
+    if check_private_file_access(user, entry.project.inner.id, pool).await?
+        && let Some(sha1) = file.hashes.get("sha1")
+    {
+        return Ok(Some(format!(
+            "{self_addr}/v3/version_file/{sha1}/download?algorithm=sha1"
+        )));
+    }
+
+    conflicts.push(DependencyConflict {
+        kind: ConflictKind::PurchaseRequired,
+        project_id: Some(entry.project.inner.id.into()),
+        version_id: Some(entry.version.inner.id.into()),
+        file_name: Some(file.filename.clone()),
+        required_by: None,
+        message: format!(
+            "{} 是付费资源，需要购买后才能下载",
+            entry.project.inner.name
+        ),
+    });
+
+    Ok(None)
+}
+
+/// 已选版本声明的不兼容依赖是否出现在解析结果中
+fn find_incompatibilities(
+    resolved: &[Resolved],
+    resolved_index: &HashMap<database::models::ProjectId, usize>,
+) -> Vec<DependencyConflict> {
+    let mut conflicts = Vec::new();
+
+    for entry in resolved {
+        for dependency in &entry.version.dependencies {
+            if dependency.dependency_type
+                != DependencyType::Incompatible.as_str()
+            {
+                continue;
+            }
+
+            let other = dependency
+                .project_id
+                .and_then(|x| resolved_index.get(&x))
+                .map(|x| &resolved[*x])
+                .or_else(|| {
+                    dependency.version_id.and_then(|version_id| {
+                        resolved
+                            .iter()
+                            .find(|x| x.version.inner.id == version_id)
+                    })
+                });
+
+            // 声明的是具体版本时，只有选中了该版本才算冲突
+            if let Some(other) = other
+                && dependency
+                    .version_id
+                    .is_none_or(|x| x == other.version.inner.id)
+            {
+                conflicts.push(DependencyConflict {
+                    kind: ConflictKind::Incompatible,
+                    project_id: Some(other.project.inner.id.into()),
+                    version_id: Some(other.version.inner.id.into()),
+                    file_name: None,
+                    required_by: Some(entry.project.inner.id.into()),
+                    message: format!(
+                        "{} {} 与 {} {} 不兼容",
+                        entry.project.inner.name,
+                        entry.version.inner.version_number,
+                        other.project.inner.name,
+                        other.version.inner.version_number
+                    ),
+                });
+            }
+        }
+    }
+
+    conflicts
+}
+
+/// 在依赖图中查找循环，每个循环报告一次
+fn find_cycles(
+    edges: &HashMap<
+        database::models::ProjectId,
+        Vec<database::models::ProjectId>,
+    >,
+    resolved: &[Resolved],
+    resolved_index: &HashMap<database::models::ProjectId, usize>,
+) -> Vec<DependencyConflict> {
+    fn visit(
+        node: database::models::ProjectId,
+        edges: &HashMap<
+            database::models::ProjectId,
+            Vec<database::models::ProjectId>,
+        >,
+        stack: &mut Vec<database::models::ProjectId>,
+        visited: &mut HashSet<database::models::ProjectId>,
+        cycles: &mut Vec<Vec<database::models::ProjectId>>,
+    ) {
+        if let Some(position) = stack.iter().position(|x| *x == node) {
+            cycles.push(stack[position..].to_vec());
+            return;
+        }
+        if !visited.insert(node) {
+            return;
+        }
+
+        stack.push(node);
+        for next in edges.get(&node).into_iter().flatten() {
+            visit(*next, edges, stack, visited, cycles);
+        }
+        stack.pop();
+    }
+
+    let mut cycles = Vec::new();
+    let mut visited = HashSet::new();
+    for entry in resolved {
+        visit(
+            entry.project.inner.id,
+            edges,
+            &mut Vec::new(),
+            &mut visited,
+            &mut cycles,
+        );
+    }
+
+    let name = |id: &database::models::ProjectId| {
+        resolved_index
+            .get(id)
+            .map(|x| resolved[*x].project.inner.name.clone())
+            .unwrap_or_else(|| ProjectId::from(*id).to_string())
+    };
+
+    cycles
+        .into_iter()
+        .map(|cycle| DependencyConflict {
+            kind: ConflictKind::Cycle,
+            project_id: cycle.first().map(|x| (*x).into()),
+            version_id: None,
+            file_name: None,
+            required_by: cycle.last().map(|x| (*x).into()),
+            message: format!(
+                "循环依赖：{} -> {}",
+                cycle.iter().map(name).collect::<Vec<_>>().join(" -> "),
+                cycle.first().map(name).unwrap_or_default()
+            ),
+        })
+        .collect()
+}
@@ -1,27 +1,46 @@
+use crate::auth::checks::{check_pat_project_id, is_visible_project};
 use crate::auth::{
     AuthenticationError, check_forum_ban, get_user_from_headers,
 };
+use crate::captcha::Captcha;
 use crate::database::models::forum::PostBuilder;
-use crate::database::models::forum::{Discussion, PostIndex};
+use crate::database::models::forum::{
+    Discussion, ForumEditHistory, PostIndex, PostQuery,
+};
 use crate::database::models::ids::{DiscussionId, PostId};
 use crate::database::models::notification_item::NotificationBuilder;
+use crate::database::models::reaction_item::{
+    Reaction, ReactionTarget, allowed_reactions, is_allowed_reaction,
+};
+use crate::database::models::subscription_item::{
+    Subscription, SubscriptionEvent, SubscriptionEventType, SubscriptionTarget,
+};
 use crate::database::redis::RedisPool;
-use crate::models::ids::base62_impl::parse_base62;
+use crate::models::ids::base62_impl::{parse_base62, to_base62};
 use crate::models::notifications::NotificationBody;
 use crate::models::pats::Scopes;
+use crate::models::teams::ProjectPermissions;
+use crate::models::users::User;
+use crate::models::v3::issues::ReactionRequest;
 use crate::queue::session::AuthQueue;
+use crate::util::mentions::{
+    MentionSource, MentionVisibility, notify_edited_mentions, notify_mentions,
+};
 
 use crate::database::models::UserId;
 use crate::util::validate::validation_errors_to_string;
 use crate::{
     database,
-    models::v3::forum::{ForumResponse, PostResponse, PostsQueryParams},
+    models::v3::forum::{
+        ForumEditHistoryResponse, ForumResponse, PostResponse, PostsQueryParams,
+    },
     routes::ApiError,
 };
 use actix_web::{HttpRequest, HttpResponse, web};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::PgPool;
+use std::sync::Arc;
 use validator::Validate;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
@@ -31,12 +50,22 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("", web::post().to(forum_create))
             .service(
                 web::scope("posts")
-                    .route("{id}", web::delete().to(post_delete)),
+                    .route("{id}", web::delete().to(post_delete))
+                    .route("{id}", web::patch().to(post_edit))
+                    .route("{id}/history", web::get().to(post_history_get))
+                    .route("{id}/reactions", web::post().to(post_reaction_add))
+                    .route(
+                        "{id}/reactions/{reaction}",
+                        web::delete().to(post_reaction_remove),
+                    ),
             )
+            .route("reactions", web::get().to(reactions_get))
             .route("{id}", web::get().to(forum_get))
             .route("{id}", web::delete().to(forum_delete))
             .route("{id}", web::patch().to(forum_edit))
             .route("{type}/lists", web::get().to(forums_get))
+            .route("{id}/history", web::get().to(forum_history_get))
+            .route("{id}/accept", web::post().to(forum_accept_post))
             .route("{id}/posts", web::get().to(posts_get))
             .route("{id}/post", web::post().to(posts_post)),
     );
@@ -51,6 +80,9 @@ pub struct ForumRequest {
 
     // 限制只能 chat 和 notice
     pub forum_type: String,
+
+    // 新注册账号需要提交人机验证结果
+    pub challenge: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
@@ -61,11 +93,28 @@ pub struct ForumEditRequest {
     pub content: String,
 }
 
+// 普通用户发布帖子或回复后可以编辑的时间窗口（版主不受限制）
+const POST_EDIT_WINDOW_MINUTES: i64 = 60 * 24;
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct PostEditRequest {
+    #[validate(length(max = 65536))]
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AcceptPostRequest {
+    // 为空时取消采纳
+    pub post_id: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct PostRequest {
     #[validate(length(max = 65536))]
     pub content: String,
     pub replied_to: Option<String>,
+    // 新注册账号需要提交人机验证结果
+    pub challenge: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate, Clone)]
@@ -174,11 +223,12 @@ pub async fn forums_get(
 }
 
 pub async fn posts_get(
-    _req: HttpRequest,
+    req: HttpRequest,
     info: web::Path<(String,)>,
     query: web::Query<PostsQueryParams>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
 ) -> Result<HttpResponse, ApiError> {
     let discussion_id_str: String = info.into_inner().0;
     let params = query.into_inner();
@@ -240,6 +290,26 @@ pub async fn posts_get(
         .map(|x| x.into())
         .collect::<Vec<PostResponse>>();
 
+    // 填充表情回应（包含当前用户的状态，不走缓存）
+    let user_id = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await
+    .ok()
+    .map(|x| UserId::from(x.1.id));
+    let mut reactions =
+        Reaction::get_summaries(ReactionTarget::Post, ids, user_id, &**pool)
+            .await?;
+    for post in posts.iter_mut() {
+        if let Some(list) = reactions.remove(&(post.post_id.0 as i64)) {
+            post.reactions = list;
+        }
+    }
+
     // 对 posts 进行最终排序
     match sort.as_str() {
         "floor_desc" => {
@@ -305,15 +375,23 @@ pub async fn forum_edit(
     let discussion = discussion.unwrap();
 
     if discussion.inner.user_id.0 != UserId::from(user.id).0
-        && !&user.role.is_admin()
+        && !&user.role.is_mod()
     {
         return Err(ApiError::InvalidInput("您没有权限修改此帖子".to_string()));
     }
-    if discussion.inner.state == "closed" && !user.role.is_admin() {
+    if discussion.inner.state == "closed" && !user.role.is_mod() {
         return Err(ApiError::InvalidInput(
             "此帖子已被关闭，无法修改".to_string(),
         ));
     }
+    if !user.role.is_mod()
+        && chrono::Utc::now() - discussion.inner.created_at
+            > chrono::Duration::minutes(POST_EDIT_WINDOW_MINUTES)
+    {
+        return Err(ApiError::InvalidInput(
+            "帖子发布已超过可编辑时间，无法修改".to_string(),
+        ));
+    }
 
     if !["article", "notice"].contains(&discussion.inner.category.as_str()) {
         return Err(ApiError::InvalidInput(
@@ -325,9 +403,10 @@ pub async fn forum_edit(
     {
         return Err(ApiError::InvalidInput("未做任何修改".to_string()));
     }
-    let mut transaction = pool.begin().await?;
+    let content_changed = discussion.inner.content != body.content;
+    let title_changed = discussion.inner.title != body.title;
 
-    if discussion.inner.content != body.content {
+    if content_changed {
         // 检查帖子内容
         let risk = crate::util::risk::check_text_risk(
             &body.content,
@@ -342,13 +421,9 @@ pub async fn forum_edit(
                 "帖子内容包含敏感词，已被记录该次提交，请勿在本网站使用涉及敏感词的帖子回复内容".to_string(),
             ));
         }
-        discussion
-            .inner
-            .update_discussion_content(body.content.clone(), &mut transaction)
-            .await?;
     }
 
-    if discussion.inner.title != body.title {
+    if title_changed {
         // 检查帖子内容
         let risk = crate::util::risk::check_text_risk(
             &body.title,
@@ -363,6 +438,56 @@ pub async fn forum_edit(
                 "帖子标题包含敏感词，已被记录该次提交，请勿在本网站使用涉及敏感词的帖子回复内容".to_string(),
             ));
         }
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    // 保存修改前的版本
+    ForumEditHistory::insert(
+        discussion_id,
+        None,
+        Some(&discussion.inner.title),
+        &discussion.inner.content,
+        UserId::from(user.id),
+        &mut transaction,
+    )
+    .await?;
+
+    if content_changed {
+        discussion
+            .inner
+            .update_discussion_content(body.content.clone(), &mut transaction)
+            .await?;
+
+        // 项目讨论只通知能看到该项目的用户
+        let project = match discussion.inner.project_id {
+            Some(project_id) => {
+                database::models::Project::get_id(project_id, &**pool, &redis)
+                    .await?
+            }
+            None => None,
+        };
+        notify_edited_mentions(
+            &discussion.inner.content,
+            &body.content,
+            &user,
+            match &project {
+                Some(project) => MentionVisibility::Project(&project.inner),
+                None => MentionVisibility::Public,
+            },
+            MentionSource {
+                source_type: "forum",
+                title: &body.title,
+                link: &format!("/d/{}", to_base62(discussion_id.0 as u64)),
+            },
+            &mut transaction,
+            &pool,
+            &redis,
+        )
+        .await?;
+    }
+
+    if title_changed {
         discussion
             .inner
             .update_discussion_title(body.title.clone(), &mut transaction)
@@ -471,6 +596,7 @@ pub async fn forum_create(
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
     session_queue: web::Data<AuthQueue>,
+    captcha: web::Data<Arc<Captcha>>,
 ) -> Result<HttpResponse, ApiError> {
     body.validate().map_err(|err| {
         ApiError::Validation(validation_errors_to_string(err, None))
@@ -526,6 +652,9 @@ pub async fn forum_create(
             "只有管理员可以创建公告".to_string(),
         ));
     }
+    captcha
+        .check_forum_new_account(user, body.challenge.as_deref(), &req, &redis)
+        .await?;
     // 检查帖子内容
     let risk = crate::util::risk::check_text_risk(
         &body.content,
@@ -586,8 +715,32 @@ pub async fn forum_create(
         avatar: user_option.as_ref().unwrap().avatar_url.clone(),
         project_id: None,
         organization: None,
+        edited_at: None,
+        accepted_post_id: None,
     };
     discussion.insert(&mut transaction).await?;
+    notify_mentions(
+        &body.content,
+        user,
+        MentionVisibility::Public,
+        MentionSource {
+            source_type: "forum",
+            title: &discussion.title,
+            link: &format!("/d/{}", to_base62(discussion_id.0 as u64)),
+        },
+        &mut transaction,
+        &pool,
+        &redis,
+    )
+    .await?;
+    // 发帖人自动关注自己的帖子
+    Subscription::subscribe_automatic(
+        discussion.user_id,
+        SubscriptionTarget::Discussion,
+        discussion_id.0,
+        &mut transaction,
+    )
+    .await?;
     transaction.commit().await?;
     crate::database::models::forum::Discussion::clear_cache_discussions(
         &[discussion.category.clone(), "all".to_string()],
@@ -613,6 +766,7 @@ pub async fn posts_post(
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
     session_queue: web::Data<AuthQueue>,
+    captcha: web::Data<Arc<Captcha>>,
 ) -> Result<HttpResponse, ApiError> {
     body.validate().map_err(|err| {
         ApiError::Validation(validation_errors_to_string(err, None))
@@ -677,6 +831,9 @@ pub async fn posts_post(
             "请先绑定手机号，再进行回复".to_string(),
         ));
     }
+    captcha
+        .check_forum_new_account(user, body.challenge.as_deref(), &req, &redis)
+        .await?;
     // 检查回复内容
     let risk = crate::util::risk::check_text_risk(
         &body.content,
@@ -736,6 +893,52 @@ pub async fn posts_post(
         .insert(discussion.user_id, &mut transaction, &redis)
         .await?;
 
+    // 项目讨论只通知能看到该项目的用户
+    let project = match discussion.project_id {
+        Some(project_id) => {
+            database::models::Project::get_id(project_id, &**pool, &redis)
+                .await?
+        }
+        None => None,
+    };
+    notify_mentions(
+        &body.content,
+        user,
+        match &project {
+            Some(project) => MentionVisibility::Project(&project.inner),
+            None => MentionVisibility::Public,
+        },
+        MentionSource {
+            source_type: "forum_post",
+            title: &discussion.title,
+            link: &format!("/d/{}", string),
+        },
+        &mut transaction,
+        &pool,
+        &redis,
+    )
+    .await?;
+
+    // 通知关注者并自动关注回复过的帖子
+    SubscriptionEvent::queue(
+        SubscriptionTarget::Discussion,
+        discussion_id.0,
+        None,
+        SubscriptionEventType::NewPost,
+        Some(post.user_id),
+        &discussion.title,
+        &format!("/d/{}", string),
+        &mut transaction,
+    )
+    .await?;
+    Subscription::subscribe_automatic(
+        post.user_id,
+        SubscriptionTarget::Discussion,
+        discussion_id.0,
+        &mut transaction,
+    )
+    .await?;
+
     transaction.commit().await?;
 
     Discussion::clear_cache(&[discussion_id], &redis).await?;
@@ -879,3 +1082,484 @@ pub async fn post_delete(
 
     Ok(HttpResponse::NoContent().finish())
 }
+
+pub async fn post_edit(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    body: web::Json<PostEditRequest>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    body.validate().map_err(|err| {
+        ApiError::Validation(validation_errors_to_string(err, None))
+    })?;
+    let user_option = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ, Scopes::VERSION_READ]),
+    )
+    .await
+    .map(|x| x.1)
+    .ok();
+
+    if user_option.is_none() {
+        return Err(ApiError::Authentication(
+            AuthenticationError::InvalidCredentials,
+        ));
+    }
+    let user = user_option.unwrap();
+
+    // 检查用户是否被论坛类封禁
+    check_forum_ban(&user, &pool).await?;
+
+    let post_id_str: String = info.into_inner().0;
+    let post_id = match parse_base62(&post_id_str) {
+        Ok(id) => PostId(id as i64),
+        Err(_) => {
+            return Err(ApiError::InvalidInput("无效的帖子ID".to_string()));
+        }
+    };
+
+    let post_info = sqlx::query!(
+        "SELECT id, discussion_id, user_id, content, created_at, replied_to FROM posts WHERE id = $1 AND deleted = false",
+        post_id.0
+    )
+    .fetch_optional(&**pool)
+    .await?;
+
+    if post_info.is_none() {
+        return Err(ApiError::NotFound);
+    }
+    let post_info = post_info.unwrap();
+    let discussion_id = DiscussionId(post_info.discussion_id);
+
+    let discussion =
+        Discussion::get_id(discussion_id.0, &**pool, &redis).await?;
+    if discussion.is_none() {
+        return Err(ApiError::NotFound);
+    }
+    let discussion = discussion.unwrap();
+
+    // 检查权限：回复发布者在时间窗口内可以编辑，版主随时可以编辑
+    let is_mod = user.role.is_mod();
+    if !is_mod {
+        if post_info.user_id != UserId::from(user.id).0 {
+            return Err(ApiError::InvalidInput(
+                "您没有权限修改此回复".to_string(),
+            ));
+        }
+        if discussion.inner.state == "closed" {
+            return Err(ApiError::InvalidInput(
+                "此帖子已被关闭，无法修改回复".to_string(),
+            ));
+        }
+        if chrono::Utc::now() - post_info.created_at
+            > chrono::Duration::minutes(POST_EDIT_WINDOW_MINUTES)
+        {
+            return Err(ApiError::InvalidInput(
+                "回复发布已超过可编辑时间，无法修改".to_string(),
+            ));
+        }
+    }
+
+    if body.content.is_empty() {
+        return Err(ApiError::InvalidInput("请输入回复内容".to_string()));
+    }
+    if post_info.content == body.content {
+        return Err(ApiError::InvalidInput("未做任何修改".to_string()));
+    }
+
+    // 检查回复内容
+    let risk = crate::util::risk::check_text_risk(
+        &body.content,
+        &user.username,
+        &format!("/d/{}", to_base62(discussion_id.0 as u64)),
+        "编辑帖子回复",
+        &redis,
+    )
+    .await?;
+    if !risk {
+        return Err(ApiError::InvalidInput(
+            "帖子回复内容包含敏感词，已被记录该次提交，请勿在本网站使用涉及敏感词的帖子回复内容".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    // 保存修改前的版本
+    ForumEditHistory::insert(
+        discussion_id,
+        Some(post_id),
+        None,
+        &post_info.content,
+        UserId::from(user.id),
+        &mut transaction,
+    )
+    .await?;
+    PostQuery::update_content(post_id, &body.content, &mut transaction).await?;
+
+    // 项目讨论只通知能看到该项目的用户
+    let project = match discussion.inner.project_id {
+        Some(project_id) => {
+            database::models::Project::get_id(project_id, &**pool, &redis)
+                .await?
+        }
+        None => None,
+    };
+    notify_edited_mentions(
+        &post_info.content,
+        &body.content,
+        &user,
+        match &project {
+            Some(project) => MentionVisibility::Project(&project.inner),
+            None => MentionVisibility::Public,
+        },
+        MentionSource {
+            source_type: "forum_post",
+            title: &discussion.inner.title,
+            link: &format!("/d/{}", to_base62(discussion_id.0 as u64)),
+        },
+        &mut transaction,
+        &pool,
+        &redis,
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    // 清理帖子缓存，以及引用了该回复内容的其他回复
+    let mut clear_ids = vec![post_id];
+    let replies = sqlx::query!(
+        "SELECT id FROM posts WHERE replied_to = $1 AND discussion_id = $2",
+        post_id.0,
+        post_info.discussion_id
+    )
+    .fetch_all(&**pool)
+    .await?;
+    clear_ids.extend(replies.iter().map(|r| PostId(r.id)));
+    if let Some(replied_to_id) = post_info.replied_to {
+        clear_ids.push(PostId(replied_to_id));
+    }
+    PostQuery::clear_cache(&clear_ids, &redis).await?;
+
+    // 清除回复作者的论坛内容缓存
+    let _ =
+        super::users::clear_user_forum_cache(post_info.user_id, &redis).await;
+
+    let posts: Vec<PostResponse> =
+        PostQuery::get_many(&[post_id.0], &discussion_id, &**pool, &redis)
+            .await?
+            .into_iter()
+            .map(|x| x.into())
+            .collect::<Vec<PostResponse>>();
+
+    Ok(HttpResponse::Ok().json(json!({
+        "post": posts.first()
+    })))
+}
+
+pub async fn post_history_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await?
+    .1;
+
+    if !user.role.is_mod() {
+        return Err(ApiError::CustomAuthentication(
+            "只有版主可以查看编辑记录".to_string(),
+        ));
+    }
+
+    let post_id = PostId(
+        parse_base62(&info.into_inner().0)
+            .map_err(|_| ApiError::InvalidInput("无效的帖子ID".to_string()))?
+            as i64,
+    );
+
+    let history: Vec<ForumEditHistoryResponse> =
+        ForumEditHistory::get_for_post(post_id, &**pool)
+            .await?
+            .into_iter()
+            .map(|x| x.into())
+            .collect();
+
+    Ok(HttpResponse::Ok().json(json!({
+        "history": history
+    })))
+}
+
+pub async fn forum_history_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await?
+    .1;
+
+    if !user.role.is_mod() {
+        return Err(ApiError::CustomAuthentication(
+            "只有版主可以查看编辑记录".to_string(),
+        ));
+    }
+
+    let discussion_id = DiscussionId(
+        parse_base62(&info.into_inner().0)
+            .map_err(|_| ApiError::InvalidInput("无效的讨论 ID".to_string()))?
+            as i64,
+    );
+
+    let history: Vec<ForumEditHistoryResponse> =
+        ForumEditHistory::get_for_discussion(discussion_id, &**pool)
+            .await?
+            .into_iter()
+            .map(|x| x.into())
+            .collect();
+
+    Ok(HttpResponse::Ok().json(json!({
+        "history": history
+    })))
+}
+
+/// 检查回复存在且用户能看到其所在的资源讨论区
+async fn check_post_visible(
+    req: &HttpRequest,
+    post_id: PostId,
+    user: &User,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(), ApiError> {
+    let discussion_id = sqlx::query!(
+        "SELECT discussion_id FROM posts WHERE id = $1 AND deleted = false",
+        post_id.0
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(ApiError::NotFound)?
+    .discussion_id;
+
+    let discussion = Discussion::get_id(discussion_id, pool, redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    if let Some(project_id) = discussion.inner.project_id {
+        let project =
+            database::models::Project::get_id(project_id, pool, redis)
+                .await?
+                .ok_or(ApiError::NotFound)?;
+        if !is_visible_project(&project.inner, &Some(user.clone()), pool, false)
+            .await?
+        {
+            return Err(ApiError::NotFound);
+        }
+        check_pat_project_id(req, project_id, pool, redis).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn reactions_get() -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(json!({
+        "reactions": allowed_reactions()
+    })))
+}
+
+pub async fn post_reaction_add(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    body: web::Json<ReactionRequest>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await?
+    .1;
+
+    // 检查用户是否被论坛类封禁
+    check_forum_ban(&user, &pool).await?;
+
+    if !is_allowed_reaction(&body.reaction) {
+        return Err(ApiError::InvalidInput("不支持的表情".to_string()));
+    }
+
+    let post_id = PostId(
+        parse_base62(&info.into_inner().0)
+            .map_err(|_| ApiError::InvalidInput("无效的帖子ID".to_string()))?
+            as i64,
+    );
+
+    check_post_visible(&req, post_id, &user, &pool, &redis).await?;
+
+    let mut transaction = pool.begin().await?;
+    Reaction::insert(
+        ReactionTarget::Post,
+        post_id.0,
+        UserId::from(user.id),
+        &body.reaction,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn post_reaction_remove(
+    req: HttpRequest,
+    info: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await?
+    .1;
+
+    let (post_id, reaction) = info.into_inner();
+    let post_id = PostId(
+        parse_base62(&post_id)
+            .map_err(|_| ApiError::InvalidInput("无效的帖子ID".to_string()))?
+            as i64,
+    );
+    check_post_visible(&req, post_id, &user, &pool, &redis).await?;
+
+    let mut transaction = pool.begin().await?;
+    Reaction::remove(
+        ReactionTarget::Post,
+        post_id.0,
+        UserId::from(user.id),
+        &reaction,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn forum_accept_post(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    body: web::Json<AcceptPostRequest>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+
+    // 检查用户是否被论坛类封禁
+    check_forum_ban(&user, &pool).await?;
+
+    let discussion_id = DiscussionId(
+        parse_base62(&info.into_inner().0)
+            .map_err(|_| ApiError::InvalidInput("无效的讨论 ID".to_string()))?
+            as i64,
+    );
+    let discussion =
+        Discussion::get_id(discussion_id.0, &**pool, &redis).await?;
+    if discussion.is_none() {
+        return Err(ApiError::NotFound);
+    }
+    let discussion = discussion.unwrap();
+    if let Some(project_id) = discussion.inner.project_id {
+        check_pat_project_id(&req, project_id, &pool, &redis).await?;
+    }
+
+    // 讨论发起者、版主，以及资源讨论区的项目成员可以采纳回答
+    let mut allowed =
+        discussion.inner.user_id == UserId::from(user.id) || user.role.is_mod();
+    if !allowed && let Some(project_id) = discussion.inner.project_id {
+        let project =
+            database::models::Project::get_id(project_id, &**pool, &redis)
+                .await?;
+        if let Some(project) = project {
+            let (team_member, organization_team_member) =
+                database::models::TeamMember::get_for_project_permissions(
+                    &project.inner,
+                    UserId::from(user.id),
+                    &**pool,
+                )
+                .await?;
+            allowed = ProjectPermissions::get_permissions_by_role(
+                &user.role,
+                &team_member,
+                &organization_team_member,
+            )
+            .is_some();
+        }
+    }
+    if !allowed {
+        return Err(ApiError::CustomAuthentication(
+            "您没有权限采纳此帖子的回答".to_string(),
+        ));
+    }
+
+    let post_id = body
+        .post_id
+        .as_ref()
+        .map(|x| {
+            parse_base62(x)
+                .map_err(|_| ApiError::InvalidInput("无效的帖子ID".to_string()))
+        })
+        .transpose()?
+        .map(|x| PostId(x as i64));
+
+    if let Some(post_id) = post_id
+        && !discussion.posts.iter().any(|p| p.post_id == post_id)
+    {
+        return Err(ApiError::InvalidInput("该回复不属于此帖子".to_string()));
+    }
+
+    let mut transaction = pool.begin().await?;
+    discussion
+        .inner
+        .update_accepted_post(post_id, &mut transaction)
+        .await?;
+    transaction.commit().await?;
+
+    Discussion::clear_cache(&[discussion_id], &redis).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
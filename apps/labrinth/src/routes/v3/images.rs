@@ -1,9 +1,8 @@
 use std::sync::Arc;
 
-use super::threads::is_authorized_thread;
 use crate::auth::checks::{
     check_forum_ban, check_global_ban, check_resource_ban,
-    is_team_member_project, is_team_member_version,
+    is_authorized_thread, is_team_member_project, is_team_member_version,
 };
 use crate::auth::get_user_from_headers;
 use crate::database;
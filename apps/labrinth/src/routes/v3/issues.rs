@@ -1,3 +1,4 @@
+use crate::auth::checks::{check_pat_project, is_visible_project};
 use crate::auth::email::send_email;
 use crate::auth::{AuthenticationError, get_user_from_headers};
 use crate::database::models::ids::{
@@ -5,18 +6,30 @@ use crate::database::models::ids::{
 };
 use crate::database::models::issues::{
     ISSUE_NAMESPACE, Issue, IssueCommentBuilder, IssueCommentQuery, IssueLabel,
+    IssueMilestone, IssueTemplate, IssueTemplateField,
+};
+use crate::database::models::reaction_item::{
+    Reaction, ReactionTarget, allowed_reactions, is_allowed_reaction,
+};
+use crate::database::models::subscription_item::{
+    Subscription, SubscriptionEvent, SubscriptionEventType, SubscriptionTarget,
 };
 use crate::database::models::{ProjectId, UserId};
 use crate::database::redis::RedisPool;
 use crate::database::{self, models};
-use crate::models::ids::base62_impl::parse_base62;
+use crate::models::ids::base62_impl::{parse_base62, to_base62};
 use crate::models::pats::Scopes;
 use crate::models::teams::ProjectPermissions;
 use crate::queue::session::AuthQueue;
+use crate::util::mentions::{
+    MentionSource, MentionVisibility, notify_mentions,
+};
 use crate::{
     models::v3::issues::{
-        CommentResponse, CommentsQueryParams, CreateCommentRequest,
-        CreateIssueRequest, IssueResponse, IssuesQueryParams, LabelResponse,
+        AcceptCommentRequest, CommentResponse, CommentsQueryParams,
+        CreateCommentRequest, CreateIssueRequest, IssueResponse,
+        IssuesQueryParams, LabelRequest, LabelResponse, MilestoneRequest,
+        MilestoneResponse, ReactionRequest, TemplateRequest, TemplateResponse,
         UpdateIssueRequest,
     },
     routes::ApiError,
@@ -30,17 +43,57 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("issues")
             .route("labels", web::get().to(labels_get))
+            .route("reactions", web::get().to(reactions_get))
             .route("{id}", web::get().to(issue_get))
             // .route("{id}", web::delete().to(issue_delete))
             .route("{id}", web::patch().to(issue_edit))
             .route("{id}/comments", web::get().to(comments_get))
             .route("{id}/comments", web::post().to(comment_create))
+            .route("{id}/accept", web::post().to(issue_accept_comment))
             // .route("comments/{comment_id}", web::patch().to(comment_edit))
             .route("comments/{comment_id}", web::delete().to(comment_delete))
+            .route(
+                "comments/{comment_id}/reactions",
+                web::post().to(comment_reaction_add),
+            )
+            .route(
+                "comments/{comment_id}/reactions/{reaction}",
+                web::delete().to(comment_reaction_remove),
+            )
             .service(
                 web::scope("project/{project_id}")
                     .route("", web::get().to(project_issues_list))
-                    .route("", web::post().to(project_issue_create)),
+                    .route("", web::post().to(project_issue_create))
+                    .route("labels", web::get().to(project_labels_get))
+                    .route("labels", web::post().to(project_label_create))
+                    .route(
+                        "labels/{label_id}",
+                        web::patch().to(project_label_edit),
+                    )
+                    .route(
+                        "labels/{label_id}",
+                        web::delete().to(project_label_delete),
+                    )
+                    .route("milestones", web::get().to(milestones_get))
+                    .route("milestones", web::post().to(milestone_create))
+                    .route(
+                        "milestones/{milestone_id}",
+                        web::patch().to(milestone_edit),
+                    )
+                    .route(
+                        "milestones/{milestone_id}",
+                        web::delete().to(milestone_delete),
+                    )
+                    .route("templates", web::get().to(templates_get))
+                    .route("templates", web::post().to(template_create))
+                    .route(
+                        "templates/{template_id}",
+                        web::patch().to(template_edit),
+                    )
+                    .route(
+                        "templates/{template_id}",
+                        web::delete().to(template_delete),
+                    ),
             ),
     );
 }
@@ -154,6 +207,7 @@ pub async fn project_issue_create(
     }
 
     let project = result.unwrap();
+    check_pat_project(&req, &project.inner)?;
 
     // 检查用户是否绑定手机号
     if user.has_phonenumber.is_none() || !user.has_phonenumber.unwrap() {
@@ -166,7 +220,23 @@ pub async fn project_issue_create(
         return Err(ApiError::InvalidInput("请输入标题".to_string()));
     }
 
-    if body.body.is_empty() {
+    // 使用模板时校验必填字段，并将字段内容整理到正文中
+    let issue_body = match body.template_id {
+        Some(template_id) => {
+            let template =
+                IssueTemplate::get_project(project.inner.id, &**pool)
+                    .await?
+                    .into_iter()
+                    .find(|x| x.id == template_id)
+                    .ok_or_else(|| {
+                        ApiError::InvalidInput("问题模板不存在".to_string())
+                    })?;
+            render_template_body(&template, &body.fields, &body.body)?
+        }
+        None => body.body.clone(),
+    };
+
+    if issue_body.is_empty() {
         return Err(ApiError::InvalidInput("请输入内容".to_string()));
     }
 
@@ -192,7 +262,7 @@ pub async fn project_issue_create(
         id: issue_id,
         mod_id: project_id,
         title: body.title.clone(),
-        body: body.body.clone(),
+        body: issue_body,
         state: "open".to_string(),
         created_at: Utc::now(),
         updated_at: Utc::now(),
@@ -205,9 +275,55 @@ pub async fn project_issue_create(
         deleted_at: None,
         labels: Vec::new(),
         assignees: Vec::new(),
+        accepted_comment_id: None,
+        milestone_id: None,
+        template_id: body.template_id,
     };
 
     issue.insert(&mut transaction).await?;
+
+    notify_mentions(
+        &issue.body,
+        &user,
+        MentionVisibility::Project(&project.inner),
+        MentionSource {
+            source_type: "issue",
+            title: &issue.title,
+            link: &format!(
+                "/project/{}/issues/{}",
+                project.inner.slug.clone().unwrap_or_default(),
+                to_base62(issue_id.0 as u64)
+            ),
+        },
+        &mut transaction,
+        &pool,
+        &redis,
+    )
+    .await?;
+
+    // 通知关注项目问题列表的用户，创建者自动关注该问题
+    SubscriptionEvent::queue(
+        SubscriptionTarget::Issue,
+        issue_id.0,
+        Some((SubscriptionTarget::ProjectIssues, project.inner.id.0)),
+        SubscriptionEventType::NewIssue,
+        Some(issue.author_id),
+        &issue.title,
+        &format!(
+            "/project/{}/issues/{}",
+            project.inner.slug.clone().unwrap_or_default(),
+            to_base62(issue_id.0 as u64)
+        ),
+        &mut transaction,
+    )
+    .await?;
+    Subscription::subscribe_automatic(
+        issue.author_id,
+        SubscriptionTarget::Issue,
+        issue_id.0,
+        &mut transaction,
+    )
+    .await?;
     transaction.commit().await?;
 
     // 清除单个Issue的缓存
@@ -325,6 +441,7 @@ pub async fn issue_edit(
     }
 
     let project = result.unwrap();
+    check_pat_project(&req, &project.inner)?;
 
     let (team_member, organization_team_member) =
         crate::database::models::TeamMember::get_for_project_permissions(
@@ -382,6 +499,22 @@ pub async fn issue_edit(
                 .await?;
             has_changes = true;
 
+            SubscriptionEvent::queue(
+                SubscriptionTarget::Issue,
+                issue_id.0,
+                Some((SubscriptionTarget::ProjectIssues, project.inner.id.0)),
+                SubscriptionEventType::StateChange,
+                Some(UserId::from(user.id)),
+                &issue.inner.title,
+                &format!(
+                    "/project/{}/issues/{}",
+                    project.inner.slug.clone().unwrap_or_default(),
+                    &issue_id_str
+                ),
+                &mut transaction,
+            )
+            .await?;
+
             // 发送邮件通知
             let mut users =
                 project.inner.get_all_users(&**pool, &redis).await?;
@@ -430,6 +563,16 @@ pub async fn issue_edit(
             ));
         }
 
+        // 只能使用全站通用标签和本项目的标签
+        let available =
+            IssueLabel::get_project(project.inner.id, &**pool, &redis).await?;
+        if label_ids
+            .iter()
+            .any(|id| !available.iter().any(|x| x.id == *id))
+        {
+            return Err(ApiError::InvalidInput("标签不存在".to_string()));
+        }
+
         // 获取当前标签ID列表进行比较
         let current_label_ids: Vec<i32> =
             issue.inner.labels.iter().map(|l| l.id).collect();
@@ -447,6 +590,34 @@ pub async fn issue_edit(
         }
     }
 
+    // 更新里程碑
+    if let Some(milestone_id) = body.milestone_id {
+        if permissions.is_none() {
+            return Err(ApiError::InvalidInput(
+                "您没有权限修改此问题的里程碑".to_string(),
+            ));
+        }
+
+        if let Some(milestone_id) = milestone_id
+            && !IssueMilestone::exists_in_project(
+                milestone_id,
+                project.inner.id,
+                &**pool,
+            )
+            .await?
+        {
+            return Err(ApiError::InvalidInput("里程碑不存在".to_string()));
+        }
+
+        if issue.inner.milestone_id != milestone_id {
+            issue
+                .inner
+                .update_milestone(milestone_id, &mut transaction)
+                .await?;
+            has_changes = true;
+        }
+    }
+
     if !has_changes {
         return Err(ApiError::InvalidInput("未做任何修改".to_string()));
     }
@@ -551,11 +722,12 @@ pub async fn issue_edit(
 
 // 获取Issue评论
 pub async fn comments_get(
-    _req: HttpRequest,
+    req: HttpRequest,
     info: web::Path<(String,)>,
     query: web::Query<CommentsQueryParams>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
 ) -> Result<HttpResponse, ApiError> {
     let issue_id_str: String = info.into_inner().0;
     let issue_id = IssuesId(parse_base62(&issue_id_str)? as i64);
@@ -610,9 +782,33 @@ pub async fn comments_get(
     // 按创建时间排序评论，确保返回顺序正确
     comments.sort_by(|a, b| a.created_at.cmp(&b.created_at));
 
-    let comments: Vec<CommentResponse> =
+    let mut comments: Vec<CommentResponse> =
         comments.into_iter().map(|x| x.into()).collect::<Vec<_>>();
 
+    // 填充表情回应（包含当前用户的状态，不走缓存）
+    let user_id = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await
+    .ok()
+    .map(|x| UserId::from(x.1.id));
+    let mut reactions = Reaction::get_summaries(
+        ReactionTarget::IssueComment,
+        &comment_ids,
+        user_id,
+        &**pool,
+    )
+    .await?;
+    for comment in comments.iter_mut() {
+        if let Some(list) = reactions.remove(&(comment.id.0 as i64)) {
+            comment.reactions = list;
+        }
+    }
+
     Ok(HttpResponse::Ok().json(json!({
         "comments": comments,
         "pagination": {
@@ -675,6 +871,7 @@ pub async fn comment_create(
         return Err(ApiError::NotFound);
     }
     let project = result.unwrap();
+    check_pat_project(&req, &project.inner)?;
 
     // 检查Issue状态
     if issue.inner.locked && !user.role.is_admin() {
@@ -705,6 +902,49 @@ pub async fn comment_create(
     };
 
     comment.insert(&mut transaction).await?;
+
+    notify_mentions(
+        &body.body,
+        &user,
+        MentionVisibility::Project(&project.inner),
+        MentionSource {
+            source_type: "issue_comment",
+            title: &issue.inner.title,
+            link: &format!(
+                "/project/{}/issues/{}",
+                project.inner.slug.clone().unwrap_or_default(),
+                &issue_id_str
+            ),
+        },
+        &mut transaction,
+        &pool,
+        &redis,
+    )
+    .await?;
+
+    // 通知关注者并自动关注评论过的问题
+    SubscriptionEvent::queue(
+        SubscriptionTarget::Issue,
+        issue_id.0,
+        Some((SubscriptionTarget::ProjectIssues, project.inner.id.0)),
+        SubscriptionEventType::NewComment,
+        Some(comment.author_id),
+        &issue.inner.title,
+        &format!(
+            "/project/{}/issues/{}",
+            project.inner.slug.clone().unwrap_or_default(),
+            &issue_id_str
+        ),
+        &mut transaction,
+    )
+    .await?;
+    Subscription::subscribe_automatic(
+        comment.author_id,
+        SubscriptionTarget::Issue,
+        issue_id.0,
+        &mut transaction,
+    )
+    .await?;
     transaction.commit().await?;
 
     Issue::clear_cache(&[issue_id], &redis).await?;
@@ -878,6 +1118,7 @@ pub async fn comment_delete(
     }
 
     let project = result.unwrap();
+    check_pat_project(&req, &project.inner)?;
 
     let (team_member, organization_team_member) =
         crate::database::models::TeamMember::get_for_project_permissions(
@@ -945,3 +1186,823 @@ pub async fn labels_get(
         "labels": labels
     })))
 }
+
+// 获取可用的表情回应
+pub async fn reactions_get() -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(json!({
+        "reactions": allowed_reactions()
+    })))
+}
+
+// 为评论添加表情回应
+pub async fn comment_reaction_add(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    body: web::Json<ReactionRequest>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let comment_id_str: String = info.into_inner().0;
+    let comment_id = IssuesCommentsId(parse_base62(&comment_id_str)? as i64);
+
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await?
+    .1;
+
+    if !is_allowed_reaction(&body.reaction) {
+        return Err(ApiError::InvalidInput("不支持的表情".to_string()));
+    }
+
+    check_comment_visible(&req, comment_id, &user, &pool, &redis).await?;
+
+    let mut transaction = pool.begin().await?;
+    Reaction::insert(
+        ReactionTarget::IssueComment,
+        comment_id.0,
+        UserId::from(user.id),
+        &body.reaction,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// 移除评论的表情回应
+pub async fn comment_reaction_remove(
+    req: HttpRequest,
+    info: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let (comment_id_str, reaction) = info.into_inner();
+    let comment_id = IssuesCommentsId(parse_base62(&comment_id_str)? as i64);
+
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await?
+    .1;
+
+    check_comment_visible(&req, comment_id, &user, &pool, &redis).await?;
+
+    let mut transaction = pool.begin().await?;
+    Reaction::remove(
+        ReactionTarget::IssueComment,
+        comment_id.0,
+        UserId::from(user.id),
+        &reaction,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// 采纳回答
+pub async fn issue_accept_comment(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    body: web::Json<AcceptCommentRequest>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let issue_id_str: String = info.into_inner().0;
+    let issue_id = IssuesId(parse_base62(&issue_id_str)? as i64);
+
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+
+    let issue = Issue::get_id(issue_id.0, &**pool, &redis).await?;
+
+    if issue.is_none() {
+        return Err(ApiError::NotFound);
+    }
+
+    let issue = issue.unwrap();
+
+    let result =
+        database::models::Project::get_id(issue.inner.mod_id, &**pool, &redis)
+            .await?;
+
+    if result.is_none() {
+        return Err(ApiError::NotFound);
+    }
+
+    let project = result.unwrap();
+    check_pat_project(&req, &project.inner)?;
+
+    let (team_member, organization_team_member) =
+        crate::database::models::TeamMember::get_for_project_permissions(
+            &project.inner,
+            UserId::from(user.id),
+            &**pool,
+        )
+        .await?;
+
+    let permissions = ProjectPermissions::get_permissions_by_role(
+        &user.role,
+        &team_member,
+        &organization_team_member,
+    );
+
+    // 权限检查：只有问题创建者和项目成员可以采纳回答
+    if issue.inner.author_id.0 != UserId::from(user.id).0
+        && permissions.is_none()
+    {
+        return Err(ApiError::InvalidInput(
+            "您没有权限采纳此问题的回答".to_string(),
+        ));
+    }
+
+    let comment_id = body.comment_id.map(|id| IssuesCommentsId(id.0 as i64));
+
+    if let Some(comment_id) = comment_id {
+        let comment = sqlx::query!(
+            "SELECT issue_id FROM issue_comments WHERE id = $1 AND deleted = false",
+            comment_id.0
+        )
+        .fetch_optional(&**pool)
+        .await?;
+
+        if comment.map(|c| c.issue_id) != Some(issue_id.0) {
+            return Err(ApiError::InvalidInput(
+                "该评论不属于此问题".to_string(),
+            ));
+        }
+    }
+
+    let mut transaction = pool.begin().await?;
+    issue
+        .inner
+        .update_accepted_comment(comment_id, &mut transaction)
+        .await?;
+    transaction.commit().await?;
+
+    Issue::clear_cache(&[issue_id], &redis).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+const TEMPLATE_TYPES: &[&str] = &["bug", "crash", "suggestion", "other"];
+const TEMPLATE_FIELD_TYPES: &[&str] = &["input", "textarea", "dropdown"];
+const MAX_TEMPLATE_FIELDS: usize = 20;
+
+// 根据模板字段生成问题正文，同时校验必填项
+fn render_template_body(
+    template: &IssueTemplate,
+    fields: &std::collections::HashMap<String, String>,
+    extra: &str,
+) -> Result<String, ApiError> {
+    let mut sections = Vec::new();
+    for field in &template.fields {
+        let value = fields.get(&field.id).map(|x| x.trim()).unwrap_or("");
+        if value.is_empty() {
+            if field.required {
+                return Err(ApiError::InvalidInput(format!(
+                    "请填写「{}」",
+                    field.label
+                )));
+            }
+            continue;
+        }
+        if field.field_type == "dropdown"
+            && !field.options.iter().any(|x| x == value)
+        {
+            return Err(ApiError::InvalidInput(format!(
+                "「{}」的选项无效",
+                field.label
+            )));
+        }
+        sections.push(format!("### {}\n\n{}", field.label, value));
+    }
+
+    if !extra.trim().is_empty() {
+        sections.push(format!("### 补充说明\n\n{}", extra.trim()));
+    }
+
+    Ok(sections.join("\n\n"))
+}
+
+fn validate_label(body: &LabelRequest) -> Result<(), ApiError> {
+    let name_len = body.name.trim().chars().count();
+    if name_len == 0 || name_len > 100 {
+        return Err(ApiError::InvalidInput(
+            "标签名称长度应为 1-100 个字符".to_string(),
+        ));
+    }
+    let color = body.color.strip_prefix('#').unwrap_or("");
+    if color.len() != 6 || !color.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ApiError::InvalidInput(
+            "标签颜色应为 #RRGGBB 格式".to_string(),
+        ));
+    }
+    if body.description.chars().count() > 500 {
+        return Err(ApiError::InvalidInput("标签描述过长".to_string()));
+    }
+    Ok(())
+}
+
+fn validate_milestone(body: &MilestoneRequest) -> Result<(), ApiError> {
+    let title_len = body.title.trim().chars().count();
+    if title_len == 0 || title_len > 100 {
+        return Err(ApiError::InvalidInput(
+            "里程碑标题长度应为 1-100 个字符".to_string(),
+        ));
+    }
+    if body.description.chars().count() > 2000 {
+        return Err(ApiError::InvalidInput("里程碑描述过长".to_string()));
+    }
+    if body
+        .version_number
+        .as_ref()
+        .is_some_and(|x| x.is_empty() || x.len() > 255)
+    {
+        return Err(ApiError::InvalidInput("版本号无效".to_string()));
+    }
+    if body
+        .state
+        .as_ref()
+        .is_some_and(|x| x != "open" && x != "closed")
+    {
+        return Err(ApiError::InvalidInput("里程碑状态无效".to_string()));
+    }
+    Ok(())
+}
+
+fn validate_template(body: &TemplateRequest) -> Result<(), ApiError> {
+    let name_len = body.name.trim().chars().count();
+    if name_len == 0 || name_len > 100 {
+        return Err(ApiError::InvalidInput(
+            "模板名称长度应为 1-100 个字符".to_string(),
+        ));
+    }
+    if !TEMPLATE_TYPES.contains(&body.template_type.as_str()) {
+        return Err(ApiError::InvalidInput("模板类型无效".to_string()));
+    }
+    if body.description.chars().count() > 500 {
+        return Err(ApiError::InvalidInput("模板描述过长".to_string()));
+    }
+    if body.fields.is_empty() || body.fields.len() > MAX_TEMPLATE_FIELDS {
+        return Err(ApiError::InvalidInput(format!(
+            "模板字段数量应为 1-{} 个",
+            MAX_TEMPLATE_FIELDS
+        )));
+    }
+
+    let mut ids: Vec<&str> = Vec::new();
+    for field in &body.fields {
+        validate_template_field(field)?;
+        if ids.contains(&field.id.as_str()) {
+            return Err(ApiError::InvalidInput(format!(
+                "模板字段 {} 重复",
+                field.id
+            )));
+        }
+        ids.push(&field.id);
+    }
+    Ok(())
+}
+
+fn validate_template_field(field: &IssueTemplateField) -> Result<(), ApiError> {
+    if field.id.is_empty()
+        || field.id.len() > 64
+        || !field
+            .id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(ApiError::InvalidInput("模板字段 ID 无效".to_string()));
+    }
+    let label_len = field.label.trim().chars().count();
+    if label_len == 0 || label_len > 100 {
+        return Err(ApiError::InvalidInput(
+            "模板字段名称长度应为 1-100 个字符".to_string(),
+        ));
+    }
+    if !TEMPLATE_FIELD_TYPES.contains(&field.field_type.as_str()) {
+        return Err(ApiError::InvalidInput("模板字段类型无效".to_string()));
+    }
+    if field.field_type == "dropdown" && field.options.is_empty() {
+        return Err(ApiError::InvalidInput(
+            "下拉字段至少需要一个选项".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// 检查评论及其问题存在，且用户能看到问题所属的项目
+async fn check_comment_visible(
+    req: &HttpRequest,
+    comment_id: IssuesCommentsId,
+    user: &crate::models::users::User,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(), ApiError> {
+    let mod_id = sqlx::query!(
+        "
+        SELECT i.mod_id
+        FROM issue_comments ic
+        INNER JOIN issues i ON i.id = ic.issue_id
+        WHERE ic.id = $1 AND ic.deleted = false AND i.deleted = false
+        ",
+        comment_id.0
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(ApiError::NotFound)?
+    .mod_id;
+
+    let project =
+        database::models::Project::get_id(ProjectId(mod_id), pool, redis)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+    if !is_visible_project(&project.inner, &Some(user.clone()), pool, false)
+        .await?
+    {
+        return Err(ApiError::NotFound);
+    }
+    check_pat_project(req, &project.inner)?;
+
+    Ok(())
+}
+
+// 获取当前用户可见的项目
+async fn get_visible_project(
+    req: &HttpRequest,
+    project_id: &str,
+    pool: &PgPool,
+    redis: &RedisPool,
+    session_queue: &AuthQueue,
+) -> Result<database::models::project_item::QueryProject, ApiError> {
+    let user_option = get_user_from_headers(
+        req,
+        pool,
+        redis,
+        session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await
+    .map(|x| x.1)
+    .ok();
+
+    let project = database::models::Project::get(project_id, pool, redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if !is_visible_project(&project.inner, &user_option, pool, false).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(project)
+}
+
+// 获取项目并检查是否有管理问题设置（标签、里程碑、模板）的权限
+async fn get_managed_project(
+    req: &HttpRequest,
+    project_id: &str,
+    pool: &PgPool,
+    redis: &RedisPool,
+    session_queue: &AuthQueue,
+) -> Result<database::models::project_item::QueryProject, ApiError> {
+    let user = get_user_from_headers(
+        req,
+        pool,
+        redis,
+        session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+
+    let project = database::models::Project::get(project_id, pool, redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    check_pat_project(req, &project.inner)?;
+
+    let (team_member, organization_team_member) =
+        crate::database::models::TeamMember::get_for_project_permissions(
+            &project.inner,
+            UserId::from(user.id),
+            pool,
+        )
+        .await?;
+
+    let permissions = ProjectPermissions::get_permissions_by_role(
+        &user.role,
+        &team_member,
+        &organization_team_member,
+    )
+    .unwrap_or_default();
+
+    if !permissions.contains(ProjectPermissions::EDIT_DETAILS) {
+        return Err(ApiError::CustomAuthentication(
+            "您没有权限管理此项目的问题设置".to_string(),
+        ));
+    }
+
+    Ok(project)
+}
+
+// 获取项目可用的标签
+pub async fn project_labels_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let project = get_visible_project(
+        &req,
+        &info.into_inner().0,
+        &pool,
+        &redis,
+        &session_queue,
+    )
+    .await?;
+
+    let labels: Vec<LabelResponse> =
+        IssueLabel::get_project(project.inner.id, &**pool, &redis)
+            .await?
+            .into_iter()
+            .map(|x| x.into())
+            .collect();
+
+    Ok(HttpResponse::Ok().json(json!({
+        "labels": labels
+    })))
+}
+
+// 创建项目标签
+pub async fn project_label_create(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    body: web::Json<LabelRequest>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let project = get_managed_project(
+        &req,
+        &info.into_inner().0,
+        &pool,
+        &redis,
+        &session_queue,
+    )
+    .await?;
+    validate_label(&body)?;
+
+    let mut transaction = pool.begin().await?;
+    let id = IssueLabel::insert(
+        project.inner.id,
+        body.name.trim(),
+        &body.color,
+        &body.description,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    IssueLabel::clear_project_cache(project.inner.id, &redis).await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "id": id
+    })))
+}
+
+// 修改项目标签
+pub async fn project_label_edit(
+    req: HttpRequest,
+    info: web::Path<(String, i32)>,
+    body: web::Json<LabelRequest>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let (project_id, label_id) = info.into_inner();
+    let project =
+        get_managed_project(&req, &project_id, &pool, &redis, &session_queue)
+            .await?;
+    validate_label(&body)?;
+
+    let mut transaction = pool.begin().await?;
+    let updated = IssueLabel::update(
+        label_id,
+        project.inner.id,
+        body.name.trim(),
+        &body.color,
+        &body.description,
+        &mut transaction,
+    )
+    .await?;
+    if !updated {
+        return Err(ApiError::NotFound);
+    }
+    let issues = IssueLabel::get_issue_ids(label_id, &mut *transaction).await?;
+    transaction.commit().await?;
+
+    IssueLabel::clear_project_cache(project.inner.id, &redis).await?;
+    Issue::clear_cache(&issues, &redis).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// 删除项目标签
+pub async fn project_label_delete(
+    req: HttpRequest,
+    info: web::Path<(String, i32)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let (project_id, label_id) = info.into_inner();
+    let project =
+        get_managed_project(&req, &project_id, &pool, &redis, &session_queue)
+            .await?;
+
+    let mut transaction = pool.begin().await?;
+    let issues =
+        IssueLabel::remove(label_id, project.inner.id, &mut transaction)
+            .await?;
+    transaction.commit().await?;
+
+    IssueLabel::clear_project_cache(project.inner.id, &redis).await?;
+    Issue::clear_cache(&issues, &redis).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// 获取项目的里程碑
+pub async fn milestones_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let project = get_visible_project(
+        &req,
+        &info.into_inner().0,
+        &pool,
+        &redis,
+        &session_queue,
+    )
+    .await?;
+
+    let milestones: Vec<MilestoneResponse> =
+        IssueMilestone::get_project(project.inner.id, &**pool)
+            .await?
+            .into_iter()
+            .map(|x| x.into())
+            .collect();
+
+    Ok(HttpResponse::Ok().json(json!({
+        "milestones": milestones
+    })))
+}
+
+// 创建里程碑
+pub async fn milestone_create(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    body: web::Json<MilestoneRequest>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let project = get_managed_project(
+        &req,
+        &info.into_inner().0,
+        &pool,
+        &redis,
+        &session_queue,
+    )
+    .await?;
+    validate_milestone(&body)?;
+
+    let mut transaction = pool.begin().await?;
+    let id = IssueMilestone::insert(
+        project.inner.id,
+        body.title.trim(),
+        &body.description,
+        body.version_number.as_deref(),
+        body.due_date,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "id": id
+    })))
+}
+
+// 修改里程碑
+pub async fn milestone_edit(
+    req: HttpRequest,
+    info: web::Path<(String, i32)>,
+    body: web::Json<MilestoneRequest>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let (project_id, milestone_id) = info.into_inner();
+    let project =
+        get_managed_project(&req, &project_id, &pool, &redis, &session_queue)
+            .await?;
+    validate_milestone(&body)?;
+
+    let mut transaction = pool.begin().await?;
+    let updated = IssueMilestone::update(
+        milestone_id,
+        project.inner.id,
+        body.title.trim(),
+        &body.description,
+        body.version_number.as_deref(),
+        body.due_date,
+        body.state.as_deref().unwrap_or("open"),
+        &mut transaction,
+    )
+    .await?;
+    if !updated {
+        return Err(ApiError::NotFound);
+    }
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// 删除里程碑
+pub async fn milestone_delete(
+    req: HttpRequest,
+    info: web::Path<(String, i32)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let (project_id, milestone_id) = info.into_inner();
+    let project =
+        get_managed_project(&req, &project_id, &pool, &redis, &session_queue)
+            .await?;
+
+    let mut transaction = pool.begin().await?;
+    let issues = IssueMilestone::remove(
+        milestone_id,
+        project.inner.id,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Issue::clear_cache(&issues, &redis).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// 获取项目的问题模板
+pub async fn templates_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let project = get_visible_project(
+        &req,
+        &info.into_inner().0,
+        &pool,
+        &redis,
+        &session_queue,
+    )
+    .await?;
+
+    let templates: Vec<TemplateResponse> =
+        IssueTemplate::get_project(project.inner.id, &**pool)
+            .await?
+            .into_iter()
+            .map(|x| x.into())
+            .collect();
+
+    Ok(HttpResponse::Ok().json(json!({
+        "templates": templates
+    })))
+}
+
+// 创建问题模板
+pub async fn template_create(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    body: web::Json<TemplateRequest>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let project = get_managed_project(
+        &req,
+        &info.into_inner().0,
+        &pool,
+        &redis,
+        &session_queue,
+    )
+    .await?;
+    validate_template(&body)?;
+
+    let mut transaction = pool.begin().await?;
+    let id = IssueTemplate::insert(
+        project.inner.id,
+        body.name.trim(),
+        &body.template_type,
+        &body.description,
+        &body.fields,
+        body.sort_order,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "id": id
+    })))
+}
+
+// 修改问题模板
+pub async fn template_edit(
+    req: HttpRequest,
+    info: web::Path<(String, i32)>,
+    body: web::Json<TemplateRequest>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let (project_id, template_id) = info.into_inner();
+    let project =
+        get_managed_project(&req, &project_id, &pool, &redis, &session_queue)
+            .await?;
+    validate_template(&body)?;
+
+    let mut transaction = pool.begin().await?;
+    let updated = IssueTemplate::update(
+        template_id,
+        project.inner.id,
+        body.name.trim(),
+        &body.template_type,
+        &body.description,
+        &body.fields,
+        body.sort_order,
+        &mut transaction,
+    )
+    .await?;
+    if !updated {
+        return Err(ApiError::NotFound);
+    }
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// 删除问题模板
+pub async fn template_delete(
+    req: HttpRequest,
+    info: web::Path<(String, i32)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let (project_id, template_id) = info.into_inner();
+    let project =
+        get_managed_project(&req, &project_id, &pool, &redis, &session_queue)
+            .await?;
+
+    let mut transaction = pool.begin().await?;
+    let removed =
+        IssueTemplate::remove(template_id, project.inner.id, &mut transaction)
+            .await?;
+    if !removed {
+        return Err(ApiError::NotFound);
+    }
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -6,6 +6,7 @@ use serde_json::json;
 pub mod analytics_get;
 pub mod bans;
 pub mod collections;
+pub mod dependencies;
 pub mod forum;
 pub mod images;
 pub mod notifications;
@@ -15,12 +16,15 @@ pub mod project_creation;
 pub mod projects;
 pub mod reports;
 pub mod statistics;
+pub mod subscriptions;
 pub mod tags;
 pub mod teams;
 pub mod threads;
+pub mod upload_sessions;
 pub mod users;
 pub mod version_creation;
 pub mod version_file;
+pub mod version_publish;
 pub mod versions;
 
 pub mod creator;
@@ -41,6 +45,7 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .wrap(default_cors())
             .configure(analytics_get::config)
             .configure(collections::config)
+            .configure(dependencies::config)
             .configure(images::config)
             .configure(notifications::config)
             .configure(organizations::config)
@@ -52,6 +57,7 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .configure(tags::config)
             .configure(teams::config)
             .configure(threads::config)
+            .configure(upload_sessions::config)
             // creator, payment_merchant, user_purchase 必须在 users 之前，
             // 因为 users 中的 user/{id} 会匹配 user/creator, user/payment, user/purchases
             .configure(creator::config)
@@ -63,6 +69,7 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .configure(versions::config)
             .configure(forum::config)
             .configure(issues::config)
+            .configure(subscriptions::config)
             .configure(bans::config)
             .configure(project_order::config),
     );
@@ -14,7 +14,10 @@ use validator::Validate;
 
 use super::ApiError;
 use crate::{
-    auth::{checks::ValidateAuthorized, get_user_from_headers},
+    auth::{
+        checks::ValidateAuthorized, get_user_from_headers,
+        oauth::oidc::SigningAlgorithm,
+    },
     database::{
         models::{
             DatabaseError, OAuthClientId, User, generate_oauth_client_id,
@@ -156,6 +159,14 @@ pub struct NewOAuthApp {
 
     #[validate(length(max = 255))]
     pub description: Option<String>,
+
+    /// 公开客户端（如桌面应用）不使用密钥，必须通过 PKCE 换取令牌
+    #[serde(default)]
+    pub is_public: bool,
+
+    /// ID 令牌的签名算法，默认 ES256
+    #[serde(default)]
+    pub id_token_signed_response_alg: SigningAlgorithm,
 }
 
 #[post("app")]
@@ -180,6 +191,13 @@ pub async fn oauth_client_create(
         CreateError::ValidationError(validation_errors_to_string(e, None))
     })?;
 
+    if !new_oauth_app.id_token_signed_response_alg.is_available() {
+        return Err(CreateError::InvalidInput(format!(
+            "服务器未配置 {} 签名密钥",
+            new_oauth_app.id_token_signed_response_alg.as_str()
+        )));
+    }
+
     let mut transaction = pool.begin().await?;
 
     let client_id = generate_oauth_client_id(&mut transaction).await?;
@@ -206,6 +224,11 @@ pub async fn oauth_client_create(
         url: new_oauth_app.url.clone(),
         description: new_oauth_app.description.clone(),
         secret_hash: client_secret_hash,
+        is_public: new_oauth_app.is_public,
+        id_token_signed_response_alg: new_oauth_app
+            .id_token_signed_response_alg
+            .as_str()
+            .to_string(),
     };
     client.clone().insert(&mut transaction).await?;
 
@@ -273,6 +296,8 @@ pub struct OAuthClientEdit {
 
     #[validate(length(max = 255))]
     pub description: Option<Option<String>>,
+
+    pub id_token_signed_response_alg: Option<SigningAlgorithm>,
 }
 
 #[patch("app/{id}")]
@@ -310,6 +335,7 @@ pub async fn oauth_client_edit(
             redirect_uris,
             url,
             description,
+            id_token_signed_response_alg,
         } = client_updates.into_inner();
         if let Some(name) = name {
             updated_client.name = name;
@@ -327,6 +353,17 @@ pub async fn oauth_client_edit(
             updated_client.description = description;
         }
 
+        if let Some(alg) = id_token_signed_response_alg {
+            if !alg.is_available() {
+                return Err(ApiError::InvalidInput(format!(
+                    "服务器未配置 {} 签名密钥",
+                    alg.as_str()
+                )));
+            }
+            updated_client.id_token_signed_response_alg =
+                alg.as_str().to_string();
+        }
+
         let mut transaction = pool.begin().await?;
         updated_client
             .update_editable_fields(&mut *transaction)
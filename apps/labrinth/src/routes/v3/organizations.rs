@@ -2,10 +2,11 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use super::ApiError;
-use crate::auth::{
-    checks::is_visible_organization, filter_visible_projects,
-    get_user_from_headers,
+use crate::auth::checks::{
+    check_pat_organization, check_pat_project, is_visible_organization,
 };
+use crate::auth::webauthn::check_organization_passkeys;
+use crate::auth::{filter_visible_projects, get_user_from_headers};
 use crate::database::models::team_item::TeamMember;
 use crate::database::models::{
     Organization, generate_organization_id, team_item,
@@ -233,6 +234,7 @@ pub async fn organization_create(
         icon_url: None,
         raw_icon_url: None,
         color: None,
+        require_passkey_2fa: false,
     };
     organization.clone().insert(&mut transaction).await?;
     transaction.commit().await?;
@@ -442,6 +444,8 @@ pub struct OrganizationEdit {
     pub slug: Option<String>,
     #[validate(length(min = 3, max = 64))]
     pub name: Option<String>,
+    /// 要求持有删除项目或收益权限的成员使用通行密钥作为第二因素
+    pub require_passkey_2fa: Option<bool>,
 }
 
 pub async fn organizations_edit(
@@ -470,6 +474,8 @@ pub async fn organizations_edit(
     let result =
         database::models::Organization::get(&string, &**pool, &redis).await?;
     if let Some(organization_item) = result {
+        check_pat_organization(&req, organization_item.id)?;
+
         let id = organization_item.id;
 
         let team_member = database::models::TeamMember::get_from_user_id(
@@ -634,6 +640,37 @@ pub async fn organizations_edit(
                 .await?;
             }
 
+            if let Some(require_passkey_2fa) =
+                new_organization.require_passkey_2fa
+            {
+                // 安全策略只能由组织所有者修改
+                if !user.role.is_admin()
+                    && !team_member.as_ref().is_some_and(|x| x.is_owner)
+                {
+                    return Err(ApiError::CustomAuthentication(
+                        "只有组织所有者可以修改通行密钥要求！".to_string(),
+                    ));
+                }
+
+                if require_passkey_2fa && !organization_item.require_passkey_2fa
+                {
+                    check_organization_passkeys(&organization_item, &pool)
+                        .await?;
+                }
+
+                sqlx::query!(
+                    "
+                    UPDATE organizations
+                    SET require_passkey_2fa = $1
+                    WHERE (id = $2)
+                    ",
+                    require_passkey_2fa,
+                    id as database::models::ids::OrganizationId,
+                )
+                .execute(&mut *transaction)
+                .await?;
+            }
+
             transaction.commit().await?;
             database::models::Organization::clear_cache(
                 organization_item.id,
@@ -677,6 +714,7 @@ pub async fn organization_delete(
             .ok_or_else(|| {
                 ApiError::InvalidInput("指定的组织不存在！".to_string())
             })?;
+    check_pat_organization(&req, organization.id)?;
 
     if !user.role.is_admin() {
         let team_member =
@@ -820,6 +858,8 @@ pub async fn organization_projects_add(
     )
     .await?
     .ok_or_else(|| ApiError::InvalidInput("指定的项目不存在！".to_string()))?;
+    check_pat_organization(&req, organization.id)?;
+    check_pat_project(&req, &project_item.inner)?;
     if project_item.inner.organization_id.is_some() {
         return Err(ApiError::InvalidInput(
             "指定的项目已由组织拥有！".to_string(),
@@ -963,6 +1003,7 @@ pub async fn organization_projects_remove(
             .ok_or_else(|| {
                 ApiError::InvalidInput("指定的组织不存在！".to_string())
             })?;
+    check_pat_organization(&req, organization.id)?;
 
     let project_item =
         database::models::Project::get(&project_id, &**pool, &redis)
@@ -1137,6 +1178,7 @@ pub async fn organization_icon_edit(
             .ok_or_else(|| {
                 ApiError::InvalidInput("指定的组织不存在！".to_string())
             })?;
+    check_pat_organization(&req, organization_item.id)?;
 
     if !user.role.is_mod() {
         let team_member = database::models::TeamMember::get_from_user_id(
@@ -1240,6 +1282,7 @@ pub async fn delete_organization_icon(
             .ok_or_else(|| {
                 ApiError::InvalidInput("指定的组织不存在！".to_string())
             })?;
+    check_pat_organization(&req, organization_item.id)?;
 
     if !user.role.is_mod() {
         let team_member = database::models::TeamMember::get_from_user_id(
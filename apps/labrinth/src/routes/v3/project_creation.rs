@@ -1138,7 +1138,7 @@ async fn project_create_inner(
             issues_type: 0,
             forum: None,
             translation_tracking: false,
-            translation_tracker: None,
+            translation_tracker: HashMap::new(),
             translation_source: None,
             is_paid: project_create_data.is_paid,
             user_has_purchased: None, // 新创建的项目不返回购买状态
@@ -8,6 +8,7 @@
 //! - POST/PATCH: 需要 PROJECT_WRITE scope、项目成员权限 EDIT_DETAILS、且是高级创作者
 
 use super::ApiError;
+use crate::auth::checks::check_pat_project;
 use crate::auth::get_user_from_headers;
 use crate::database::models::UserId as DBUserId;
 use crate::database::models::user_purchase_item::UserPurchase;
@@ -129,6 +130,7 @@ pub async fn set_pricing(
     let project = models::Project::get(&project_id_str, &**pool, &redis)
         .await?
         .ok_or_else(|| ApiError::InvalidInput("项目不存在".to_string()))?;
+    check_pat_project(&req, &project.inner)?;
 
     // 转换用户 ID 类型
     let db_user_id = DBUserId(current_user.id.0 as i64);
@@ -371,6 +373,7 @@ pub async fn revoke_purchase(
     let project = models::Project::get(&project_id_str, &**pool, &redis)
         .await?
         .ok_or_else(|| ApiError::InvalidInput("项目不存在".to_string()))?;
+    check_pat_project(&req, &project.inner)?;
 
     // 转换用户 ID 类型
     let db_user_id = DBUserId(current_user.id.0 as i64);
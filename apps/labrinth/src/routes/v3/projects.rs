@@ -2,7 +2,8 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::auth::checks::{
-    check_resource_ban, filter_visible_versions, is_visible_project,
+    check_pat_project, check_resource_ban, filter_visible_versions,
+    is_visible_project,
 };
 use crate::auth::{
     AuthenticationError, filter_visible_projects, get_user_from_headers,
@@ -11,6 +12,7 @@ use crate::database::models::notification_item::NotificationBuilder;
 use crate::database::models::project_item::{GalleryItem, ModCategory};
 use crate::database::models::project_pricing_item::ProjectPricing;
 use crate::database::models::thread_item::ThreadMessageBuilder;
+use crate::database::models::translation_coverage_item::TranslationCoverage;
 use crate::database::models::user_purchase_item::UserPurchase;
 use crate::database::models::{TeamMember, ids as db_ids, image_item};
 use crate::database::redis::RedisPool;
@@ -26,6 +28,9 @@ use crate::models::projects::{
 };
 use crate::models::teams::ProjectPermissions;
 use crate::models::threads::MessageBody;
+use crate::models::translation_coverage::{
+    TranslationCoverageResponse, TranslationCoverageSummaryResponse,
+};
 use crate::queue::moderation::AutomatedModerationQueue;
 use crate::queue::session::AuthQueue;
 use crate::routes::ApiError;
@@ -122,6 +127,14 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                         "translation_links",
                         web::get().to(get_translation_links),
                     )
+                    .route(
+                        "translation_coverage",
+                        web::get().to(translation_coverage_list),
+                    )
+                    .route(
+                        "translation_coverage/{version_id}",
+                        web::get().to(translation_coverage_get),
+                    )
                     // 定价路由
                     .route(
                         "pricing",
@@ -379,6 +392,7 @@ pub async fn project_edit(
     let string = info.into_inner().0;
     let result = db_models::Project::get(&string, &**pool, &redis).await?;
     if let Some(project_item) = result {
+        check_pat_project(&req, &project_item.inner)?;
         let id = project_item.inner.id;
 
         let (team_member, organization_team_member) =
@@ -1531,6 +1545,9 @@ pub async fn projects_edit(
             ProjectId(id.0 as u64)
         )));
     }
+    for project in &projects_data {
+        check_pat_project(&req, &project.inner)?;
+    }
 
     let team_ids = projects_data
         .iter()
@@ -1810,6 +1827,7 @@ pub async fn project_icon_edit(
         .ok_or_else(|| {
             ApiError::InvalidInput("指定的项目不存在!".to_string())
         })?;
+    check_pat_project(&req, &project_item.inner)?;
 
     if !user.role.is_mod() {
         let (team_member, organization_team_member) =
@@ -1921,6 +1939,7 @@ pub async fn delete_project_icon(
         .ok_or_else(|| {
             ApiError::InvalidInput("指定的项目不存在!".to_string())
         })?;
+    check_pat_project(&req, &project_item.inner)?;
 
     if !user.role.is_mod() {
         let (team_member, organization_team_member) =
@@ -2025,6 +2044,7 @@ pub async fn add_gallery_item(
         .ok_or_else(|| {
             ApiError::InvalidInput("指定的项目不存在!".to_string())
         })?;
+    check_pat_project(&req, &project_item.inner)?;
 
     if project_item.gallery_items.len() > 64
         && user.username.to_lowercase() != "bbsmc"
@@ -2266,6 +2286,7 @@ pub async fn edit_gallery_item(
         .ok_or_else(|| {
             ApiError::InvalidInput("指定的项目不存在!".to_string())
         })?;
+    check_pat_project(&req, &project_item.inner)?;
 
     if !user.role.is_mod() {
         let (team_member, organization_team_member) =
@@ -2426,6 +2447,7 @@ pub async fn delete_gallery_item(
         .ok_or_else(|| {
             ApiError::InvalidInput("指定的项目不存在!".to_string())
         })?;
+    check_pat_project(&req, &project_item.inner)?;
 
     if !user.role.is_mod() {
         let (team_member, organization_team_member) =
@@ -2534,6 +2556,7 @@ pub async fn project_delete(
         .ok_or_else(|| {
             ApiError::InvalidInput("指定的项目不存在!".to_string())
         })?;
+    check_pat_project(&req, &project.inner)?;
 
     if !user.role.is_admin() {
         let (team_member, organization_team_member) =
@@ -2969,6 +2992,8 @@ pub async fn project_forum_create(
             organization: None,
             organization_id: None,
             project_id: None,
+            edited_at: None,
+            accepted_post_id: None,
         };
         discussion.insert(&mut transaction).await?;
         discussion
@@ -3164,6 +3189,146 @@ pub async fn get_translation_links(
     }
 }
 
+#[derive(Deserialize)]
+pub struct TranslationCoverageQuery {
+    /// 目标语言代码，例如 zh_cn
+    pub language: Option<String>,
+}
+
+/// 获取汉化覆盖率报告对应的追踪项目及目标语言。
+/// 既可以通过被追踪的整合包查询，也可以通过其本地化资源（例如 `{slug}-cn`）查询，
+/// 后者的语言由本地化资源决定
+async fn get_coverage_project(
+    req: &HttpRequest,
+    id: &str,
+    language: Option<String>,
+    pool: &PgPool,
+    redis: &RedisPool,
+    session_queue: &AuthQueue,
+) -> Result<(db_models::project_item::QueryProject, Option<String>), ApiError> {
+    let language = language.map(|x| x.to_lowercase());
+
+    let user_option = get_user_from_headers(
+        req,
+        pool,
+        redis,
+        session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await
+    .map(|x| x.1)
+    .ok();
+
+    let project = db_models::Project::get(id, pool, redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    if !is_visible_project(&project.inner, &user_option, pool, false).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    if project.inner.translation_tracking {
+        return Ok((project, language));
+    }
+
+    let Some(source) = &project.inner.translation_source else {
+        return Err(ApiError::InvalidInput(
+            "该项目没有开启汉化追踪".to_string(),
+        ));
+    };
+
+    let tracked = db_models::Project::get(source, pool, redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    if !is_visible_project(&tracked.inner, &user_option, pool, false).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    let language = tracked
+        .inner
+        .translation_tracker
+        .iter()
+        .find(|(_, slug)| {
+            project
+                .inner
+                .slug
+                .as_ref()
+                .is_some_and(|x| x.eq_ignore_ascii_case(slug))
+        })
+        .map(|(code, _)| code.clone())
+        .or(language);
+
+    Ok((tracked, language))
+}
+
+/// 汉化追踪项目各版本的汉化覆盖率摘要
+pub async fn translation_coverage_list(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    web::Query(query): web::Query<TranslationCoverageQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let (project, language) = get_coverage_project(
+        &req,
+        &info.0,
+        query.language,
+        &pool,
+        &redis,
+        &session_queue,
+    )
+    .await?;
+
+    // 未指定语言时返回所有语言的报告
+    let summaries =
+        TranslationCoverage::get_project_summaries(project.inner.id, &**pool)
+            .await?
+            .into_iter()
+            .filter(|x| language.as_ref().is_none_or(|l| *l == x.language_code))
+            .map(TranslationCoverageSummaryResponse::from)
+            .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(json!({
+        "project_id": ProjectId::from(project.inner.id),
+        "language": language,
+        "translation_projects": project.inner.translation_tracker,
+        "versions": summaries,
+    })))
+}
+
+/// 单个版本的完整汉化覆盖率报告
+pub async fn translation_coverage_get(
+    req: HttpRequest,
+    info: web::Path<(String, String)>,
+    web::Query(query): web::Query<TranslationCoverageQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, version_id) = info.into_inner();
+    let (project, language) = get_coverage_project(
+        &req,
+        &id,
+        query.language,
+        &pool,
+        &redis,
+        &session_queue,
+    )
+    .await?;
+    let language = language.unwrap_or_else(|| "zh_cn".to_string());
+
+    let version_id = models::ids::base62_impl::parse_base62(&version_id)
+        .map(|x| db_ids::VersionId(x as i64))
+        .map_err(|_| ApiError::InvalidInput("无效的版本ID".to_string()))?;
+
+    let coverage = TranslationCoverage::get(version_id, &language, &**pool)
+        .await?
+        .filter(|x| x.project_id == project.inner.id)
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(HttpResponse::Ok().json(TranslationCoverageResponse::from(coverage)))
+}
+
 // ==================== 用户购买状态辅助函数 ====================
 
 /// 获取单个项目的用户购买状态（考虑权限）
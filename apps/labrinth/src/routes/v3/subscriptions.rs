@@ -0,0 +1,286 @@
+use crate::auth::checks::is_visible_project;
+use crate::auth::get_user_from_headers;
+use crate::database;
+use crate::database::models::forum::Discussion;
+use crate::database::models::issues::Issue;
+use crate::database::models::subscription_item::{
+    Subscription, SubscriptionTarget,
+};
+use crate::database::models::{ProjectId, UserId};
+use crate::database::redis::RedisPool;
+use crate::models::ids::base62_impl::parse_base62;
+use crate::models::pats::Scopes;
+use crate::models::subscriptions::{
+    SubscriptionResponse, SubscriptionSettings,
+};
+use crate::models::users::User;
+use crate::queue::session::AuthQueue;
+use crate::routes::ApiError;
+use actix_web::{HttpRequest, HttpResponse, web};
+use serde_json::json;
+use sqlx::PgPool;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("subscriptions")
+            .route("", web::get().to(subscriptions_get))
+            .route("settings", web::get().to(settings_get))
+            .route("settings", web::patch().to(settings_edit))
+            .route("{type}/{id}", web::get().to(subscription_get))
+            .route("{type}/{id}", web::post().to(subscribe))
+            .route("{type}/{id}", web::delete().to(unsubscribe)),
+    );
+}
+
+fn parse_target(
+    target_type: &str,
+    target_id: &str,
+) -> Result<(SubscriptionTarget, i64), ApiError> {
+    let target = SubscriptionTarget::from_string(target_type)
+        .ok_or_else(|| ApiError::InvalidInput("无效的关注类型".to_string()))?;
+    let id = parse_base62(target_id)
+        .map_err(|_| ApiError::InvalidInput("无效的关注对象ID".to_string()))?
+        as i64;
+
+    Ok((target, id))
+}
+
+/// 检查关注对象是否存在，以及所属项目对当前用户是否可见
+async fn check_target_visible(
+    target: SubscriptionTarget,
+    target_id: i64,
+    user: &User,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(), ApiError> {
+    let project_id = match target {
+        SubscriptionTarget::Discussion => {
+            let discussion = Discussion::get_id(target_id, pool, redis)
+                .await?
+                .ok_or(ApiError::NotFound)?;
+            if discussion.inner.deleted {
+                return Err(ApiError::NotFound);
+            }
+            discussion.inner.project_id
+        }
+        SubscriptionTarget::Issue => {
+            let issue = Issue::get_id(target_id, pool, redis)
+                .await?
+                .ok_or(ApiError::NotFound)?;
+            if issue.inner.deleted {
+                return Err(ApiError::NotFound);
+            }
+            Some(issue.inner.mod_id)
+        }
+        SubscriptionTarget::ProjectIssues => Some(ProjectId(target_id)),
+        SubscriptionTarget::Wiki => {
+            let wiki = sqlx::query!(
+                "SELECT mod_id FROM wikis WHERE id = $1",
+                target_id
+            )
+            .fetch_optional(pool)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+            Some(ProjectId(wiki.mod_id))
+        }
+    };
+
+    if let Some(project_id) = project_id {
+        let project =
+            database::models::Project::get_id(project_id, pool, redis)
+                .await?
+                .ok_or(ApiError::NotFound)?;
+        if !is_visible_project(&project.inner, &Some(user.clone()), pool, false)
+            .await?
+        {
+            return Err(ApiError::NotFound);
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn subscriptions_get(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::NOTIFICATION_READ]),
+    )
+    .await?
+    .1;
+
+    let subscriptions = Subscription::get_user(UserId::from(user.id), &**pool)
+        .await?
+        .into_iter()
+        .map(SubscriptionResponse::from)
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(subscriptions))
+}
+
+pub async fn subscription_get(
+    req: HttpRequest,
+    info: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::NOTIFICATION_READ]),
+    )
+    .await?
+    .1;
+
+    let (target_type, target_id) = info.into_inner();
+    let (target, target_id) = parse_target(&target_type, &target_id)?;
+
+    let subscribed = sqlx::query!(
+        "
+        SELECT EXISTS(
+            SELECT 1 FROM subscriptions
+            WHERE user_id = $1 AND target_type = $2 AND target_id = $3 AND active = true
+        )
+        ",
+        UserId::from(user.id).0,
+        target.as_str(),
+        target_id
+    )
+    .fetch_one(&**pool)
+    .await?
+    .exists
+    .unwrap_or(false);
+
+    Ok(HttpResponse::Ok().json(json!({
+        "subscribed": subscribed
+    })))
+}
+
+pub async fn subscribe(
+    req: HttpRequest,
+    info: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::NOTIFICATION_WRITE]),
+    )
+    .await?
+    .1;
+
+    let (target_type, target_id) = info.into_inner();
+    let (target, target_id) = parse_target(&target_type, &target_id)?;
+
+    check_target_visible(target, target_id, &user, &pool, &redis).await?;
+
+    let mut transaction = pool.begin().await?;
+    Subscription::subscribe(
+        UserId::from(user.id),
+        target,
+        target_id,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn unsubscribe(
+    req: HttpRequest,
+    info: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::NOTIFICATION_WRITE]),
+    )
+    .await?
+    .1;
+
+    let (target_type, target_id) = info.into_inner();
+    let (target, target_id) = parse_target(&target_type, &target_id)?;
+
+    // 取消关注会保留记录，避免之后参与讨论时被重新自动关注
+    let mut transaction = pool.begin().await?;
+    Subscription::unsubscribe(
+        UserId::from(user.id),
+        target,
+        target_id,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn settings_get(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::NOTIFICATION_READ]),
+    )
+    .await?
+    .1;
+
+    let muted = Subscription::get_muted(UserId::from(user.id), &**pool).await?;
+
+    Ok(HttpResponse::Ok().json(SubscriptionSettings { muted }))
+}
+
+pub async fn settings_edit(
+    req: HttpRequest,
+    body: web::Json<SubscriptionSettings>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::NOTIFICATION_WRITE]),
+    )
+    .await?
+    .1;
+
+    let mut transaction = pool.begin().await?;
+    Subscription::set_muted(
+        UserId::from(user.id),
+        &body.muted,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -1,5 +1,8 @@
-use crate::auth::checks::{check_resource_ban, is_visible_project};
+use crate::auth::checks::{
+    check_pat_team, check_resource_ban, is_visible_project,
+};
 use crate::auth::get_user_from_headers;
+use crate::auth::webauthn::check_passkey_2fa_requirement;
 use crate::database::Project;
 use crate::database::models::notification_item::NotificationBuilder;
 use crate::database::models::team_item::TeamAssociationId;
@@ -354,6 +357,7 @@ pub async fn join_team(
 
     // 检查资源封禁
     check_resource_ban(&current_user, &pool).await?;
+    check_pat_team(&req, team_id, &pool, &redis).await?;
 
     let member = TeamMember::get_from_user_id_pending(
         team_id,
@@ -368,6 +372,16 @@ pub async fn join_team(
                 "您已经是此团队的一员".to_string(),
             ));
         }
+        check_passkey_2fa_requirement(
+            team_id,
+            current_user.id.into(),
+            member.permissions,
+            member.is_owner,
+            &pool,
+            &redis,
+        )
+        .await?;
+
         let mut transaction = pool.begin().await?;
 
         // 将 Team Member 的 Accepted 设置为 True
@@ -446,6 +460,7 @@ pub async fn add_team_member(
 
     // 检查资源封禁
     check_resource_ban(&current_user, &pool).await?;
+    check_pat_team(&req, team_id, &pool, &redis).await?;
 
     let team_association = Team::get_association(team_id, &**pool)
         .await?
@@ -692,6 +707,7 @@ pub async fn edit_team_member(
 
     // 检查资源封禁
     check_resource_ban(&current_user, &pool).await?;
+    check_pat_team(&req, id, &pool, &redis).await?;
 
     let team_association =
         Team::get_association(id, &**pool).await?.ok_or_else(|| {
@@ -837,6 +853,21 @@ pub async fn edit_team_member(
         ));
     }
 
+    // 尚未接受邀请的成员在加入团队时检查
+    if let Some(new_permissions) = edit_member.permissions
+        && edit_member_db.accepted
+    {
+        check_passkey_2fa_requirement(
+            id,
+            user_id,
+            new_permissions,
+            edit_member_db.is_owner,
+            &pool,
+            &redis,
+        )
+        .await?;
+    }
+
     if let Some(role) = &edit_member.role {
         let risk = crate::util::risk::check_text_risk(
             role,
@@ -900,6 +931,7 @@ pub async fn transfer_ownership(
 
     // 检查资源封禁
     check_resource_ban(&current_user, &pool).await?;
+    check_pat_team(&req, id.into(), &pool, &redis).await?;
 
     // 禁止转移项目团队的所有权，这些团队由组织拥有
     // 这些团队由组织所有者拥有，必须首先从组织中删除
@@ -952,6 +984,16 @@ pub async fn transfer_ownership(
         ));
     }
 
+    check_passkey_2fa_requirement(
+        id.into(),
+        new_owner.user_id.into(),
+        ProjectPermissions::all(),
+        true,
+        &pool,
+        &redis,
+    )
+    .await?;
+
     let mut transaction = pool.begin().await?;
 
     // 以下是修改 is_owner 的唯一位置
@@ -1061,6 +1103,7 @@ pub async fn remove_team_member(
 
     // 检查资源封禁
     check_resource_ban(&current_user, &pool).await?;
+    check_pat_team(&req, id, &pool, &redis).await?;
 
     let team_association =
         Team::get_association(id, &**pool).await?.ok_or_else(|| {
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use crate::auth::checks::is_authorized_thread;
 use crate::auth::{check_forum_ban, get_user_from_headers};
 use crate::database;
 use crate::database::models::image_item;
@@ -7,7 +8,7 @@ use crate::database::models::notification_item::NotificationBuilder;
 use crate::database::models::thread_item::ThreadMessageBuilder;
 use crate::database::redis::RedisPool;
 use crate::file_hosting::FileHost;
-use crate::models::ids::ThreadMessageId;
+use crate::models::ids::{ProjectId, ReportId, ThreadMessageId};
 use crate::models::images::{Image, ImageContext};
 use crate::models::notifications::NotificationBody;
 use crate::models::pats::Scopes;
@@ -16,6 +17,9 @@ use crate::models::threads::{MessageBody, Thread, ThreadId, ThreadType};
 use crate::models::users::User;
 use crate::queue::session::AuthQueue;
 use crate::routes::ApiError;
+use crate::util::mentions::{
+    MentionSource, MentionVisibility, notify_mentions,
+};
 use actix_web::{HttpRequest, HttpResponse, web};
 use futures::TryStreamExt;
 use serde::Deserialize;
@@ -33,147 +37,6 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.route("threads", web::get().to(threads_get));
 }
 
-pub async fn is_authorized_thread(
-    thread: &database::models::Thread,
-    user: &User,
-    pool: &PgPool,
-) -> Result<bool, ApiError> {
-    if user.role.is_mod() {
-        return Ok(true);
-    }
-
-    let user_id: database::models::UserId = user.id.into();
-    Ok(match thread.type_ {
-        ThreadType::Report => {
-            if let Some(report_id) = thread.report_id {
-                let report_exists = sqlx::query!(
-                    "SELECT EXISTS(SELECT 1 FROM reports WHERE id = $1 AND reporter = $2)",
-                    report_id as database::models::ids::ReportId,
-                    user_id as database::models::ids::UserId,
-                )
-                .fetch_one(pool)
-                .await?
-                .exists;
-
-                report_exists.unwrap_or(false)
-            } else {
-                false
-            }
-        }
-        ThreadType::Project => {
-            if let Some(project_id) = thread.project_id {
-                let project_exists = sqlx::query!(
-                    "SELECT EXISTS(SELECT 1 FROM mods m INNER JOIN team_members tm ON tm.team_id = m.team_id AND tm.user_id = $2 WHERE m.id = $1)",
-                    project_id as database::models::ids::ProjectId,
-                    user_id as database::models::ids::UserId,
-                )
-                    .fetch_one(pool)
-                    .await?
-                    .exists;
-
-                if !project_exists.unwrap_or(false) {
-                    let org_exists = sqlx::query!(
-                        "SELECT EXISTS(SELECT 1 FROM mods m INNER JOIN organizations o ON m.organization_id = o.id INNER JOIN team_members tm ON tm.team_id = o.team_id AND tm.user_id = $2 WHERE m.id = $1)",
-                        project_id as database::models::ids::ProjectId,
-                        user_id as database::models::ids::UserId,
-                    )
-                        .fetch_one(pool)
-                        .await?
-                        .exists;
-
-                    org_exists.unwrap_or(false)
-                } else {
-                    true
-                }
-            } else {
-                false
-            }
-        }
-        ThreadType::DirectMessage => thread.members.contains(&user_id),
-        ThreadType::VersionLink => {
-            // 获取版本链接信息
-            let link_info = sqlx::query!(
-                r#"
-                SELECT vlv.version_id, vlv.joining_version_id, v1.mod_id as translation_project_id, v2.mod_id as original_project_id
-                FROM version_link_version vlv
-                INNER JOIN versions v1 ON v1.id = vlv.version_id
-                INNER JOIN versions v2 ON v2.id = vlv.joining_version_id
-                WHERE vlv.thread_id = $1
-                "#,
-                thread.id.0
-            )
-            .fetch_optional(pool)
-            .await?;
-
-            if let Some(link) = link_info {
-                // 检查用户是否是翻译项目的成员
-                let translation_member = sqlx::query!(
-                    "SELECT EXISTS(SELECT 1 FROM mods m INNER JOIN team_members tm ON tm.team_id = m.team_id AND tm.user_id = $2 WHERE m.id = $1)",
-                    link.translation_project_id,
-                    user_id as database::models::ids::UserId,
-                )
-                .fetch_one(pool)
-                .await?
-                .exists
-                .unwrap_or(false);
-
-                if translation_member {
-                    return Ok(true);
-                }
-
-                // 检查用户是否是原项目的成员
-
-                sqlx::query!(
-                    "SELECT EXISTS(SELECT 1 FROM mods m INNER JOIN team_members tm ON tm.team_id = m.team_id AND tm.user_id = $2 WHERE m.id = $1)",
-                    link.original_project_id,
-                    user_id as database::models::ids::UserId,
-                )
-                .fetch_one(pool)
-                .await?
-                .exists
-                .unwrap_or(false)
-            } else {
-                false
-            }
-        }
-        ThreadType::BanAppeal => {
-            // 封禁申诉线程：申诉人可以访问
-            if let Some(ban_appeal_id) = thread.ban_appeal_id {
-                let appeal_exists = sqlx::query!(
-                    "SELECT EXISTS(SELECT 1 FROM user_ban_appeals WHERE id = $1 AND user_id = $2)",
-                    ban_appeal_id as database::models::ids::BanAppealId,
-                    user_id as database::models::ids::UserId,
-                )
-                .fetch_one(pool)
-                .await?
-                .exists;
-
-                appeal_exists.unwrap_or(false)
-            } else {
-                false
-            }
-        }
-        ThreadType::CreatorApplication => {
-            // 创作者申请线程：申请人可以访问
-            if let Some(creator_application_id) = thread.creator_application_id
-            {
-                let application_exists = sqlx::query!(
-                    "SELECT EXISTS(SELECT 1 FROM creator_applications WHERE id = $1 AND user_id = $2)",
-                    creator_application_id as database::models::ids::CreatorApplicationId,
-                    user_id as database::models::ids::UserId,
-                )
-                .fetch_one(pool)
-                .await?
-                .exists;
-
-                application_exists.unwrap_or(false)
-            } else {
-                false
-            }
-        }
-    })
-}
-
 pub async fn filter_authorized_threads(
     threads: Vec<database::models::Thread>,
     user: &User,
@@ -609,6 +472,49 @@ pub async fn thread_send_message(
         .insert(&mut transaction)
         .await?;
 
+        if let MessageBody::Text { body, private, .. } = &new_message.body {
+            // 项目线程以项目名作为标题，其他线程由通知按来源类型展示
+            let (title, link) = if let Some(project_id) = thread.project_id {
+                let title = database::models::Project::get_id(
+                    project_id, &**pool, &redis,
+                )
+                .await?
+                .map(|x| x.inner.name)
+                .unwrap_or_default();
+                (
+                    title,
+                    format!(
+                        "/project/{}/moderation",
+                        ProjectId::from(project_id)
+                    ),
+                )
+            } else if let Some(report_id) = thread.report_id {
+                (
+                    String::new(),
+                    format!("/dashboard/report/{}", ReportId::from(report_id)),
+                )
+            } else {
+                (String::new(), "/dashboard/notifications".to_string())
+            };
+            notify_mentions(
+                body,
+                &user,
+                MentionVisibility::Thread {
+                    thread: &thread,
+                    private: *private,
+                },
+                MentionSource {
+                    source_type: "thread_message",
+                    title: &title,
+                    link: &link,
+                },
+                &mut transaction,
+                &pool,
+                &redis,
+            )
+            .await?;
+        }
+
         if let Some(project_id) = thread.project_id {
             let project =
                 database::models::Project::get_id(project_id, &**pool, &redis)
@@ -0,0 +1,382 @@
+use super::ApiError;
+use crate::auth::{check_resource_ban, get_user_from_headers};
+use crate::database::models::ids::UserId as DBUserId;
+use crate::database::models::upload_session_item::{
+    UPLOAD_SESSION_EXPIRY, UploadSession,
+};
+use crate::database::redis::RedisPool;
+use crate::models::pats::Scopes;
+use crate::models::users::User;
+use crate::queue::session::AuthQueue;
+use crate::util::routes::read_from_payload;
+use crate::util::validate::validation_errors_to_string;
+use actix_web::{HttpRequest, HttpResponse, web};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use sqlx::PgPool;
+use std::io::{Read, Seek, SeekFrom, Write};
+use validator::Validate;
+
+// 与 multipart 上传的单文件上限保持一致
+const MAX_UPLOAD_SIZE: u64 = 1024 * (1 << 20);
+const MAX_CHUNK_SIZE: usize = 32 * (1 << 20);
+// 每个用户同时保留的会话数与暂存总大小上限
+const MAX_USER_SESSIONS: usize = 16;
+const MAX_USER_STAGED_SIZE: u64 = 4 * 1024 * (1 << 20);
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("upload", web::post().to(upload_session_create));
+    cfg.service(
+        web::scope("upload")
+            .route("{id}", web::get().to(upload_session_get))
+            .route("{id}", web::put().to(upload_session_chunk))
+            .route("{id}", web::delete().to(upload_session_delete))
+            .route("{id}/finalize", web::post().to(upload_session_finalize)),
+    );
+}
+
+#[derive(Serialize)]
+pub struct UploadSessionResponse {
+    pub id: String,
+    pub file_name: String,
+    pub size: u64,
+    pub offset: u64,
+    pub completed: bool,
+    pub sha1: Option<String>,
+    pub sha512: Option<String>,
+    pub expires: DateTime<Utc>,
+}
+
+impl From<UploadSession> for UploadSessionResponse {
+    fn from(session: UploadSession) -> Self {
+        UploadSessionResponse {
+            id: session.id,
+            file_name: session.file_name,
+            size: session.size,
+            offset: session.offset,
+            completed: session.completed,
+            sha1: session.sha1,
+            sha512: session.sha512,
+            expires: session.created
+                + chrono::Duration::seconds(UPLOAD_SESSION_EXPIRY),
+        }
+    }
+}
+
+async fn get_upload_user(
+    req: &HttpRequest,
+    pool: &PgPool,
+    redis: &RedisPool,
+    session_queue: &AuthQueue,
+) -> Result<User, ApiError> {
+    Ok(get_user_from_headers(
+        req,
+        pool,
+        redis,
+        session_queue,
+        Some(&[Scopes::VERSION_CREATE]),
+    )
+    .await?
+    .1)
+}
+
+/// 获取属于当前用户的上传会话，其他用户的会话视为不存在
+async fn get_owned_session(
+    id: &str,
+    user: &User,
+    redis: &RedisPool,
+) -> Result<UploadSession, ApiError> {
+    UploadSession::get(id, redis)
+        .await?
+        .filter(|x| x.user_id == DBUserId::from(user.id))
+        .ok_or(ApiError::NotFound)
+}
+
+#[derive(Deserialize, Validate)]
+pub struct UploadSessionCreate {
+    #[validate(length(min = 1, max = 255))]
+    pub file_name: String,
+    pub size: u64,
+}
+
+pub async fn upload_session_create(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+    create: web::Json<UploadSessionCreate>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_upload_user(&req, &pool, &redis, &session_queue).await?;
+
+    // 检查用户是否被资源类封禁
+    check_resource_ban(&user, &pool).await?;
+
+    create.0.validate().map_err(|err| {
+        ApiError::Validation(validation_errors_to_string(err, None))
+    })?;
+
+    if create.file_name.contains('/') || create.file_name.contains('\\') {
+        return Err(ApiError::InvalidInput("文件名不能包含斜杠！".to_string()));
+    }
+
+    let file_extension = create
+        .file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext)
+        .ok_or_else(|| ApiError::InvalidInput("文件缺少扩展名".to_string()))?;
+    if crate::util::ext::project_file_type(file_extension).is_none() {
+        return Err(ApiError::InvalidInput(format!(
+            "不支持的文件类型: {file_extension}"
+        )));
+    }
+
+    if create.size == 0 || create.size > MAX_UPLOAD_SIZE {
+        return Err(ApiError::InvalidInput(
+            "项目文件超出了 1GB 的上限。请联系版主或管理员以请求上传更大文件的权限。"
+                .to_string(),
+        ));
+    }
+
+    let user_id = DBUserId::from(user.id);
+    let Some(user_lock) = UploadSession::lock_user(user_id, &redis).await?
+    else {
+        return Err(ApiError::InvalidInput(
+            "正在创建其他上传会话，请稍后重试".to_string(),
+        ));
+    };
+
+    let sessions = UploadSession::get_user_sessions(user_id, &redis).await?;
+    if sessions.len() >= MAX_USER_SESSIONS {
+        return Err(ApiError::InvalidInput(format!(
+            "最多同时保留 {MAX_USER_SESSIONS} 个上传会话，请先完成或删除已有会话"
+        )));
+    }
+    if sessions.iter().map(|x| x.size).sum::<u64>() + create.size
+        > MAX_USER_STAGED_SIZE
+    {
+        return Err(ApiError::InvalidInput(
+            "上传会话的暂存总大小超出了 4GB 的上限，请先完成或删除已有会话"
+                .to_string(),
+        ));
+    }
+
+    let create = create.into_inner();
+    let session = UploadSession::new(user_id, create.file_name, create.size);
+
+    let path = session.staging_path();
+    web::block(move || {
+        std::fs::create_dir_all(UploadSession::staging_dir())?;
+        std::fs::File::create(path).map(|_| ())
+    })
+    .await
+    .map_err(|_| {
+        ApiError::InvalidInput("创建上传暂存文件失败".to_string())
+    })??;
+
+    session.insert(&redis).await?;
+    user_lock.release().await?;
+
+    Ok(HttpResponse::Ok().json(UploadSessionResponse::from(session)))
+}
+
+pub async fn upload_session_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_upload_user(&req, &pool, &redis, &session_queue).await?;
+    let session =
+        get_owned_session(&info.into_inner().0, &user, &redis).await?;
+
+    Ok(HttpResponse::Ok().json(UploadSessionResponse::from(session)))
+}
+
+#[derive(Deserialize)]
+pub struct ChunkQuery {
+    pub offset: u64,
+}
+
+pub async fn upload_session_chunk(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    query: web::Query<ChunkQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+    mut payload: web::Payload,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_upload_user(&req, &pool, &redis, &session_queue).await?;
+    let id = info.into_inner().0;
+    // 先确认会话归属，避免为他人的会话加锁
+    get_owned_session(&id, &user, &redis).await?;
+
+    // 连接中断导致 future 被丢弃时，锁会在 drop 时释放
+    let Some(lock) = UploadSession::lock(&id, &redis).await? else {
+        return Err(ApiError::InvalidInput(
+            "该上传会话正在写入其他分块，请稍后重试".to_string(),
+        ));
+    };
+
+    let result = upload_session_chunk_inner(
+        &id,
+        &user,
+        query.offset,
+        &mut payload,
+        &redis,
+    )
+    .await;
+    lock.release().await?;
+
+    Ok(HttpResponse::Ok().json(UploadSessionResponse::from(result?)))
+}
+
+async fn upload_session_chunk_inner(
+    id: &str,
+    user: &User,
+    offset: u64,
+    payload: &mut web::Payload,
+    redis: &RedisPool,
+) -> Result<UploadSession, ApiError> {
+    // 加锁后重新读取，确保偏移是最新的
+    let mut session = get_owned_session(id, user, redis).await?;
+
+    if session.completed {
+        return Err(ApiError::InvalidInput("该上传会话已完成".to_string()));
+    }
+
+    // 重复发送已接收的分块时直接返回当前进度，便于客户端重试
+    if offset < session.offset {
+        return Ok(session);
+    }
+    if offset > session.offset {
+        return Err(ApiError::InvalidInput(format!(
+            "分块偏移不连续，服务器已接收 {} 字节",
+            session.offset
+        )));
+    }
+
+    let chunk =
+        read_from_payload(payload, MAX_CHUNK_SIZE, "单个分块不能超过 32MB")
+            .await?;
+    if chunk.is_empty() {
+        return Err(ApiError::InvalidInput("分块内容为空".to_string()));
+    }
+    if session.offset + chunk.len() as u64 > session.size {
+        return Err(ApiError::InvalidInput(
+            "分块超出了创建会话时声明的文件大小".to_string(),
+        ));
+    }
+
+    // 从偏移处写入并截断，丢弃之前中断写入留下的残余数据
+    let path = session.staging_path();
+    let new_offset = offset + chunk.len() as u64;
+    web::block(move || -> std::io::Result<()> {
+        let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        file.write_all(&chunk)?;
+        file.set_len(new_offset)?;
+        file.sync_data()
+    })
+    .await
+    .map_err(|_| ApiError::InvalidInput("写入上传分块失败".to_string()))??;
+
+    session.offset = new_offset;
+    session.upsert(redis).await?;
+
+    Ok(session)
+}
+
+#[derive(Deserialize)]
+pub struct UploadSessionFinalize {
+    pub sha1: String,
+    pub sha512: Option<String>,
+}
+
+pub async fn upload_session_finalize(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+    finalize: web::Json<UploadSessionFinalize>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_upload_user(&req, &pool, &redis, &session_queue).await?;
+    let id = info.into_inner().0;
+    let mut session = get_owned_session(&id, &user, &redis).await?;
+
+    if session.completed {
+        return Ok(
+            HttpResponse::Ok().json(UploadSessionResponse::from(session))
+        );
+    }
+
+    if session.offset != session.size {
+        return Err(ApiError::InvalidInput(format!(
+            "文件尚未上传完成，服务器已接收 {}/{} 字节",
+            session.offset, session.size
+        )));
+    }
+
+    let path = session.staging_path();
+    let (sha1, sha512) = web::block(move || -> std::io::Result<_> {
+        let mut file = std::fs::File::open(path)?;
+        let mut sha1 = sha1::Sha1::new();
+        let mut sha512 = sha2::Sha512::new();
+        let mut buffer = vec![0; 1 << 20];
+        loop {
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            sha1.update(&buffer[..read]);
+            sha512.update(&buffer[..read]);
+        }
+        Ok((
+            format!("{:x}", sha1.finalize()),
+            format!("{:x}", sha512.finalize()),
+        ))
+    })
+    .await
+    .map_err(|_| ApiError::InvalidInput("校验上传文件失败".to_string()))??;
+
+    let finalize = finalize.into_inner();
+    if !finalize.sha1.eq_ignore_ascii_case(&sha1)
+        || finalize
+            .sha512
+            .is_some_and(|x| !x.eq_ignore_ascii_case(&sha512))
+    {
+        // 内容已损坏，重置会话以便重新上传
+        session.offset = 0;
+        session.upsert(&redis).await?;
+
+        return Err(ApiError::InvalidInput(
+            "文件哈希不匹配，请重新上传".to_string(),
+        ));
+    }
+
+    session.completed = true;
+    session.sha1 = Some(sha1);
+    session.sha512 = Some(sha512);
+    session.upsert(&redis).await?;
+
+    Ok(HttpResponse::Ok().json(UploadSessionResponse::from(session)))
+}
+
+pub async fn upload_session_delete(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_upload_user(&req, &pool, &redis, &session_queue).await?;
+    let session =
+        get_owned_session(&info.into_inner().0, &user, &redis).await?;
+
+    session.remove(&redis).await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
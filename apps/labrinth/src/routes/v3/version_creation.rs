@@ -1,18 +1,24 @@
 use super::project_creation::{CreateError, UploadedFile};
+use crate::auth::checks::check_pat_project;
 use crate::auth::{check_resource_ban, get_user_from_headers};
+use crate::database::models::issues::IssueMilestone;
 use crate::database::models::loader_fields::{
     LoaderField, LoaderFieldEnumValue, VersionField,
 };
+use crate::database::models::modpack_file_item::{
+    ModpackFile, ModpackFileBuilder, ModpackFileKind,
+};
 use crate::database::models::notification_item::NotificationBuilder;
+use crate::database::models::upload_session_item::UploadSession;
 use crate::database::models::version_item::{
     DependencyBuilder, QueryDisk, VersionBuilder, VersionFileBuilder,
 };
 use crate::database::models::{self, Organization, image_item};
 use crate::database::redis::RedisPool;
-use crate::file_hosting::{FileHost, S3PrivateHost};
+use crate::file_hosting::{FileHost, FileHostingError, S3PrivateHost};
 use crate::models::images::{Image, ImageContext, ImageId};
 use crate::models::notifications::NotificationBody;
-use crate::models::pack::PackFileHash;
+use crate::models::pack::{PackDiff, PackFileEntry, PackFileHash};
 use crate::models::pats::Scopes;
 use crate::models::projects::{
     Dependency, FileType, Loader, ProjectId, Version, VersionFile, VersionId,
@@ -28,6 +34,7 @@ use crate::validate::{ValidationResult, validate_file};
 use actix_multipart::{Field, Multipart};
 use actix_web::web::Data;
 use actix_web::{HttpRequest, HttpResponse, web};
+use bytes::{BufMut, BytesMut};
 use chrono::Utc;
 use futures::stream::StreamExt;
 use itertools::Itertools;
@@ -35,6 +42,7 @@ use serde::{Deserialize, Serialize};
 use sha2::Digest;
 use sqlx::postgres::PgPool;
 use std::collections::{HashMap, HashSet};
+use std::io::Read;
 use std::sync::Arc;
 use validator::Validate;
 
@@ -48,6 +56,10 @@ pub struct InitialVersionData {
     pub project_id: Option<ProjectId>,
     #[validate(length(min = 0, max = 256))]
     pub file_parts: Vec<String>,
+    // 通过分块上传会话提交的文件，primary_file 与 file_types 中以会话 ID 引用
+    #[validate(length(max = 16))]
+    #[serde(default)]
+    pub upload_sessions: Vec<String>,
     #[validate(
         length(min = 1, max = 32),
         regex(path = *crate::util::validate::RE_URL_SAFE)
@@ -95,6 +107,9 @@ pub struct InitialVersionData {
 
     pub disk_only: bool,
     pub disk_urls: Option<Vec<QueryDisk>>,
+    // 根据整合包文件变化，在更新日志末尾附加与上一版本相比的模组和配置变化
+    #[serde(default)]
+    pub generate_pack_changelog: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -117,6 +132,7 @@ pub async fn version_create(
 ) -> Result<HttpResponse, CreateError> {
     let mut transaction = client.begin().await?;
     let mut uploaded_files = Vec::new();
+    let mut consumed_sessions = Vec::new();
 
     let result = version_create_inner(
         req,
@@ -126,6 +142,7 @@ pub async fn version_create(
         &***file_host,
         private_file_host.as_ref().as_ref().map(|h| h.as_ref()),
         &mut uploaded_files,
+        &mut consumed_sessions,
         &client,
         &session_queue,
         &moderation_queue,
@@ -146,6 +163,7 @@ pub async fn version_create(
         }
     } else {
         transaction.commit().await?;
+        remove_upload_sessions(consumed_sessions, &redis).await;
     }
 
     result
@@ -160,6 +178,7 @@ async fn version_create_inner(
     file_host: &dyn FileHost,
     private_file_host: Option<&S3PrivateHost>,
     uploaded_files: &mut Vec<UploadedFile>,
+    consumed_sessions: &mut Vec<UploadSession>,
     pool: &PgPool,
     session_queue: &AuthQueue,
     moderation_queue: &AutomatedModerationQueue,
@@ -196,9 +215,12 @@ async fn version_create_inner(
         }
 
         let result = async {
-            let content_disposition = field.content_disposition().cloned().ok_or_else(|| {
-                CreateError::MissingValueError("缺少 Content-Disposition".to_string())
-            })?;
+            let content_disposition =
+                field.content_disposition().cloned().ok_or_else(|| {
+                    CreateError::MissingValueError(
+                        "缺少 Content-Disposition".to_string(),
+                    )
+                })?;
             let name = content_disposition.get_name().ok_or_else(|| {
                 CreateError::MissingValueError("缺少内容名称".to_string())
             })?;
@@ -209,281 +231,53 @@ async fn version_create_inner(
                     data.extend_from_slice(&chunk?);
                 }
 
-                let version_create_data: InitialVersionData = serde_json::from_slice(&data)?;
-                initial_version_data = Some(version_create_data);
-                let version_create_data = initial_version_data.as_ref().unwrap();
-                if version_create_data.project_id.is_none() {
-                    return Err(CreateError::MissingValueError(
-                        "缺少项目id".to_string(),
-                    ));
-                }
-
-                version_create_data.validate().map_err(|err| {
-                    CreateError::ValidationError(validation_errors_to_string(err, None))
-                })?;
-
-                if !version_create_data.status.can_be_requested() {
-                    return Err(CreateError::InvalidInput(
-                        "指定的状态不能被请求".to_string(),
-                    ));
-                }
-                if version_create_data.disk_only && version_create_data.disk_urls.is_some() && version_create_data.disk_urls.clone().unwrap().len() > 3{
-                    return Err(CreateError::InvalidInput(
-                        "最多提供三个网盘".to_string(),
-                    ));
-                }
-
-                let project_id: models::ProjectId = version_create_data.project_id.unwrap().into();
-
-                // 确保项目存在并获取项目信息
-                let project = models::Project::get_id(project_id, &mut **transaction, redis)
-                    .await?
-                    .ok_or_else(|| CreateError::InvalidInput(
-                        "提供的项目id无效".to_string(),
-                    ))?;
-
-                // 保存项目的付费状态，用于后续设置文件的 is_private
-                project_is_paid = project.inner.is_paid;
-                project_slug = project.inner.slug.clone();
-
-                // 检查创建此版本的用户是否是项目团队成员
-                // 项目版本正在添加。
-                let team_member = models::TeamMember::get_from_user_id_project(
-                    project_id,
-                    user.id.into(),
-                    false,
-                    &mut **transaction,
-                )
-                .await?;
-
-                // 获取附加的组织，如果存在，并获取成员项目权限
-                let organization = models::Organization::get_associated_organization_project_id(
-                    project_id,
-                    &mut **transaction,
-                )
-                .await?;
-
-                let organization_team_member = if let Some(organization) = &organization {
-                    models::TeamMember::get_from_user_id(
-                        organization.team_id,
-                        user.id.into(),
-                        &mut **transaction,
-                    )
-                    .await?
-                } else {
-                    None
-                };
-
-                let permissions = ProjectPermissions::get_permissions_by_role(
-                    &user.role,
-                    &team_member,
-                    &organization_team_member,
-                )
-                .unwrap_or_default();
-
-                if !permissions.contains(ProjectPermissions::UPLOAD_VERSION) {
-                    return Err(CreateError::CustomAuthenticationError(
-                        "您没有权限上传此版本!".to_string(),
-                    ));
-                }
-
-                let version_id: VersionId = models::generate_version_id(transaction).await?.into();
-
-                let all_loaders =
-                    models::loader_fields::Loader::list(&mut **transaction, redis).await?;
-                let loaders = version_create_data
-                    .loaders
-                    .iter()
-                    .map(|x| {
-                        all_loaders
-                            .iter()
-                            .find(|y| y.loader == x.0)
-                            .cloned()
-                            .ok_or_else(|| CreateError::InvalidLoader(x.0.clone()))
-                    })
-                    .collect::<Result<Vec<_>, _>>()?;
-                selected_loaders = Some(loaders.clone());
-                let loader_ids: Vec<models::LoaderId> = loaders.iter().map(|y| y.id).collect_vec();
-
-                // 付费项目只允许上传插件类型的版本
-                if project.inner.is_paid {
-                    // 检查所有加载器是否都支持 plugin 类型
-                    let all_plugin_loaders = loaders.iter().all(|loader| {
-                        loader.supported_project_types.contains(&"plugin".to_string())
-                    });
-
-                    if !all_plugin_loaders {
-                        return Err(CreateError::InvalidInput(
-                            "付费资源只能上传插件类型的版本。请选择插件加载器（如 bukkit、spigot、paper、purpur、folia、bungeecord、waterfall、velocity 等）".to_string(),
-                        ));
-                    }
-                }
-
-                let loader_fields =
-                    LoaderField::get_fields(&loader_ids, &mut **transaction, redis).await?;
-                let mut loader_field_enum_values = LoaderFieldEnumValue::list_many_loader_fields(
-                    &loader_fields,
-                    &mut **transaction,
+                let version_create_data: InitialVersionData =
+                    serde_json::from_slice(&data)?;
+                let prepared = prepare_version(
+                    &req,
+                    &user,
+                    &version_create_data,
+                    transaction,
                     redis,
                 )
                 .await?;
-                let version_fields = try_create_version_fields(
-                    version_id,
-                    &version_create_data.fields,
-                    &loader_fields,
-                    &mut loader_field_enum_values,
-                )?;
-
-                let dependencies = version_create_data
-                    .dependencies
-                    .iter()
-                    .map(|d| models::version_item::DependencyBuilder {
-                        version_id: d.version_id.map(|x| x.into()),
-                        project_id: d.project_id.map(|x| x.into()),
-                        dependency_type: d.dependency_type.to_string(),
-                        file_name: None,
-                    })
-                    .collect::<Vec<_>>();
-
-                // 处理版本链接
-                let mut version_links = Vec::new();
-
-                if let Some(links) = &version_create_data.version_links {
-                    for link in links {
-                        // 获取被翻译版本的信息
-                        let target_version_id: models::VersionId = link.joining_version_id.into();
-                        let target_version = models::Version::get(target_version_id, &mut **transaction, redis).await?;
-
-                        let mut auto_approve = false;
-
-                        if let Some(target_version) = target_version {
-                            // 获取目标项目的团队成员信息
-                            let target_project_id = target_version.inner.project_id;
-                            let target_team = models::TeamMember::get_from_user_id_project(
-                                target_project_id,
-                                user.id.into(),
-                                false,  // allow_pending
-                                &mut **transaction,
-                            ).await?;
-
-                            // 获取目标项目的组织团队成员信息（如果有）
-                            let target_project = models::Project::get_id(target_project_id, &mut **transaction, redis).await?;
-                            let target_org_team = if let Some(target_project) = target_project {
-                                if let Some(org_id) = target_project.inner.organization_id {
-                                    models::TeamMember::get_from_user_id_organization(
-                                        org_id,
-                                        user.id.into(),
-                                        false,  // allow_pending
-                                        &mut **transaction,
-                                    ).await?
-                                } else {
-                                    None
-                                }
-                            } else {
-                                None
-                            };
-
-                            // 判断是否需要自动审核通过
-                            if user.role.is_admin() {
-                                log::info!(
-                                    "Version link auto-approved for translation version {} targeting version {} in project {}: User {} is an ADMIN",
-                                    version_id, target_version_id.0, target_project_id.0, user.username
-                                );
-                                auto_approve = true;
-                            } else if user.role.is_mod() {
-                                log::info!(
-                                    "Version link auto-approved for translation version {} targeting version {} in project {}: User {} is a MODERATOR",
-                                    version_id, target_version_id.0, target_project_id.0, user.username
-                                );
-                                auto_approve = true;
-                            } else if target_team.as_ref().is_some_and(|m| m.accepted && m.permissions.contains(ProjectPermissions::UPLOAD_VERSION)) {
-                                log::info!(
-                                    "Version link auto-approved for translation version {} targeting version {} in project {}: User {} is a TARGET PROJECT TEAM MEMBER with UPLOAD_VERSION permission",
-                                    version_id, target_version_id.0, target_project_id.0, user.username
-                                );
-                                auto_approve = true;
-                            } else if target_org_team.as_ref().is_some_and(|m| m.accepted && m.permissions.contains(ProjectPermissions::UPLOAD_VERSION)) {
-                                log::info!(
-                                    "Version link auto-approved for translation version {} targeting version {} in project {}: User {} is a TARGET ORGANIZATION TEAM MEMBER with UPLOAD_VERSION permission",
-                                    version_id, target_version_id.0, target_project_id.0, user.username
-                                );
-                                auto_approve = true;
-                            } else {
-                                log::info!(
-                                    "Version link requires approval for translation version {} targeting version {} in project {}: User {} does not have auto-approval permissions in the target project",
-                                    version_id, target_version_id.0, target_project_id.0, user.username
-                                );
-                            }
-                        } else {
-                            log::warn!(
-                                "Target version {} not found for version link from version {}",
-                                target_version_id.0, version_id
-                            );
-                        }
-
-                        version_links.push(models::version_item::VersionLinkBuilder {
-                            joining_version_id: link.joining_version_id.into(),
-                            link_type: link.link_type.clone(),
-                            language_code: link.language_code.clone(),
-                            description: link.description.clone(),
-                            approval_status: if auto_approve {
-                                "approved".to_string()
-                            } else {
-                                "pending".to_string()
-                            },
-                        });
-                    }
-                }
-
-                // let disk_url = None;
-                // if version_create_data.disk_only && version_create_data.disk_urls.is_some(){
-                //     disk_url = version_create_data.disk_urls.clone().unwrap();
-                // }
-                version_builder = Some(VersionBuilder {
-                    version_id: version_id.into(),
-                    project_id,
-                    author_id: user.id.into(),
-                    name: version_create_data.version_title.clone(),
-                    version_number: version_create_data.version_number.clone(),
-                    changelog: version_create_data.version_body.clone().unwrap_or_default(),
-                    files: Vec::new(),
-                    dependencies,
-                    version_links,
-                    loaders: loader_ids,
-                    version_fields,
-                    version_type: version_create_data.release_channel.to_string(),
-                    featured: version_create_data.featured,
-                    status: version_create_data.status,
-                    requested_status: None,
-                    ordering: version_create_data.ordering,
-                    disk_url: version_create_data.disk_urls.clone(),
-                });
+                project_is_paid = prepared.is_paid;
+                project_slug = prepared.slug;
+                selected_loaders = Some(prepared.loaders);
+                version_builder = Some(prepared.builder);
+                initial_version_data = Some(version_create_data);
 
                 return Ok(());
             }
 
             let version = version_builder.as_mut().ok_or_else(|| {
-                CreateError::InvalidInput("`data` field 必须在文件字段之前".to_string())
+                CreateError::InvalidInput(
+                    "`data` field 必须在文件字段之前".to_string(),
+                )
             })?;
 
-
-
             let loaders = selected_loaders.as_ref().ok_or_else(|| {
-                CreateError::InvalidInput("`data` field 必须在文件字段之前".to_string())
+                CreateError::InvalidInput(
+                    "`data` field 必须在文件字段之前".to_string(),
+                )
             })?;
             let loaders = loaders
                 .iter()
                 .map(|x| Loader(x.loader.clone()))
                 .collect::<Vec<_>>();
 
-            let version_data = initial_version_data
-                .clone()
-                .ok_or_else(|| CreateError::InvalidInput("`data` field 是必需的".to_string()))?;
+            let version_data =
+                initial_version_data.clone().ok_or_else(|| {
+                    CreateError::InvalidInput(
+                        "`data` field 是必需的".to_string(),
+                    )
+                })?;
 
-            let existing_file_names = version.files.iter().map(|x| x.filename.clone()).collect();
+            let existing_file_names =
+                version.files.iter().map(|x| x.filename.clone()).collect();
 
             if version_data.file_parts.is_empty() && version_data.disk_only {
-                return Ok(())
+                return Ok(());
             }
 
             if !version_data.disk_only && version_data.file_parts.is_empty() {
@@ -496,7 +290,8 @@ async fn version_create_inner(
                 &mut field,
                 file_host,
                 private_file_host,
-                version_data.file_parts.len(),
+                version_data.file_parts.len()
+                    + version_data.upload_sessions.len(),
                 uploaded_files,
                 &mut version.files,
                 &mut version.dependencies,
@@ -533,10 +328,375 @@ async fn version_create_inner(
     let version_data = initial_version_data.ok_or_else(|| {
         CreateError::InvalidInput("`data` field 是必需的".to_string())
     })?;
-    let builder = version_builder.ok_or_else(|| {
+    let mut builder = version_builder.ok_or_else(|| {
         CreateError::InvalidInput("`data` field 是必需的".to_string())
     })?;
 
+    upload_session_files(
+        &user,
+        &version_data,
+        &mut builder,
+        selected_loaders
+            .iter()
+            .flatten()
+            .map(|x| Loader(x.loader.clone()))
+            .collect(),
+        project_is_paid,
+        file_host,
+        private_file_host,
+        uploaded_files,
+        consumed_sessions,
+        &cdn_url,
+        transaction,
+        redis,
+    )
+    .await?;
+
+    let project_id = builder.project_id;
+    let response = insert_version(
+        &user,
+        version_data,
+        builder,
+        selected_loaders.unwrap_or_default(),
+        transaction,
+        redis,
+    )
+    .await?;
+    after_version_published(
+        project_id,
+        &project_slug,
+        &response,
+        pool,
+        moderation_queue,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// 已通过校验、尚未写入文件的版本
+pub struct PreparedVersion {
+    pub builder: VersionBuilder,
+    pub loaders: Vec<models::loader_fields::Loader>,
+    pub is_paid: bool,
+    pub slug: Option<String>,
+}
+
+/// 校验版本数据与上传权限，并构建尚未包含文件的 VersionBuilder
+pub async fn prepare_version(
+    req: &HttpRequest,
+    user: &crate::models::users::User,
+    version_create_data: &InitialVersionData,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<PreparedVersion, CreateError> {
+    if version_create_data.project_id.is_none() {
+        return Err(CreateError::MissingValueError("缺少项目id".to_string()));
+    }
+
+    version_create_data.validate().map_err(|err| {
+        CreateError::ValidationError(validation_errors_to_string(err, None))
+    })?;
+
+    if !version_create_data.status.can_be_requested() {
+        return Err(CreateError::InvalidInput(
+            "指定的状态不能被请求".to_string(),
+        ));
+    }
+    if version_create_data.disk_only
+        && version_create_data.disk_urls.is_some()
+        && version_create_data.disk_urls.clone().unwrap().len() > 3
+    {
+        return Err(CreateError::InvalidInput("最多提供三个网盘".to_string()));
+    }
+
+    let project_id: models::ProjectId =
+        version_create_data.project_id.unwrap().into();
+
+    // 确保项目存在并获取项目信息
+    let project =
+        models::Project::get_id(project_id, &mut **transaction, redis)
+            .await?
+            .ok_or_else(|| {
+                CreateError::InvalidInput("提供的项目id无效".to_string())
+            })?;
+    check_pat_project(req, &project.inner)?;
+
+    // 检查创建此版本的用户是否是项目团队成员
+    // 项目版本正在添加。
+    let team_member = models::TeamMember::get_from_user_id_project(
+        project_id,
+        user.id.into(),
+        false,
+        &mut **transaction,
+    )
+    .await?;
+
+    // 获取附加的组织，如果存在，并获取成员项目权限
+    let organization =
+        models::Organization::get_associated_organization_project_id(
+            project_id,
+            &mut **transaction,
+        )
+        .await?;
+
+    let organization_team_member = if let Some(organization) = &organization {
+        models::TeamMember::get_from_user_id(
+            organization.team_id,
+            user.id.into(),
+            &mut **transaction,
+        )
+        .await?
+    } else {
+        None
+    };
+
+    let permissions = ProjectPermissions::get_permissions_by_role(
+        &user.role,
+        &team_member,
+        &organization_team_member,
+    )
+    .unwrap_or_default();
+
+    if !permissions.contains(ProjectPermissions::UPLOAD_VERSION) {
+        return Err(CreateError::CustomAuthenticationError(
+            "您没有权限上传此版本!".to_string(),
+        ));
+    }
+
+    let version_id: VersionId =
+        models::generate_version_id(transaction).await?.into();
+
+    let all_loaders =
+        models::loader_fields::Loader::list(&mut **transaction, redis).await?;
+    let loaders = version_create_data
+        .loaders
+        .iter()
+        .map(|x| {
+            all_loaders
+                .iter()
+                .find(|y| y.loader == x.0)
+                .cloned()
+                .ok_or_else(|| CreateError::InvalidLoader(x.0.clone()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let loader_ids: Vec<models::LoaderId> =
+        loaders.iter().map(|y| y.id).collect_vec();
+
+    // 付费项目只允许上传插件类型的版本
+    if project.inner.is_paid {
+        // 检查所有加载器是否都支持 plugin 类型
+        let all_plugin_loaders = loaders.iter().all(|loader| {
+            loader
+                .supported_project_types
+                .contains(&"plugin".to_string())
+        });
+
+        if !all_plugin_loaders {
+            return Err(CreateError::InvalidInput(
+                "付费资源只能上传插件类型的版本。请选择插件加载器（如 bukkit、spigot、paper、purpur、folia、bungeecord、waterfall、velocity 等）".to_string(),
+            ));
+        }
+    }
+
+    let loader_fields =
+        LoaderField::get_fields(&loader_ids, &mut **transaction, redis).await?;
+    let mut loader_field_enum_values =
+        LoaderFieldEnumValue::list_many_loader_fields(
+            &loader_fields,
+            &mut **transaction,
+            redis,
+        )
+        .await?;
+    let version_fields = try_create_version_fields(
+        version_id,
+        &version_create_data.fields,
+        &loader_fields,
+        &mut loader_field_enum_values,
+    )?;
+
+    let dependencies = version_create_data
+        .dependencies
+        .iter()
+        .map(|d| models::version_item::DependencyBuilder {
+            version_id: d.version_id.map(|x| x.into()),
+            project_id: d.project_id.map(|x| x.into()),
+            dependency_type: d.dependency_type.to_string(),
+            file_name: None,
+        })
+        .collect::<Vec<_>>();
+
+    // 处理版本链接
+    let mut version_links = Vec::new();
+
+    if let Some(links) = &version_create_data.version_links {
+        for link in links {
+            // 获取被翻译版本的信息
+            let target_version_id: models::VersionId =
+                link.joining_version_id.into();
+            let target_version = models::Version::get(
+                target_version_id,
+                &mut **transaction,
+                redis,
+            )
+            .await?;
+
+            let mut auto_approve = false;
+
+            if let Some(target_version) = target_version {
+                // 获取目标项目的团队成员信息
+                let target_project_id = target_version.inner.project_id;
+                let target_team = models::TeamMember::get_from_user_id_project(
+                    target_project_id,
+                    user.id.into(),
+                    false, // allow_pending
+                    &mut **transaction,
+                )
+                .await?;
+
+                // 获取目标项目的组织团队成员信息（如果有）
+                let target_project = models::Project::get_id(
+                    target_project_id,
+                    &mut **transaction,
+                    redis,
+                )
+                .await?;
+                let target_org_team = if let Some(target_project) =
+                    target_project
+                {
+                    if let Some(org_id) = target_project.inner.organization_id {
+                        models::TeamMember::get_from_user_id_organization(
+                            org_id,
+                            user.id.into(),
+                            false, // allow_pending
+                            &mut **transaction,
+                        )
+                        .await?
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                };
+
+                // 判断是否需要自动审核通过
+                if user.role.is_admin() {
+                    log::info!(
+                        "Version link auto-approved for translation version {} targeting version {} in project {}: User {} is an ADMIN",
+                        version_id,
+                        target_version_id.0,
+                        target_project_id.0,
+                        user.username
+                    );
+                    auto_approve = true;
+                } else if user.role.is_mod() {
+                    log::info!(
+                        "Version link auto-approved for translation version {} targeting version {} in project {}: User {} is a MODERATOR",
+                        version_id,
+                        target_version_id.0,
+                        target_project_id.0,
+                        user.username
+                    );
+                    auto_approve = true;
+                } else if target_team.as_ref().is_some_and(|m| {
+                    m.accepted
+                        && m.permissions
+                            .contains(ProjectPermissions::UPLOAD_VERSION)
+                }) {
+                    log::info!(
+                        "Version link auto-approved for translation version {} targeting version {} in project {}: User {} is a TARGET PROJECT TEAM MEMBER with UPLOAD_VERSION permission",
+                        version_id,
+                        target_version_id.0,
+                        target_project_id.0,
+                        user.username
+                    );
+                    auto_approve = true;
+                } else if target_org_team.as_ref().is_some_and(|m| {
+                    m.accepted
+                        && m.permissions
+                            .contains(ProjectPermissions::UPLOAD_VERSION)
+                }) {
+                    log::info!(
+                        "Version link auto-approved for translation version {} targeting version {} in project {}: User {} is a TARGET ORGANIZATION TEAM MEMBER with UPLOAD_VERSION permission",
+                        version_id,
+                        target_version_id.0,
+                        target_project_id.0,
+                        user.username
+                    );
+                    auto_approve = true;
+                } else {
+                    log::info!(
+                        "Version link requires approval for translation version {} targeting version {} in project {}: User {} does not have auto-approval permissions in the target project",
+                        version_id,
+                        target_version_id.0,
+                        target_project_id.0,
+                        user.username
+                    );
+                }
+            } else {
+                log::warn!(
+                    "Target version {} not found for version link from version {}",
+                    target_version_id.0,
+                    version_id
+                );
+            }
+
+            version_links.push(models::version_item::VersionLinkBuilder {
+                joining_version_id: link.joining_version_id.into(),
+                link_type: link.link_type.clone(),
+                language_code: link.language_code.clone(),
+                description: link.description.clone(),
+                approval_status: if auto_approve {
+                    "approved".to_string()
+                } else {
+                    "pending".to_string()
+                },
+            });
+        }
+    }
+
+    // let disk_url = None;
+    // if version_create_data.disk_only && version_create_data.disk_urls.is_some(){
+    //     disk_url = version_create_data.disk_urls.clone().unwrap();
+    // }
+    let builder = VersionBuilder {
+        version_id: version_id.into(),
+        project_id,
+        author_id: user.id.into(),
+        name: version_create_data.version_title.clone(),
+        version_number: version_create_data.version_number.clone(),
+        changelog: version_create_data.version_body.clone().unwrap_or_default(),
+        files: Vec::new(),
+        dependencies,
+        version_links,
+        loaders: loader_ids,
+        version_fields,
+        version_type: version_create_data.release_channel.to_string(),
+        featured: version_create_data.featured,
+        status: version_create_data.status,
+        requested_status: None,
+        ordering: version_create_data.ordering,
+        disk_url: version_create_data.disk_urls.clone(),
+    };
+
+    Ok(PreparedVersion {
+        builder,
+        loaders,
+        // 保存项目的付费状态，用于后续设置文件的 is_private
+        is_paid: project.inner.is_paid,
+        slug: project.inner.slug.clone(),
+    })
+}
+
+/// 写入已上传文件的版本，并处理关注者通知、整合包变更日志、里程碑和变更日志图片
+pub async fn insert_version(
+    user: &crate::models::users::User,
+    version_data: InitialVersionData,
+    builder: VersionBuilder,
+    loaders: Vec<models::loader_fields::Loader>,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<Version, CreateError> {
     if builder.files.is_empty() && !version_data.disk_only {
         return Err(CreateError::InvalidInput(
             "必须上传一个文件！".to_string(),
@@ -573,7 +733,7 @@ async fn version_create_inner(
     .insert_many(users, &mut *transaction, redis)
     .await?;
 
-    let loader_structs = selected_loaders.unwrap_or_default();
+    let loader_structs = loaders;
     let (all_project_types, all_games): (Vec<String>, Vec<String>) =
         loader_structs.iter().fold((vec![], vec![]), |mut acc, x| {
             acc.0.extend_from_slice(&x.supported_project_types);
@@ -634,7 +794,7 @@ async fn version_create_inner(
         })
         .unwrap_or_default();
 
-    let response = Version {
+    let mut response = Version {
         id: builder.version_id.into(),
         project_id: builder.project_id.into(),
         author_id: user.id,
@@ -661,8 +821,66 @@ async fn version_create_inner(
     };
 
     let project_id = builder.project_id;
+    let builder_version_id = builder.version_id;
     builder.insert(transaction).await?;
 
+    if version_data.generate_pack_changelog
+        && let Some(base_version_id) = ModpackFile::get_previous_version(
+            builder_version_id,
+            &mut **transaction,
+        )
+        .await?
+    {
+        let base =
+            ModpackFile::get_version(base_version_id, &mut **transaction)
+                .await?;
+        let target =
+            ModpackFile::get_version(builder_version_id, &mut **transaction)
+                .await?;
+        let diff = PackDiff::new(
+            base_version_id.into(),
+            base.into_iter().map(PackFileEntry::from).collect(),
+            response.id,
+            target.into_iter().map(PackFileEntry::from).collect(),
+        );
+
+        if !diff.is_empty() {
+            let base_version_number = sqlx::query!(
+                "SELECT version_number FROM versions WHERE id = $1",
+                base_version_id as models::VersionId
+            )
+            .fetch_one(&mut **transaction)
+            .await?
+            .version_number;
+
+            let section = diff.to_markdown(&base_version_number);
+            response.changelog = if response.changelog.trim().is_empty() {
+                section
+            } else {
+                format!("{}\n\n{}", response.changelog, section)
+            };
+
+            sqlx::query!(
+                "UPDATE versions SET changelog = $1 WHERE id = $2",
+                response.changelog,
+                builder_version_id as models::VersionId
+            )
+            .execute(&mut **transaction)
+            .await?;
+        }
+    }
+
+    // 版本发布后关闭关联该版本号的里程碑
+    if response.status.is_listed() {
+        IssueMilestone::close_for_version(
+            project_id,
+            &response.version_number,
+            response.id.into(),
+            transaction,
+        )
+        .await?;
+    }
+
     // 清除版本链接目标版本的缓存（新建版本时）
     for target_version_id in target_version_ids_to_clear {
         if let Some(target_version) =
@@ -711,6 +929,17 @@ async fn version_create_inner(
 
     models::Project::clear_cache(project_id, None, Some(true), redis).await?;
 
+    Ok(response)
+}
+
+/// 版本发布后的审核队列与 IndexNow 通知
+pub async fn after_version_published(
+    project_id: models::ProjectId,
+    project_slug: &Option<String>,
+    response: &Version,
+    pool: &PgPool,
+    moderation_queue: &AutomatedModerationQueue,
+) -> Result<(), CreateError> {
     let project_status = sqlx::query!(
         "SELECT status FROM mods WHERE id = $1",
         project_id as models::ProjectId,
@@ -728,7 +957,7 @@ async fn version_create_inner(
     if let Some(ref ps) = project_status
         && ProjectStatus::from_string(&ps.status).is_searchable()
         && let (Some(slug), Some(project_type)) =
-            (&project_slug, response.project_types.first())
+            (project_slug, response.project_types.first())
     {
         crate::util::indexnow::notify_version(
             project_type,
@@ -737,7 +966,7 @@ async fn version_create_inner(
         );
     }
 
-    Ok(HttpResponse::Ok().json(response))
+    Ok(())
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -849,6 +1078,7 @@ async fn upload_file_to_version_inner(
     )
     .await?
     .ok_or_else(|| CreateError::InvalidInput("提供的项目id无效".to_string()))?;
+    check_pat_project(&req, &project.inner)?;
 
     let project_is_paid = project.inner.is_paid;
 
@@ -1027,7 +1257,45 @@ pub async fn upload_file(
     is_paid_project: bool,
 ) -> Result<(), CreateError> {
     let (file_name, file_extension) = get_name_ext(content_disposition)?;
+    check_file_name(file_name, file_extension, &other_file_names)?;
+
+    let data = read_from_field(
+        field, 1024 * (1 << 20),
+        "项目文件超出了 1GB 的上限。请联系版主或管理员以请求上传更大文件的权限。"
+    ).await?;
 
+    upload_file_data(
+        data,
+        file_name,
+        file_extension,
+        file_host,
+        private_file_host,
+        total_files_len,
+        uploaded_files,
+        version_files,
+        dependencies,
+        cdn_url,
+        project_id,
+        version_id,
+        version_fields,
+        loaders,
+        ignore_primary,
+        force_primary,
+        file_type,
+        transaction,
+        redis,
+        username,
+        is_paid_project,
+    )
+    .await
+}
+
+/// 检查文件名是否可用，返回文件的 Content-Type
+pub fn check_file_name(
+    file_name: &str,
+    file_extension: &str,
+    other_file_names: &[String],
+) -> Result<&'static str, CreateError> {
     if other_file_names.contains(&format!("{}.{}", file_name, file_extension)) {
         return Err(CreateError::InvalidInput(
             "此文件在这之前已经被上传到 BBSMC 过，无法重复上传".to_string(),
@@ -1040,17 +1308,18 @@ pub async fn upload_file(
         ));
     }
 
-    let content_type = crate::util::ext::project_file_type(file_extension)
-        .ok_or_else(|| {
-            CreateError::InvalidFileType(file_extension.to_string())
-        })?;
-
-    let data = read_from_field(
-        field, 1024 * (1 << 20),
-        "项目文件超出了 1GB 的上限。请联系版主或管理员以请求上传更大文件的权限。"
-    ).await?;
+    crate::util::ext::project_file_type(file_extension)
+        .ok_or_else(|| CreateError::InvalidFileType(file_extension.to_string()))
+}
 
-    let hash = format!("{:x}", sha1::Sha1::digest(&data));
+/// 检查文件是否已被其他项目上传过
+pub async fn check_file_hash_unused(
+    data: &[u8],
+    project_id: ProjectId,
+    username: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), CreateError> {
+    let hash = format!("{:x}", sha1::Sha1::digest(data));
     let exists = sqlx::query!(
         "
         SELECT EXISTS(SELECT 1 FROM hashes h
@@ -1073,6 +1342,41 @@ pub async fn upload_file(
         ));
     }
 
+    Ok(())
+}
+
+/// 校验并上传已读取的文件内容，写入版本文件列表
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_file_data(
+    data: BytesMut,
+    file_name: &str,
+    file_extension: &str,
+    file_host: &dyn FileHost,
+    private_file_host: Option<&S3PrivateHost>,
+    total_files_len: usize,
+    uploaded_files: &mut Vec<UploadedFile>,
+    version_files: &mut Vec<VersionFileBuilder>,
+    dependencies: &mut Vec<DependencyBuilder>,
+    cdn_url: &str,
+    project_id: ProjectId,
+    version_id: VersionId,
+    version_fields: &[VersionField],
+    loaders: Vec<Loader>,
+    ignore_primary: bool,
+    force_primary: bool,
+    file_type: Option<FileType>,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+    username: String,
+    is_paid_project: bool,
+) -> Result<(), CreateError> {
+    let content_type = crate::util::ext::project_file_type(file_extension)
+        .ok_or_else(|| {
+            CreateError::InvalidFileType(file_extension.to_string())
+        })?;
+
+    check_file_hash_unused(&data, project_id, &username, transaction).await?;
+
     let validation_result = validate_file(
         data.clone().into(),
         file_extension.to_string(),
@@ -1084,9 +1388,36 @@ pub async fn upload_file(
     )
     .await?;
 
+    // 记录整合包的文件列表，用于版本之间的文件差异对比
+    let mut pack_files = Vec::new();
+    if let ValidationResult::PassWithPackDataAndFiles {
+        ref format,
+        ref override_files,
+        ..
+    } = validation_result
+    {
+        for file in &format.files {
+            if let Some(sha1) = file.hashes.get(&PackFileHash::Sha1) {
+                pack_files.push(ModpackFileBuilder {
+                    path: file.path.to_string(),
+                    kind: ModpackFileKind::Index,
+                    sha1: sha1.as_bytes().to_vec(),
+                });
+            }
+        }
+        for (path, sha1) in override_files {
+            pack_files.push(ModpackFileBuilder {
+                path: path.clone(),
+                kind: ModpackFileKind::from_override_path(path),
+                sha1: sha1.as_bytes().to_vec(),
+            });
+        }
+    }
+
     if let ValidationResult::PassWithPackDataAndFiles {
         ref format,
         ref files,
+        ..
     } = validation_result
         && dependencies.is_empty()
     {
@@ -1235,11 +1566,134 @@ pub async fn upload_file(
         size: upload_data.content_length,
         file_type,
         is_private: use_private,
+        pack_files,
     });
 
     Ok(())
 }
 
+/// 读取已完成的分块上传会话，返回会话及暂存的文件内容
+pub async fn read_upload_session(
+    id: &str,
+    user: &crate::models::users::User,
+    redis: &RedisPool,
+) -> Result<(UploadSession, BytesMut), CreateError> {
+    let session = UploadSession::get(id, redis)
+        .await?
+        .filter(|x| x.user_id == models::UserId::from(user.id))
+        .ok_or_else(|| {
+            CreateError::InvalidInput(format!("上传会话 {id} 不存在或已过期"))
+        })?;
+
+    if !session.completed {
+        return Err(CreateError::InvalidInput(format!(
+            "上传会话 {id} 尚未完成"
+        )));
+    }
+
+    // 文件校验与存储上传都需要完整内容，因此仍需读入内存；
+    // 同一时间只读取一个会话，且最多读取创建会话时声明的大小
+    let path = session.staging_path();
+    let size = session.size as usize;
+    let data = web::block(move || -> std::io::Result<BytesMut> {
+        let mut writer = BytesMut::with_capacity(size).writer();
+        std::io::copy(
+            &mut std::fs::File::open(path)?.take(size as u64),
+            &mut writer,
+        )?;
+        Ok(writer.into_inner())
+    })
+    .await
+    .map_err(|_| CreateError::InvalidInput("读取上传暂存文件失败".to_string()))?
+    .map_err(FileHostingError::from)?;
+
+    if data.len() != size {
+        return Err(CreateError::InvalidInput(format!(
+            "上传会话 {id} 的暂存文件不完整，请重新上传"
+        )));
+    }
+
+    Ok((session, data))
+}
+
+/// 将已完成的分块上传会话中的文件加入版本，与 multipart 文件走相同的校验和上传流程
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_session_files(
+    user: &crate::models::users::User,
+    version_data: &InitialVersionData,
+    version: &mut VersionBuilder,
+    loaders: Vec<Loader>,
+    is_paid_project: bool,
+    file_host: &dyn FileHost,
+    private_file_host: Option<&S3PrivateHost>,
+    uploaded_files: &mut Vec<UploadedFile>,
+    consumed_sessions: &mut Vec<UploadSession>,
+    cdn_url: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<(), CreateError> {
+    for session_id in &version_data.upload_sessions {
+        let (session, data) =
+            read_upload_session(session_id, user, redis).await?;
+        let file_extension = session
+            .file_name
+            .rsplit_once('.')
+            .map(|(_, ext)| ext)
+            .unwrap_or_default();
+        let existing_file_names = version
+            .files
+            .iter()
+            .map(|x| x.filename.clone())
+            .collect::<Vec<_>>();
+        check_file_name(
+            &session.file_name,
+            file_extension,
+            &existing_file_names,
+        )?;
+
+        upload_file_data(
+            data,
+            &session.file_name,
+            file_extension,
+            file_host,
+            private_file_host,
+            version_data.file_parts.len() + version_data.upload_sessions.len(),
+            uploaded_files,
+            &mut version.files,
+            &mut version.dependencies,
+            cdn_url,
+            version.project_id.into(),
+            version.version_id.into(),
+            &version.version_fields,
+            loaders.clone(),
+            version_data.primary_file.is_some(),
+            version_data.primary_file.as_deref() == Some(session_id.as_str()),
+            version_data.file_types.get(session_id).copied().flatten(),
+            transaction,
+            redis,
+            user.username.clone(),
+            is_paid_project,
+        )
+        .await?;
+
+        consumed_sessions.push(session);
+    }
+
+    Ok(())
+}
+
+/// 版本提交后删除已使用的上传会话，失败时仅记录日志，暂存文件由定时任务兜底清理
+pub async fn remove_upload_sessions(
+    sessions: Vec<UploadSession>,
+    redis: &RedisPool,
+) {
+    for session in sessions {
+        if let Err(err) = session.remove(redis).await {
+            log::warn!("删除上传会话 {} 失败: {err}", session.id);
+        }
+    }
+}
+
 pub fn get_name_ext(
     content_disposition: &actix_web::http::header::ContentDisposition,
 ) -> Result<(&str, &str), CreateError> {
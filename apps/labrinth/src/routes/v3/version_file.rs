@@ -1,5 +1,7 @@
 use super::ApiError;
-use crate::auth::checks::{filter_visible_versions, is_visible_version};
+use crate::auth::checks::{
+    check_pat_project_id, filter_visible_versions, is_visible_version,
+};
 use crate::auth::{filter_visible_projects, get_user_from_headers};
 use crate::database::models::user_purchase_item::UserPurchase;
 use crate::database::redis::RedisPool;
@@ -563,6 +565,8 @@ pub async fn delete_file(
     .await?;
 
     if let Some(row) = file {
+        check_pat_project_id(&req, row.project_id, &pool, &redis).await?;
+
         if !user.role.is_admin() {
             let team_member =
                 database::models::TeamMember::get_from_user_id_version(
@@ -662,7 +666,7 @@ const PRIVATE_URL_PREFIX: &str = "private://";
 
 /// 检查用户是否有权限访问私有文件
 /// 权限包括：团队成员、组织成员、已购买用户、管理员
-async fn check_private_file_access(
+pub(crate) async fn check_private_file_access(
     user: Option<&models::v3::users::User>,
     project_id: database::models::ProjectId,
     pool: &PgPool,
@@ -719,6 +723,40 @@ async fn check_private_file_access(
     Ok(has_purchased)
 }
 
+/// 为私有文件生成临时下载链接（有效期 15 分钟）
+pub(crate) async fn presign_private_file(
+    url: &str,
+    filename: &str,
+    private_file_host: &Option<Arc<S3PrivateHost>>,
+) -> Result<String, ApiError> {
+    // 检查私有存储是否配置
+    let private_host = private_file_host.as_ref().ok_or_else(|| {
+        ApiError::CustomAuthentication("私有文件存储未配置".to_string())
+    })?;
+
+    // 从 private:// URL 提取文件路径
+    let file_path = url.strip_prefix(PRIVATE_URL_PREFIX).ok_or_else(|| {
+        ApiError::InvalidInput("无效的私有文件 URL".to_string())
+    })?;
+
+    // 解码 URL 编码的路径
+    let decoded_path = urlencoding::decode(file_path).map_err(|_| {
+        ApiError::InvalidInput("无效的文件路径编码".to_string())
+    })?;
+
+    private_host
+        .presign_get(
+            &format!("/{}", decoded_path),
+            900, // 15 分钟
+            Some(filename),
+        )
+        .await
+        .map_err(|e| {
+            log::error!("生成 presigned URL 失败: {:?}", e);
+            ApiError::CustomAuthentication("生成下载链接失败".to_string())
+        })
+}
+
 // 在 /api/v1/version_file/{hash}/download 下
 pub async fn download_version(
     req: HttpRequest,
@@ -781,42 +819,12 @@ pub async fn download_version(
                     ));
                 }
 
-                // 检查私有存储是否配置
-                let private_host =
-                    private_file_host.as_ref().as_ref().ok_or_else(|| {
-                        ApiError::CustomAuthentication(
-                            "私有文件存储未配置".to_string(),
-                        )
-                    })?;
-
-                // 从 private:// URL 提取文件路径
-                let file_path = file
-                    .url
-                    .strip_prefix(PRIVATE_URL_PREFIX)
-                    .ok_or_else(|| {
-                        ApiError::InvalidInput("无效的私有文件 URL".to_string())
-                    })?;
-
-                // 解码 URL 编码的路径
-                let decoded_path =
-                    urlencoding::decode(file_path).map_err(|_| {
-                        ApiError::InvalidInput("无效的文件路径编码".to_string())
-                    })?;
-
-                // 生成 presigned URL（有效期 15 分钟）
-                let presigned_url = private_host
-                    .presign_get(
-                        &format!("/{}", decoded_path),
-                        900, // 15 分钟
-                        Some(&file.filename),
-                    )
-                    .await
-                    .map_err(|e| {
-                        log::error!("生成 presigned URL 失败: {:?}", e);
-                        ApiError::CustomAuthentication(
-                            "生成下载链接失败".to_string(),
-                        )
-                    })?;
+                let presigned_url = presign_private_file(
+                    &file.url,
+                    &file.filename,
+                    &private_file_host,
+                )
+                .await?;
 
                 // 私有文件：返回 JSON 响应（不重定向），前端需要自己处理下载
                 // 因为 fetch 会自动跟随重定向，前端无法获取到 JSON body
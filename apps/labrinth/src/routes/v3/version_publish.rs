@@ -0,0 +1,1090 @@
+use super::project_creation::{CreateError, UploadedFile};
+use super::version_creation::{
+    InitialVersionData, PreparedVersion, after_version_published,
+    check_file_hash_unused, check_file_name, get_name_ext, insert_version,
+    prepare_version, read_upload_session, remove_upload_sessions,
+    upload_file_data, upload_session_files,
+};
+use crate::auth::{check_resource_ban, get_user_from_headers};
+use crate::database::models;
+use crate::database::models::upload_session_item::UploadSession;
+use crate::database::redis::{RedisLock, RedisPool};
+use crate::file_hosting::{FileHost, S3PrivateHost};
+use crate::models::pats::Scopes;
+use crate::models::projects::{Loader, Version};
+use crate::models::users::User;
+use crate::queue::moderation::AutomatedModerationQueue;
+use crate::queue::session::AuthQueue;
+use crate::routes::ApiError;
+use crate::util::routes::read_from_field;
+use crate::validate::validate_file_for_pack;
+use actix_multipart::{Field, Multipart};
+use actix_web::http::StatusCode;
+use actix_web::http::header::ContentDisposition;
+use actix_web::web::Data;
+use actix_web::{HttpRequest, HttpResponse};
+use bytes::BytesMut;
+use futures::stream::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use validator::{Validate, ValidationErrors, ValidationErrorsKind};
+
+const IDEMPOTENCY_HEADER: &str = "Idempotency-Key";
+const IDEMPOTENCY_NAMESPACE: &str = "version_publish";
+const IDEMPOTENCY_LOCK_NAMESPACE: &str = "version_publish_lock";
+// 发布结果保留 24 小时，供超时重试时直接返回
+const IDEMPOTENCY_EXPIRY: i64 = 60 * 60 * 24;
+const IDEMPOTENCY_LOCK_EXPIRY: i64 = 60 * 30;
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 128;
+const MAX_PUBLISH_TARGETS: usize = 10;
+// 整个请求的字段数与总大小上限，更大的文件应使用分块上传会话
+const MAX_PUBLISH_FIELDS: usize = 64;
+const MAX_PUBLISH_SIZE: usize = 1024 * (1 << 20);
+
+#[derive(Deserialize)]
+pub struct PublishData {
+    /// 每个目标对应一个项目的新版本，例如模组本体与其汉化项目
+    pub targets: Vec<InitialVersionData>,
+    /// 仅校验数据、文件与敏感词，不写入数据库也不上传文件
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// 结构化的发布错误，便于 CI 脚本按字段和错误码处理
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PublishIssue {
+    /// 出错的目标下标，为空表示整个请求
+    pub target: Option<usize>,
+    pub field: Option<String>,
+    pub code: String,
+    pub message: String,
+}
+
+impl PublishIssue {
+    fn new(
+        target: Option<usize>,
+        field: Option<String>,
+        code: &str,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            target,
+            field,
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PublishResponse {
+    pub dry_run: bool,
+    pub success: bool,
+    pub versions: Vec<Version>,
+    pub errors: Vec<PublishIssue>,
+}
+
+/// 按幂等键保存的发布结果
+#[derive(Serialize, Deserialize)]
+struct PublishRecord {
+    fingerprint: String,
+    status: u16,
+    response: PublishResponse,
+}
+
+struct FilePart {
+    content_disposition: ContentDisposition,
+    data: BytesMut,
+}
+
+struct PublishedVersion {
+    version: Version,
+    project_id: models::ProjectId,
+    slug: Option<String>,
+}
+
+// under `/v3/version/publish`
+#[allow(clippy::too_many_arguments)]
+pub async fn version_publish(
+    req: HttpRequest,
+    mut payload: Multipart,
+    client: Data<PgPool>,
+    redis: Data<RedisPool>,
+    file_host: Data<Arc<dyn FileHost + Send + Sync>>,
+    private_file_host: Data<Option<Arc<S3PrivateHost>>>,
+    session_queue: Data<AuthQueue>,
+    moderation_queue: Data<AutomatedModerationQueue>,
+) -> Result<HttpResponse, CreateError> {
+    let user = get_user_from_headers(
+        &req,
+        &**client,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::VERSION_CREATE]),
+    )
+    .await?
+    .1;
+
+    // 检查用户是否被资源类封禁
+    check_resource_ban(&user, &client)
+        .await
+        .map_err(|e| CreateError::Banned(e.to_string()))?;
+
+    let idempotency_key = req
+        .headers()
+        .get(IDEMPOTENCY_HEADER)
+        .map(|x| {
+            x.to_str()
+                .ok()
+                .filter(|x| {
+                    !x.is_empty()
+                        && x.len() <= MAX_IDEMPOTENCY_KEY_LEN
+                        && x.chars().all(|c| c.is_ascii_graphic())
+                })
+                .map(|x| x.to_string())
+                .ok_or_else(|| {
+                    CreateError::InvalidInput(format!(
+                        "{IDEMPOTENCY_HEADER} 必须为 1 到 {MAX_IDEMPOTENCY_KEY_LEN} 个可见 ASCII 字符"
+                    ))
+                })
+        })
+        .transpose()?;
+
+    // 在读取文件内容之前占用幂等键，避免重复请求同时缓冲大文件
+    let mut record_id =
+        idempotency_key.map(|key| format!("{}:{}", user.id, key));
+    let mut lock = None;
+    if let Some(record_id) = &record_id {
+        lock = RedisLock::acquire(
+            &redis,
+            IDEMPOTENCY_LOCK_NAMESPACE,
+            record_id,
+            IDEMPOTENCY_LOCK_EXPIRY,
+        )
+        .await?;
+        if lock.is_none() {
+            return Ok(publish_response(
+                StatusCode::CONFLICT,
+                PublishResponse {
+                    dry_run: false,
+                    success: false,
+                    versions: Vec::new(),
+                    errors: vec![PublishIssue::new(
+                        None,
+                        None,
+                        "idempotency_key_in_use",
+                        "使用相同幂等键的请求正在处理中",
+                    )],
+                },
+                false,
+            ));
+        }
+    }
+
+    let (data, files, fingerprint) = read_publish_payload(&mut payload).await?;
+
+    let data = match data {
+        Ok(data) => data,
+        Err(issue) => {
+            return Ok(publish_response(
+                StatusCode::BAD_REQUEST,
+                PublishResponse {
+                    dry_run: false,
+                    success: false,
+                    versions: Vec::new(),
+                    errors: vec![issue],
+                },
+                false,
+            ));
+        }
+    };
+
+    // 预检不写入任何数据，无需占用幂等键
+    if data.dry_run {
+        record_id = None;
+        if let Some(lock) = lock.take() {
+            lock.release().await?;
+        }
+    }
+
+    if let Some(record_id) = &record_id {
+        let mut redis_conn = redis.connect().await?;
+        if let Some(record) = redis_conn
+            .get_deserialized_from_json::<PublishRecord>(
+                IDEMPOTENCY_NAMESPACE,
+                record_id,
+            )
+            .await?
+        {
+            if let Some(lock) = lock.take() {
+                lock.release().await?;
+            }
+
+            if record.fingerprint != fingerprint {
+                return Ok(publish_response(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    PublishResponse {
+                        dry_run: false,
+                        success: false,
+                        versions: Vec::new(),
+                        errors: vec![PublishIssue::new(
+                            None,
+                            None,
+                            "idempotency_key_reused",
+                            "该幂等键已用于内容不同的发布请求",
+                        )],
+                    },
+                    false,
+                ));
+            }
+
+            let status =
+                StatusCode::from_u16(record.status).unwrap_or(StatusCode::OK);
+            return Ok(publish_response(status, record.response, true));
+        }
+    }
+
+    let result = version_publish_transaction(
+        &req,
+        &user,
+        data,
+        &files,
+        &client,
+        &redis,
+        &***file_host,
+        private_file_host.as_ref().as_ref().map(|h| h.as_ref()),
+    )
+    .await;
+
+    // 事务提交后立即记录结果，后续步骤失败时重试也不会再次发布；
+    // 服务端错误不记录结果，允许客户端使用同一幂等键重试
+    if let Some(record_id) = &record_id
+        && let Ok((status, response, _)) = &result
+    {
+        let record = PublishRecord {
+            fingerprint,
+            status: status.as_u16(),
+            response: response.clone(),
+        };
+        let stored = match redis.connect().await {
+            Ok(mut redis_conn) => {
+                redis_conn
+                    .set_serialized_to_json(
+                        IDEMPOTENCY_NAMESPACE,
+                        record_id,
+                        record,
+                        Some(IDEMPOTENCY_EXPIRY),
+                    )
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+        if let Err(err) = stored {
+            log::error!("保存版本发布结果 {record_id} 失败: {err}");
+        }
+    }
+    if let Some(lock) = lock {
+        lock.release().await?;
+    }
+
+    let (status, response, published) = result?;
+
+    // 审核队列与 IndexNow 通知失败不影响已提交的发布
+    for (version, (project_id, slug)) in response.versions.iter().zip(published)
+    {
+        if let Err(err) = after_version_published(
+            project_id,
+            &slug,
+            version,
+            &client,
+            &moderation_queue,
+        )
+        .await
+        {
+            log::warn!("版本 {} 发布后的处理失败: {err}", version.id);
+        }
+    }
+
+    Ok(publish_response(status, response, false))
+}
+
+fn publish_response(
+    status: StatusCode,
+    response: PublishResponse,
+    replayed: bool,
+) -> HttpResponse {
+    let mut builder = HttpResponse::build(status);
+    if replayed {
+        builder.insert_header(("Idempotent-Replayed", "true"));
+    }
+    builder.json(response)
+}
+
+/// 读取 `data` 字段与全部文件字段，并计算请求内容指纹
+async fn read_publish_payload(
+    payload: &mut Multipart,
+) -> Result<
+    (
+        Result<PublishData, PublishIssue>,
+        HashMap<String, FilePart>,
+        String,
+    ),
+    CreateError,
+> {
+    let mut data = None;
+    let mut files = HashMap::new();
+    let mut hasher = sha2::Sha256::new();
+
+    let mut fields = 0;
+    let mut total_size = 0;
+
+    while let Some(item) = payload.next().await {
+        fields += 1;
+        if fields > MAX_PUBLISH_FIELDS {
+            return Err(CreateError::InvalidInput(format!(
+                "发布请求最多包含 {MAX_PUBLISH_FIELDS} 个字段"
+            )));
+        }
+
+        let mut field: Field = item?;
+        let content_disposition =
+            field.content_disposition().cloned().ok_or_else(|| {
+                CreateError::MissingValueError(
+                    "缺少 Content-Disposition".to_string(),
+                )
+            })?;
+        let name = content_disposition
+            .get_name()
+            .ok_or_else(|| {
+                CreateError::MissingValueError("缺少内容名称".to_string())
+            })?
+            .to_string();
+
+        let bytes = read_from_field(
+            &mut field,
+            MAX_PUBLISH_SIZE - total_size,
+            "发布请求超出了 1GB 的总大小上限，较大的文件请使用分块上传会话",
+        )
+        .await?;
+        total_size += bytes.len();
+        if total_size > MAX_PUBLISH_SIZE {
+            return Err(CreateError::InvalidInput(
+                "发布请求超出了 1GB 的总大小上限，较大的文件请使用分块上传会话"
+                    .to_string(),
+            ));
+        }
+
+        hasher.update(name.as_bytes());
+        hasher.update(sha2::Sha256::digest(&bytes));
+
+        if name == "data" {
+            data = Some(serde_json::from_slice::<PublishData>(&bytes).map_err(
+                |err| {
+                    PublishIssue::new(
+                        None,
+                        Some("data".to_string()),
+                        "invalid_json",
+                        err.to_string(),
+                    )
+                },
+            ));
+        } else if files
+            .insert(
+                name.clone(),
+                FilePart {
+                    content_disposition,
+                    data: bytes,
+                },
+            )
+            .is_some()
+        {
+            return Err(CreateError::InvalidInput(format!(
+                "文件字段 {name} 重复"
+            )));
+        }
+    }
+
+    let data = data.unwrap_or_else(|| {
+        Err(PublishIssue::new(
+            None,
+            Some("data".to_string()),
+            "missing_data",
+            "`data` field 是必需的",
+        ))
+    });
+
+    Ok((data, files, format!("{:x}", hasher.finalize())))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn version_publish_transaction(
+    req: &HttpRequest,
+    user: &User,
+    data: PublishData,
+    files: &HashMap<String, FilePart>,
+    pool: &PgPool,
+    redis: &RedisPool,
+    file_host: &dyn FileHost,
+    private_file_host: Option<&S3PrivateHost>,
+) -> Result<
+    (
+        StatusCode,
+        PublishResponse,
+        Vec<(models::ProjectId, Option<String>)>,
+    ),
+    CreateError,
+> {
+    let dry_run = data.dry_run;
+    let mut transaction = pool.begin().await?;
+    let mut uploaded_files = Vec::new();
+    let mut consumed_sessions = Vec::new();
+
+    let result = version_publish_inner(
+        req,
+        user,
+        data,
+        files,
+        &mut transaction,
+        redis,
+        file_host,
+        private_file_host,
+        &mut uploaded_files,
+        &mut consumed_sessions,
+    )
+    .await;
+
+    // 所有目标在同一事务中发布，任一目标失败时全部回滚
+    let published = match result {
+        Ok(Ok(published)) if !dry_run => {
+            transaction.commit().await?;
+            remove_upload_sessions(consumed_sessions, redis).await;
+            published
+        }
+        result => {
+            let undo_result = super::project_creation::undo_uploads(
+                file_host,
+                &uploaded_files,
+            )
+            .await;
+            let rollback_result = transaction.rollback().await;
+
+            undo_result?;
+            rollback_result?;
+
+            let errors = match result {
+                Ok(Ok(_)) => Vec::new(),
+                Ok(Err(errors)) => errors,
+                Err(err) => return Err(err),
+            };
+
+            let status = if errors.is_empty() {
+                StatusCode::OK
+            } else {
+                StatusCode::BAD_REQUEST
+            };
+            return Ok((
+                status,
+                PublishResponse {
+                    dry_run,
+                    success: errors.is_empty(),
+                    versions: Vec::new(),
+                    errors,
+                },
+                Vec::new(),
+            ));
+        }
+    };
+
+    let (versions, published) = published
+        .into_iter()
+        .map(|x| (x.version, (x.project_id, x.slug)))
+        .unzip();
+
+    Ok((
+        StatusCode::OK,
+        PublishResponse {
+            dry_run,
+            success: true,
+            versions,
+            errors: Vec::new(),
+        },
+        published,
+    ))
+}
+
+/// 预检所有目标后再依次上传与写入；返回内层错误表示请求本身有误
+#[allow(clippy::too_many_arguments)]
+async fn version_publish_inner(
+    req: &HttpRequest,
+    user: &User,
+    data: PublishData,
+    files: &HashMap<String, FilePart>,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+    file_host: &dyn FileHost,
+    private_file_host: Option<&S3PrivateHost>,
+    uploaded_files: &mut Vec<UploadedFile>,
+    consumed_sessions: &mut Vec<UploadSession>,
+) -> Result<Result<Vec<PublishedVersion>, Vec<PublishIssue>>, CreateError> {
+    let mut issues = Vec::new();
+
+    if data.targets.is_empty() || data.targets.len() > MAX_PUBLISH_TARGETS {
+        issues.push(PublishIssue::new(
+            None,
+            Some("targets".to_string()),
+            "invalid_target_count",
+            format!("一次最多发布到 {MAX_PUBLISH_TARGETS} 个项目，且至少一个"),
+        ));
+        return Ok(Err(issues));
+    }
+
+    for part_name in files.keys().filter(|part_name| {
+        !data
+            .targets
+            .iter()
+            .any(|target| target.file_parts.contains(*part_name))
+    }) {
+        issues.push(PublishIssue::new(
+            None,
+            Some(format!("files.{part_name}")),
+            "unused_file_part",
+            format!("文件字段 {part_name} 未被任何目标引用"),
+        ));
+    }
+
+    let mut prepared_targets = Vec::new();
+    for (index, target) in data.targets.iter().enumerate() {
+        let mut target_issues =
+            check_target(index, target, files, &data.targets);
+        target_issues
+            .extend(check_target_sessions(index, target, user, redis).await?);
+        if !target_issues.is_empty() {
+            issues.extend(target_issues);
+            continue;
+        }
+
+        let prepared = match prepare_version(
+            req,
+            user,
+            target,
+            transaction,
+            redis,
+        )
+        .await
+        {
+            Ok(prepared) => prepared,
+            Err(err) => {
+                issues.push(issue_from_error(index, None, err)?);
+                continue;
+            }
+        };
+
+        issues.extend(
+            check_target_text(index, target, &prepared, user, redis).await?,
+        );
+
+        if data.dry_run {
+            issues.extend(
+                check_target_files(
+                    index,
+                    target,
+                    &prepared,
+                    files,
+                    user,
+                    transaction,
+                    redis,
+                )
+                .await?,
+            );
+        }
+
+        prepared_targets.push(prepared);
+    }
+
+    if !issues.is_empty() || data.dry_run {
+        return Ok(Err(issues));
+    }
+
+    let cdn_url = dotenvy::var("CDN_URL")?;
+    let mut published = Vec::new();
+    for (index, (target, mut prepared)) in
+        data.targets.into_iter().zip(prepared_targets).enumerate()
+    {
+        let loaders = prepared
+            .loaders
+            .iter()
+            .map(|x| Loader(x.loader.clone()))
+            .collect::<Vec<_>>();
+
+        for part_name in &target.file_parts {
+            let part = &files[part_name];
+            let field = Some(format!("files.{part_name}"));
+            let (file_name, file_extension) =
+                get_name_ext(&part.content_disposition)?;
+            let existing_file_names = prepared
+                .builder
+                .files
+                .iter()
+                .map(|x| x.filename.clone())
+                .collect::<Vec<_>>();
+
+            let result = match check_file_name(
+                file_name,
+                file_extension,
+                &existing_file_names,
+            ) {
+                Ok(_) => {
+                    upload_file_data(
+                        part.data.clone(),
+                        file_name,
+                        file_extension,
+                        file_host,
+                        private_file_host,
+                        target.file_parts.len() + target.upload_sessions.len(),
+                        uploaded_files,
+                        &mut prepared.builder.files,
+                        &mut prepared.builder.dependencies,
+                        &cdn_url,
+                        prepared.builder.project_id.into(),
+                        prepared.builder.version_id.into(),
+                        &prepared.builder.version_fields,
+                        loaders.clone(),
+                        target.primary_file.is_some(),
+                        target.primary_file.as_deref()
+                            == Some(part_name.as_str()),
+                        target.file_types.get(part_name).copied().flatten(),
+                        transaction,
+                        redis,
+                        user.username.clone(),
+                        prepared.is_paid,
+                    )
+                    .await
+                }
+                Err(err) => Err(err),
+            };
+
+            if let Err(err) = result {
+                return Ok(Err(vec![issue_from_error(index, field, err)?]));
+            }
+        }
+
+        if let Err(err) = upload_session_files(
+            user,
+            &target,
+            &mut prepared.builder,
+            loaders,
+            prepared.is_paid,
+            file_host,
+            private_file_host,
+            uploaded_files,
+            consumed_sessions,
+            &cdn_url,
+            transaction,
+            redis,
+        )
+        .await
+        {
+            return Ok(Err(vec![issue_from_error(
+                index,
+                Some("upload_sessions".to_string()),
+                err,
+            )?]));
+        }
+
+        let project_id = prepared.builder.project_id;
+        match insert_version(
+            user,
+            target,
+            prepared.builder,
+            prepared.loaders,
+            transaction,
+            redis,
+        )
+        .await
+        {
+            Ok(version) => published.push(PublishedVersion {
+                version,
+                project_id,
+                slug: prepared.slug,
+            }),
+            Err(err) => {
+                return Ok(Err(vec![issue_from_error(index, None, err)?]));
+            }
+        }
+    }
+
+    Ok(Ok(published))
+}
+
+/// 不访问数据库的目标数据检查
+fn check_target(
+    index: usize,
+    target: &InitialVersionData,
+    files: &HashMap<String, FilePart>,
+    targets: &[InitialVersionData],
+) -> Vec<PublishIssue> {
+    let mut issues = Vec::new();
+
+    if let Err(errors) = target.validate() {
+        validation_issues(index, &errors, "", &mut issues);
+    }
+
+    match target.project_id {
+        None => issues.push(PublishIssue::new(
+            Some(index),
+            Some("project_id".to_string()),
+            "missing_project_id",
+            "缺少项目id",
+        )),
+        Some(project_id)
+            if targets[..index]
+                .iter()
+                .any(|x| x.project_id == Some(project_id)) =>
+        {
+            issues.push(PublishIssue::new(
+                Some(index),
+                Some("project_id".to_string()),
+                "duplicate_project",
+                "同一请求中不能向同一项目发布多个版本",
+            ))
+        }
+        Some(_) => {}
+    }
+
+    if !target.status.can_be_requested() {
+        issues.push(PublishIssue::new(
+            Some(index),
+            Some("status".to_string()),
+            "status_not_requestable",
+            "指定的状态不能被请求",
+        ));
+    }
+
+    if target.disk_only {
+        match &target.disk_urls {
+            None => issues.push(PublishIssue::new(
+                Some(index),
+                Some("disk_urls".to_string()),
+                "missing_disk_urls",
+                "未填写网盘地址",
+            )),
+            Some(disk_urls) if disk_urls.len() > 3 => {
+                issues.push(PublishIssue::new(
+                    Some(index),
+                    Some("disk_urls".to_string()),
+                    "too_many_disk_urls",
+                    "最多提供三个网盘",
+                ))
+            }
+            Some(_) => {}
+        }
+    } else if target.file_parts.is_empty() && target.upload_sessions.is_empty()
+    {
+        issues.push(PublishIssue::new(
+            Some(index),
+            Some("file_parts".to_string()),
+            "missing_files",
+            "必须上传一个文件！",
+        ));
+    }
+
+    for part_name in &target.file_parts {
+        if !files.contains_key(part_name) {
+            issues.push(PublishIssue::new(
+                Some(index),
+                Some(format!("files.{part_name}")),
+                "missing_file_part",
+                format!("未找到文件字段 {part_name}"),
+            ));
+        }
+    }
+
+    issues
+}
+
+/// 对版本名称与更新日志进行敏感词检测
+async fn check_target_text(
+    index: usize,
+    target: &InitialVersionData,
+    prepared: &PreparedVersion,
+    user: &User,
+    redis: &RedisPool,
+) -> Result<Vec<PublishIssue>, CreateError> {
+    let mut issues = Vec::new();
+    let url = format!(
+        "/project/{}/version/{}",
+        prepared.slug.clone().unwrap_or_else(|| {
+            crate::models::ids::ProjectId::from(prepared.builder.project_id)
+                .to_string()
+        }),
+        target.version_number
+    );
+
+    let texts = [
+        (
+            "version_title",
+            Some(&target.version_title),
+            "发布版本-名称",
+        ),
+        (
+            "version_body",
+            target.version_body.as_ref(),
+            "发布版本-更新日志",
+        ),
+    ];
+    for (field, text, pos) in texts {
+        let Some(text) = text.filter(|x| !x.trim().is_empty()) else {
+            continue;
+        };
+
+        match crate::util::risk::check_text_risk(
+            text,
+            &user.username,
+            &url,
+            pos,
+            redis,
+        )
+        .await
+        {
+            Ok(true) => {}
+            Ok(false) => issues.push(PublishIssue::new(
+                Some(index),
+                Some(field.to_string()),
+                "text_risk",
+                "内容包含敏感词，已被记录该次提交",
+            )),
+            Err(ApiError::RiskLimit(time)) => {
+                issues.push(PublishIssue::new(
+                    Some(index),
+                    Some(field.to_string()),
+                    "risk_limit",
+                    format!("提交过于频繁，请于 {time} 后重试"),
+                ));
+                break;
+            }
+            Err(err) => {
+                log::warn!("版本发布敏感词检测失败: {err}");
+                issues.push(PublishIssue::new(
+                    Some(index),
+                    Some(field.to_string()),
+                    "risk_check_failed",
+                    "敏感词检测服务暂时不可用",
+                ));
+            }
+        }
+    }
+
+    Ok(issues)
+}
+
+/// 检查引用的上传会话是否存在且已完成，不读取文件内容
+async fn check_target_sessions(
+    index: usize,
+    target: &InitialVersionData,
+    user: &User,
+    redis: &RedisPool,
+) -> Result<Vec<PublishIssue>, CreateError> {
+    let mut issues = Vec::new();
+
+    for session_id in &target.upload_sessions {
+        let session = UploadSession::get(session_id, redis)
+            .await?
+            .filter(|x| x.user_id == models::UserId::from(user.id));
+        let (code, message) = match session {
+            Some(session) if session.completed => continue,
+            Some(_) => ("upload_session_incomplete", "上传会话尚未完成"),
+            None => ("upload_session_not_found", "上传会话不存在或已过期"),
+        };
+        issues.push(PublishIssue::new(
+            Some(index),
+            Some(format!("upload_sessions.{session_id}")),
+            code,
+            message,
+        ));
+    }
+
+    Ok(issues)
+}
+
+/// 预检模式下校验文件，不上传到存储
+async fn check_target_files(
+    index: usize,
+    target: &InitialVersionData,
+    prepared: &PreparedVersion,
+    files: &HashMap<String, FilePart>,
+    user: &User,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<Vec<PublishIssue>, CreateError> {
+    let mut issues = Vec::new();
+    let mut file_names = Vec::new();
+    let mut has_primary = false;
+    let total_files = target.file_parts.len() + target.upload_sessions.len();
+    let loaders = prepared
+        .loaders
+        .iter()
+        .map(|x| Loader(x.loader.clone()))
+        .collect::<Vec<_>>();
+
+    // (文件引用名, 错误字段, 文件名, 文件内容)
+    let mut sources = Vec::new();
+    for part_name in &target.file_parts {
+        let part = &files[part_name];
+        let field = format!("files.{part_name}");
+        match get_name_ext(&part.content_disposition) {
+            Ok((file_name, _)) => sources.push((
+                part_name,
+                field,
+                file_name.to_string(),
+                part.data.clone().freeze(),
+            )),
+            Err(err) => issues.push(issue_from_error(index, Some(field), err)?),
+        }
+    }
+    for session_id in &target.upload_sessions {
+        let field = format!("upload_sessions.{session_id}");
+        match read_upload_session(session_id, user, redis).await {
+            Ok((session, data)) => sources.push((
+                session_id,
+                field,
+                session.file_name,
+                data.freeze(),
+            )),
+            Err(err) => issues.push(issue_from_error(index, Some(field), err)?),
+        }
+    }
+
+    for (key, field, file_name, data) in sources {
+        let result = async {
+            let file_extension = file_name
+                .rsplit_once('.')
+                .map(|(_, ext)| ext)
+                .unwrap_or_default();
+            check_file_name(&file_name, file_extension, &file_names)?;
+            file_names.push(file_name.clone());
+
+            check_file_hash_unused(
+                &data,
+                prepared.builder.project_id.into(),
+                &user.username,
+                transaction,
+            )
+            .await?;
+
+            let validation_result = validate_file_for_pack(
+                data,
+                file_extension.to_string(),
+                loaders.clone(),
+                target.file_types.get(key).copied().flatten(),
+                prepared.builder.version_fields.clone(),
+                transaction,
+                redis,
+            )
+            .await?;
+
+            // 与正式上传时的主文件判定保持一致
+            let primary = (validation_result.is_passed()
+                && !has_primary
+                && target.primary_file.is_none())
+                || target.primary_file.as_deref() == Some(key.as_str())
+                || total_files == 1;
+            has_primary |= primary;
+
+            if let crate::validate::ValidationResult::Warning(msg) =
+                validation_result
+                && primary
+            {
+                return Err(CreateError::InvalidInput(msg.to_string()));
+            }
+
+            Ok::<(), CreateError>(())
+        }
+        .await;
+
+        if let Err(err) = result {
+            issues.push(issue_from_error(index, Some(field), err)?);
+        }
+    }
+
+    Ok(issues)
+}
+
+/// 将校验错误展开为逐字段的结构化错误
+fn validation_issues(
+    index: usize,
+    errors: &ValidationErrors,
+    prefix: &str,
+    issues: &mut Vec<PublishIssue>,
+) {
+    for (field, kind) in errors.errors() {
+        let path = if prefix.is_empty() {
+            field.to_string()
+        } else {
+            format!("{prefix}.{field}")
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                for error in errors {
+                    issues.push(PublishIssue::new(
+                        Some(index),
+                        Some(path.clone()),
+                        &error.code,
+                        error
+                            .message
+                            .as_ref()
+                            .map(|x| x.to_string())
+                            .unwrap_or_else(|| format!("字段 {path} 无效")),
+                    ));
+                }
+            }
+            ValidationErrorsKind::Struct(errors) => {
+                validation_issues(index, errors, &path, issues);
+            }
+            ValidationErrorsKind::List(list) => {
+                for (i, errors) in list {
+                    validation_issues(
+                        index,
+                        errors,
+                        &format!("{path}[{i}]"),
+                        issues,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// 将目标处理中的错误转为结构化错误，服务端错误原样返回
+fn issue_from_error(
+    index: usize,
+    field: Option<String>,
+    err: CreateError,
+) -> Result<PublishIssue, CreateError> {
+    let (default_field, code) = match &err {
+        CreateError::MissingValueError(..)
+        | CreateError::InvalidInput(..)
+        | CreateError::SerDeError(..) => (None, "invalid_input"),
+        CreateError::ValidationError(..) => (None, "validation_failed"),
+        CreateError::InvalidGameVersion(..) => {
+            (Some("game_versions"), "invalid_game_version")
+        }
+        CreateError::InvalidLoader(..) => (Some("loaders"), "invalid_loader"),
+        CreateError::InvalidFileType(..) => (None, "invalid_file_type"),
+        CreateError::FileValidationError(..) => (None, "invalid_file"),
+        CreateError::Unauthorized(..)
+        | CreateError::CustomAuthenticationError(..) => (None, "forbidden"),
+        CreateError::Banned(..) => (None, "user_banned"),
+        _ => return Err(err),
+    };
+
+    Ok(PublishIssue::new(
+        Some(index),
+        field.or_else(|| default_field.map(|x| x.to_string())),
+        code,
+        err.to_string(),
+    ))
+}
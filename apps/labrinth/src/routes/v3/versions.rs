@@ -1,13 +1,16 @@
 use super::ApiError;
 use crate::auth::checks::{
-    check_resource_ban, filter_visible_versions, is_visible_project,
-    is_visible_version,
+    check_pat_project_id, check_resource_ban, filter_visible_versions,
+    is_visible_project, is_visible_version,
 };
 use crate::auth::get_user_from_headers;
 use crate::database;
+use crate::database::models::issues::IssueMilestone;
 use crate::database::models::loader_fields::{
     self, LoaderField, LoaderFieldEnumValue, VersionField,
 };
+use crate::database::models::modpack_file_item::ModpackFile;
+use crate::database::models::translation_task_item::TranslationTask;
 use crate::database::models::version_item::{
     DependencyBuilder, LoaderVersion, QueryDisk, VersionLinkBuilder,
 };
@@ -18,6 +21,7 @@ use crate::models::analytics::Download;
 use crate::models::ids::base62_impl::parse_base62;
 use crate::models::ids::{ProjectId, VersionId};
 use crate::models::images::ImageContext;
+use crate::models::pack::{PackDiff, PackFileEntry};
 use crate::models::pats::Scopes;
 use crate::models::projects::{
     Dependency, FileType, VersionLink, VersionStatus, VersionType,
@@ -47,6 +51,10 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         "version",
         web::post().to(super::version_creation::version_create),
     );
+    cfg.route(
+        "version/publish",
+        web::post().to(super::version_publish::version_publish),
+    );
     cfg.route("versions", web::get().to(versions_get));
 
     cfg.service(
@@ -78,7 +86,9 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route(
                 "{version_id}/link/{target_version_id}/thread",
                 web::post().to(version_link_thread::send_version_link_message),
-            ),
+            )
+            .route("{id}/pack_files", web::get().to(version_pack_files))
+            .route("{id}/pack_diff", web::get().to(version_pack_diff)),
     );
 }
 
@@ -227,6 +237,134 @@ pub async fn version_get_helper(
     Err(ApiError::NotFound)
 }
 
+/// 获取一个可见版本，不可见时返回 NotFound
+async fn get_visible_version(
+    req: &HttpRequest,
+    id: models::ids::VersionId,
+    pool: &PgPool,
+    redis: &RedisPool,
+    session_queue: &AuthQueue,
+) -> Result<database::models::version_item::QueryVersion, ApiError> {
+    let user_option = get_user_from_headers(
+        req,
+        pool,
+        redis,
+        session_queue,
+        Some(&[Scopes::VERSION_READ]),
+    )
+    .await
+    .map(|x| x.1)
+    .ok();
+
+    match database::models::Version::get(id.into(), pool, redis).await? {
+        Some(version)
+            if is_visible_version(
+                &version.inner,
+                &user_option,
+                pool,
+                redis,
+            )
+            .await? =>
+        {
+            Ok(version)
+        }
+        _ => Err(ApiError::NotFound),
+    }
+}
+
+/// 获取整合包版本解析后的文件列表
+pub async fn version_pack_files(
+    req: HttpRequest,
+    info: web::Path<(models::ids::VersionId,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let version = get_visible_version(
+        &req,
+        info.into_inner().0,
+        &pool,
+        &redis,
+        &session_queue,
+    )
+    .await?;
+
+    let files = ModpackFile::get_version(version.inner.id, &**pool)
+        .await?
+        .into_iter()
+        .map(PackFileEntry::from)
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(files))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PackDiffQuery {
+    /// 对比的旧版本，默认为同一项目中的上一个整合包版本
+    pub base: Option<VersionId>,
+}
+
+/// 对比同一整合包两个版本之间新增、移除、更新的模组以及变化的配置文件
+pub async fn version_pack_diff(
+    req: HttpRequest,
+    info: web::Path<(models::ids::VersionId,)>,
+    query: web::Query<PackDiffQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let version = get_visible_version(
+        &req,
+        info.into_inner().0,
+        &pool,
+        &redis,
+        &session_queue,
+    )
+    .await?;
+
+    let base_id = match query.base {
+        Some(base) => base.into(),
+        None => ModpackFile::get_previous_version(version.inner.id, &**pool)
+            .await?
+            .ok_or_else(|| {
+                ApiError::InvalidInput(
+                    "没有可以对比的上一个整合包版本".to_string(),
+                )
+            })?,
+    };
+    let base = get_visible_version(
+        &req,
+        base_id.into(),
+        &pool,
+        &redis,
+        &session_queue,
+    )
+    .await?;
+
+    if base.inner.project_id != version.inner.project_id {
+        return Err(ApiError::InvalidInput(
+            "只能对比同一项目的两个版本".to_string(),
+        ));
+    }
+
+    let base_files = ModpackFile::get_version(base.inner.id, &**pool).await?;
+    let files = ModpackFile::get_version(version.inner.id, &**pool).await?;
+    if base_files.is_empty() || files.is_empty() {
+        return Err(ApiError::InvalidInput(
+            "只有记录了文件列表的整合包版本可以对比".to_string(),
+        ));
+    }
+
+    let diff = PackDiff::new(
+        base.inner.id.into(),
+        base_files.into_iter().map(PackFileEntry::from).collect(),
+        version.inner.id.into(),
+        files.into_iter().map(PackFileEntry::from).collect(),
+    );
+
+    Ok(HttpResponse::Ok().json(diff))
+}
+
 #[derive(Serialize, Deserialize, Validate, Default, Debug)]
 pub struct EditVersion {
     #[validate(
@@ -334,6 +472,8 @@ pub async fn version_download(
         let ip = crate::util::ip::convert_to_ip_v6(&ip)
             .unwrap_or_else(|_| Ipv4Addr::new(127, 0, 0, 1).to_ipv6_mapped());
         let id: ProjectId = version_item.inner.project_id.into();
+        let (loaders, game_versions) =
+            crate::routes::analytics::version_dimensions(&version_item);
 
         // if version_item.disks.is_empty() {
         //     return Err(ApiError::NotFound);
@@ -361,7 +501,22 @@ pub async fn version_download(
                     .get("user-agent")
                     .cloned()
                     .unwrap_or_default(),
-                headers: Vec::new(),
+                headers: headers
+                    .iter()
+                    .filter(|x| {
+                        !crate::routes::analytics::FILTERED_HEADERS
+                            .contains(&x.0.as_str())
+                    })
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect(),
+                referrer: crate::routes::analytics::referrer_host(
+                    headers.get("referer").map(|x| x.as_str()),
+                ),
+                launcher: crate::routes::analytics::classify_launcher(
+                    headers.get("user-agent").map(|x| x.as_str()).unwrap_or(""),
+                ),
+                loaders: loaders.clone(),
+                game_versions: game_versions.clone(),
             });
         } else {
             let url = version_item.disks.first().unwrap().url.clone();
@@ -383,7 +538,22 @@ pub async fn version_download(
                     .get("user-agent")
                     .cloned()
                     .unwrap_or_default(),
-                headers: Vec::new(),
+                headers: headers
+                    .iter()
+                    .filter(|x| {
+                        !crate::routes::analytics::FILTERED_HEADERS
+                            .contains(&x.0.as_str())
+                    })
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect(),
+                referrer: crate::routes::analytics::referrer_host(
+                    headers.get("referer").map(|x| x.as_str()),
+                ),
+                launcher: crate::routes::analytics::classify_launcher(
+                    headers.get("user-agent").map(|x| x.as_str()).unwrap_or(""),
+                ),
+                loaders: loaders.clone(),
+                game_versions: game_versions.clone(),
             });
         }
         Ok(HttpResponse::NoContent().body(""))
@@ -443,6 +613,14 @@ pub async fn version_edit_helper(
     let result = database::models::Version::get(id, &**pool, &redis).await?;
 
     if let Some(version_item) = result {
+        check_pat_project_id(
+            &req,
+            version_item.inner.project_id,
+            &pool,
+            &redis,
+        )
+        .await?;
+
         let team_member =
             database::models::TeamMember::get_from_user_id_project(
                 version_item.inner.project_id,
@@ -1044,6 +1222,20 @@ pub async fn version_edit_helper(
                 .execute(&mut *transaction)
                 .await?;
 
+                // 版本发布后关闭关联该版本号的里程碑
+                if status.is_listed() {
+                    IssueMilestone::close_for_version(
+                        version_item.inner.project_id,
+                        new_version
+                            .version_number
+                            .as_deref()
+                            .unwrap_or(&version_item.inner.version_number),
+                        id,
+                        &mut transaction,
+                    )
+                    .await?;
+                }
+
                 // 如果状态发生变化，且这个版本是汉化包，清除所有目标版本的缓存
                 let version_links = sqlx::query!(
                     "
@@ -1357,6 +1549,7 @@ pub async fn version_delete(
         .ok_or_else(|| {
             ApiError::InvalidInput("指定的版本不存在！".to_string())
         })?;
+    check_pat_project_id(&req, version.inner.project_id, &pool, &redis).await?;
 
     if !user.role.is_admin() {
         let team_member =
@@ -1469,6 +1662,7 @@ pub async fn approve_version_link(
 
     // 检查用户是否有权限管理目标项目的版本
     let target_project_id = target_version.inner.project_id;
+    check_pat_project_id(&req, target_project_id, &pool, &redis).await?;
     let team_member = database::models::TeamMember::get_from_user_id_project(
         target_project_id,
         user.id.into(),
@@ -1527,7 +1721,7 @@ pub async fn approve_version_link(
 
     // 先获取当前链接信息，看是否有thread_id
     let link_info = sqlx::query!(
-        "SELECT thread_id FROM version_link_version WHERE version_id = $1 AND joining_version_id = $2",
+        "SELECT thread_id, link_type, language_code FROM version_link_version WHERE version_id = $1 AND joining_version_id = $2",
         translation_version_id.0 as i64,
         target_version_id.0 as i64,
     )
@@ -1546,6 +1740,20 @@ pub async fn approve_version_link(
     .execute(&mut *transaction)
     .await?;
 
+    // 汉化绑定通过后，自动关闭汉化资源上对应的“汉化过期”任务
+    let closed_tasks = match &link_info {
+        Some(link) if link.link_type == "translation" => {
+            TranslationTask::close_for_version(
+                target_version_id.into(),
+                &link.language_code.to_lowercase(),
+                translation_version_id.into(),
+                &mut transaction,
+            )
+            .await?
+        }
+        _ => Vec::new(),
+    };
+
     // 创建或获取thread，然后添加批准消息
     if let Some(link) = link_info {
         use crate::database::models::thread_item::{
@@ -1599,6 +1807,8 @@ pub async fn approve_version_link(
 
     transaction.commit().await?;
 
+    TranslationTask::clear_issue_cache(&closed_tasks, &redis).await?;
+
     // 清除两个版本的缓存，确保 translated_by 和 version_links 都更新
     database::models::Version::clear_cache(&target_version, &redis).await?;
     database::models::Version::clear_cache(&translation_version, &redis)
@@ -1654,6 +1864,7 @@ pub async fn reject_version_link(
 
     // 检查用户是否有权限管理目标项目的版本
     let target_project_id = target_version.inner.project_id;
+    check_pat_project_id(&req, target_project_id, &pool, &redis).await?;
     let team_member = database::models::TeamMember::get_from_user_id_project(
         target_project_id,
         user.id.into(),
@@ -1839,6 +2050,7 @@ pub async fn revoke_version_link(
 
     // 检查用户是否有权限管理目标项目的版本
     let target_project_id = target_version.inner.project_id;
+    check_pat_project_id(&req, target_project_id, &pool, &redis).await?;
     let team_member = database::models::TeamMember::get_from_user_id_project(
         target_project_id,
         user.id.into(),
@@ -1979,6 +2191,7 @@ pub async fn resubmit_version_link(
 
     // 检查用户是否是翻译项目的成员
     let translation_project_id = translation_version.inner.project_id;
+    check_pat_project_id(&req, translation_project_id, &pool, &redis).await?;
     let team_member = database::models::TeamMember::get_from_user_id_project(
         translation_project_id,
         user.id.into(),
@@ -1,8 +1,13 @@
-use crate::auth::checks::{check_forum_ban, is_visible_project};
+use crate::auth::checks::{
+    check_forum_ban, check_pat_project, check_wiki_paid_access,
+    is_visible_project,
+};
 use crate::auth::{AuthenticationError, get_user_from_headers};
 use crate::database;
 use crate::database::models::notification_item::NotificationBuilder;
-use crate::database::models::user_purchase_item::UserPurchase;
+use crate::database::models::subscription_item::{
+    Subscription, SubscriptionEvent, SubscriptionEventType, SubscriptionTarget,
+};
 use crate::database::models::wiki_item::{WikiDisplays, Wikis};
 use crate::database::models::{
     User, UserId, Wiki, WikiCache, WikiCacheId, WikiId, generate_wiki_cache_id,
@@ -16,6 +21,9 @@ use crate::models::teams::ProjectPermissions;
 use crate::queue::session::AuthQueue;
 use crate::routes::ApiError;
 use crate::routes::v3::users::user_get_;
+use crate::util::mentions::{
+    MentionSource, MentionVisibility, notify_mentions,
+};
 use crate::util::validate::validation_errors_to_string;
 use actix_web::{HttpRequest, HttpResponse, web};
 use chrono::Utc;
@@ -112,6 +120,7 @@ pub async fn wiki_delete(
         {
             return Err(ApiError::NotFound);
         }
+        check_pat_project(&req, &project.inner)?;
         let wiki_cache = database::models::WikiCache::get_draft(
             project.inner.id,
             UserId::from(user_option.as_ref().unwrap().id),
@@ -197,6 +206,7 @@ pub async fn wiki_edit(
         {
             return Err(ApiError::NotFound);
         }
+        check_pat_project(&req, &project.inner)?;
         let wiki_cache = database::models::WikiCache::get_draft(
             project.inner.id,
             UserId::from(user_option.as_ref().unwrap().id),
@@ -281,6 +291,7 @@ pub async fn wiki_star(
         {
             return Err(ApiError::NotFound);
         }
+        check_pat_project(&req, &project.inner)?;
         let wiki_cache = database::models::WikiCache::get_draft(
             project.inner.id,
             UserId::from(user_option.as_ref().unwrap().id),
@@ -367,6 +378,7 @@ pub async fn wiki_create(
         {
             return Err(ApiError::NotFound);
         }
+        check_pat_project(&req, &project.inner)?;
         let wiki_cache = database::models::WikiCache::get_draft(
             project.inner.id,
             UserId::from(user_option.as_ref().unwrap().id),
@@ -498,6 +510,7 @@ pub async fn wiki_edit_start(
         {
             return Err(ApiError::NotFound);
         }
+        check_pat_project(&req, &project.inner)?;
         // let wiki_cache = database::models::WikiCache::get_draft(
         //     project.inner.id,
         //     UserId::from(user_option.as_ref().unwrap().id),
@@ -792,6 +805,7 @@ pub async fn wiki_accept(
         {
             return Err(ApiError::NotFound);
         }
+        check_pat_project(&req, &project.inner)?;
 
         let mut wikis = database::models::Wiki::get_many(
             &project.wikis,
@@ -1001,6 +1015,7 @@ pub async fn wiki_accept(
             }
 
             let mut transaction = pool.begin().await?;
+            let mut changed_wikis: Vec<&Wiki> = Vec::new();
             for (old_wiki, new_wiki) in common_wikis.values() {
                 if old_wiki.body != new_wiki.body
                     || old_wiki.sort_order != new_wiki.sort_order
@@ -1011,12 +1026,93 @@ pub async fn wiki_accept(
                     wiki.updated = chrono::Utc::now();
                     wiki.update(&mut transaction).await?;
                     wiki.clear_cache(&redis).await?;
+                    changed_wikis.push(new_wiki);
                 }
             }
             for new_wiki in added_wikis.values() {
                 let wiki = new_wiki.clone();
                 wiki.update(&mut transaction).await?;
                 wiki.clear_cache(&redis).await?;
+                changed_wikis.push(new_wiki);
+            }
+
+            // 通知百科页面的关注者，提交者自动关注其修改过的页面
+            let project_slug = project.inner.slug.clone().unwrap_or_default();
+            let acting_user_id = UserId::from(user_option.as_ref().unwrap().id);
+            // 不公开或付费的百科只通知仍能查看的关注者，避免泄露页面标题
+            let is_public = !project.inner.is_paid
+                && is_visible_project(&project.inner, &None, &pool, false)
+                    .await?;
+            for wiki in changed_wikis {
+                let title = format!("{} - {}", project.inner.name, wiki.title);
+                let link =
+                    format!("/project/{}/wiki/{}", project_slug, wiki.slug);
+                if is_public {
+                    SubscriptionEvent::queue(
+                        SubscriptionTarget::Wiki,
+                        wiki.id.0,
+                        None,
+                        SubscriptionEventType::WikiEditAccepted,
+                        Some(acting_user_id),
+                        &title,
+                        &link,
+                        &mut transaction,
+                    )
+                    .await?;
+                } else {
+                    let mut recipients = Vec::new();
+                    for subscriber_id in Subscription::get_subscribers(
+                        SubscriptionTarget::Wiki,
+                        wiki.id.0,
+                        &**pool,
+                    )
+                    .await?
+                    {
+                        let Some(subscriber) =
+                            User::get_id(subscriber_id, &**pool, &redis)
+                                .await?
+                        else {
+                            continue;
+                        };
+                        let subscriber =
+                            crate::models::users::User::from(subscriber);
+                        if is_visible_project(
+                            &project.inner,
+                            &Some(subscriber.clone()),
+                            &pool,
+                            false,
+                        )
+                        .await?
+                            && (!project.inner.is_paid
+                                || check_wiki_paid_access(
+                                    &subscriber,
+                                    &project.inner,
+                                    &pool,
+                                )
+                                .await?)
+                        {
+                            recipients.push(subscriber_id);
+                        }
+                    }
+                    SubscriptionEvent::queue_for_users(
+                        SubscriptionTarget::Wiki,
+                        wiki.id.0,
+                        SubscriptionEventType::WikiEditAccepted,
+                        Some(acting_user_id),
+                        &title,
+                        &link,
+                        &recipients,
+                        &mut transaction,
+                    )
+                    .await?;
+                }
+                Subscription::subscribe_automatic(
+                    wiki_cache_.user_id,
+                    SubscriptionTarget::Wiki,
+                    wiki.id.0,
+                    &mut transaction,
+                )
+                .await?;
             }
             for old_wiki in removed_wikis.values() {
                 if old_wiki.id != old_wiki.parent_wiki_id {
@@ -1032,17 +1128,20 @@ pub async fn wiki_accept(
                 .message_add(user_option.as_ref().unwrap(), "通过")
                 .await;
             wiki_cache_.finish_cache(&mut transaction).await?;
-            NotificationBuilder {
-                body: NotificationBody::WikiCache {
-                    project_id: ProjectId::from(project.inner.id),
-                    project_title: project.inner.name.clone(),
-                    wiki_cache_id: wiki_cache_.id,
-                    type_: "accept".to_string(),
-                    msg: "通过".to_string(),
-                },
+            // 审核自己提交的修改时无需通知
+            if wiki_cache_.user_id != acting_user_id {
+                NotificationBuilder {
+                    body: NotificationBody::WikiCache {
+                        project_id: ProjectId::from(project.inner.id),
+                        project_title: project.inner.name.clone(),
+                        wiki_cache_id: wiki_cache_.id,
+                        type_: "accept".to_string(),
+                        msg: "通过".to_string(),
+                    },
+                }
+                .insert(wiki_cache_.user_id, &mut transaction, &redis)
+                .await?;
             }
-            .insert(wiki_cache_.user_id, &mut transaction, &redis)
-            .await?;
 
             transaction.commit().await?;
             crate::database::models::Project::clear_cache(
@@ -1100,6 +1199,7 @@ pub async fn wiki_reject(
         {
             return Err(ApiError::NotFound);
         }
+        check_pat_project(&req, &project.inner)?;
 
         let mut wikis = database::models::Wiki::get_many(
             &project.wikis,
@@ -1152,6 +1252,23 @@ pub async fn wiki_reject(
                 .message_add(user_option.as_ref().unwrap(), &body.msg)
                 .await;
             wiki_cache_.reject_cache(&mut transaction).await?;
+            notify_mentions(
+                &body.msg,
+                user_option.as_ref().unwrap(),
+                MentionVisibility::Wiki(&project.inner),
+                MentionSource {
+                    source_type: "wiki_review",
+                    title: &project.inner.name,
+                    link: &format!(
+                        "/project/{}/wikis",
+                        project.inner.slug.clone().unwrap_or_default()
+                    ),
+                },
+                &mut transaction,
+                &pool,
+                &redis,
+            )
+            .await?;
             NotificationBuilder {
                 body: NotificationBody::WikiCache {
                     project_id: ProjectId::from(project.inner.id),
@@ -1214,6 +1331,7 @@ pub async fn wiki_submit(
         {
             return Err(ApiError::NotFound);
         }
+        check_pat_project(&req, &project.inner)?;
 
         let mut wikis = database::models::Wiki::get_many(
             &project.wikis,
@@ -1278,6 +1396,23 @@ pub async fn wiki_submit(
                 .message_add(user_option.as_ref().unwrap(), &body.msg)
                 .await;
             cache.review_cache(&mut transaction).await?;
+            notify_mentions(
+                &body.msg,
+                user_option.as_ref().unwrap(),
+                MentionVisibility::Wiki(&project.inner),
+                MentionSource {
+                    source_type: "wiki_review",
+                    title: &project.inner.name,
+                    link: &format!(
+                        "/project/{}/wikis",
+                        project.inner.slug.clone().unwrap_or_default()
+                    ),
+                },
+                &mut transaction,
+                &pool,
+                &redis,
+            )
+            .await?;
 
             let mut new_member =
                 database::models::TeamMember::get_from_team_full(
@@ -1543,6 +1678,23 @@ pub async fn wiki_submit(
                 .message_add(user_option.as_ref().unwrap(), &body.msg)
                 .await;
             wiki_cache_.finish_cache(&mut transaction).await?;
+            notify_mentions(
+                &body.msg,
+                user_option.as_ref().unwrap(),
+                MentionVisibility::Wiki(&project.inner),
+                MentionSource {
+                    source_type: "wiki_review",
+                    title: &project.inner.name,
+                    link: &format!(
+                        "/project/{}/wikis",
+                        project.inner.slug.clone().unwrap_or_default()
+                    ),
+                },
+                &mut transaction,
+                &pool,
+                &redis,
+            )
+            .await?;
             transaction.commit().await?;
             crate::database::models::Project::clear_cache(
                 project.inner.id,
@@ -1599,6 +1751,7 @@ pub async fn wiki_submit_again(
         {
             return Err(ApiError::NotFound);
         }
+        check_pat_project(&req, &project.inner)?;
 
         let more = database::models::WikiCache::get_draft_or_review(
             project.inner.id,
@@ -1681,6 +1834,7 @@ pub async fn wiki_given_up(
         {
             return Err(ApiError::NotFound);
         }
+        check_pat_project(&req, &project.inner)?;
 
         let cache_id_parsed: i64 = cache_id
             .parse()
@@ -1723,57 +1877,6 @@ pub async fn wiki_given_up(
     }
 }
 
-/// 检查用户是否有权访问付费项目的 Wiki
-async fn check_wiki_paid_access(
-    user: &crate::models::v3::users::User,
-    project: &database::models::project_item::Project,
-    pool: &PgPool,
-) -> Result<bool, ApiError> {
-    // 管理员/版主
-    if user.role.is_admin() || user.role.is_mod() {
-        return Ok(true);
-    }
-
-    let user_id: database::models::UserId = user.id.into();
-    let project_id = project.id;
-
-    // 团队成员
-    let team_member = database::models::TeamMember::get_from_user_id_project(
-        project_id, user_id, false, pool,
-    )
-    .await
-    .map_err(ApiError::Database)?;
-    if team_member.is_some() {
-        return Ok(true);
-    }
-
-    // 组织成员
-    let organization =
-        database::models::Organization::get_associated_organization_project_id(
-            project_id, pool,
-        )
-        .await
-        .map_err(ApiError::Database)?;
-    if let Some(org) = organization {
-        let org_member =
-            database::models::TeamMember::get_from_user_id_organization(
-                org.id, user_id, false, pool,
-            )
-            .await
-            .map_err(ApiError::Database)?;
-        if org_member.is_some() {
-            return Ok(true);
-        }
-    }
-
-    // 已购买
-    let has_purchased = UserPurchase::check_access(user_id, project_id, pool)
-        .await
-        .map_err(ApiError::Database)?;
-
-    Ok(has_purchased)
-}
-
 pub fn wiki_format(wikis: Vec<Wiki>) -> Vec<WikiDisplays> {
     let mut wikis_: HashMap<i64, WikiDisplays> = HashMap::new();
     for wiki in &wikis {
@@ -0,0 +1,318 @@
+//! 分析报告邮件调度器
+//!
+//! 定期检查到期的分析报告订阅，统计所选项目本周期与上一周期的
+//! 下载量、浏览量、新增关注、购买次数和收入，通过邮件发送给订阅者。
+
+use crate::analytics::AnalyticsStorage;
+use crate::auth::email::send_email;
+use crate::database::models::analytics_report_item::{
+    AnalyticsReportSubscription, ReportFrequency,
+};
+use crate::database::models::{self, ProjectId};
+use crate::database::redis::RedisPool;
+use crate::models::ids::base62_impl::to_base62;
+use crate::routes::ApiError;
+use crate::routes::v3::analytics_get::filter_allowed_ids;
+use crate::util::env::parse_var;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::Scheduler;
+
+// 每轮最多处理的订阅数量，剩余订阅留到下一轮
+const REPORTS_PER_RUN: i64 = 100;
+
+pub fn schedule_analytics_reports(
+    scheduler: &mut Scheduler,
+    pool: sqlx::Pool<sqlx::Postgres>,
+    redis: RedisPool,
+    analytics: Arc<dyn AnalyticsStorage + Send + Sync>,
+) {
+    let interval = std::time::Duration::from_secs(
+        parse_var("ANALYTICS_REPORT_INTERVAL").unwrap_or(3600),
+    );
+
+    scheduler.run_local(interval, move || {
+        let pool_ref = pool.clone();
+        let redis = redis.clone();
+        let analytics = analytics.clone();
+        async move {
+            match send_analytics_reports(&pool_ref, &redis, &*analytics).await {
+                Ok(count) if count > 0 => {
+                    info!("已发送 {} 封分析报告邮件", count);
+                }
+                Err(e) => {
+                    warn!("发送分析报告邮件失败：{}", e);
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+/// 单个项目在一个周期内的数据
+#[derive(Default, Clone)]
+struct PeriodStats {
+    downloads: u64,
+    views: u64,
+    follows: u64,
+    purchases: u64,
+    revenue: Decimal,
+}
+
+impl PeriodStats {
+    fn add(&mut self, other: &PeriodStats) {
+        self.downloads += other.downloads;
+        self.views += other.views;
+        self.follows += other.follows;
+        self.purchases += other.purchases;
+        self.revenue += other.revenue;
+    }
+}
+
+async fn send_analytics_reports(
+    pool: &sqlx::Pool<sqlx::Postgres>,
+    redis: &RedisPool,
+    analytics: &(dyn AnalyticsStorage + Send + Sync),
+) -> Result<usize, ApiError> {
+    let now = Utc::now();
+    let reports =
+        AnalyticsReportSubscription::get_due(now, REPORTS_PER_RUN, pool)
+            .await?;
+
+    let site_url = dotenvy::var("SITE_URL")?;
+    let mut sent = 0;
+    for report in reports {
+        // 无论是否发送成功都记录本次发送时间，避免失败的订阅每轮重试
+        AnalyticsReportSubscription::mark_sent(report.id, now, pool).await?;
+
+        let Some(user) =
+            models::User::get_id(report.user_id, pool, redis).await?
+        else {
+            continue;
+        };
+        let Some(email) = user.email.clone().filter(|_| user.email_verified)
+        else {
+            continue;
+        };
+
+        // 订阅后可能失去了项目的分析数据查看权限，发送前重新过滤
+        let project_ids = filter_allowed_ids(
+            Some(
+                report
+                    .project_ids
+                    .iter()
+                    .map(|x| to_base62(x.0 as u64))
+                    .collect(),
+            ),
+            user.clone().into(),
+            pool,
+            redis,
+            Some(true),
+        )
+        .await?
+        .unwrap_or_default()
+        .into_iter()
+        .map(ProjectId::from)
+        .collect::<Vec<_>>();
+        if project_ids.is_empty() {
+            continue;
+        }
+
+        let start = report.frequency.period_start(now);
+        let previous_start = report.frequency.period_start(start);
+
+        let current = fetch_period_stats(
+            &project_ids,
+            user.id,
+            start,
+            now,
+            pool,
+            analytics,
+        )
+        .await?;
+        let previous = fetch_period_stats(
+            &project_ids,
+            user.id,
+            previous_start,
+            start,
+            pool,
+            analytics,
+        )
+        .await?;
+
+        let projects =
+            models::Project::get_many_ids(&project_ids, pool, redis).await?;
+
+        let mut lines = Vec::new();
+        let mut current_total = PeriodStats::default();
+        let mut previous_total = PeriodStats::default();
+        for project in &projects {
+            let current =
+                current.get(&project.inner.id).cloned().unwrap_or_default();
+            let previous =
+                previous.get(&project.inner.id).cloned().unwrap_or_default();
+            current_total.add(&current);
+            previous_total.add(&previous);
+
+            lines.push(format!(
+                "<b>{}</b><br>{}",
+                escape_html(&project.inner.name),
+                render_stats(&current, &previous)
+            ));
+        }
+
+        let period = match report.frequency {
+            ReportFrequency::Weekly => "本周",
+            ReportFrequency::Monthly => "本月",
+        };
+        let description = format!(
+            "{}，你订阅的 {} 个项目{}共获得 {} 次下载、{} 次浏览。",
+            user.username,
+            projects.len(),
+            period,
+            current_total.downloads,
+            current_total.views
+        );
+        let body = format!(
+            "<b>总计</b><br>{}<br><br>{}<br><br>与上一周期（{} 至 {}）相比。",
+            render_stats(&current_total, &previous_total),
+            lines.join("<br><br>"),
+            previous_start.format("%Y-%m-%d"),
+            start.format("%Y-%m-%d")
+        );
+
+        match send_email(
+            email,
+            &format!("{period}分析报告"),
+            &description,
+            &body,
+            Some(("查看分析数据", &format!("{site_url}/dashboard/analytics"))),
+        ) {
+            Ok(()) => sent += 1,
+            Err(e) => warn!("发送分析报告 {} 失败：{}", report.id, e),
+        }
+    }
+
+    Ok(sent)
+}
+
+/// 统计一组项目在 `[start, end)` 内的数据。
+/// 收入只统计订阅者作为卖家收到的金额
+async fn fetch_period_stats(
+    project_ids: &[ProjectId],
+    user_id: models::UserId,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    pool: &sqlx::Pool<sqlx::Postgres>,
+    analytics: &(dyn AnalyticsStorage + Send + Sync),
+) -> Result<HashMap<ProjectId, PeriodStats>, ApiError> {
+    let mut stats: HashMap<ProjectId, PeriodStats> = HashMap::new();
+    let ids = project_ids
+        .iter()
+        .map(|x| crate::models::ids::ProjectId::from(*x))
+        .collect::<Vec<_>>();
+
+    for total in analytics
+        .fetch_downloads_total(ids.clone(), start, end)
+        .await?
+    {
+        stats
+            .entry(ProjectId(total.id as i64))
+            .or_default()
+            .downloads += total.total;
+    }
+
+    for view in analytics.fetch_views(ids, start, end, 60 * 24).await? {
+        stats.entry(ProjectId(view.id as i64)).or_default().views += view.total;
+    }
+
+    let raw_ids = project_ids.iter().map(|x| x.0).collect::<Vec<_>>();
+
+    // 取消关注会删除记录，这里统计的是期间新增且仍然保留的关注
+    let follows = sqlx::query!(
+        "
+        SELECT mod_id, COUNT(*) AS count
+        FROM mod_follows
+        WHERE mod_id = ANY($1) AND created >= $2 AND created < $3
+        GROUP BY mod_id
+        ",
+        &raw_ids,
+        start,
+        end
+    )
+    .fetch_all(pool)
+    .await?;
+    for row in follows {
+        stats.entry(ProjectId(row.mod_id)).or_default().follows =
+            row.count.unwrap_or(0) as u64;
+    }
+
+    let purchases = sqlx::query!(
+        "
+        SELECT project_id, COUNT(*) AS count,
+            SUM(seller_amount) FILTER (WHERE seller_id = $4) AS revenue
+        FROM payment_orders
+        WHERE project_id = ANY($1) AND status = 'paid'
+        AND paid_at >= $2 AND paid_at < $3
+        GROUP BY project_id
+        ",
+        &raw_ids,
+        start,
+        end,
+        user_id.0
+    )
+    .fetch_all(pool)
+    .await?;
+    for row in purchases {
+        let entry = stats.entry(ProjectId(row.project_id)).or_default();
+        entry.purchases = row.count.unwrap_or(0) as u64;
+        entry.revenue = row.revenue.unwrap_or_default();
+    }
+
+    Ok(stats)
+}
+
+fn render_stats(current: &PeriodStats, previous: &PeriodStats) -> String {
+    format!(
+        "下载 {}（{}）· 浏览 {}（{}）· 新增关注 {}（{}）<br>购买 {}（{}）· 收入 ¥{}（{}）",
+        current.downloads,
+        change(current.downloads as f64, previous.downloads as f64),
+        current.views,
+        change(current.views as f64, previous.views as f64),
+        current.follows,
+        change(current.follows as f64, previous.follows as f64),
+        current.purchases,
+        change(current.purchases as f64, previous.purchases as f64),
+        current.revenue.round_dp(2),
+        change(
+            current.revenue.to_f64().unwrap_or(0.0),
+            previous.revenue.to_f64().unwrap_or(0.0)
+        ),
+    )
+}
+
+/// 与上一周期相比的变化
+fn change(current: f64, previous: f64) -> String {
+    if previous == 0.0 {
+        if current == 0.0 {
+            "持平".to_string()
+        } else {
+            "上期为 0".to_string()
+        }
+    } else {
+        format!("{:+.1}%", (current - previous) / previous * 100.0)
+    }
+}
+
+fn escape_html(string: &str) -> String {
+    string
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -2,9 +2,18 @@ use actix_rt::Arbiter;
 use futures::StreamExt;
 use tokio_stream::wrappers::IntervalStream;
 
+mod analytics_reports;
+mod oidc_keys;
+mod subscriptions;
+mod translation_coverage;
+mod translation_languages;
 mod translation_tracking;
 mod versions;
 
+pub use analytics_reports::schedule_analytics_reports;
+pub use oidc_keys::schedule_oidc_key_rotation;
+pub use subscriptions::schedule_subscription_notifications;
+pub use translation_coverage::schedule_translation_coverage;
 pub use translation_tracking::schedule_translation_tracking;
 pub use versions::schedule_versions;
 
@@ -35,6 +44,22 @@ impl Scheduler {
 
         self.arbiter.spawn(future);
     }
+
+    /// 与 `run` 相同，但任务在当前线程的运行时上执行，
+    /// 适用于用到 Redis 缓存查询等不满足 `Send` 的任务
+    pub fn run_local<F, R>(
+        &mut self,
+        interval: std::time::Duration,
+        mut task: F,
+    ) where
+        F: FnMut() -> R + 'static,
+        R: std::future::Future<Output = ()> + 'static,
+    {
+        let future = IntervalStream::new(actix_rt::time::interval(interval))
+            .for_each_concurrent(2, move |_| task());
+
+        actix_rt::spawn(future);
+    }
 }
 
 impl Drop for Scheduler {
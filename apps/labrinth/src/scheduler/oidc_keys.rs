@@ -0,0 +1,26 @@
+//! OpenID Connect 签名密钥轮换调度器
+//!
+//! 定期生成新的 ES256 签名密钥、导入配置的 RS256 密钥，
+//! 并清理已退役超过保留期的旧密钥。
+
+use log::warn;
+
+use super::Scheduler;
+
+pub fn schedule_oidc_key_rotation(
+    scheduler: &mut Scheduler,
+    pool: sqlx::Pool<sqlx::Postgres>,
+) {
+    let interval = std::time::Duration::from_secs(60 * 60);
+
+    scheduler.run(interval, move || {
+        let pool_ref = pool.clone();
+        async move {
+            if let Err(e) =
+                crate::auth::oauth::oidc::rotate_signing_keys(&pool_ref).await
+            {
+                warn!("轮换 OIDC 签名密钥失败: {e}");
+            }
+        }
+    });
+}
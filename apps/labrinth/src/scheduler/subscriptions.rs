@@ -0,0 +1,117 @@
+//! 关注通知调度器
+//!
+//! 定期将关注者的待发送事件按对象合并，每个对象只生成一条通知，
+//! 避免热门讨论刷屏。
+
+use crate::database::models::DatabaseError;
+use crate::database::models::notification_item::NotificationBuilder;
+use crate::database::models::subscription_item::SubscriptionEvent;
+use crate::database::redis::RedisPool;
+use crate::models::ids::base62_impl::to_base62;
+use crate::models::notifications::NotificationBody;
+use crate::util::env::parse_var;
+use log::{info, warn};
+use std::collections::HashMap;
+
+use super::Scheduler;
+
+// 每轮最多处理的事件数量，剩余事件留到下一轮
+const EVENTS_PER_RUN: i64 = 5000;
+
+pub fn schedule_subscription_notifications(
+    scheduler: &mut Scheduler,
+    pool: sqlx::Pool<sqlx::Postgres>,
+    redis: RedisPool,
+) {
+    let interval = std::time::Duration::from_secs(
+        parse_var("SUBSCRIPTION_NOTIFY_INTERVAL").unwrap_or(300),
+    );
+
+    scheduler.run(interval, move || {
+        let pool_ref = pool.clone();
+        let redis = redis.clone();
+        async move {
+            match send_subscription_notifications(&pool_ref, &redis).await {
+                Ok(count) if count > 0 => {
+                    info!("已发送 {} 条关注通知", count);
+                }
+                Err(e) => {
+                    warn!("发送关注通知失败：{}", e);
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+struct PendingNotification {
+    title: String,
+    link: String,
+    event_types: Vec<String>,
+    event_count: u32,
+    actors: Vec<String>,
+}
+
+async fn send_subscription_notifications(
+    pool: &sqlx::Pool<sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<usize, DatabaseError> {
+    let events = SubscriptionEvent::get_pending(EVENTS_PER_RUN, pool).await?;
+    if events.is_empty() {
+        return Ok(0);
+    }
+
+    let event_ids = events.iter().map(|x| x.id).collect::<Vec<_>>();
+
+    // 按 (用户, 对象类型, 对象ID) 合并
+    let mut grouped: HashMap<
+        (crate::database::models::UserId, String, i64),
+        PendingNotification,
+    > = HashMap::new();
+    for event in events {
+        let entry = grouped
+            .entry((event.user_id, event.target_type, event.target_id))
+            .or_insert_with(|| PendingNotification {
+                title: event.title.clone(),
+                link: event.link.clone(),
+                event_types: Vec::new(),
+                event_count: 0,
+                actors: Vec::new(),
+            });
+
+        // 使用最新的标题和链接
+        entry.title = event.title;
+        entry.link = event.link;
+        entry.event_count += 1;
+        if !entry.event_types.contains(&event.event_type) {
+            entry.event_types.push(event.event_type);
+        }
+        if let Some(actor) = event.actor_name
+            && !entry.actors.contains(&actor)
+        {
+            entry.actors.push(actor);
+        }
+    }
+
+    let count = grouped.len();
+    let mut transaction = pool.begin().await?;
+    for ((user_id, target_type, target_id), pending) in grouped {
+        NotificationBuilder {
+            body: NotificationBody::Subscription {
+                target_type,
+                target_id: to_base62(target_id as u64),
+                title: pending.title,
+                link: pending.link,
+                event_types: pending.event_types,
+                event_count: pending.event_count,
+                actors: pending.actors,
+            },
+        }
+        .insert(user_id, &mut transaction, redis)
+        .await?;
+    }
+    SubscriptionEvent::remove_many(&event_ids, &mut transaction).await?;
+    transaction.commit().await?;
+
+    Ok(count)
+}
@@ -0,0 +1,514 @@
+//! 汉化覆盖率分析调度器
+//!
+//! 为汉化追踪项目的新版本提取整合包文件及内置模组中的 en_us 语言键，
+//! 与绑定的各语言汉化版本（`version_link_version`）中的目标语言键对比，
+//! 为每个版本、每种追踪语言生成覆盖率报告（新增、移除、未翻译的键）。
+
+use crate::database::models::DatabaseError;
+use crate::database::models::ids::VersionId;
+use crate::database::models::translation_coverage_item::{
+    COVERAGE_COMPLETE, COVERAGE_FAILED, COVERAGE_UNAVAILABLE, PendingCoverage,
+    TranslationCoverage,
+};
+use crate::database::models::version_item::{QueryFile, Version};
+use crate::database::redis::RedisPool;
+use crate::models::pack::{PackFileHash, PackFormat};
+use crate::util::env::parse_var;
+use bytes::Bytes;
+use chrono::Utc;
+use itertools::Itertools;
+use log::{debug, info, warn};
+use std::collections::{BTreeSet, HashMap};
+use std::io::{Cursor, Read};
+use thiserror::Error;
+use validator::Validate;
+use zip::ZipArchive;
+
+use super::Scheduler;
+
+// 模组语言键缓存，按文件 sha1 区分
+const LANG_KEYS_NAMESPACE: &str = "translation_lang_keys";
+const LANG_KEYS_EXPIRY: i64 = 60 * 60 * 24 * 7;
+
+// 每轮最多分析的版本数量，剩余版本留到下一轮
+const VERSIONS_PER_RUN: i64 = 3;
+// 每个项目只分析最近的若干个版本，不回溯全部历史
+const VERSION_HISTORY: i64 = 5;
+
+const SOURCE_LANG: &str = "en_us";
+
+// 整合包文件与嵌套压缩包（模组、资源包）的大小上限
+const MAX_ARCHIVE_SIZE: u64 = 1024 * 1024 * 1024;
+const MAX_NESTED_SIZE: u64 = 256 * 1024 * 1024;
+// 语言文件与 modrinth.index.json 解压后的大小上限
+const MAX_ENTRY_SIZE: u64 = 16 * 1024 * 1024;
+// 压缩包嵌套层数上限（整合包 -> 模组 -> 模组内嵌的 jar）
+const MAX_NESTING_DEPTH: usize = 2;
+
+pub fn schedule_translation_coverage(
+    scheduler: &mut Scheduler,
+    pool: sqlx::Pool<sqlx::Postgres>,
+    redis: RedisPool,
+) {
+    let interval = std::time::Duration::from_secs(
+        parse_var("TRANSLATION_COVERAGE_INTERVAL").unwrap_or(600),
+    );
+
+    scheduler.run_local(interval, move || {
+        let pool_ref = pool.clone();
+        let redis_ref = redis.clone();
+
+        async move {
+            match run_translation_coverage(&pool_ref, &redis_ref).await {
+                Ok(count) if count > 0 => {
+                    info!("已生成 {} 个版本的汉化覆盖率报告", count);
+                }
+                Err(e) => {
+                    warn!("汉化覆盖率分析失败：{}", e);
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+async fn run_translation_coverage(
+    pool: &sqlx::Pool<sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<usize, TranslationCoverageError> {
+    let pending = TranslationCoverage::get_pending(
+        VERSION_HISTORY,
+        VERSIONS_PER_RUN,
+        pool,
+    )
+    .await?;
+
+    // 同一版本的多种语言只需下载一次整合包
+    let mut source_cache: HashMap<VersionId, Vec<String>> = HashMap::new();
+
+    let mut count = 0;
+    for mut item in pending {
+        if item.existing_source_keys.is_none() {
+            item.existing_source_keys =
+                source_cache.get(&item.version_id).cloned();
+        }
+
+        let (status, error, source_keys, target_keys) =
+            match analyze_version(&item, pool, redis).await {
+                Ok(Some((source_keys, target_keys))) => {
+                    (COVERAGE_COMPLETE, None, source_keys, target_keys)
+                }
+                Ok(None) => (
+                    COVERAGE_UNAVAILABLE,
+                    None,
+                    BTreeSet::new(),
+                    BTreeSet::new(),
+                ),
+                Err(e) => {
+                    debug!(
+                        "分析版本 {} 的汉化覆盖率失败：{}",
+                        item.version_id.0, e
+                    );
+                    (
+                        COVERAGE_FAILED,
+                        Some(e.to_string()),
+                        BTreeSet::new(),
+                        BTreeSet::new(),
+                    )
+                }
+            };
+
+        if status == COVERAGE_COMPLETE {
+            source_cache
+                .insert(item.version_id, source_keys.iter().cloned().collect());
+        }
+
+        let previous = if status == COVERAGE_COMPLETE {
+            TranslationCoverage::get_previous(item.version_id, pool).await?
+        } else {
+            None
+        };
+
+        // 第一个被分析的版本没有可对比的上一版本，不记录新增/移除
+        let (new_keys, removed_keys) = match &previous {
+            Some((_, previous_keys)) => {
+                let previous_keys =
+                    previous_keys.iter().cloned().collect::<BTreeSet<_>>();
+                (
+                    source_keys
+                        .difference(&previous_keys)
+                        .cloned()
+                        .collect::<Vec<_>>(),
+                    previous_keys
+                        .difference(&source_keys)
+                        .cloned()
+                        .collect::<Vec<_>>(),
+                )
+            }
+            None => (Vec::new(), Vec::new()),
+        };
+
+        let untranslated_keys = source_keys
+            .difference(&target_keys)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let now = Utc::now();
+        let coverage = TranslationCoverage {
+            version_id: item.version_id,
+            language_code: item.language_code.clone(),
+            project_id: item.project_id,
+            translation_version_id: item.translation_version_id,
+            previous_version_id: previous.map(|x| x.0),
+            status: status.to_string(),
+            error,
+            total_keys: source_keys.len() as i32,
+            translated_keys: (source_keys.len() - untranslated_keys.len())
+                as i32,
+            source_keys: source_keys.into_iter().collect(),
+            new_keys,
+            removed_keys,
+            untranslated_keys,
+            created_at: now,
+            updated_at: now,
+        };
+
+        let mut transaction = pool.begin().await?;
+        coverage.upsert(&mut transaction).await?;
+        transaction.commit().await?;
+
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// 返回版本的原文键与汉化键；版本没有可下载的文件时返回 None
+async fn analyze_version(
+    item: &PendingCoverage,
+    pool: &sqlx::Pool<sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<
+    Option<(BTreeSet<String>, BTreeSet<String>)>,
+    TranslationCoverageError,
+> {
+    let source_keys = if let Some(keys) = &item.existing_source_keys {
+        keys.iter().cloned().collect()
+    } else {
+        let Some(version) = Version::get(item.version_id, pool, redis).await?
+        else {
+            return Ok(None);
+        };
+        let Some(file) = downloadable_file(&version.files) else {
+            return Ok(None);
+        };
+
+        debug!("提取版本 {} 的语言键：{}", item.version_id.0, file.filename);
+        let data = download(&file.url, MAX_ARCHIVE_SIZE).await?;
+        extract_modpack_keys(data, redis).await?
+    };
+
+    let target_keys = match item.translation_version_id {
+        Some(translation_version_id) => {
+            extract_translation_keys(
+                translation_version_id,
+                &item.language_code,
+                pool,
+                redis,
+            )
+            .await?
+        }
+        None => BTreeSet::new(),
+    };
+
+    Ok(Some((source_keys, target_keys)))
+}
+
+async fn extract_translation_keys(
+    version_id: VersionId,
+    language_code: &str,
+    pool: &sqlx::Pool<sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<BTreeSet<String>, TranslationCoverageError> {
+    let mut keys = BTreeSet::new();
+
+    let Some(version) = Version::get(version_id, pool, redis).await? else {
+        return Ok(keys);
+    };
+
+    for file in version.files.iter().filter(|x| !x.is_private) {
+        let data = download(&file.url, MAX_ARCHIVE_SIZE).await?;
+        keys.extend(archive_keys(data, language_code, 0).await?);
+    }
+
+    Ok(keys)
+}
+
+/// 整合包的主文件，网盘版本和付费私有文件无法直接下载
+fn downloadable_file(files: &[QueryFile]) -> Option<&QueryFile> {
+    files
+        .iter()
+        .find_or_first(|x| x.primary)
+        .filter(|x| !x.is_private && x.url.starts_with("http"))
+}
+
+/// 提取整合包中的语言键，包括 overrides 中的文件、内置的模组与资源包，
+/// 以及 modrinth.index.json 中引用的外部模组
+async fn extract_modpack_keys(
+    data: Bytes,
+    redis: &RedisPool,
+) -> Result<BTreeSet<String>, TranslationCoverageError> {
+    let (mut keys, pack) = tokio::task::spawn_blocking(
+        move || -> Result<_, TranslationCoverageError> {
+            let mut keys = BTreeSet::new();
+            collect_archive_keys(&data, SOURCE_LANG, 0, &mut keys)?;
+
+            let mut zip = ZipArchive::new(Cursor::new(&data[..]))?;
+            let pack = match zip.by_name("modrinth.index.json") {
+                Ok(mut file) => read_entry(&mut file, MAX_ENTRY_SIZE)?
+                    .and_then(|x| {
+                        serde_json::from_slice::<PackFormat>(&x).ok()
+                    }),
+                Err(_) => None,
+            };
+
+            Ok((keys, pack))
+        },
+    )
+    .await??;
+
+    let Some(pack) = pack else {
+        return Ok(keys);
+    };
+
+    for file in pack.files {
+        let path = file.path.to_string();
+        if !(path.starts_with("mods/") || path.starts_with("resourcepacks/"))
+            || !(path.ends_with(".jar") || path.ends_with(".zip"))
+        {
+            continue;
+        }
+        // 只从整合包校验允许的下载域名获取外部文件
+        if file.validate().is_err() {
+            continue;
+        }
+        let (Some(sha1), Some(url)) =
+            (file.hashes.get(&PackFileHash::Sha1), file.downloads.first())
+        else {
+            continue;
+        };
+
+        match get_file_keys(sha1, url, redis).await {
+            Ok(file_keys) => keys.extend(file_keys),
+            Err(e) => {
+                debug!("提取 {} 的语言键失败：{}", path, e);
+            }
+        }
+    }
+
+    Ok(keys)
+}
+
+/// 获取外部模组的语言键，同一文件在多个版本间复用缓存
+async fn get_file_keys(
+    sha1: &str,
+    url: &str,
+    redis: &RedisPool,
+) -> Result<Vec<String>, TranslationCoverageError> {
+    let mut conn = redis.connect().await?;
+    if let Some(keys) = conn
+        .get_deserialized_from_json::<Vec<String>>(LANG_KEYS_NAMESPACE, sha1)
+        .await?
+    {
+        return Ok(keys);
+    }
+
+    let data = download(url, MAX_NESTED_SIZE).await?;
+    let keys = archive_keys(data, SOURCE_LANG, 1)
+        .await?
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    conn.set_serialized_to_json(
+        LANG_KEYS_NAMESPACE,
+        sha1,
+        &keys,
+        Some(LANG_KEYS_EXPIRY),
+    )
+    .await?;
+
+    Ok(keys)
+}
+
+async fn download(
+    url: &str,
+    max_size: u64,
+) -> Result<Bytes, TranslationCoverageError> {
+    let response = reqwest::get(url).await?.error_for_status()?;
+    if response.content_length().is_some_and(|x| x > max_size) {
+        return Err(TranslationCoverageError::TooLarge(url.to_string()));
+    }
+
+    let data = response.bytes().await?;
+    if data.len() as u64 > max_size {
+        return Err(TranslationCoverageError::TooLarge(url.to_string()));
+    }
+
+    Ok(data)
+}
+
+/// 在阻塞线程池中解析压缩包，避免大文件阻塞异步运行时
+async fn archive_keys(
+    data: Bytes,
+    lang: &str,
+    depth: usize,
+) -> Result<BTreeSet<String>, TranslationCoverageError> {
+    let lang = lang.to_string();
+    tokio::task::spawn_blocking(move || {
+        let mut keys = BTreeSet::new();
+        collect_archive_keys(&data, &lang, depth, &mut keys)?;
+        Ok(keys)
+    })
+    .await?
+}
+
+/// 读取压缩包内的文件，解压后超过上限时返回 `None`，防止压缩炸弹耗尽内存
+fn read_entry(
+    file: &mut impl Read,
+    max_size: u64,
+) -> Result<Option<Vec<u8>>, std::io::Error> {
+    let mut contents = Vec::new();
+    file.by_ref()
+        .take(max_size + 1)
+        .read_to_end(&mut contents)?;
+
+    Ok((contents.len() as u64 <= max_size).then_some(contents))
+}
+
+/// 遍历压缩包，收集指定语言文件中的键，并递归进入内置的 jar/zip
+fn collect_archive_keys(
+    data: &[u8],
+    lang: &str,
+    depth: usize,
+    keys: &mut BTreeSet<String>,
+) -> Result<(), TranslationCoverageError> {
+    let mut zip = ZipArchive::new(Cursor::new(data))?;
+
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_string();
+
+        if let Some((namespace, legacy)) = lang_file_namespace(&name, lang) {
+            match read_entry(&mut file, MAX_ENTRY_SIZE)? {
+                Some(contents) => {
+                    let contents = String::from_utf8_lossy(&contents);
+                    parse_lang_keys(&namespace, &contents, legacy, keys);
+                }
+                None => debug!("跳过过大的语言文件 {}", name),
+            }
+            continue;
+        }
+
+        let lowercase = name.to_lowercase();
+        if depth < MAX_NESTING_DEPTH
+            && (lowercase.ends_with(".jar") || lowercase.ends_with(".zip"))
+            && file.size() <= MAX_NESTED_SIZE
+        {
+            let Some(contents) = read_entry(&mut file, MAX_NESTED_SIZE)? else {
+                debug!("跳过解压后过大的压缩包 {}", name);
+                continue;
+            };
+            if let Err(e) =
+                collect_archive_keys(&contents, lang, depth + 1, keys)
+            {
+                debug!("跳过无法读取的压缩包 {}：{}", name, e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 判断路径是否为 `assets/<namespace>/lang/<lang>.json`（或旧版 `.lang`），
+/// 返回命名空间以及是否为旧版格式
+fn lang_file_namespace(path: &str, lang: &str) -> Option<(String, bool)> {
+    let parts = path.split('/').collect::<Vec<_>>();
+    if parts.len() < 4 {
+        return None;
+    }
+
+    let len = parts.len();
+    if parts[len - 4] != "assets" || parts[len - 2] != "lang" {
+        return None;
+    }
+
+    let file_name = parts[len - 1].to_lowercase();
+    let legacy = if file_name == format!("{lang}.json") {
+        false
+    } else if file_name == format!("{lang}.lang") {
+        true
+    } else {
+        return None;
+    };
+
+    Some((parts[len - 3].to_string(), legacy))
+}
+
+/// 解析语言文件，键以 `命名空间:键` 的形式加入集合
+fn parse_lang_keys(
+    namespace: &str,
+    contents: &str,
+    legacy: bool,
+    keys: &mut BTreeSet<String>,
+) {
+    let contents = contents.trim_start_matches('\u{feff}');
+
+    if legacy {
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty()
+                || line.starts_with('#')
+                || line.starts_with("//")
+            {
+                continue;
+            }
+            if let Some((key, _)) = line.split_once('=') {
+                keys.insert(format!("{}:{}", namespace, key.trim()));
+            }
+        }
+    } else {
+        match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(
+            contents,
+        ) {
+            Ok(map) => {
+                for key in map.keys() {
+                    keys.insert(format!("{namespace}:{key}"));
+                }
+            }
+            Err(e) => {
+                debug!("无法解析 {} 的语言文件：{}", namespace, e);
+            }
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum TranslationCoverageError {
+    #[error("数据库错误：{0}")]
+    SqlxError(#[from] sqlx::Error),
+    #[error("数据库模型错误：{0}")]
+    DatabaseError(#[from] DatabaseError),
+    #[error("下载文件失败：{0}")]
+    ReqwestError(#[from] reqwest::Error),
+    #[error("读取压缩包失败：{0}")]
+    ZipError(#[from] zip::result::ZipError),
+    #[error("读取文件失败：{0}")]
+    IoError(#[from] std::io::Error),
+    #[error("文件过大：{0}")]
+    TooLarge(String),
+    #[error("解析任务失败：{0}")]
+    JoinError(#[from] tokio::task::JoinError),
+}
@@ -0,0 +1,67 @@
+use super::{SmsError, SmsReceipt, SmsSender};
+use alibaba_cloud_sdk_rust::services::dysmsapi;
+use async_trait::async_trait;
+
+/// 阿里云短信服务 (dysmsapi)
+pub struct AliyunSms {
+    region: String,
+    access_key_id: String,
+    access_key_secret: String,
+    sign_name: String,
+    template_code: String,
+}
+
+impl AliyunSms {
+    pub fn new(
+        region: &str,
+        access_key_id: &str,
+        access_key_secret: &str,
+        sign_name: &str,
+        template_code: &str,
+    ) -> Self {
+        AliyunSms {
+            region: region.to_string(),
+            access_key_id: access_key_id.to_string(),
+            access_key_secret: access_key_secret.to_string(),
+            sign_name: sign_name.to_string(),
+            template_code: template_code.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl SmsSender for AliyunSms {
+    fn provider(&self) -> &'static str {
+        "aliyun"
+    }
+
+    async fn send_code(
+        &self,
+        phone_number: &str,
+        code: &str,
+    ) -> Result<SmsReceipt, SmsError> {
+        let mut client = dysmsapi::Client::NewClientWithAccessKey(
+            &self.region,
+            &self.access_key_id,
+            &self.access_key_secret,
+        )?;
+        let mut request = dysmsapi::CreateSendSmsRequest();
+        request.PhoneNumbers = phone_number.to_owned();
+        request.SignName = self.sign_name.clone();
+        request.TemplateCode = self.template_code.clone();
+        request.TemplateParam = serde_json::json!({ "code": code }).to_string();
+        let response = client.SendSms(&mut request)?;
+
+        // 请求成功时 Code 为 OK，其余均为业务错误（如触发服务商的流控）
+        if response.Code != "OK" {
+            return Err(SmsError::Provider {
+                code: response.Code,
+                message: response.Message,
+            });
+        }
+
+        Ok(SmsReceipt {
+            message_id: response.BizId,
+        })
+    }
+}
@@ -0,0 +1,49 @@
+use super::{SmsError, SmsReceipt, SmsSender};
+use async_trait::async_trait;
+use chrono::Utc;
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+
+/// 不发送短信，只把验证码写入日志；配置 `SMS_LOCAL_PATH` 时同时追加到该文件，
+/// 每行一条 JSON，便于测试读取验证码
+#[derive(Default)]
+pub struct LocalSms {
+    path: Option<String>,
+}
+
+impl LocalSms {
+    pub fn new(path: Option<String>) -> Self {
+        LocalSms { path }
+    }
+}
+
+#[async_trait]
+impl SmsSender for LocalSms {
+    fn provider(&self) -> &'static str {
+        "local"
+    }
+
+    async fn send_code(
+        &self,
+        phone_number: &str,
+        code: &str,
+    ) -> Result<SmsReceipt, SmsError> {
+        info!("短信验证码（未实际发送）: {phone_number} -> {code}");
+
+        if let Some(path) = &self.path {
+            let line = serde_json::json!({
+                "phone_number": phone_number,
+                "code": code,
+                "sent": Utc::now(),
+            });
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await?;
+            file.write_all(format!("{line}\n").as_bytes()).await?;
+        }
+
+        Ok(SmsReceipt::default())
+    }
+}
@@ -0,0 +1,49 @@
+//! 短信发送
+//!
+//! 由 `SMS_BACKEND` 选择服务商：`aliyun`（阿里云短信）、`tencent`（腾讯云短信）
+//! 或 `local`（只写日志和本地文件，用于开发环境和 CI）。
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+mod aliyun;
+mod local;
+mod tencent;
+
+pub use aliyun::AliyunSms;
+pub use local::LocalSms;
+pub use tencent::TencentSms;
+
+#[derive(Error, Debug)]
+pub enum SmsError {
+    #[error("短信服务配置错误: {0}")]
+    Env(#[from] dotenvy::Error),
+    #[error("与短信服务通信时出错: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("阿里云短信错误: {0}")]
+    Aliyun(#[from] alibaba_cloud_sdk_rust::error::AliyunSDKError),
+    #[error("写入短信日志时出错: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("短信服务返回错误 {code}: {message}")]
+    Provider { code: String, message: String },
+}
+
+/// 短信发送成功后服务商返回的回执
+#[derive(Debug, Clone, Default)]
+pub struct SmsReceipt {
+    /// 服务商的消息或请求 ID，便于在服务商控制台追踪
+    pub message_id: Option<String>,
+}
+
+#[async_trait]
+pub trait SmsSender {
+    /// 服务商名称，记录在发送日志中
+    fn provider(&self) -> &'static str;
+
+    /// 向手机号发送验证码
+    async fn send_code(
+        &self,
+        phone_number: &str,
+        code: &str,
+    ) -> Result<SmsReceipt, SmsError>;
+}
@@ -0,0 +1,182 @@
+use super::{SmsError, SmsReceipt, SmsSender};
+use async_trait::async_trait;
+use chrono::Utc;
+use hex::ToHex;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+const HOST: &str = "sms.tencentcloudapi.com";
+const SERVICE: &str = "sms";
+const VERSION: &str = "2021-01-11";
+const CONTENT_TYPE: &str = "application/json; charset=utf-8";
+
+/// 腾讯云短信服务，使用 TC3-HMAC-SHA256 签名调用 API 3.0
+pub struct TencentSms {
+    client: reqwest::Client,
+    region: String,
+    secret_id: String,
+    secret_key: String,
+    sdk_app_id: String,
+    sign_name: String,
+    template_id: String,
+}
+
+impl TencentSms {
+    pub fn new(
+        region: &str,
+        secret_id: &str,
+        secret_key: &str,
+        sdk_app_id: &str,
+        sign_name: &str,
+        template_id: &str,
+    ) -> Self {
+        TencentSms {
+            client: reqwest::Client::new(),
+            region: region.to_string(),
+            secret_id: secret_id.to_string(),
+            secret_key: secret_key.to_string(),
+            sdk_app_id: sdk_app_id.to_string(),
+            sign_name: sign_name.to_string(),
+            template_id: template_id.to_string(),
+        }
+    }
+
+    fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+        // HMAC 接受任意长度的密钥，不会失败
+        let mut mac = Hmac::<Sha256>::new_from_slice(key)
+            .expect("HMAC can take key of any size");
+        mac.update(data.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    /// 参见：https://cloud.tencent.com/document/api/382/52071
+    fn authorization(&self, payload: &str, timestamp: i64) -> String {
+        let date = chrono::DateTime::from_timestamp(timestamp, 0)
+            .unwrap_or_default()
+            .format("%Y-%m-%d")
+            .to_string();
+
+        let canonical_request = format!(
+            "POST\n/\n\ncontent-type:{CONTENT_TYPE}\nhost:{HOST}\n\ncontent-type;host\n{}",
+            Sha256::digest(payload.as_bytes()).encode_hex::<String>()
+        );
+        let credential_scope = format!("{date}/{SERVICE}/tc3_request");
+        let string_to_sign = format!(
+            "TC3-HMAC-SHA256\n{timestamp}\n{credential_scope}\n{}",
+            Sha256::digest(canonical_request.as_bytes()).encode_hex::<String>()
+        );
+
+        let secret_date =
+            Self::hmac(format!("TC3{}", self.secret_key).as_bytes(), &date);
+        let secret_service = Self::hmac(&secret_date, SERVICE);
+        let secret_signing = Self::hmac(&secret_service, "tc3_request");
+        let signature =
+            Self::hmac(&secret_signing, &string_to_sign).encode_hex::<String>();
+
+        format!(
+            "TC3-HMAC-SHA256 Credential={}/{credential_scope}, SignedHeaders=content-type;host, Signature={signature}",
+            self.secret_id
+        )
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TencentResponse {
+    response: TencentResponseBody,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TencentResponseBody {
+    #[serde(default)]
+    send_status_set: Vec<TencentSendStatus>,
+    error: Option<TencentError>,
+    request_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TencentSendStatus {
+    serial_no: String,
+    code: String,
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TencentError {
+    code: String,
+    message: String,
+}
+
+#[async_trait]
+impl SmsSender for TencentSms {
+    fn provider(&self) -> &'static str {
+        "tencent"
+    }
+
+    async fn send_code(
+        &self,
+        phone_number: &str,
+        code: &str,
+    ) -> Result<SmsReceipt, SmsError> {
+        // 腾讯云要求 E.164 格式，未带国家码的视为中国大陆号码
+        let phone_number = if phone_number.starts_with('+') {
+            phone_number.to_string()
+        } else {
+            format!("+86{phone_number}")
+        };
+
+        let payload = serde_json::json!({
+            "PhoneNumberSet": [phone_number],
+            "SmsSdkAppId": self.sdk_app_id,
+            "SignName": self.sign_name,
+            "TemplateId": self.template_id,
+            "TemplateParamSet": [code],
+        })
+        .to_string();
+        let timestamp = Utc::now().timestamp();
+
+        let response: TencentResponse = self
+            .client
+            .post(format!("https://{HOST}"))
+            .header("Authorization", self.authorization(&payload, timestamp))
+            .header("Content-Type", CONTENT_TYPE)
+            .header("Host", HOST)
+            .header("X-TC-Action", "SendSms")
+            .header("X-TC-Timestamp", timestamp.to_string())
+            .header("X-TC-Version", VERSION)
+            .header("X-TC-Region", &self.region)
+            .body(payload)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let response = response.response;
+
+        if let Some(error) = response.error {
+            return Err(SmsError::Provider {
+                code: error.code,
+                message: error.message,
+            });
+        }
+
+        // 请求成功时仍需检查每个号码的发送状态
+        match response.send_status_set.into_iter().next() {
+            Some(status) if status.code == "Ok" => Ok(SmsReceipt {
+                message_id: Some(status.serial_no),
+            }),
+            Some(status) => Err(SmsError::Provider {
+                code: status.code,
+                message: status.message,
+            }),
+            None => Err(SmsError::Provider {
+                code: "EmptySendStatus".to_string(),
+                message: format!("请求 {} 未返回发送状态", response.request_id),
+            }),
+        }
+    }
+}
@@ -20,7 +20,7 @@ pub fn get_image_ext(content_type: &str) -> Option<&'static str> {
     }
 }
 
-pub fn project_file_type(ext: &str) -> Option<&str> {
+pub fn project_file_type(ext: &str) -> Option<&'static str> {
     match ext {
         "jar" => Some("application/java-archive"),
         "zip" | "litemod" => Some("application/zip"),
@@ -23,3 +23,32 @@ pub fn strip_ip(ip: Ipv6Addr) -> u64 {
         ])
     }
 }
+
+/// 解析 CIDR 格式的 IP 范围（如 "203.0.113.0/24"），单个 IP 视为完整前缀。
+/// IPv4 统一转换为 IPv6 映射地址，前缀长度相应加 96
+pub fn parse_ip_range(src: &str) -> Option<(Ipv6Addr, u8)> {
+    let (addr, prefix) = match src.split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix.parse::<u8>().ok()?)),
+        None => (src, None),
+    };
+
+    match addr.parse::<IpAddr>().ok()? {
+        IpAddr::V4(x) => {
+            let prefix = prefix.unwrap_or(32);
+            (prefix <= 32).then(|| (x.to_ipv6_mapped(), prefix + 96))
+        }
+        IpAddr::V6(x) => {
+            let prefix = prefix.unwrap_or(128);
+            (prefix <= 128).then_some((x, prefix))
+        }
+    }
+}
+
+pub fn ip_in_range(ip: Ipv6Addr, range: &str) -> bool {
+    let Some((network, prefix)) = parse_ip_range(range) else {
+        return false;
+    };
+
+    let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+    u128::from(ip) & mask == u128::from(network) & mask
+}
@@ -0,0 +1,257 @@
+//! 论坛、问题、消息线程与百科审核留言中的 @提及
+//!
+//! 只有能看到对应内容的用户才会收到提及通知，同时限制单个用户在一段时间内
+//! 能发出的提及数量，防止利用 @ 刷屏骚扰。
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::auth::checks::{
+    check_wiki_paid_access, is_authorized_thread, is_visible_project,
+};
+use crate::database;
+use crate::database::models::notification_item::NotificationBuilder;
+use crate::database::models::project_item::Project;
+use crate::database::redis::RedisPool;
+use crate::models::notifications::NotificationBody;
+use crate::models::users::User;
+use crate::routes::ApiError;
+use crate::util::env::parse_var;
+use sqlx::PgPool;
+
+lazy_static! {
+    static ref RE_MENTION: Regex =
+        Regex::new(r"(?:^|[^\p{L}\p{N}_])@([\p{L}\p{N}_-]{1,39})").unwrap();
+}
+
+// 单条内容最多解析的提及数量
+const MAX_MENTIONS_PER_MESSAGE: usize = 10;
+// 提及次数统计窗口（秒）
+const MENTION_WINDOW_SECONDS: i64 = 60 * 60;
+// 摘要最大字符数
+const EXCERPT_LENGTH: usize = 100;
+
+const MENTION_LIMIT_NAMESPACE: &str = "mention_limit";
+
+/// 提及所在内容的可见范围
+pub enum MentionVisibility<'a> {
+    /// 全站可见，例如不属于任何项目的论坛帖子
+    Public,
+    /// 属于某个项目，被提及的用户需要能看到该项目
+    Project(&'a Project),
+    /// 项目百科，付费项目还需要被提及的用户有访问权限
+    Wiki(&'a Project),
+    /// 消息线程，私密消息只有版主能看到
+    Thread {
+        thread: &'a database::models::Thread,
+        private: bool,
+    },
+}
+
+/// 提及通知中展示的内容来源
+pub struct MentionSource<'a> {
+    pub source_type: &'a str,
+    pub title: &'a str,
+    pub link: &'a str,
+}
+
+/// 解析文本中的 @用户名，去重后按出现顺序返回
+pub fn parse_mentions(text: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+    for capture in RE_MENTION.captures_iter(text) {
+        let username = capture[1].trim_end_matches(['-', '_']).to_string();
+        if username.is_empty()
+            || mentions
+                .iter()
+                .any(|x| x.to_lowercase() == username.to_lowercase())
+        {
+            continue;
+        }
+        mentions.push(username);
+        if mentions.len() >= MAX_MENTIONS_PER_MESSAGE {
+            break;
+        }
+    }
+    mentions
+}
+
+fn excerpt(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() > EXCERPT_LENGTH {
+        format!("{}…", text.chars().take(EXCERPT_LENGTH).collect::<String>())
+    } else {
+        text
+    }
+}
+
+/// 扣减发送者的提及额度，返回本次还能通知的人数。
+/// 额度通过 `MENTION_RATE_LIMIT` 配置（每小时），版主不受限制
+async fn take_mention_quota(
+    sender: &User,
+    requested: usize,
+    redis: &RedisPool,
+) -> Result<usize, ApiError> {
+    if sender.role.is_mod() {
+        return Ok(requested);
+    }
+
+    let limit = parse_var::<i64>("MENTION_RATE_LIMIT").unwrap_or(30);
+    let key = sender.id.to_string();
+
+    // 每通知一人计数一次，INCR 保证并发请求不会超出额度
+    let mut conn = redis.connect().await?;
+    let mut allowed = 0;
+    while allowed < requested {
+        let count = conn
+            .increment(MENTION_LIMIT_NAMESPACE, &key, MENTION_WINDOW_SECONDS)
+            .await?;
+        if count > limit {
+            break;
+        }
+        allowed += 1;
+    }
+
+    Ok(allowed)
+}
+
+async fn can_see(
+    user: &User,
+    visibility: &MentionVisibility<'_>,
+    pool: &PgPool,
+) -> Result<bool, ApiError> {
+    let user_option = Some(user.clone());
+    Ok(match visibility {
+        MentionVisibility::Public => true,
+        MentionVisibility::Project(project) => {
+            is_visible_project(project, &user_option, pool, false).await?
+        }
+        MentionVisibility::Wiki(project) => {
+            is_visible_project(project, &user_option, pool, false).await?
+                && (!project.is_paid
+                    || check_wiki_paid_access(user, project, pool).await?)
+        }
+        MentionVisibility::Thread { thread, private } => {
+            (!private || user.role.is_mod())
+                && is_authorized_thread(thread, user, pool).await?
+        }
+    })
+}
+
+/// 解析 `text` 中的提及并通知能看到该内容的用户
+pub async fn notify_mentions(
+    text: &str,
+    sender: &User,
+    visibility: MentionVisibility<'_>,
+    source: MentionSource<'_>,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(), ApiError> {
+    notify_users(
+        parse_mentions(text),
+        text,
+        sender,
+        visibility,
+        source,
+        transaction,
+        pool,
+        redis,
+    )
+    .await
+}
+
+/// 内容被编辑后，只通知 `previous` 中没有提及过的用户
+#[allow(clippy::too_many_arguments)]
+pub async fn notify_edited_mentions(
+    previous: &str,
+    text: &str,
+    sender: &User,
+    visibility: MentionVisibility<'_>,
+    source: MentionSource<'_>,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(), ApiError> {
+    let previous = parse_mentions(previous)
+        .into_iter()
+        .map(|x| x.to_lowercase())
+        .collect::<Vec<_>>();
+    let mentions = parse_mentions(text)
+        .into_iter()
+        .filter(|x| !previous.contains(&x.to_lowercase()))
+        .collect::<Vec<_>>();
+
+    notify_users(
+        mentions,
+        text,
+        sender,
+        visibility,
+        source,
+        transaction,
+        pool,
+        redis,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn notify_users(
+    mentions: Vec<String>,
+    text: &str,
+    sender: &User,
+    visibility: MentionVisibility<'_>,
+    source: MentionSource<'_>,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(), ApiError> {
+    let mentions = mentions
+        .into_iter()
+        .filter(|x| x.to_lowercase() != sender.username.to_lowercase())
+        .collect::<Vec<_>>();
+    if mentions.is_empty() {
+        return Ok(());
+    }
+
+    let lowercase = mentions
+        .iter()
+        .map(|x| x.to_lowercase())
+        .collect::<Vec<_>>();
+    // get_many 同时会按 ID 匹配，这里只保留用户名完全一致的用户
+    let users = database::models::User::get_many(&mentions, pool, redis)
+        .await?
+        .into_iter()
+        .filter(|x| lowercase.contains(&x.username.to_lowercase()))
+        .map(User::from)
+        .collect::<Vec<_>>();
+
+    let mut recipients = Vec::new();
+    for user in users {
+        if can_see(&user, &visibility, pool).await? {
+            recipients.push(database::models::UserId::from(user.id));
+        }
+    }
+    if recipients.is_empty() {
+        return Ok(());
+    }
+
+    let allowed = take_mention_quota(sender, recipients.len(), redis).await?;
+    recipients.truncate(allowed);
+    if recipients.is_empty() {
+        return Ok(());
+    }
+
+    NotificationBuilder {
+        body: NotificationBody::Mention {
+            sender: sender.username.clone(),
+            source_type: source.source_type.to_string(),
+            title: source.title.to_string(),
+            link: source.link.to_string(),
+            excerpt: excerpt(text),
+        },
+    }
+    .insert_many(recipients, transaction, redis)
+    .await?;
+
+    Ok(())
+}
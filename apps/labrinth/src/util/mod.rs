@@ -1,7 +1,6 @@
 pub mod actix;
 pub mod ban_check;
 pub mod bitflag;
-pub mod captcha;
 pub mod cors;
 pub mod date;
 pub mod encrypt;
@@ -11,6 +10,7 @@ pub mod guards;
 pub mod img;
 pub mod indexnow;
 pub mod ip;
+pub mod mentions;
 pub mod phone;
 pub mod ratelimit;
 pub mod redis;
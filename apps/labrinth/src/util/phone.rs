@@ -1,29 +1,115 @@
+use crate::database::models::UserId;
+use crate::database::models::sms_send_log_item::{SmsSendLog, SmsSendStatus};
+use crate::database::redis::RedisPool;
 use crate::routes::ApiError;
-use alibaba_cloud_sdk_rust::services::dysmsapi;
+use crate::sms::SmsSender;
+use sqlx::PgPool;
+use tracing::{error, warn};
 
+const PHONE_COOLDOWN_NAMESPACE: &str = "sms_phone_cooldown";
+const PHONE_DAILY_NAMESPACE: &str = "sms_phone_daily";
+const IP_HOURLY_NAMESPACE: &str = "sms_ip_hourly";
+
+/// 同一手机号两次发送的最小间隔（秒）
+const PHONE_COOLDOWN_SECONDS: i64 = 60;
+/// 同一手机号每天最多发送的条数
+const PHONE_DAILY_LIMIT: i64 = 10;
+/// 同一 IP 每小时最多发送的条数
+const IP_HOURLY_LIMIT: i64 = 20;
+
+/// 发送手机验证码，按手机号和 IP 限制频率，并记录每次发送结果
 pub async fn send_phone_number_code(
+    sender: &(dyn SmsSender + Send + Sync),
     phone_number: &str,
     code: &str,
-) -> Result<bool, ApiError> {
-    let access_key_id = dotenvy::var("ALIYUN_SMS_ACCESS_KEYID")?;
-    let access_key_secret = dotenvy::var("ALIYUN_SMS_ACCESS_KEY_SECRET")?;
-    let report_templeate_code =
-        dotenvy::var("ALIYUN_SMS_REPORT_TEMPLETE_CODE")?;
-    let sign_name = dotenvy::var("ALIYUN_SMS_SIGN_NAME")?;
-    let region = dotenvy::var("ALIYUN_SMS_REGION")?;
-
-    let mut client = dysmsapi::Client::NewClientWithAccessKey(
-        &region,
-        &access_key_id,
-        &access_key_secret,
-    )?;
-    let mut request = dysmsapi::CreateSendSmsRequest();
-    request.PhoneNumbers = phone_number.to_owned();
-    request.SignName = sign_name.to_owned();
-    request.TemplateCode = report_templeate_code.to_owned();
-    request.TemplateParam = format!("{{\"code\":\"{}\"}}", code).to_owned();
-    let response = client.SendSms(&mut request)?;
-    println!("{:?}", &response);
-
-    Ok(true)
+    user_id: Option<UserId>,
+    ip: Option<&str>,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(), ApiError> {
+    let mut conn = redis.connect().await?;
+
+    // 原子地占用发送间隔，避免并发请求同时通过检查
+    let limited = if !conn
+        .set_if_absent(
+            PHONE_COOLDOWN_NAMESPACE,
+            phone_number,
+            "1",
+            PHONE_COOLDOWN_SECONDS,
+        )
+        .await?
+    {
+        Some(format!("该手机号{PHONE_COOLDOWN_SECONDS}秒内已发送过短信"))
+    } else if conn
+        .increment(PHONE_DAILY_NAMESPACE, phone_number, 60 * 60 * 24)
+        .await?
+        > PHONE_DAILY_LIMIT
+    {
+        Some("该手机号今日接收短信次数已达上限".to_string())
+    } else if let Some(ip) = ip
+        && conn.increment(IP_HOURLY_NAMESPACE, ip, 60 * 60).await?
+            > IP_HOURLY_LIMIT
+    {
+        Some("发送短信过于频繁，请稍后再试".to_string())
+    } else {
+        None
+    };
+
+    if let Some(reason) = limited {
+        SmsSendLog::insert(
+            user_id,
+            phone_number,
+            ip,
+            sender.provider(),
+            SmsSendStatus::RateLimited,
+            None,
+            Some(&reason),
+            pool,
+        )
+        .await?;
+
+        return Err(ApiError::InvalidInput(reason));
+    }
+
+    match sender.send_code(phone_number, code).await {
+        Ok(receipt) => {
+            SmsSendLog::insert(
+                user_id,
+                phone_number,
+                ip,
+                sender.provider(),
+                SmsSendStatus::Sent,
+                receipt.message_id.as_deref(),
+                None,
+                pool,
+            )
+            .await?;
+
+            Ok(())
+        }
+        Err(err) => {
+            error!("短信发送失败 ({}): {}", sender.provider(), err);
+
+            // 发送失败时允许立即重试
+            if let Err(err) =
+                conn.delete(PHONE_COOLDOWN_NAMESPACE, phone_number).await
+            {
+                warn!("清除短信发送间隔失败: {}", err);
+            }
+
+            SmsSendLog::insert(
+                user_id,
+                phone_number,
+                ip,
+                sender.provider(),
+                SmsSendStatus::Failed,
+                None,
+                Some(&err.to_string()),
+                pool,
+            )
+            .await?;
+
+            Err(err.into())
+        }
+    }
 }
@@ -6,7 +6,7 @@ use std::sync::Arc;
 use crate::routes::ApiError;
 use crate::util::env::parse_var;
 use actix_web::{
-    Error, ResponseError,
+    Error, HttpRequest, ResponseError,
     body::EitherBody,
     dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
 };
@@ -22,6 +22,22 @@ pub type KeyedRateLimiter<
 
 pub struct RateLimit(pub KeyedRateLimiter);
 
+/// 获取请求方的 IP，开启 Cloudflare 集成时优先使用 `x-real-ip` 头，
+/// 无法获取时返回空字符串
+pub fn client_ip(req: &HttpRequest) -> String {
+    if parse_var("CLOUDFLARE_INTEGRATION").unwrap_or(false)
+        && let Some(ip) =
+            req.headers().get("x-real-ip").and_then(|x| x.to_str().ok())
+    {
+        return ip.to_string();
+    }
+
+    req.connection_info()
+        .peer_addr()
+        .map(|x| x.to_string())
+        .unwrap_or_default()
+}
+
 impl<S, B> Transform<S, ServiceRequest> for RateLimit
 where
     S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
@@ -73,20 +89,9 @@ where
             });
         }
 
-        let conn_info = req.connection_info().clone();
-        let ip = if parse_var("CLOUDFLARE_INTEGRATION").unwrap_or(false) {
-            if let Some(header) = req.headers().get("x-real-ip") {
-                header.to_str().ok()
-            } else {
-                conn_info.peer_addr()
-            }
-        } else {
-            conn_info.peer_addr()
-        };
-
-        if let Some(ip) = ip {
-            let ip = ip.to_string();
+        let ip = client_ip(req.request());
 
+        if !ip.is_empty() {
             match self.rate_limiter.check_key(&ip) {
                 Ok(snapshot) => {
                     let fut = self.service.call(req);
@@ -32,6 +32,76 @@ impl UploadLimit {
     pub fn add(&mut self) {
         self.count += 1;
     }
+
+    fn limit_time(&self) -> String {
+        // 使用北京时间输出
+        self.time
+            .with_timezone(&chrono::FixedOffset::east_opt(8 * 3600).unwrap())
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    }
+}
+
+/// 检查是否因多次触发风控而处于限制期
+pub async fn check_risk_limit(
+    key: &str,
+    redis: &RedisPool,
+) -> Result<(), ApiError> {
+    let mut conn = redis.connect().await?;
+    let upload_limit: Option<UploadLimit> =
+        conn.get_deserialized_from_json("upload_limit", key).await?;
+
+    match upload_limit {
+        Some(upload_limit) if upload_limit.is_limit() => {
+            Err(ApiError::RiskLimit(upload_limit.limit_time()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 记录一次来自其他来源（如人机验证风险分）的风控事件，
+/// 与内容风控共用计数：60秒内出现5次风险，暂时禁止十分钟
+pub async fn record_risk_event(
+    key: &str,
+    redis: &RedisPool,
+) -> Result<(), ApiError> {
+    let mut conn = redis.connect().await?;
+    let upload_limit: Option<UploadLimit> =
+        conn.get_deserialized_from_json("upload_limit", key).await?;
+
+    if let Some(mut upload_limit) = upload_limit {
+        upload_limit.add();
+        if upload_limit.is_limit() {
+            upload_limit.time = Utc::now().add(chrono::Duration::minutes(10));
+            conn.set_serialized_to_json(
+                "upload_limit",
+                key,
+                &upload_limit,
+                Some(600),
+            )
+            .await?;
+
+            return Err(ApiError::RiskLimit(upload_limit.limit_time()));
+        }
+        conn.set_serialized_to_json(
+            "upload_limit",
+            key,
+            &upload_limit,
+            Some(60),
+        )
+        .await?;
+    } else {
+        let upload_limit = UploadLimit::new(Utc::now());
+        conn.set_serialized_to_json(
+            "upload_limit",
+            key,
+            &upload_limit,
+            Some(60),
+        )
+        .await?;
+    }
+
+    Ok(())
 }
 
 pub async fn check_text_risk(
@@ -56,6 +56,8 @@ pub enum ValidationResult {
     PassWithPackDataAndFiles {
         format: PackFormat,
         files: Vec<String>,
+        /// 覆盖目录中的文件路径及其 SHA1
+        override_files: Vec<(String, String)>,
     },
     /// 文件应标记为主要文件
     Pass,
@@ -3,6 +3,7 @@ use crate::util::validate::validation_errors_to_string;
 use crate::validate::{
     SupportedGameVersions, ValidationError, ValidationResult,
 };
+use sha1::Digest;
 use std::io::{Cursor, Read};
 // 注意：路径验证已迁移到 SafeRelativePath 类型中，在反序列化时自动执行
 // 来源于上游提交 ab6e9dd5d - stricter mrpack file path validation (#4482)
@@ -120,8 +121,24 @@ impl super::Validator for ModpackValidator {
             // 包括：空路径检查、反斜杠检查、特殊组件检查、Windows 保留名称检查
         }
 
+        // 记录覆盖文件的 SHA1，用于对比整合包版本之间的文件变化
+        let mut override_files = Vec::new();
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if file.is_dir() || !is_override_path(file.name()) {
+                continue;
+            }
+
+            // 逐块写入哈希，避免将解压后的文件完整读入内存
+            let path = file.name().to_string();
+            let mut hasher = sha1::Sha1::new();
+            std::io::copy(&mut file, &mut hasher)?;
+            override_files.push((path, format!("{:x}", hasher.finalize())));
+        }
+
         Ok(ValidationResult::PassWithPackDataAndFiles {
             format: pack,
+            override_files,
             files: archive
                 .file_names()
                 .filter(|x| {
@@ -139,3 +156,9 @@ impl super::Validator for ModpackValidator {
         })
     }
 }
+
+fn is_override_path(path: &str) -> bool {
+    path.starts_with("overrides/")
+        || path.starts_with("client-overrides/")
+        || path.starts_with("server-overrides/")
+}
@@ -0,0 +1 @@
+ALTER TABLE modrinth_users ADD COLUMN refresh_token TEXT NULL;
@@ -1,3 +1,4 @@
+use crate::event::emit::emit_device_login;
 use crate::state::{ModrinthCredentials, ModrinthCredentialsResult};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -89,6 +90,35 @@ pub async fn create_account(
     Ok(creds)
 }
 
+/// Logs in without a browser redirect, for headless setups and CLI tools.
+/// The user code is emitted as a `device_login` event and the call returns
+/// once the user approves the login on the website.
+#[tracing::instrument]
+pub async fn login_device() -> crate::Result<ModrinthCredentials> {
+    let state = crate::State::get().await?;
+    let device_code =
+        crate::state::begin_device_login(&state.api_semaphore).await?;
+
+    emit_device_login(
+        &device_code.user_code,
+        &device_code.verification_uri,
+        &device_code.verification_uri_complete,
+        device_code.expires_in,
+    )
+    .await?;
+
+    let creds = crate::state::finish_device_login(
+        &device_code,
+        &state.api_semaphore,
+        &state.pool,
+    )
+    .await?;
+
+    creds.upsert(&state.pool).await?;
+
+    Ok(creds)
+}
+
 #[tracing::instrument]
 pub async fn logout() -> crate::Result<()> {
     let state = crate::State::get().await?;
@@ -2,5 +2,10 @@
 
 pub const MODRINTH_API_URL: &str = "https://api.bbsmc.net/v2/";
 pub const MODRINTH_API_URL_V3: &str = "https://api.bbsmc.net/v3/";
+pub const MODRINTH_API_URL_INTERNAL: &str = "https://api.bbsmc.net/_internal/";
+
+// Public OAuth application the launcher uses for device logins
+pub const MODRINTH_OAUTH_CLIENT_ID: Option<&str> =
+    option_env!("BBSMC_OAUTH_CLIENT_ID");
 
 pub const META_URL: &str = "https://launcher-meta.modrinth.com/";
@@ -5,7 +5,8 @@ use crate::event::{
 };
 #[cfg(feature = "tauri")]
 use crate::event::{
-    LoadingPayload, ProcessPayload, ProfilePayload, WarningPayload,
+    DeviceLoginPayload, LoadingPayload, ProcessPayload, ProfilePayload,
+    WarningPayload,
 };
 use futures::prelude::*;
 #[cfg(feature = "tauri")]
@@ -226,6 +227,38 @@ pub async fn emit_warning(message: &str) -> crate::Result<()> {
     Ok(())
 }
 
+// emit_device_login(user_code, verification_uri, verification_uri_complete, expires_in)
+// Shows the user code of a pending device login, which the user enters on the website
+#[allow(unused_variables)]
+pub async fn emit_device_login(
+    user_code: &str,
+    verification_uri: &str,
+    verification_uri_complete: &str,
+    expires_in: i64,
+) -> crate::Result<()> {
+    #[cfg(feature = "tauri")]
+    {
+        let event_state = crate::EventState::get()?;
+        event_state
+            .app
+            .emit(
+                "device_login",
+                DeviceLoginPayload {
+                    user_code: user_code.to_string(),
+                    verification_uri: verification_uri.to_string(),
+                    verification_uri_complete: verification_uri_complete
+                        .to_string(),
+                    expires_in,
+                },
+            )
+            .map_err(EventError::from)?;
+    }
+    tracing::info!(
+        "To sign in, visit {verification_uri} and enter the code {user_code}"
+    );
+    Ok(())
+}
+
 // emit_command(CommandPayload::Something { something })
 // ie: installing a pack, opening an .mrpack, etc
 // Generally used for url deep links and file opens that we want to handle in the frontend
@@ -198,6 +198,14 @@ pub struct WarningPayload {
     pub message: String,
 }
 
+#[derive(Serialize, Clone)]
+pub struct DeviceLoginPayload {
+    pub user_code: String,
+    pub verification_uri: String,
+    pub verification_uri_complete: String,
+    pub expires_in: i64,
+}
+
 #[derive(Serialize, Clone)]
 #[serde(tag = "event")]
 pub enum CommandPayload {
@@ -105,6 +105,7 @@ where
                 expires: creds.expires_at,
                 user_id: creds.user.id,
                 active: true,
+                refresh_token: None,
             }
             .upsert(exec)
             .await?;
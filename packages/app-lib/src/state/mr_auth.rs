@@ -1,10 +1,13 @@
-use crate::config::MODRINTH_API_URL;
+use crate::config::{
+    MODRINTH_API_URL, MODRINTH_API_URL_INTERNAL, MODRINTH_OAUTH_CLIENT_ID,
+};
 use crate::state::{CacheBehaviour, CachedEntry};
-use crate::util::fetch::{fetch_advanced, FetchSemaphore};
+use crate::util::fetch::{fetch_advanced, FetchSemaphore, REQWEST_CLIENT};
 use chrono::{DateTime, Duration, TimeZone, Utc};
 use dashmap::DashMap;
 
 use reqwest::Method;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -15,6 +18,9 @@ pub struct ModrinthCredentials {
     pub expires: DateTime<Utc>,
     pub user_id: String,
     pub active: bool,
+    /// Only set for credentials obtained through a device login, which hold
+    /// an OAuth access token instead of a session
+    pub refresh_token: Option<String>,
 }
 
 impl ModrinthCredentials {
@@ -26,6 +32,27 @@ impl ModrinthCredentials {
 
         if let Some(mut creds) = creds {
             if creds.expires < Utc::now() {
+                if let Some(refresh_token) = &creds.refresh_token {
+                    // Network and server errors are returned as-is so the
+                    // user stays logged in; only a rejected refresh token
+                    // removes the credentials
+                    return if let Some(token) =
+                        refresh_oauth_token(refresh_token, semaphore).await?
+                    {
+                        creds.session = token.access_token;
+                        creds.expires =
+                            Utc::now() + Duration::seconds(token.expires_in);
+                        creds.refresh_token = Some(token.refresh_token);
+                        creds.upsert(exec).await?;
+
+                        Ok(Some(creds))
+                    } else {
+                        Self::remove(&creds.user_id, exec).await?;
+
+                        Ok(None)
+                    };
+                }
+
                 #[derive(Deserialize)]
                 struct Session {
                     session: String,
@@ -70,7 +97,7 @@ impl ModrinthCredentials {
         let res = sqlx::query!(
             "
             SELECT
-                id, active, session_id, expires
+                id, active, session_id, expires, refresh_token
             FROM modrinth_users
             WHERE active = TRUE
             "
@@ -86,6 +113,7 @@ impl ModrinthCredentials {
                 .unwrap_or_else(Utc::now),
             user_id: x.id,
             active: x.active == 1,
+            refresh_token: x.refresh_token,
         }))
     }
 
@@ -95,7 +123,7 @@ impl ModrinthCredentials {
         let res = sqlx::query!(
             "
             SELECT
-                id, active, session_id, expires
+                id, active, session_id, expires, refresh_token
             FROM modrinth_users
             "
         )
@@ -111,6 +139,7 @@ impl ModrinthCredentials {
                         .unwrap_or_else(Utc::now),
                     user_id: x.id,
                     active: x.active == 1,
+                    refresh_token: x.refresh_token,
                 },
             );
 
@@ -140,17 +169,21 @@ impl ModrinthCredentials {
 
         sqlx::query!(
             "
-            INSERT INTO modrinth_users (id, active, session_id, expires)
-            VALUES ($1, $2, $3, $4)
+            INSERT INTO modrinth_users (
+                id, active, session_id, expires, refresh_token
+            )
+            VALUES ($1, $2, $3, $4, $5)
             ON CONFLICT (id) DO UPDATE SET
                 active = $2,
                 session_id = $3,
-                expires = $4
+                expires = $4,
+                refresh_token = $5
             ",
             self.user_id,
             self.active,
             self.session,
             expires,
+            self.refresh_token,
         )
         .execute(exec)
         .await?;
@@ -219,6 +252,7 @@ async fn get_result_from_res(
                 expires: Utc::now() + Duration::weeks(2),
                 user_id: info.id,
                 active: true,
+                refresh_token: None,
             },
         ))
     } else if let Some(error) =
@@ -249,6 +283,7 @@ async fn get_creds_from_res(
             expires: Utc::now() + Duration::weeks(2),
             user_id: info.id,
             active: true,
+            refresh_token: None,
         })
     } else if let Some(error) =
         response.get("description").and_then(|x| x.as_str())
@@ -364,6 +399,172 @@ pub async fn create_account(
     get_creds_from_res(response, semaphore, exec).await
 }
 
+const DEVICE_CODE_GRANT_TYPE: &str =
+    "urn:ietf:params:oauth:grant-type:device_code";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ModrinthDeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub verification_uri_complete: String,
+    pub expires_in: i64,
+    pub interval: i64,
+}
+
+#[derive(Deserialize)]
+struct OAuthToken {
+    access_token: String,
+    refresh_token: String,
+    expires_in: i64,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OAuthResponse<T> {
+    Success(T),
+    Error { error: String, description: String },
+}
+
+fn oauth_client_id() -> crate::Result<&'static str> {
+    MODRINTH_OAUTH_CLIENT_ID.ok_or_else(|| {
+        crate::ErrorKind::OtherError(String::from(
+            "Device login is not available in this build!",
+        ))
+        .as_error()
+    })
+}
+
+async fn post_oauth_form<T: DeserializeOwned>(
+    endpoint: &str,
+    form: &[(&str, &str)],
+    semaphore: &FetchSemaphore,
+) -> crate::Result<OAuthResponse<T>> {
+    let _permit = semaphore.0.acquire().await?;
+
+    let resp = REQWEST_CLIENT
+        .post(format!("{MODRINTH_API_URL_INTERNAL}oauth/{endpoint}"))
+        .header("Accept", "application/json")
+        .form(form)
+        .send()
+        .await?
+        .bytes()
+        .await?;
+
+    Ok(serde_json::from_slice(&resp)?)
+}
+
+/// Starts a device login (RFC 8628). The returned user code must be shown
+/// to the user, who enters it on the website to approve the login.
+pub async fn begin_device_login(
+    semaphore: &FetchSemaphore,
+) -> crate::Result<ModrinthDeviceCode> {
+    let client_id = oauth_client_id()?;
+
+    match post_oauth_form("device/code", &[("client_id", client_id)], semaphore)
+        .await?
+    {
+        OAuthResponse::Success(device_code) => Ok(device_code),
+        OAuthResponse::Error { description, .. } => {
+            Err(crate::ErrorKind::OtherError(format!(
+                "Failed to login with error {description}"
+            ))
+            .as_error())
+        }
+    }
+}
+
+/// Polls the token endpoint until the user approves or denies the device
+/// login, or the device code expires
+pub async fn finish_device_login(
+    device_code: &ModrinthDeviceCode,
+    semaphore: &FetchSemaphore,
+    exec: impl sqlx::Executor<'_, Database = sqlx::Sqlite>,
+) -> crate::Result<ModrinthCredentials> {
+    let client_id = oauth_client_id()?;
+    let expires = Utc::now() + Duration::seconds(device_code.expires_in);
+    let mut interval = device_code.interval;
+
+    while Utc::now() < expires {
+        tokio::time::sleep(std::time::Duration::from_secs(interval as u64))
+            .await;
+
+        let resp = post_oauth_form::<OAuthToken>(
+            "token",
+            &[
+                ("grant_type", DEVICE_CODE_GRANT_TYPE),
+                ("device_code", &device_code.device_code),
+                ("client_id", client_id),
+            ],
+            semaphore,
+        )
+        .await?;
+
+        match resp {
+            OAuthResponse::Success(token) => {
+                let info =
+                    fetch_info(&token.access_token, semaphore, exec).await?;
+
+                return Ok(ModrinthCredentials {
+                    session: token.access_token,
+                    expires: Utc::now() + Duration::seconds(token.expires_in),
+                    user_id: info.id,
+                    active: true,
+                    refresh_token: Some(token.refresh_token),
+                });
+            }
+            OAuthResponse::Error { error, .. }
+                if error == "authorization_pending" => {}
+            OAuthResponse::Error { error, .. } if error == "slow_down" => {
+                interval += 5;
+            }
+            OAuthResponse::Error { description, .. } => {
+                return Err(crate::ErrorKind::OtherError(format!(
+                    "Failed to login with error {description}"
+                ))
+                .as_error());
+            }
+        }
+    }
+
+    Err(crate::ErrorKind::OtherError(String::from(
+        "Device login expired before it was approved!",
+    ))
+    .as_error())
+}
+
+/// Returns `None` if the server rejected the refresh token, meaning the
+/// login has been revoked or has expired
+async fn refresh_oauth_token(
+    refresh_token: &str,
+    semaphore: &FetchSemaphore,
+) -> crate::Result<Option<OAuthToken>> {
+    let client_id = oauth_client_id()?;
+
+    match post_oauth_form(
+        "token",
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("client_id", client_id),
+        ],
+        semaphore,
+    )
+    .await?
+    {
+        OAuthResponse::Success(token) => Ok(Some(token)),
+        OAuthResponse::Error { error, .. } if error == "invalid_grant" => {
+            Ok(None)
+        }
+        OAuthResponse::Error { description, .. } => {
+            Err(crate::ErrorKind::OtherError(format!(
+                "Failed to refresh login with error {description}"
+            ))
+            .as_error())
+        }
+    }
+}
+
 async fn fetch_info(
     token: &str,
     semaphore: &FetchSemaphore,